@@ -0,0 +1,132 @@
+// Package epoprocessor embeds the EPO download, extract and parse stages in another Go
+// program. Build a Processor with NewBuilder, then run the whole pipeline with Run or a
+// single stage through its Downloader, Extractor and Parser:
+//
+//	p, err := epoprocessor.NewBuilder().
+//		ConfigFile("epo.yaml").
+//		DownloadDir("/data/epo").
+//		Output("/data/patents.parquet", "parquet").
+//		Logger(logger).
+//		Build()
+//	if err != nil {
+//		return err
+//	}
+//	defer p.Close()
+//	summary, err := p.Run(ctx)
+//
+// The settings are those of the config file described in the README; the epo-processor
+// command is a thin layer over the same stages.
+package epoprocessor
+
+import (
+	"go.opentelemetry.io/otel/metric"
+	metricnoop "go.opentelemetry.io/otel/metric/noop"
+	"go.opentelemetry.io/otel/trace"
+	tracenoop "go.opentelemetry.io/otel/trace/noop"
+	"go.uber.org/zap"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+)
+
+// Builder collects the configuration of a Processor. Settings are applied on top of the
+// config file, if any, and the EPO_* environment variables.
+type Builder struct {
+	file     string
+	template string
+	settings map[string]any
+	logger   *zap.SugaredLogger
+	tracer   trace.Tracer
+	meter    metric.Meter
+}
+
+// NewBuilder returns a builder with the defaults of the config file, except that no
+// progress bars are drawn, nothing is logged and no telemetry is recorded.
+func NewBuilder() *Builder {
+	return &Builder{settings: map[string]any{"log.progress": false}}
+}
+
+// ConfigFile reads the settings from path. Without it, the config file is looked up as
+// the command does: the nearest processor.yaml, then the default search paths.
+func (b *Builder) ConfigFile(path string) *Builder {
+	b.file = path
+	return b
+}
+
+// Template starts from one of the built-in job templates, as --template does.
+func (b *Builder) Template(name string) *Builder {
+	b.template = name
+	return b
+}
+
+// Set overrides the setting at a dotted key of the config file, e.g.
+// Set("server.concurrent_downloads", 8). Unknown keys fail Build.
+func (b *Builder) Set(key string, value any) *Builder {
+	b.settings[key] = value
+	return b
+}
+
+// ProductID selects the EPO product to download.
+func (b *Builder) ProductID(id int) *Builder {
+	return b.Set("server.product_id", id)
+}
+
+// DownloadDir is where archives are downloaded and extracted.
+func (b *Builder) DownloadDir(dir string) *Builder {
+	return b.Set("download.directory", dir)
+}
+
+// Output is where the parse stage writes, in one of the output formats of
+// parse.output_format.
+func (b *Builder) Output(path, format string) *Builder {
+	return b.Set("parse.output_csv", path).Set("parse.output_format", format)
+}
+
+// Stages enables the download, extract and parse stages of Run.
+func (b *Builder) Stages(download, extract, parse bool) *Builder {
+	return b.Set("download.enabled", download).
+		Set("extract.enabled", extract).
+		Set("parse.enabled", parse)
+}
+
+// Logger receives the logs of the stages.
+func (b *Builder) Logger(logger *zap.SugaredLogger) *Builder {
+	b.logger = logger
+	return b
+}
+
+// Tracer records a span per run, stage and item.
+func (b *Builder) Tracer(tracer trace.Tracer) *Builder {
+	b.tracer = tracer
+	return b
+}
+
+// Meter records the download, extract and parse metrics.
+func (b *Builder) Meter(meter metric.Meter) *Builder {
+	b.meter = meter
+	return b
+}
+
+// Build loads and validates the configuration and opens the services it needs, such as
+// the manifest of state.enabled. Close the Processor when done.
+func (b *Builder) Build() (*Processor, error) {
+	cfg, err := config.LoadSettings(b.file, b.template, b.settings)
+	if err != nil {
+		return nil, err
+	}
+	logger, tracer, meter := b.logger, b.tracer, b.meter
+	if logger == nil {
+		logger = zap.NewNop().Sugar()
+	}
+	if tracer == nil {
+		tracer = tracenoop.NewTracerProvider().Tracer(cfg.Telemetry.ServiceName)
+	}
+	if meter == nil {
+		meter = metricnoop.NewMeterProvider().Meter(cfg.Telemetry.ServiceName)
+	}
+	services, err := internal.InitServices(cfg, tracer, logger, meter)
+	if err != nil {
+		return nil, err
+	}
+	return &Processor{cfg: cfg, services: services}, nil
+}
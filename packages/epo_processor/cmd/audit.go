@@ -0,0 +1,86 @@
+package cmd
+
+import (
+	"context"
+	"encoding/json"
+	"fmt"
+	"os"
+	"os/signal"
+	"syscall"
+	"text/tabwriter"
+
+	ET "github.com/IBM/fp-go/v2/either"
+	"github.com/spf13/cobra"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/audit"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/download"
+)
+
+var auditFlags struct {
+	json    bool
+	offline bool
+}
+
+var auditCmd = &cobra.Command{
+	Use:   "audit",
+	Short: "Cross-check product metadata, manifest, files on disk and the dataset",
+	RunE: func(cmd *cobra.Command, args []string) error {
+		ctx, cancel := signal.NotifyContext(context.Background(), os.Interrupt, syscall.SIGTERM)
+		defer cancel()
+		var items []download.ProductItem
+		if !auditFlags.offline {
+			var err error
+			items, err = ET.UnwrapError(services.Downloader.ProductItems(ctx)())
+			if err != nil {
+				return fmt.Errorf("fetch product metadata: %w", err)
+			}
+		}
+		report, err := audit.Run(cfg, services.Manifest, items)
+		if err != nil {
+			return fmt.Errorf("audit failed: %w", err)
+		}
+		if auditFlags.json {
+			data, err := json.MarshalIndent(report, "", "  ")
+			if err != nil {
+				return err
+			}
+			fmt.Println(string(data))
+		} else if err := printAuditReport(report); err != nil {
+			return err
+		}
+		logger.Infow("Audit completed",
+			"discrepancies", len(report.Discrepancies),
+			"errors", report.Errors())
+		if !report.Complete() {
+			return fmt.Errorf("corpus incomplete: %d errors", report.Errors())
+		}
+		return nil
+	},
+}
+
+func printAuditReport(report audit.Report) error {
+	if report.Snapshot != "" {
+		fmt.Printf("Snapshot: %s\n", report.Snapshot)
+	}
+	if report.ProductItems >= 0 {
+		fmt.Printf("Product items: %d\n", report.ProductItems)
+	}
+	fmt.Printf("Manifest items: %d, XML files: %d\n", report.ManifestItems, report.XMLFiles)
+	fmt.Printf("Dataset: %s (%d rows)\n\n", report.Output, report.Rows)
+	if len(report.Discrepancies) == 0 {
+		fmt.Println("No discrepancies")
+		return nil
+	}
+	w := tabwriter.NewWriter(os.Stdout, 0, 0, 2, ' ', 0)
+	fmt.Fprintln(w, "SEVERITY\tCHECK\tSUBJECT\tDETAIL")
+	for _, d := range report.Discrepancies {
+		fmt.Fprintf(w, "%s\t%s\t%s\t%s\n", d.Severity, d.Check, d.Subject, d.Detail)
+	}
+	return w.Flush()
+}
+
+func init() {
+	auditCmd.Flags().BoolVar(&auditFlags.json, "json", false, "Print the report as JSON")
+	auditCmd.Flags().BoolVar(&auditFlags.offline, "offline", false,
+		"Skip the checks against the product metadata")
+}
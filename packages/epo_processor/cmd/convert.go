@@ -0,0 +1,25 @@
+package cmd
+
+import (
+	"fmt"
+
+	"github.com/spf13/cobra"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/sink"
+)
+
+var convertCmd = &cobra.Command{
+	Use:   "convert <in> <out>",
+	Short: "Convert a parse output between Parquet, CSV and JSONL",
+	Long: "Convert a parse output between Parquet, CSV and JSONL, chosen by the file extensions " +
+		"(.parquet, .csv, .jsonl). A CSV output has the columns of parse.columns or parse.fields.",
+	Args: cobra.ExactArgs(2),
+	RunE: func(cmd *cobra.Command, args []string) error {
+		rows, err := sink.Convert(args[0], args[1], cfg.Parse)
+		if err != nil {
+			return fmt.Errorf("convert failed: %w", err)
+		}
+		logger.Infow("Conversion completed", "in", args[0], "out", args[1], "records", rows)
+		return nil
+	},
+}
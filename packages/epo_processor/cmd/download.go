@@ -1,22 +1,52 @@
 package cmd
 
 import (
+	"bufio"
 	"context"
+	"encoding/json"
+	"errors"
 	"fmt"
 	"os"
-	"os/signal"
-	"syscall"
+	"strings"
+	"text/tabwriter"
 
 	ET "github.com/IBM/fp-go/v2/either"
 	"github.com/spf13/cobra"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/download"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/shutdown"
 )
 
+var downloadFlags struct {
+	dir         string
+	productID   int
+	concurrency int
+	skipExists  bool
+	since       string
+	until       string
+	items       []string
+	plan        bool
+	dryRun      bool
+	json        bool
+	yes         bool
+}
+
 var downloadEpoCmd = &cobra.Command{
-	Use:   "download-epo",
-	Short: "Download EPO files",
+	Use:     "download",
+	Aliases: []string{"download-epo"},
+	Short:   "Download EPO files",
 	RunE: func(cmd *cobra.Command, args []string) error {
-		ctx, cancel := signal.NotifyContext(context.Background(), os.Interrupt, syscall.SIGTERM)
+		ctx, cancel := shutdown.Notify(context.Background(), cfg.Pipeline.ShutdownGrace, logger)
 		defer cancel()
+		watchConfig(ctx, cmd, &liveServices{list: []*internal.Services{services}})
+		if downloadFlags.plan || downloadFlags.dryRun {
+			proceed, err := confirmPlan(ctx)
+			if err != nil || !proceed {
+				return err
+			}
+		}
 		res := services.Downloader.FetchEPOFiles(ctx)()
 		if ET.IsLeft(res) {
 			_, err := ET.UnwrapError(res)
@@ -31,7 +61,7 @@ var downloadHupdCmd = &cobra.Command{
 	Use:   "download-hupd",
 	Short: "Download HUPD files",
 	RunE: func(cmd *cobra.Command, args []string) error {
-		ctx, cancel := signal.NotifyContext(context.Background(), os.Interrupt, syscall.SIGTERM)
+		ctx, cancel := shutdown.Notify(context.Background(), cfg.Pipeline.ShutdownGrace, logger)
 		defer cancel()
 		res := services.Downloader.DownloadHupd(ctx)()
 		if ET.IsLeft(res) {
@@ -42,3 +72,109 @@ var downloadHupdCmd = &cobra.Command{
 		return nil
 	},
 }
+
+// confirmPlan prints the download plan and reports whether to go on with the download:
+// never for --dry-run, at once for --yes, otherwise once the user confirms.
+func confirmPlan(ctx context.Context) (bool, error) {
+	plan, err := ET.UnwrapError(services.Downloader.Plan(ctx)())
+	if err != nil {
+		return false, fmt.Errorf("download plan failed: %w", err)
+	}
+	if downloadFlags.json {
+		data, err := json.MarshalIndent(plan, "", "  ")
+		if err != nil {
+			return false, err
+		}
+		fmt.Println(string(data))
+	} else if err := printPlan(plan); err != nil {
+		return false, err
+	}
+	var pending int
+	for _, it := range plan {
+		if it.Action == download.PlanDownload {
+			pending++
+		}
+	}
+	switch {
+	case downloadFlags.dryRun || pending == 0:
+		return false, nil
+	case downloadFlags.yes:
+		return true, nil
+	}
+	if fi, err := os.Stdin.Stat(); err != nil || fi.Mode()&os.ModeCharDevice == 0 {
+		return false, errors.New("confirming the plan needs a terminal; pass --yes to download without asking")
+	}
+	fmt.Fprintf(os.Stderr, "Download %d items? [y/N] ", pending)
+	answer, _ := bufio.NewReader(os.Stdin).ReadString('\n')
+	switch strings.ToLower(strings.TrimSpace(answer)) {
+	case "y", "yes":
+		return true, nil
+	}
+	logger.Info("Download cancelled")
+	return false, nil
+}
+
+// printPlan writes plan as a table, followed by the item count and size per action.
+func printPlan(plan []download.PlanItem) error {
+	w := tabwriter.NewWriter(os.Stdout, 0, 0, 2, ' ', 0)
+	fmt.Fprintln(w, "DELIVERY\tITEM\tSIZE\tACTION\tREASON")
+	counts := map[string]int{}
+	sizes := map[string]int64{}
+	for _, it := range plan {
+		fmt.Fprintf(w, "%s\t%s\t%s\t%s\t%s\n",
+			it.Delivery, it.Item, download.FormatBytes(it.Size), it.Action, it.Reason)
+		counts[it.Action]++
+		sizes[it.Action] += it.Size
+	}
+	if err := w.Flush(); err != nil {
+		return err
+	}
+	fmt.Printf("\ndownload: %d items, %s\nskip: %d items, %s\n",
+		counts[download.PlanDownload], download.FormatBytes(sizes[download.PlanDownload]),
+		counts[download.PlanSkip], download.FormatBytes(sizes[download.PlanSkip]))
+	return nil
+}
+
+func init() {
+	f := downloadEpoCmd.Flags()
+	f.StringVar(&downloadFlags.dir, "dir", "", "Download directory (overrides download.directory)")
+	f.IntVar(&downloadFlags.productID, "product", 0, "Product ID (overrides server.product_id)")
+	f.IntVar(&downloadFlags.concurrency, "concurrency", 0,
+		"Concurrent downloads (overrides server.concurrent_downloads)")
+	f.BoolVar(&downloadFlags.skipExists, "skip-exists", true,
+		"Skip files that already exist with a valid checksum")
+	f.StringVar(&downloadFlags.since, "since", "",
+		"Only deliveries published on or after this date (YYYY-MM-DD)")
+	f.StringVar(&downloadFlags.until, "until", "",
+		"Only deliveries published on or before this date (YYYY-MM-DD)")
+	f.StringSliceVar(&downloadFlags.items, "items", nil,
+		"Only items whose name matches one of these globs (e.g. EPRTBJV*)")
+	f.BoolVar(&downloadFlags.plan, "plan", false,
+		"List the items to download and to skip, then ask before downloading")
+	f.BoolVar(&downloadFlags.dryRun, "dry-run", false, "List the plan like --plan and exit")
+	f.BoolVar(&downloadFlags.json, "json", false, "Print the plan as JSON instead of a table")
+	f.BoolVar(&downloadFlags.yes, "yes", false, "Download the plan without asking for confirmation")
+	commandOverrides[downloadEpoCmd] = func(c *config.Config, cmd *cobra.Command) {
+		if cmd.Flags().Changed("dir") {
+			c.Download.Directory = downloadFlags.dir
+		}
+		if cmd.Flags().Changed("product") {
+			c.Server.ProductID = downloadFlags.productID
+		}
+		if cmd.Flags().Changed("concurrency") {
+			c.Server.ConcurrentDownloads = downloadFlags.concurrency
+		}
+		if cmd.Flags().Changed("skip-exists") {
+			c.Download.SkipExists = downloadFlags.skipExists
+		}
+		if cmd.Flags().Changed("since") {
+			c.Download.Filter.Since = downloadFlags.since
+		}
+		if cmd.Flags().Changed("until") {
+			c.Download.Filter.Until = downloadFlags.until
+		}
+		if cmd.Flags().Changed("items") {
+			c.Download.Filter.Items = downloadFlags.items
+		}
+	}
+}
@@ -1,15 +1,25 @@
 package main
 
 import (
+	"context"
+	"errors"
 	"fmt"
 	"os"
 
 	"github.com/Qubut/IP-Claim/packages/epo_processor/cmd"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/failures"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/shutdown"
 )
 
 func main() {
 	if err := cmd.RootCmd.Execute(); err != nil {
 		fmt.Fprintf(os.Stderr, "Error: %v\n", err)
+		switch {
+		case errors.Is(err, context.Canceled):
+			os.Exit(shutdown.ExitCode)
+		case failures.IsPartial(err):
+			os.Exit(failures.ExitCode)
+		}
 		os.Exit(1)
 	}
 }
@@ -3,19 +3,24 @@ package cmd
 import (
 	"context"
 	"fmt"
-	"os"
-	"os/signal"
-	"syscall"
 
 	ET "github.com/IBM/fp-go/v2/either"
 	"github.com/spf13/cobra"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/shutdown"
 )
 
+var extractFlags struct {
+	dir         string
+	deleteAfter bool
+}
+
 var extractCmd = &cobra.Command{
 	Use:   "extract",
 	Short: "Extract downloaded files",
 	RunE: func(cmd *cobra.Command, args []string) error {
-		ctx, cancel := signal.NotifyContext(context.Background(), os.Interrupt, syscall.SIGTERM)
+		ctx, cancel := shutdown.Notify(context.Background(), cfg.Pipeline.ShutdownGrace, logger)
 		defer cancel()
 		res := services.Extractor.ExtractAll(ctx, cfg.Download.Directory)()
 		if ET.IsLeft(res) {
@@ -26,3 +31,18 @@ var extractCmd = &cobra.Command{
 		return nil
 	},
 }
+
+func init() {
+	f := extractCmd.Flags()
+	f.StringVar(&extractFlags.dir, "dir", "", "Directory with archives (overrides download.directory)")
+	f.BoolVar(&extractFlags.deleteAfter, "delete-after", false,
+		"Delete archives after extraction (overrides extract.delete_after_extract)")
+	commandOverrides[extractCmd] = func(c *config.Config, cmd *cobra.Command) {
+		if cmd.Flags().Changed("dir") {
+			c.Download.Directory = extractFlags.dir
+		}
+		if cmd.Flags().Changed("delete-after") {
+			c.Extract.DeleteAfterExtract = extractFlags.deleteAfter
+		}
+	}
+}
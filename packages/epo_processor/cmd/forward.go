@@ -0,0 +1,40 @@
+package cmd
+
+import (
+	"fmt"
+
+	"github.com/spf13/cobra"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/sink"
+)
+
+var forwardFlags struct {
+	out      string
+	external bool
+}
+
+var forwardCitationsCmd = &cobra.Command{
+	Use:   "forward-citations <in>...",
+	Short: "Count how often each parsed patent is cited by the others",
+	Long: "Count for each patent_id of the parse outputs how many other parsed patents cite it, " +
+		"and write patent_id,forward_citations as CSV. Pass every output of the corpus: only " +
+		"citations from the given files are counted. Formats are chosen by the file extensions " +
+		"(.parquet, .csv, .jsonl).",
+	Args: cobra.MinimumNArgs(1),
+	RunE: func(cmd *cobra.Command, args []string) error {
+		res, err := sink.ForwardCitations(forwardFlags.out, args, forwardFlags.external)
+		if err != nil {
+			return fmt.Errorf("forward citations failed: %w", err)
+		}
+		logger.Infow("Forward citations counted", "out", forwardFlags.out, "inputs", len(args),
+			"patents", res.Patents, "rows", res.Rows)
+		return nil
+	},
+}
+
+func init() {
+	f := forwardCitationsCmd.Flags()
+	f.StringVar(&forwardFlags.out, "out", "forward_citations.csv", "CSV file to write the counts to")
+	f.BoolVar(&forwardFlags.external, "external", false,
+		"Also list the cited publications that are not in the outputs")
+}
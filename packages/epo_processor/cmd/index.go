@@ -0,0 +1,37 @@
+package cmd
+
+import (
+	"fmt"
+
+	"github.com/spf13/cobra"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/search"
+)
+
+var indexFlags struct {
+	out string
+}
+
+var indexCmd = &cobra.Command{
+	Use:   "index <in>...",
+	Short: "Build a full-text search index over parse outputs",
+	Long: "Build a local full-text index of the patent_id, titles, abstracts and CPC symbols of " +
+		"the parse outputs for the search command. Titles and abstracts are indexed when the " +
+		"outputs were parsed with title and abstract in parse.fields. Formats are chosen by the " +
+		"file extensions (.parquet, .csv, .jsonl). Indexing into an existing index adds the " +
+		"outputs to it; a patent_id indexed again replaces its earlier document.",
+	Args: cobra.MinimumNArgs(1),
+	RunE: func(cmd *cobra.Command, args []string) error {
+		res, err := search.Build(indexFlags.out, args)
+		if err != nil {
+			return fmt.Errorf("index failed: %w", err)
+		}
+		logger.Infow("Search index built", "out", indexFlags.out, "inputs", len(args),
+			"documents", res.Documents, "total", res.Total)
+		return nil
+	},
+}
+
+func init() {
+	indexCmd.Flags().StringVar(&indexFlags.out, "out", "patents.search", "Directory of the search index to create or extend")
+}
@@ -0,0 +1,37 @@
+package cmd
+
+import (
+	"fmt"
+
+	"github.com/spf13/cobra"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+)
+
+var initFlags struct {
+	force bool
+}
+
+var initCmd = &cobra.Command{
+	Use:   "init <dir>",
+	Short: "Create a project directory with " + config.ProjectFile + " and its layout",
+	Long: "Creates " + config.ProjectFile + " with logs, data, output, state and scratch " +
+		"directories below <dir>. Paths in the project file are relative to <dir>, and the " +
+		"project is picked up when epo-processor runs anywhere inside it.",
+	Args: cobra.ExactArgs(1),
+	// a new project needs neither a loaded config nor services
+	PersistentPreRunE: func(cmd *cobra.Command, args []string) error { return nil },
+	RunE: func(cmd *cobra.Command, args []string) error {
+		path, err := config.InitProject(args[0], initFlags.force)
+		if err != nil {
+			return err
+		}
+		fmt.Printf("Created %s\n", path)
+		return nil
+	},
+}
+
+func init() {
+	initCmd.Flags().BoolVar(&initFlags.force, "force", false,
+		"Overwrite an existing "+config.ProjectFile)
+}
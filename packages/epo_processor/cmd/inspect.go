@@ -0,0 +1,69 @@
+package cmd
+
+import (
+	"context"
+	"fmt"
+	"maps"
+	"os"
+	"slices"
+	"strings"
+	"text/tabwriter"
+
+	"github.com/spf13/cobra"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+var inspectFlags struct {
+	json bool
+}
+
+var inspectCmd = &cobra.Command{
+	Use:   "inspect <file.xml>",
+	Short: "Parse one XML file and print the records extracted from it",
+	Args:  cobra.ExactArgs(1),
+	RunE: func(cmd *cobra.Command, args []string) error {
+		in, err := services.Parser.Inspect(context.Background(), args[0])
+		if err != nil {
+			return fmt.Errorf("inspect %s: %w", args[0], err)
+		}
+		if inspectFlags.json {
+			return printJSON(in)
+		}
+		w := tabwriter.NewWriter(os.Stdout, 0, 0, 2, ' ', 0)
+		fmt.Fprintln(w, "PATENT_ID\tSTATUS\tCPC\tIPC\tCITATIONS\tFAMILY\tTITLE")
+		for _, r := range in.Records {
+			fmt.Fprintf(w, "%s\t%s\t%d\t%d\t%d\t%d\t%s\n", r.PatentID, r.Status,
+				len(r.CPCList), len(r.IPCList), len(r.Citations), len(r.FamilyPatents), title(r))
+		}
+		if err := w.Flush(); err != nil {
+			return err
+		}
+		fmt.Printf("\n%s: %d documents, %d classifications, %d citations, %d NPL citations, %d family members\n",
+			in.File, in.Documents, in.Classifications, in.Citations, in.NPLCitations, in.FamilyMembers)
+		var empty []string
+		for _, column := range slices.Sorted(maps.Keys(in.Empty)) {
+			empty = append(empty, fmt.Sprintf("%s=%d/%d", column, in.Empty[column], in.Documents))
+		}
+		if len(empty) > 0 {
+			fmt.Printf("empty: %s\n", strings.Join(empty, " "))
+		}
+		return nil
+	},
+}
+
+// title is the first title of r, shortened to fit a table row.
+func title(r models.PatentRecord) string {
+	if len(r.Titles) == 0 {
+		return ""
+	}
+	t := []rune(r.Titles[0].Text)
+	if len(t) > 60 {
+		return string(t[:57]) + "..."
+	}
+	return string(t)
+}
+
+func init() {
+	inspectCmd.Flags().BoolVar(&inspectFlags.json, "json", false, "Print the records and counts as JSON")
+}
@@ -0,0 +1,90 @@
+package cmd
+
+import (
+	"context"
+	"encoding/json"
+	"fmt"
+	"os"
+	"text/tabwriter"
+
+	ET "github.com/IBM/fp-go/v2/either"
+	"github.com/spf13/cobra"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/download"
+)
+
+var listFlags struct {
+	productID int
+	json      bool
+}
+
+var listProductsCmd = &cobra.Command{
+	Use:   "list-products",
+	Short: "List the products the EPO API offers",
+	RunE: func(cmd *cobra.Command, args []string) error {
+		products, err := ET.UnwrapError(services.Downloader.Products(context.Background())())
+		if err != nil {
+			return fmt.Errorf("list products failed: %w", err)
+		}
+		if listFlags.json {
+			return printJSON(products)
+		}
+		w := tabwriter.NewWriter(os.Stdout, 0, 0, 2, ' ', 0)
+		fmt.Fprintln(w, "ID\tNAME\tDESCRIPTION")
+		for _, p := range products {
+			fmt.Fprintf(w, "%d\t%s\t%s\n", p.ID, p.Name, p.Description)
+		}
+		return w.Flush()
+	},
+}
+
+var listDeliveriesCmd = &cobra.Command{
+	Use:   "list-deliveries",
+	Short: "List the deliveries of a product with their item counts and sizes",
+	RunE: func(cmd *cobra.Command, args []string) error {
+		deliveries, err := ET.UnwrapError(services.Downloader.Deliveries(context.Background())())
+		if err != nil {
+			return fmt.Errorf("list deliveries failed: %w", err)
+		}
+		if listFlags.json {
+			return printJSON(deliveries)
+		}
+		w := tabwriter.NewWriter(os.Stdout, 0, 0, 2, ' ', 0)
+		fmt.Fprintln(w, "ID\tDELIVERY\tPUBLISHED\tITEMS\tSIZE")
+		var items int
+		var size int64
+		for _, d := range deliveries {
+			fmt.Fprintf(w, "%d\t%s\t%s\t%d\t%s\n",
+				d.ID, d.Name, d.Published, d.Items, download.FormatBytes(d.Size))
+			items += d.Items
+			size += d.Size
+		}
+		if err := w.Flush(); err != nil {
+			return err
+		}
+		fmt.Printf("\n%d deliveries, %d items, %s\n", len(deliveries), items, download.FormatBytes(size))
+		return nil
+	},
+}
+
+func printJSON(v any) error {
+	data, err := json.MarshalIndent(v, "", "  ")
+	if err != nil {
+		return err
+	}
+	fmt.Println(string(data))
+	return nil
+}
+
+func init() {
+	listProductsCmd.Flags().BoolVar(&listFlags.json, "json", false, "Print the products as JSON")
+	f := listDeliveriesCmd.Flags()
+	f.IntVar(&listFlags.productID, "product", 0, "Product ID (overrides server.product_id)")
+	f.BoolVar(&listFlags.json, "json", false, "Print the deliveries as JSON")
+	commandOverrides[listDeliveriesCmd] = func(c *config.Config, cmd *cobra.Command) {
+		if cmd.Flags().Changed("product") {
+			c.Server.ProductID = listFlags.productID
+		}
+	}
+}
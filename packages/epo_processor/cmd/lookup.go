@@ -0,0 +1,47 @@
+package cmd
+
+import (
+	"fmt"
+	"os"
+
+	"github.com/spf13/cobra"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/parse"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/snapshot"
+)
+
+var lookupFlags struct {
+	index string
+}
+
+var lookupCmd = &cobra.Command{
+	Use:   "lookup <patent_id>",
+	Short: "Print the original exchange-document XML of a record via the parse index",
+	Args:  cobra.ExactArgs(1),
+	RunE: func(cmd *cobra.Command, args []string) error {
+		if cfg.Parse.IndexPath == "" {
+			return fmt.Errorf("no record index configured (set parse.index_path or --index)")
+		}
+		indexPath := snapshot.Expand(
+			cfg.Parse.IndexPath,
+			snapshot.Resolve(cfg.Snapshot, services.Manifest),
+		)
+		raw, err := parse.LookupRecord(indexPath, args[0])
+		if err != nil {
+			return fmt.Errorf("lookup failed: %w", err)
+		}
+		_, err = os.Stdout.Write(append(raw, '\n'))
+		return err
+	},
+}
+
+func init() {
+	lookupCmd.Flags().
+		StringVar(&lookupFlags.index, "index", "", "Record index file (overrides parse.index_path)")
+	commandOverrides[lookupCmd] = func(c *config.Config, cmd *cobra.Command) {
+		if cmd.Flags().Changed("index") {
+			c.Parse.IndexPath = lookupFlags.index
+		}
+	}
+}
@@ -0,0 +1,27 @@
+package cmd
+
+import (
+	"fmt"
+
+	"github.com/spf13/cobra"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/sink"
+)
+
+var mergeCmd = &cobra.Command{
+	Use:   "merge <out> <in>...",
+	Short: "Union parse outputs, keeping the latest record of each patent_id",
+	Long: "Union parse outputs into one file, deduplicating on patent_id. List the inputs oldest " +
+		"first (e.g. the back-file baseline, then the weekly front-file outputs): the record of " +
+		"the latest input wins. Formats are chosen by the file extensions (.parquet, .csv, .jsonl).",
+	Args: cobra.MinimumNArgs(2),
+	RunE: func(cmd *cobra.Command, args []string) error {
+		res, err := sink.Merge(args[0], args[1:], cfg.Parse)
+		if err != nil {
+			return fmt.Errorf("merge failed: %w", err)
+		}
+		logger.Infow("Merge completed", "out", args[0], "inputs", len(args)-1,
+			"records", res.Records, "duplicates", res.Duplicates)
+		return nil
+	},
+}
@@ -3,22 +3,29 @@ package cmd
 import (
 	"context"
 	"fmt"
-	"os"
-	"os/signal"
-	"syscall"
 
 	"github.com/spf13/cobra"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/shutdown"
 )
 
+var parseFlags struct {
+	input   string
+	output  string
+	format  string
+	workers int
+}
+
 var parseCmd = &cobra.Command{
 	Use:   "parse",
-	Short: "Parse extracted files to CSV",
+	Short: "Parse extracted files to the configured output format",
 	RunE: func(cmd *cobra.Command, args []string) error {
-		ctx, cancel := signal.NotifyContext(context.Background(), os.Interrupt, syscall.SIGTERM)
+		ctx, cancel := shutdown.Notify(context.Background(), cfg.Pipeline.ShutdownGrace, logger)
 		defer cancel()
-		err := services.Parser.ParseAllToParquet(
+		err := services.Parser.ParseAll(
 			ctx,
-			cfg.Download.Directory,
+			cfg.ParseInputDir(),
 			cfg.Parse.OutputCSV,
 			int64(cfg.Parse.Workers),
 		)
@@ -29,3 +36,25 @@ var parseCmd = &cobra.Command{
 		return nil
 	},
 }
+
+func init() {
+	f := parseCmd.Flags()
+	f.StringVar(&parseFlags.input, "input", "", "Directory with extracted XML (overrides parse.input_dir)")
+	f.StringVar(&parseFlags.output, "output", "", "Output file (overrides parse.output_csv)")
+	f.StringVar(&parseFlags.format, "format", "", "Output format: parquet|csv|jsonl|tables|postgres|duckdb|neo4j|arrow|clickhouse|elasticsearch (overrides parse.output_format)")
+	f.IntVar(&parseFlags.workers, "workers", 0, "Parse workers (overrides parse.workers)")
+	commandOverrides[parseCmd] = func(c *config.Config, cmd *cobra.Command) {
+		if cmd.Flags().Changed("input") {
+			c.Parse.InputDir = parseFlags.input
+		}
+		if cmd.Flags().Changed("output") {
+			c.Parse.OutputCSV = parseFlags.output
+		}
+		if cmd.Flags().Changed("format") {
+			c.Parse.OutputFormat = parseFlags.format
+		}
+		if cmd.Flags().Changed("workers") {
+			c.Parse.Workers = parseFlags.workers
+		}
+	}
+}
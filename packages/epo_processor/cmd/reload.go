@@ -0,0 +1,60 @@
+package cmd
+
+import (
+	"context"
+	"sync"
+
+	"github.com/spf13/cobra"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/telemetry"
+)
+
+// liveServices are the services a config reload is applied to: those of the command, or
+// of every product a multi-product run has started.
+type liveServices struct {
+	mu   sync.Mutex
+	list []*internal.Services
+}
+
+func (l *liveServices) add(s *internal.Services) {
+	l.mu.Lock()
+	defer l.mu.Unlock()
+	l.list = append(l.list, s)
+}
+
+// watchConfig applies the reloadable settings of config.Watch to the running command
+// until ctx is done. The config is loaded the way PersistentPreRunE loads it, so flags
+// and command overrides keep their effect on a reload.
+func watchConfig(ctx context.Context, cmd *cobra.Command, live *liveServices) {
+	load := func() (config.Config, error) {
+		c, err := config.Load(cfgFile, template, cmd.Flags())
+		if err != nil {
+			return config.Config{}, err
+		}
+		if apply, ok := commandOverrides[cmd]; ok {
+			apply(&c, cmd)
+		}
+		if cmd.Flags().Changed("incremental") {
+			c.State.Incremental = incremental
+		}
+		if cmd == RootCmd {
+			if err := config.SelectStages(&c, onlyStages, skipStages); err != nil {
+				return config.Config{}, err
+			}
+		}
+		return c, nil
+	}
+	apply := func(next config.Config) {
+		if err := telemetry.SetLogLevel(next.Log.LogLevel); err != nil {
+			logger.Warnw("Failed to set log level", "level", next.Log.LogLevel, "err", err)
+		}
+		live.mu.Lock()
+		defer live.mu.Unlock()
+		for _, s := range live.list {
+			s.Reload(next)
+		}
+	}
+	go config.Watch(ctx, cfg, load, apply, logger)
+}
@@ -3,48 +3,65 @@ package cmd
 import (
 	"context"
 	"encoding/json"
+	"errors"
 	"fmt"
 	"os"
-	"os/signal"
 	"path/filepath"
 	"strings"
-	"syscall"
 	"time"
 
-	ET "github.com/IBM/fp-go/v2/either"
-	"github.com/IBM/fp-go/v2/function"
 	"github.com/spf13/cobra"
-	"github.com/spf13/viper"
 	"go.opentelemetry.io/otel/metric"
 	"go.opentelemetry.io/otel/trace"
 	"go.uber.org/zap"
 
 	"github.com/Qubut/IP-Claim/packages/epo_processor/internal"
 	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/notify"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/pipeline"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/shutdown"
 	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/telemetry"
-	T "github.com/Qubut/IP-Claim/packages/epo_processor/internal/typing"
 )
 
 var (
-	cfgFile  string
-	cfg      config.Config
-	logger   *zap.SugaredLogger
-	tracer   trace.Tracer
-	meter    metric.Meter
-	shutdown func(context.Context) error
-	services *internal.Services
-	Version  = "dev" // Set at build time: go build -ldflags "-X github.com/Qubut/IP-Claim/packages/epo_processor/cmd.Version=v1.0.0"
+	cfgFile     string
+	template    string
+	incremental bool
+	onlyStages  []string
+	skipStages  []string
+	cfg         config.Config
+	logger      *zap.SugaredLogger
+	tracer      trace.Tracer
+	meter       metric.Meter
+	flushOTEL   func(context.Context) error
+	services    *internal.Services
+	Version     = "dev" // Set at build time: go build -ldflags "-X github.com/Qubut/IP-Claim/packages/epo_processor/cmd.Version=v1.0.0"
 )
 
+// commandOverrides maps a subcommand to the function that applies its local flags on top of
+// the loaded config. It runs before services are built so every stage sees the overrides.
+var commandOverrides = map[*cobra.Command]func(c *config.Config, cmd *cobra.Command){}
+
 var RootCmd = &cobra.Command{
 	Use:   "epo-processor",
 	Short: "EPO Patent Processor CLI",
 	PersistentPreRunE: func(cmd *cobra.Command, args []string) error {
 		var err error
-		cfg, err = config.Load(cfgFile)
+		cfg, err = config.Load(cfgFile, template, cmd.Flags())
 		if err != nil {
 			return fmt.Errorf("load config: %w", err)
 		}
+		if apply, ok := commandOverrides[cmd]; ok {
+			apply(&cfg, cmd)
+		}
+		if cmd.Flags().Changed("incremental") {
+			cfg.State.Incremental = incremental
+		}
+		if cmd == RootCmd {
+			if err := config.SelectStages(&cfg, onlyStages, skipStages); err != nil {
+				return err
+			}
+		}
 		logDir := cfg.Log.LogDir
 		if err := os.MkdirAll(logDir, 0o755); err != nil {
 			return fmt.Errorf("create log directory: %w", err)
@@ -54,6 +71,7 @@ var RootCmd = &cobra.Command{
 			fmt.Sprintf("epo-processor[%s].log", time.Now().Format("20060102-150405")))
 
 		teleCfg := telemetry.Config{
+			Enabled:     cfg.Telemetry.Enabled,
 			ServiceName: cfg.Telemetry.ServiceName,
 			Exporter:    cfg.Telemetry.Exporter,
 			Endpoint:    cfg.Telemetry.Endpoint,
@@ -63,7 +81,7 @@ var RootCmd = &cobra.Command{
 			LogFile:     logFile,
 			LogLevel:    cfg.Log.LogLevel,
 		}
-		tracer, meter, logger, shutdown, err = telemetry.InitOTEL(teleCfg)
+		tracer, meter, logger, flushOTEL, err = telemetry.InitOTEL(teleCfg)
 		if err != nil {
 			return fmt.Errorf("init telemetry: %w", err)
 		}
@@ -74,8 +92,13 @@ var RootCmd = &cobra.Command{
 		return nil
 	},
 	PersistentPostRunE: func(cmd *cobra.Command, args []string) error {
-		if shutdown != nil {
-			if err := shutdown(context.Background()); err != nil {
+		if services != nil {
+			if err := services.Close(); err != nil {
+				logger.Warnw("Failed to clean up", "err", err)
+			}
+		}
+		if flushOTEL != nil {
+			if err := flushOTEL(context.Background()); err != nil {
 				logger.Errorw("shutdown error", "err", err)
 				return err
 			}
@@ -83,45 +106,65 @@ var RootCmd = &cobra.Command{
 		return nil
 	},
 	RunE: func(cmd *cobra.Command, args []string) error {
-		ctx, cancel := signal.NotifyContext(context.Background(), os.Interrupt, syscall.SIGTERM)
+		ctx, cancel := shutdown.Notify(context.Background(), cfg.Pipeline.ShutdownGrace, logger)
 		defer cancel()
+		live := &liveServices{}
+		watchConfig(ctx, cmd, live)
+		notifier := notify.New(cfg.Notify, logger)
+		started := time.Now()
 
-		if cfg.Download.Enabled {
-			res := services.Downloader.FetchEPOFiles(ctx)()
-			err := function.Pipe1(
-				res,
-				ET.Fold(
-					func(e error) error { return fmt.Errorf("download: %w", e) },
-					func(_ []int64) error { return nil },
-				),
-			)
-			if err != nil {
-				return err
+		if len(cfg.Products) > 0 {
+			summaries, err := pipeline.RunProducts(ctx, cfg,
+				func(c config.Config) (*internal.Services, error) {
+					s, err := internal.InitServices(c, tracer, logger, meter)
+					if err == nil {
+						live.add(s)
+					}
+					return s, err
+				})
+			for _, s := range summaries {
+				logger.Infow("Product finished", "product", s.Product, "snapshot", s.Snapshot, "err", s.Error)
+				logStages(s.Stages)
+				notifier.Send(notify.NewEvent(s.Product, started, s.Summary, s.Err()))
 			}
+			return err
 		}
-		if cfg.Extract.Enabled {
-			res := services.Extractor.ExtractAll(ctx, cfg.Download.Directory)()
-			err := function.Pipe1(
-				res,
-				ET.Fold(
-					func(e error) error { return fmt.Errorf("extract: %w", e) },
-					func(_ T.Unit) error { return nil },
-				),
-			)
-			if err != nil {
-				return err
-			}
+		for _, warning := range pipeline.Preflight(cfg) {
+			logger.Warnw("Stage input missing", "warning", warning)
 		}
-		if cfg.Parse.Enabled {
-			if err := services.Parser.ParseAllToParquet(ctx, cfg.Download.Directory, cfg.Parse.OutputCSV, int64(cfg.Parse.Workers)); err != nil {
-				return fmt.Errorf("parse: %w", err)
-			}
+		live.add(services)
+		summary, err := pipeline.Run(ctx, cfg, services, nil)
+		logStages(summary.Stages)
+		notifier.Send(notify.NewEvent("", started, summary, err))
+		if errors.Is(err, shutdown.ErrInterrupted) && services.Manifest != nil {
+			logger.Warnw("Run interrupted; run again to continue where it stopped", "manifest", cfg.State.Path)
 		}
-		logger.Info("All steps completed")
+		if err != nil {
+			return err
+		}
+		if c := summary.Citations; c != nil {
+			logger.Infow("Citation statistics",
+				"total", c.Total, "by_office", c.ByOffice, "by_phase", c.ByPhase)
+		}
+		if services.Explain != nil {
+			logger.Infow("Skip report written", "path", cfg.Log.Explain, "skipped", services.Explain.Counts())
+		}
+		logger.Infow("All steps completed", "snapshot", summary.Snapshot)
 		return nil
 	},
 }
 
+// logStages logs how each stage of a run ended, and every item that failed in it.
+func logStages(stages []pipeline.StageResult) {
+	for _, s := range stages {
+		logger.Infow("Stage finished", "stage", s.Stage, "status", s.Status,
+			"succeeded", s.Items, "failed", s.Failed, "duration", s.Duration)
+		for _, f := range s.Failures {
+			logger.Warnw("Item failed", "stage", s.Stage, "item", f.Item, "reason", f.Reason)
+		}
+	}
+}
+
 var versionCmd = &cobra.Command{
 	Use:   "version",
 	Short: "Print the version of epo-processor",
@@ -150,44 +193,20 @@ var printConfigCmd = &cobra.Command{
 
 func init() {
 	RootCmd.PersistentFlags().
-		StringVar(&cfgFile, "config", "", "Path to config file (yaml/json/toml)")
+		StringVar(&cfgFile, "config", "",
+			"Path to config file (yaml/json/toml); defaults to the nearest "+config.ProjectFile)
+	RootCmd.PersistentFlags().StringVar(&template, "template", "",
+		"Built-in job template ("+strings.Join(config.Templates(), ", ")+")")
+	RootCmd.PersistentFlags().BoolVar(&incremental, "incremental", false,
+		"Only process deliveries published after the last run (needs state.enabled)")
 
-	// Flag map to avoid repetition
-	type flagDef struct {
-		name, def, usage string
-	}
-	flags := []flagDef{
-		{"log-level", "info", "Log level (debug/info/warn/error)"},
-		{"telemetry.enabled", "true", "Enable OpenTelemetry"},
-		{"telemetry.exporter", "otlp", "Telemetry exporter (otlp|stdout|none)"},
-		{"telemetry.endpoint", "localhost:4317", "OTLP endpoint (host:port)"},
-		{"telemetry.protocol", "grpc", "OTLP protocol (grpc|http)"},
-		{"telemetry.insecure", "true", "Allow insecure OTLP connection"},
-		{"telemetry.service-name", "epo-processor", "Service name for telemetry"},
-		{"server.base-url", "", "Server base URL"},
-		{"server.timeout", "30s", "Request timeout (duration)"},
-		{"server.max-retries", "3", "Max retries"},
-		{"server.concurrent-downloads", "5", "Concurrent downloads"},
-		{"server.product-id", "0", "Product ID"},
-		{"download.directory", "./downloads", "Download directory"},
-		{"download.skip-exists", "true", "Skip existing files"},
-		{"download.verify-sha1", "false", "Verify SHA1"},
-		{"download.enabled", "true", "Enable download"},
-		{"download.hupd.url", "", "HUPD URL"},
-		{"download.hupd.filename", "", "HUPD filename"},
-		{"extract.enabled", "true", "Enable extract"},
-		{"extract.delete-after-extract", "false", "Delete after extract"},
-		{"parse.enabled", "true", "Enable parse"},
-		{"parse.output-csv", "./output.csv", "Output CSV path"},
-		{"parse.workers", "10", "Parse workers"},
-	}
-	for _, f := range flags {
-		RootCmd.PersistentFlags().String(f.name, f.def, f.usage)
-		viper.BindPFlag(
-			strings.ReplaceAll(f.name, "-", "_"),
-			RootCmd.PersistentFlags().Lookup(f.name),
-		)
-	}
+	config.RegisterFlags(RootCmd.PersistentFlags())
+
+	stages := strings.Join(config.Stages, ", ")
+	RootCmd.Flags().StringSliceVar(&onlyStages, "only", nil,
+		"Run only these stages of a full run ("+stages+"), overriding <stage>.enabled")
+	RootCmd.Flags().StringSliceVar(&skipStages, "skip", nil,
+		"Skip these stages of a full run ("+stages+"), overriding <stage>.enabled")
 
 	configCmd.AddCommand(printConfigCmd)
 
@@ -195,6 +214,21 @@ func init() {
 	RootCmd.AddCommand(downloadHupdCmd)
 	RootCmd.AddCommand(extractCmd)
 	RootCmd.AddCommand(parseCmd)
+	RootCmd.AddCommand(verifyCmd)
+	RootCmd.AddCommand(serveGRPCCmd)
+	RootCmd.AddCommand(lookupCmd)
+	RootCmd.AddCommand(inspectCmd)
+	RootCmd.AddCommand(convertCmd)
+	RootCmd.AddCommand(mergeCmd)
+	RootCmd.AddCommand(forwardCitationsCmd)
+	RootCmd.AddCommand(indexCmd)
+	RootCmd.AddCommand(searchCmd)
+	RootCmd.AddCommand(statusCmd)
+	RootCmd.AddCommand(listProductsCmd)
+	RootCmd.AddCommand(listDeliveriesCmd)
+	RootCmd.AddCommand(watchCmd)
+	RootCmd.AddCommand(auditCmd)
 	RootCmd.AddCommand(versionCmd)
+	RootCmd.AddCommand(initCmd)
 	RootCmd.AddCommand(configCmd)
 }
@@ -0,0 +1,48 @@
+package cmd
+
+import (
+	"bufio"
+	"fmt"
+	"os"
+
+	"github.com/spf13/cobra"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/search"
+)
+
+var searchFlags struct {
+	index string
+	limit int
+}
+
+var searchCmd = &cobra.Command{
+	Use:   "search <query>",
+	Short: "Query a search index built by the index command",
+	Long: "Print the patents matching the query as patent_id, score and title, the best first. " +
+		"Terms combine with AND, OR, NOT and parentheses, and are ANDed without an operator; " +
+		"field:word limits a term to id, title, abstract or cpc. A bare CPC code such as Y02E " +
+		"also matches every symbol below it.",
+	Args: cobra.ExactArgs(1),
+	RunE: func(cmd *cobra.Command, args []string) error {
+		idx, err := search.Open(searchFlags.index)
+		if err != nil {
+			return fmt.Errorf("search failed: %w", err)
+		}
+		defer idx.Close()
+		hits, err := idx.Search(args[0], searchFlags.limit)
+		if err != nil {
+			return fmt.Errorf("search failed: %w", err)
+		}
+		w := bufio.NewWriter(os.Stdout)
+		for _, hit := range hits {
+			fmt.Fprintf(w, "%s\t%.3f\t%s\n", hit.PatentID, hit.Score, hit.Title)
+		}
+		return w.Flush()
+	},
+}
+
+func init() {
+	f := searchCmd.Flags()
+	f.StringVar(&searchFlags.index, "index", "patents.search", "Search index written by the index command")
+	f.IntVar(&searchFlags.limit, "limit", 20, "Maximum number of results; 0 for all")
+}
@@ -0,0 +1,51 @@
+package cmd
+
+import (
+	"context"
+	"fmt"
+	"os"
+	"os/signal"
+	"syscall"
+
+	"github.com/spf13/cobra"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/server"
+)
+
+var serveGRPCFlags struct {
+	address string
+}
+
+var serveGRPCCmd = &cobra.Command{
+	Use:   "serve-grpc",
+	Short: "Expose the pipeline as a gRPC service (JSON codec) for remote orchestration",
+	RunE: func(cmd *cobra.Command, args []string) error {
+		ctx, cancel := signal.NotifyContext(context.Background(), os.Interrupt, syscall.SIGTERM)
+		defer cancel()
+		srv := server.NewPipelineServer(cfg, func(c config.Config) (*internal.Services, error) {
+			return internal.InitServices(c, tracer, logger, meter)
+		}, logger)
+		logger.Infow("Starting gRPC server", "address", cfg.Serve.GRPCAddress, "tls", cfg.Serve.TLS.CertFile != "")
+		if len(cfg.Serve.APIKeys) > 0 && cfg.Serve.TLS.CertFile == "" && !server.IsLoopback(cfg.Serve.GRPCAddress) {
+			logger.Warnw("API keys are sent in plaintext; set serve.tls to serve over TLS",
+				"address", cfg.Serve.GRPCAddress)
+		}
+		fmt.Printf("gRPC server listening on %s\n", cfg.Serve.GRPCAddress)
+		if err := server.ServeGRPC(ctx, cfg.Serve, srv); err != nil {
+			return fmt.Errorf("serve grpc: %w", err)
+		}
+		return nil
+	},
+}
+
+func init() {
+	serveGRPCCmd.Flags().
+		StringVar(&serveGRPCFlags.address, "address", "", "Listen address (overrides serve.grpc_address)")
+	commandOverrides[serveGRPCCmd] = func(c *config.Config, cmd *cobra.Command) {
+		if cmd.Flags().Changed("address") {
+			c.Serve.GRPCAddress = serveGRPCFlags.address
+		}
+	}
+}
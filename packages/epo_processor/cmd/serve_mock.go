@@ -0,0 +1,69 @@
+//go:build mock
+
+package cmd
+
+import (
+	"context"
+	"fmt"
+	"os"
+	"os/signal"
+	"syscall"
+	"time"
+
+	"github.com/spf13/cobra"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/mockserver"
+)
+
+var serveMockFlags struct {
+	address          string
+	deliveries       int
+	itemsPerDelivery int
+	docsPerItem      int
+	firstDelivery    string
+	latency          time.Duration
+	pageSize         int
+}
+
+var serveMockCmd = &cobra.Command{
+	Use:   "serve-mock",
+	Short: "Serve a synthetic EPO publication API for integration tests (mock builds only)",
+	RunE: func(cmd *cobra.Command, args []string) error {
+		ctx, cancel := signal.NotifyContext(context.Background(), os.Interrupt, syscall.SIGTERM)
+		defer cancel()
+		first, err := time.Parse("2006-01-02", serveMockFlags.firstDelivery)
+		if err != nil {
+			return fmt.Errorf("invalid --first-delivery: %w", err)
+		}
+		srv, err := mockserver.New(mockserver.Options{
+			Address:          serveMockFlags.address,
+			ProductID:        uint32(cfg.Server.ProductID),
+			Deliveries:       serveMockFlags.deliveries,
+			ItemsPerDelivery: serveMockFlags.itemsPerDelivery,
+			DocsPerItem:      serveMockFlags.docsPerItem,
+			FirstDelivery:    first,
+			Latency:          serveMockFlags.latency,
+			PageSize:         serveMockFlags.pageSize,
+		}, logger)
+		if err != nil {
+			return fmt.Errorf("build mock product: %w", err)
+		}
+		fmt.Printf("Mock EPO API listening on %s (server.base_url: http://%s, product %d)\n",
+			serveMockFlags.address, serveMockFlags.address, cfg.Server.ProductID)
+		return srv.ListenAndServe(ctx)
+	},
+}
+
+func init() {
+	f := serveMockCmd.Flags()
+	f.StringVar(&serveMockFlags.address, "address", "127.0.0.1:8089", "Listen address")
+	f.IntVar(&serveMockFlags.deliveries, "deliveries", 3, "Weekly deliveries in the product")
+	f.IntVar(&serveMockFlags.itemsPerDelivery, "items", 2, "Items (zip archives) per delivery")
+	f.IntVar(&serveMockFlags.docsPerItem, "docs", 50, "Exchange documents per item")
+	f.StringVar(&serveMockFlags.firstDelivery, "first-delivery", "2024-01-04",
+		"Publication date of the first delivery (YYYY-MM-DD)")
+	f.DurationVar(&serveMockFlags.latency, "latency", 0, "Delay added to every response")
+	f.IntVar(&serveMockFlags.pageSize, "page-size", 0,
+		"Deliveries per page of the product metadata (0 disables pagination)")
+	RootCmd.AddCommand(serveMockCmd)
+}
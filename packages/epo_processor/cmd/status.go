@@ -0,0 +1,78 @@
+package cmd
+
+import (
+	"encoding/json"
+	"fmt"
+	"os"
+	"sort"
+	"strings"
+	"text/tabwriter"
+	"time"
+
+	"github.com/spf13/cobra"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/state"
+)
+
+var statusFlags struct {
+	json bool
+}
+
+var statusCmd = &cobra.Command{
+	Use:   "status",
+	Short: "Print a summary of the pipeline manifest",
+	RunE: func(cmd *cobra.Command, args []string) error {
+		if services.Manifest == nil {
+			return fmt.Errorf("state tracking is disabled (set state.enabled)")
+		}
+		summary := services.Manifest.Summary()
+		if statusFlags.json {
+			data, err := json.MarshalIndent(summary, "", "  ")
+			if err != nil {
+				return err
+			}
+			fmt.Println(string(data))
+			return nil
+		}
+		fmt.Printf("Manifest: %s (%d items)\n", cfg.State.Path, summary.Items)
+		if summary.Snapshot != "" {
+			fmt.Printf("Snapshot: %s\n", summary.Snapshot)
+		}
+		if summary.LastDelivery != nil {
+			fmt.Printf("Last delivery: %s\n", summary.LastDelivery.Format(time.RFC3339))
+		}
+		fmt.Println()
+		w := tabwriter.NewWriter(os.Stdout, 0, 0, 2, ' ', 0)
+		fmt.Fprintln(w, "STEP\tCOUNTS")
+		for _, row := range []struct {
+			name   string
+			counts state.StepCounts
+		}{
+			{"download", summary.Download},
+			{"checksum", summary.Checksum},
+			{"extract", summary.Extract},
+			{"parse", summary.Parse},
+			{"xml files", summary.Files},
+		} {
+			fmt.Fprintf(w, "%s\t%s\n", row.name, formatCounts(row.counts))
+		}
+		return w.Flush()
+	},
+}
+
+func formatCounts(counts state.StepCounts) string {
+	parts := make([]string, 0, len(counts))
+	for st, n := range counts {
+		name := string(st)
+		if st == state.StatusPending {
+			name = "pending"
+		}
+		parts = append(parts, fmt.Sprintf("%s=%d", name, n))
+	}
+	sort.Strings(parts)
+	return strings.Join(parts, " ")
+}
+
+func init() {
+	statusCmd.Flags().BoolVar(&statusFlags.json, "json", false, "Print the summary as JSON")
+}
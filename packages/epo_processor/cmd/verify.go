@@ -0,0 +1,146 @@
+package cmd
+
+import (
+	"context"
+	"encoding/csv"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"os"
+	"os/signal"
+	"path/filepath"
+	"strconv"
+	"strings"
+	"syscall"
+
+	ET "github.com/IBM/fp-go/v2/either"
+	"github.com/spf13/cobra"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/download"
+)
+
+var verifyFlags struct {
+	dir    string
+	json   bool
+	report string
+	repair bool
+}
+
+// verifyEntry is one row of the verify report.
+type verifyEntry struct {
+	Item     string                `json:"item"`
+	Path     string                `json:"path"`
+	Status   download.VerifyStatus `json:"status"`
+	Checksum string                `json:"expected_sha1"`
+	Error    string                `json:"error,omitempty"`
+	Repaired bool                  `json:"repaired,omitempty"`
+}
+
+var verifyCmd = &cobra.Command{
+	Use:   "verify",
+	Short: "Verify SHA-1 checksums of downloaded files against the product metadata",
+	RunE: func(cmd *cobra.Command, args []string) error {
+		ctx, cancel := signal.NotifyContext(context.Background(), os.Interrupt, syscall.SIGTERM)
+		defer cancel()
+		run := services.Downloader.VerifyEPOFiles
+		if verifyFlags.repair {
+			run = services.Downloader.RepairEPOFiles
+		}
+		results, err := ET.UnwrapError(run(ctx)())
+		if err != nil {
+			return fmt.Errorf("verify failed: %w", err)
+		}
+		entries := make([]verifyEntry, 0, len(results))
+		counts := map[download.VerifyStatus]int{}
+		repaired := 0
+		for _, r := range results {
+			counts[r.Status]++
+			if r.Repaired {
+				repaired++
+			}
+			e := verifyEntry{
+				Item:     r.Filename,
+				Path:     r.Path,
+				Status:   r.Status,
+				Checksum: r.Checksum,
+				Repaired: r.Repaired,
+			}
+			if r.Err != nil {
+				e.Error = r.Err.Error()
+			}
+			entries = append(entries, e)
+		}
+		if verifyFlags.report != "" {
+			if err := writeVerifyReport(verifyFlags.report, entries); err != nil {
+				return fmt.Errorf("write verify report: %w", err)
+			}
+		}
+		if verifyFlags.json {
+			data, err := json.MarshalIndent(entries, "", "  ")
+			if err != nil {
+				return err
+			}
+			fmt.Println(string(data))
+		} else {
+			for _, e := range entries {
+				switch {
+				case e.Repaired && e.Status == download.VerifyOK:
+					fmt.Printf("%-8s %s\n", "repaired", e.Path)
+				case e.Status != download.VerifyOK:
+					fmt.Printf("%-8s %s\n", e.Status, e.Path)
+				}
+			}
+			fmt.Printf("ok: %d, corrupt: %d, missing: %d\n",
+				counts[download.VerifyOK], counts[download.VerifyCorrupt], counts[download.VerifyMissing])
+			if verifyFlags.repair {
+				fmt.Printf("repaired: %d\n", repaired)
+			}
+		}
+		logger.Infow("Verify completed",
+			"ok", counts[download.VerifyOK],
+			"corrupt", counts[download.VerifyCorrupt],
+			"missing", counts[download.VerifyMissing],
+			"repaired", repaired)
+		if counts[download.VerifyOK] != len(results) {
+			return fmt.Errorf("%d of %d files failed verification",
+				len(results)-counts[download.VerifyOK], len(results))
+		}
+		return nil
+	},
+}
+
+// writeVerifyReport exports every checked item to path, as CSV when it ends in .csv
+// and as JSON otherwise.
+func writeVerifyReport(path string, entries []verifyEntry) error {
+	f, err := os.Create(path)
+	if err != nil {
+		return err
+	}
+	if !strings.EqualFold(filepath.Ext(path), ".csv") {
+		enc := json.NewEncoder(f)
+		enc.SetIndent("", "  ")
+		return errors.Join(enc.Encode(entries), f.Close())
+	}
+	w := csv.NewWriter(f)
+	_ = w.Write([]string{"item", "path", "status", "expected_sha1", "error", "repaired"})
+	for _, e := range entries {
+		_ = w.Write([]string{e.Item, e.Path, string(e.Status), e.Checksum, e.Error, strconv.FormatBool(e.Repaired)})
+	}
+	w.Flush()
+	return errors.Join(w.Error(), f.Close())
+}
+
+func init() {
+	f := verifyCmd.Flags()
+	f.StringVar(&verifyFlags.dir, "dir", "", "Download directory (overrides download.directory)")
+	f.BoolVar(&verifyFlags.json, "json", false, "Print every checked item as JSON")
+	f.StringVar(&verifyFlags.report, "report", "", "Export the report to a file (.csv, otherwise JSON)")
+	f.BoolVar(&verifyFlags.repair, "repair", false,
+		"Delete corrupt files and download corrupt and missing items again")
+	commandOverrides[verifyCmd] = func(c *config.Config, cmd *cobra.Command) {
+		if cmd.Flags().Changed("dir") {
+			c.Download.Directory = verifyFlags.dir
+		}
+	}
+}
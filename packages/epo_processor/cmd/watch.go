@@ -0,0 +1,99 @@
+package cmd
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"strings"
+	"time"
+
+	ET "github.com/IBM/fp-go/v2/either"
+	"github.com/spf13/cobra"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/notify"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/pipeline"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/shutdown"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/snapshot"
+)
+
+var watchFlags struct {
+	interval time.Duration
+}
+
+var watchCmd = &cobra.Command{
+	Use:   "watch",
+	Short: "Keep running and process new deliveries as they are published",
+	Long: "Polls the product metadata every --interval and, when deliveries newer than the " +
+		"last run are published, runs download, extract and parse incrementally for them. " +
+		"Needs state.enabled. Ctrl-C stops it; a run in progress drains first.",
+	RunE: func(cmd *cobra.Command, args []string) error {
+		if services.Manifest == nil {
+			return fmt.Errorf("watch needs state.enabled to remember the last delivery")
+		}
+		if len(cfg.Products) > 0 {
+			return fmt.Errorf("watch does not support products; start one watch per product")
+		}
+		if watchFlags.interval <= 0 {
+			return fmt.Errorf("--interval must be positive")
+		}
+		if cfg.Parse.Enabled && !strings.Contains(cfg.Parse.OutputCSV, snapshot.Placeholder) {
+			logger.Warnw("Output has no "+snapshot.Placeholder+" placeholder; each run replaces it",
+				"output", cfg.Parse.OutputCSV)
+		}
+		ctx, cancel := shutdown.Notify(context.Background(), cfg.Pipeline.ShutdownGrace, logger)
+		defer cancel()
+		watchConfig(ctx, cmd, &liveServices{list: []*internal.Services{services}})
+
+		notifier := notify.New(cfg.Notify, logger)
+		logger.Infow("Watching for new deliveries", "interval", watchFlags.interval)
+		for {
+			err := pollOnce(ctx, notifier)
+			if errors.Is(err, shutdown.ErrInterrupted) || ctx.Err() != nil {
+				return err
+			}
+			if err != nil {
+				logger.Errorw("Run failed; retrying at the next poll", "err", err)
+			}
+			next := time.Now().Add(watchFlags.interval)
+			logger.Infow("Next poll", "at", next.Format(time.RFC3339))
+			select {
+			case <-time.After(watchFlags.interval):
+			case <-shutdown.Stopping(ctx):
+				logger.Info("Stopped watching")
+				return nil
+			}
+		}
+	},
+}
+
+// pollOnce runs the pipeline if the product has deliveries newer than the last run, and
+// reports the run to notifier. A poll that finds nothing new is not reported.
+func pollOnce(ctx context.Context, notifier *notify.Notifier) error {
+	deliveries, err := ET.UnwrapError(services.Downloader.NewDeliveries(ctx)())
+	if err != nil {
+		return fmt.Errorf("fetch product metadata: %w", err)
+	}
+	if len(deliveries) == 0 {
+		logger.Info("No new deliveries")
+		return nil
+	}
+	logger.Infow("New deliveries", "count", len(deliveries), "deliveries", deliveries)
+	started := time.Now()
+	summary, err := pipeline.Run(ctx, cfg, services, nil)
+	notifier.Send(notify.NewEvent("", started, summary, err))
+	if err != nil {
+		return err
+	}
+	logger.Infow("Run completed", "snapshot", summary.Snapshot, "stages", summary.Stages)
+	return nil
+}
+
+func init() {
+	watchCmd.Flags().DurationVar(&watchFlags.interval, "interval", 6*time.Hour,
+		"Time between two polls of the product metadata")
+	commandOverrides[watchCmd] = func(c *config.Config, cmd *cobra.Command) {
+		c.State.Incremental = true
+	}
+}
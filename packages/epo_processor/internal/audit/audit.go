@@ -0,0 +1,275 @@
+package audit
+
+import (
+	"errors"
+	"fmt"
+	"io/fs"
+	"os"
+	"path/filepath"
+	"sort"
+	"strings"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/download"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/sink"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/snapshot"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/state"
+)
+
+type Severity string
+
+const (
+	SeverityError   Severity = "error"
+	SeverityWarning Severity = "warning"
+)
+
+// Discrepancy is one inconsistency between the product metadata, the manifest, the
+// files on disk and the dataset.
+type Discrepancy struct {
+	Severity Severity `json:"severity"`
+	Check    string   `json:"check"`
+	Subject  string   `json:"subject"`
+	Detail   string   `json:"detail"`
+}
+
+// Report is the outcome of an audit. ProductItems is -1 when the product metadata
+// was not consulted.
+type Report struct {
+	Snapshot      string        `json:"snapshot,omitempty"`
+	Output        string        `json:"output"`
+	ProductItems  int           `json:"product_items"`
+	ManifestItems int           `json:"manifest_items"`
+	XMLFiles      int           `json:"xml_files"`
+	Rows          int64         `json:"rows"`
+	Discrepancies []Discrepancy `json:"discrepancies"`
+}
+
+// Errors returns the number of discrepancies with error severity.
+func (r Report) Errors() int {
+	n := 0
+	for _, d := range r.Discrepancies {
+		if d.Severity == SeverityError {
+			n++
+		}
+	}
+	return n
+}
+
+// Complete reports whether the corpus has no error-level discrepancies.
+func (r Report) Complete() bool {
+	return r.Errors() == 0
+}
+
+type auditor struct {
+	cfg      config.Config
+	manifest *state.Manifest
+	report   Report
+}
+
+// Run cross-checks the manifest against the product items, the download directory and
+// the dataset written by the parse stage. items may be nil to skip the product checks.
+func Run(cfg config.Config, manifest *state.Manifest, items []download.ProductItem) (Report, error) {
+	if manifest == nil {
+		return Report{}, fmt.Errorf("state tracking is disabled (set state.enabled)")
+	}
+	label := snapshot.Resolve(cfg.Snapshot, manifest)
+	a := &auditor{
+		cfg:      cfg,
+		manifest: manifest,
+		report: Report{
+			Snapshot:      label,
+			Output:        snapshot.Expand(cfg.Parse.OutputCSV, label),
+			ProductItems:  -1,
+			ManifestItems: len(manifest.Names()),
+			Discrepancies: []Discrepancy{},
+		},
+	}
+	if items != nil {
+		a.report.ProductItems = len(items)
+		a.checkProduct(items)
+	}
+	a.checkItemStates()
+	if err := a.checkFiles(); err != nil {
+		return Report{}, err
+	}
+	a.checkDataset()
+	sort.SliceStable(a.report.Discrepancies, func(i, j int) bool {
+		di, dj := a.report.Discrepancies[i], a.report.Discrepancies[j]
+		if di.Severity != dj.Severity {
+			return di.Severity == SeverityError
+		}
+		if di.Check != dj.Check {
+			return di.Check < dj.Check
+		}
+		return di.Subject < dj.Subject
+	})
+	return a.report, nil
+}
+
+func (a *auditor) add(sev Severity, check, subject, format string, args ...any) {
+	a.report.Discrepancies = append(a.report.Discrepancies, Discrepancy{
+		Severity: sev,
+		Check:    check,
+		Subject:  subject,
+		Detail:   fmt.Sprintf(format, args...),
+	})
+}
+
+func (a *auditor) extracts() bool {
+	return a.cfg.Extract.Enabled && !a.cfg.Parse.FromArchives
+}
+
+// checkProduct compares the product metadata with the manifest and the download directory.
+func (a *auditor) checkProduct(items []download.ProductItem) {
+	known := make(map[string]bool, len(items))
+	for _, item := range items {
+		known[item.Name] = true
+		it, recorded := a.manifest.Item(item.Name)
+		if !recorded {
+			a.add(SeverityError, "item_missing", item.Name, "product item not recorded in the manifest")
+		}
+		a.checkItemFile(item, it)
+		switch {
+		case it.Download != state.StatusDone:
+			if recorded {
+				a.add(SeverityError, "not_downloaded", item.Name, "download status %q", statusName(it.Download))
+			}
+		case it.Checksum == state.StatusMismatch:
+			a.add(SeverityError, "checksum_mismatch", item.Name, "SHA-1 differs from the product metadata")
+		case a.extracts() && it.Extract != state.StatusDone:
+			a.add(SeverityError, "not_extracted", item.Name, "extract status %q", statusName(it.Extract))
+		case it.Parse != state.StatusDone:
+			a.add(SeverityError, "not_parsed", item.Name, "parse status %q", statusName(it.Parse))
+		}
+	}
+	for _, name := range a.manifest.Names() {
+		if !known[name] {
+			a.add(SeverityWarning, "unknown_item", name, "recorded in the manifest but not in the product metadata")
+		}
+	}
+}
+
+// checkItemFile checks the downloaded file of item, which may be gone legitimately
+// when it was extracted with delete_after_extract.
+func (a *auditor) checkItemFile(item download.ProductItem, it state.ItemState) {
+	fi, err := os.Stat(item.Path)
+	if err != nil {
+		deleted := it.Extract == state.StatusDone && a.cfg.Extract.DeleteAfterExtract
+		if it.Download == state.StatusDone && !deleted {
+			a.add(SeverityError, "file_missing", item.Name, "%s: %v", item.Path, err)
+		}
+		return
+	}
+	if item.ExpectedSize > 0 && !approxSize(fi.Size(), item.ExpectedSize) {
+		a.add(SeverityWarning, "size_mismatch", item.Name,
+			"%d bytes on disk, product metadata lists %d", fi.Size(), item.ExpectedSize)
+	}
+}
+
+// approxSize allows for the rounding of human-readable sizes in the product metadata.
+func approxSize(actual, expected int64) bool {
+	diff := actual - expected
+	if diff < 0 {
+		diff = -diff
+	}
+	return diff*100 <= expected
+}
+
+// checkItemStates flags steps recorded as done although the step before them is not.
+func (a *auditor) checkItemStates() {
+	for _, name := range a.manifest.Names() {
+		it, _ := a.manifest.Item(name)
+		if it.Extract == state.StatusDone && it.Download != state.StatusDone {
+			a.add(SeverityError, "inconsistent_state", name, "extracted but not downloaded")
+		}
+		if it.Parse == state.StatusDone && it.Download != state.StatusDone {
+			a.add(SeverityError, "inconsistent_state", name, "parsed but not downloaded")
+		}
+		if a.extracts() && it.Parse == state.StatusDone && it.Extract != state.StatusDone {
+			a.add(SeverityError, "inconsistent_state", name, "parsed but not extracted")
+		}
+		if it.Error != "" {
+			a.add(SeverityWarning, "item_error", name, "%s", it.Error)
+		}
+	}
+}
+
+// checkFiles compares the XML files recorded in the manifest with those on disk.
+func (a *auditor) checkFiles() error {
+	files := a.manifest.FileStatuses()
+	a.report.XMLFiles = len(files)
+	for path, st := range files {
+		switch st {
+		case state.StatusFailed:
+			a.add(SeverityError, "xml_failed", path, "parsing failed")
+		case state.StatusDone:
+			if strings.Contains(path, "!/") {
+				continue
+			}
+			if _, err := os.Stat(path); err != nil {
+				a.add(SeverityWarning, "xml_missing", path, "parsed but no longer on disk")
+			}
+		}
+	}
+	dir := a.cfg.ParseInputDir()
+	err := filepath.WalkDir(dir, func(path string, d fs.DirEntry, err error) error {
+		if err != nil {
+			if errors.Is(err, fs.ErrNotExist) {
+				return nil
+			}
+			return err
+		}
+		if d.IsDir() || !strings.EqualFold(filepath.Ext(path), ".xml") {
+			return nil
+		}
+		if _, ok := files[path]; !ok {
+			a.add(SeverityError, "xml_unparsed", path, "XML file on disk not recorded as parsed")
+		}
+		return nil
+	})
+	if err != nil {
+		return fmt.Errorf("scan %s: %w", dir, err)
+	}
+	return nil
+}
+
+// checkDataset compares the dataset with its descriptor and the manifest snapshot.
+func (a *auditor) checkDataset() {
+	output := a.report.Output
+	rows, err := sink.CountRows(output, a.cfg.Parse.OutputFormat)
+	if err != nil {
+		a.add(SeverityError, "dataset_unreadable", output, "%v", err)
+		return
+	}
+	a.report.Rows = rows
+	if !a.cfg.Parse.Descriptor.Enabled {
+		return
+	}
+	desc, err := sink.ReadDescriptor(output)
+	if err != nil {
+		a.add(SeverityError, "descriptor_missing", output+sink.DescriptorSuffix, "%v", err)
+		return
+	}
+	if desc.Rows != rows {
+		a.add(SeverityError, "row_count", output, "descriptor lists %d rows, dataset has %d", desc.Rows, rows)
+	}
+	var partitioned int64
+	for _, p := range desc.Partitions {
+		partitioned += p.Rows
+	}
+	if partitioned != desc.Rows {
+		a.add(SeverityError, "row_count", output+sink.DescriptorSuffix,
+			"partitions sum to %d rows, descriptor lists %d", partitioned, desc.Rows)
+	}
+	if desc.Snapshot != a.report.Snapshot {
+		a.add(SeverityWarning, "snapshot_mismatch", output+sink.DescriptorSuffix,
+			"descriptor snapshot %q, manifest snapshot %q", desc.Snapshot, a.report.Snapshot)
+	}
+}
+
+func statusName(st state.Status) string {
+	if st == state.StatusPending {
+		return "pending"
+	}
+	return string(st)
+}
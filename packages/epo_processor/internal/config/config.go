@@ -2,10 +2,18 @@ package config
 
 import (
 	"fmt"
+	"io"
+	"net/url"
+	"os"
+	"path"
+	"path/filepath"
+	"regexp"
+	"slices"
 	"strings"
 	"time"
 
 	"github.com/go-playground/validator/v10"
+	"github.com/spf13/pflag"
 	"github.com/spf13/viper"
 )
 
@@ -13,14 +21,35 @@ type Config struct {
 	Log       Log       `mapstructure:"log"       validate:"required"`
 	Telemetry Telemetry `mapstructure:"telemetry" validate:"required"`
 	Server    Server    `mapstructure:"server"    validate:"required"`
+	Source    Source    `mapstructure:"source"`
 	Download  Download  `mapstructure:"download"`
 	Extract   Extract   `mapstructure:"extract"`
 	Parse     Parse     `mapstructure:"parse"`
+	Serve     Serve     `mapstructure:"serve"`
+	State     State     `mapstructure:"state"`
+	Snapshot  Snapshot  `mapstructure:"snapshot"`
+	Pipeline  Pipeline  `mapstructure:"pipeline"`
+	Scratch   Scratch   `mapstructure:"scratch"`
+	Reload    Reload    `mapstructure:"reload"`
+	Notify    Notify    `mapstructure:"notify"`
+	Storage   Storage   `mapstructure:"storage"`
+	Products  []Product `mapstructure:"products"  validate:"dive"`
+
+	file string // the config file read, if any
 }
 
 type Log struct {
 	LogLevel string `mapstructure:"log_level" validate:"required,oneof=debug info warn error"`
 	LogDir   string `mapstructure:"log_dir"   validate:"omitempty,dir"`
+	// Progress draws the download, extract and parse progress bars on stdout.
+	Progress bool `mapstructure:"progress"`
+	// ProgressInterval is the minimum time between two redraws of a progress display;
+	// ProgressMaxItems caps the products shown on the shared line of concurrent runs.
+	ProgressInterval time.Duration `mapstructure:"progress_interval"  validate:"min=0"`
+	ProgressMaxItems int           `mapstructure:"progress_max_items" validate:"min=1"`
+	// Explain appends a JSONL entry for every skipped delivery, item, archive entry and
+	// file to this path, with the reason it was left out; empty disables the report.
+	Explain string `mapstructure:"explain"`
 }
 
 type Telemetry struct {
@@ -34,19 +63,151 @@ type Telemetry struct {
 }
 
 type Server struct {
-	BaseURL             string        `mapstructure:"base_url"             validate:"required,url"`
-	Timeout             time.Duration `mapstructure:"timeout"              validate:"required,gt=0"`
-	MaxRetries          int           `mapstructure:"max_retries"          validate:"min=0,max=10"`
-	ConcurrentDownloads int           `mapstructure:"concurrent_downloads" validate:"min=1,max=30"`
-	ProductID           int           `mapstructure:"product_id"           validate:"required"`
+	BaseURL             string              `mapstructure:"base_url"             validate:"required,url"`
+	Timeout             time.Duration       `mapstructure:"timeout"              validate:"required,gt=0"`
+	MaxRetries          int                 `mapstructure:"max_retries"          validate:"min=0,max=10"`
+	ConcurrentDownloads int                 `mapstructure:"concurrent_downloads" validate:"min=1,max=30"`
+	ProductID           int                 `mapstructure:"product_id"           validate:"required"`
+	AdaptiveConcurrency AdaptiveConcurrency `mapstructure:"adaptive_concurrency"`
+	Auth                Auth                `mapstructure:"auth"`
+	Compression         bool                `mapstructure:"compression"`
+	Network             Network             `mapstructure:"network"`
+	Throttle            Throttle            `mapstructure:"throttle"`
+	RateLimit           RateLimit           `mapstructure:"rate_limit"`
+	MetadataCache       MetadataCache       `mapstructure:"metadata_cache"`
+}
+
+// MetadataCache keeps the product metadata responses in Dir with their ETag and
+// Last-Modified, and revalidates them with conditional requests on later runs. Offline
+// answers from the cache without contacting the API.
+type MetadataCache struct {
+	Enabled bool   `mapstructure:"enabled"`
+	Dir     string `mapstructure:"dir"     validate:"required_if=Enabled true"`
+	Offline bool   `mapstructure:"offline"`
+}
+
+// RateLimit caps the requests per second sent to the EPO API, shared by the metadata
+// fetches and all concurrent downloads (0 = no limit). Burst requests may start at once.
+type RateLimit struct {
+	RequestsPerSecond float64 `mapstructure:"requests_per_second" validate:"min=0"`
+	Burst             int     `mapstructure:"burst"               validate:"min=1"`
+}
+
+// Throttle handles 429 Too Many Requests answers to item downloads. All downloads pause for
+// the Retry-After of the answer, or DefaultWait without one, before the item is requested
+// again. An item that waited MaxWait in total fails with the 429 (0 = no limit).
+type Throttle struct {
+	DefaultWait time.Duration `mapstructure:"default_wait" validate:"gt=0"`
+	MaxWait     time.Duration `mapstructure:"max_wait"     validate:"min=0"`
+}
+
+// Network tunes how connections to the EPO API are established. IPFamily auto races A and
+// AAAA records (Happy Eyeballs, FallbackDelay); prefer_ipv4/prefer_ipv6 try one family
+// first and fall back after ConnectTimeout; ipv4/ipv6 restrict to one family. ReadTimeout
+// bounds the wait for the response to a request once it is sent (0 = no limit).
+type Network struct {
+	IPFamily       string         `mapstructure:"ip_family"       validate:"omitempty,oneof=auto ipv4 ipv6 prefer_ipv4 prefer_ipv6"`
+	ConnectTimeout time.Duration  `mapstructure:"connect_timeout" validate:"min=0"`
+	ReadTimeout    time.Duration  `mapstructure:"read_timeout"    validate:"min=0"`
+	FallbackDelay  time.Duration  `mapstructure:"fallback_delay"`
+	Hosts          []HostOverride `mapstructure:"hosts"           validate:"dive"`
+	Proxy          Proxy          `mapstructure:"proxy"`
+	TLS            TLS            `mapstructure:"tls"`
+}
+
+// TLS adjusts certificate handling for TLS-intercepting proxies and mutual TLS. CAFiles
+// are PEM bundles trusted in addition to the system roots; CertFile and KeyFile are the
+// client certificate. InsecureSkipVerify disables verification entirely and is meant for
+// diagnosis only.
+type TLS struct {
+	CAFiles            []string `mapstructure:"ca_files"`
+	CertFile           string   `mapstructure:"cert_file"            validate:"required_with=KeyFile"`
+	KeyFile            string   `mapstructure:"key_file"             validate:"required_with=CertFile"`
+	InsecureSkipVerify bool     `mapstructure:"insecure_skip_verify"`
+}
+
+// Proxy routes EPO API and HUPD requests through an http, https or socks5(h) proxy.
+// Hosts in NoProxy (names, .domain suffixes, IPs or CIDRs) connect directly. Without a
+// URL the HTTP_PROXY, HTTPS_PROXY and NO_PROXY environment variables apply. The password
+// can be supplied via EPO_SERVER_NETWORK_PROXY_PASSWORD.
+type Proxy struct {
+	URL      string   `mapstructure:"url"      validate:"omitempty,url"`
+	NoProxy  []string `mapstructure:"no_proxy"`
+	Username string   `mapstructure:"username"`
+	Password string   `mapstructure:"password" json:"-"`
+}
+
+// HostOverride pins a hostname to an address, like an /etc/hosts entry.
+type HostOverride struct {
+	Host    string `mapstructure:"host"    validate:"required"`
+	Address string `mapstructure:"address" validate:"required,ip"`
+}
+
+// Auth configures credentials for the EPO publication API. Secrets can be supplied via
+// EPO_SERVER_AUTH_TOKEN, EPO_SERVER_AUTH_API_KEY and EPO_SERVER_AUTH_CLIENT_SECRET.
+type Auth struct {
+	Type         string   `mapstructure:"type"           validate:"omitempty,oneof=none bearer api_key oauth2"`
+	Token        string   `mapstructure:"token"          validate:"required_if=Type bearer"                     json:"-"`
+	APIKey       string   `mapstructure:"api_key"        validate:"required_if=Type api_key"                    json:"-"`
+	APIKeyHeader string   `mapstructure:"api_key_header" validate:"required_if=Type api_key"`
+	TokenURL     string   `mapstructure:"token_url"      validate:"required_if=Type oauth2,omitempty,url"`
+	ClientID     string   `mapstructure:"client_id"      validate:"required_if=Type oauth2"`
+	ClientSecret string   `mapstructure:"client_secret"  validate:"required_if=Type oauth2"                     json:"-"`
+	Scopes       []string `mapstructure:"scopes"`
+}
+
+// AdaptiveConcurrency tunes AIMD back-off of download parallelism. The limit starts at
+// ConcurrentDownloads and never drops below MinConcurrency.
+type AdaptiveConcurrency struct {
+	Enabled        bool    `mapstructure:"enabled"`
+	MinConcurrency int     `mapstructure:"min_concurrency" validate:"min=1"`
+	ErrorThreshold float64 `mapstructure:"error_threshold" validate:"gt=0,lte=1"`
+	DecreaseFactor float64 `mapstructure:"decrease_factor" validate:"gt=0,lt=1"`
+	Window         int     `mapstructure:"window"          validate:"min=1"`
 }
 
 type Download struct {
-	Directory  string `mapstructure:"directory"   validate:"required_if=Enabled true,dir"`
+	Directory  string `mapstructure:"directory"   validate:"required_if=Enabled true,dir|startswith=s3://"`
 	SkipExists bool   `mapstructure:"skip_exists"`
 	VerifySHA1 bool   `mapstructure:"verify_sha1"`
 	Enabled    bool   `mapstructure:"enabled"`
 	HUPD       HUPD   `mapstructure:"hupd"`
+	Filter     Filter `mapstructure:"filter"`
+	// MaxAttempts stops retrying an item across runs after this many failed attempts
+	// (0 = unlimited); the attempts are recorded in the manifest.
+	MaxAttempts int `mapstructure:"max_attempts" validate:"min=0"`
+	// ResumePartial continues a <item>.part file left by an interrupted download with a
+	// range request instead of starting over.
+	ResumePartial bool      `mapstructure:"resume_partial"`
+	DiskSpace     DiskSpace `mapstructure:"disk_space"`
+	// Order is the order the items are downloaded in: product keeps the order of the
+	// product metadata, largest_first starts the biggest items (by fileSize) first.
+	Order string `mapstructure:"order" validate:"oneof=product largest_first"`
+	// PathTemplate lays out the items below Directory, e.g. {delivery_id}/{item_name}, so
+	// that items of the same name in different deliveries do not collide. Placeholders:
+	// {item_name}, {delivery_id}, {delivery_name}, {delivery_publication_date} (YYYY-MM-DD)
+	// and {product_id}.
+	PathTemplate string `mapstructure:"path_template" validate:"required"`
+	// StallTimeout aborts a download that received no bytes for this long, so that it is
+	// retried instead of hanging on a dead connection (0 = never).
+	StallTimeout time.Duration `mapstructure:"stall_timeout" validate:"min=0"`
+}
+
+// DiskSpace checks, before downloading, that the queued items fit on the filesystem of
+// the download directory. Check warn logs a shortfall, abort fails the download stage and
+// off skips the check. ExtractRatio estimates the size of the extracted files relative to
+// the archives, counted when extract unpacks into the download directory.
+type DiskSpace struct {
+	Check        string  `mapstructure:"check"         validate:"oneof=off warn abort"`
+	ExtractRatio float64 `mapstructure:"extract_ratio" validate:"min=0"`
+}
+
+// Filter restricts downloads to deliveries published between Since and Until (inclusive,
+// YYYY-MM-DD, empty means unbounded) and to items whose name matches one of the Items globs.
+type Filter struct {
+	Since string   `mapstructure:"since" validate:"omitempty,datetime=2006-01-02"`
+	Until string   `mapstructure:"until" validate:"omitempty,datetime=2006-01-02"`
+	Items []string `mapstructure:"items"`
 }
 
 type HUPD struct {
@@ -56,23 +217,425 @@ type HUPD struct {
 }
 
 type Extract struct {
-	Enabled            bool `mapstructure:"enabled"`
-	DeleteAfterExtract bool `mapstructure:"delete_after_extract"`
+	Enabled            bool   `mapstructure:"enabled"`
+	DeleteAfterExtract bool   `mapstructure:"delete_after_extract"`
+	PathPolicy         string `mapstructure:"path_policy"          validate:"omitempty,oneof=strict lenient"`
 }
 
 type Parse struct {
-	Enabled   bool   `mapstructure:"enabled"`
-	OutputCSV string `mapstructure:"output_csv"`
-	Workers   int    `mapstructure:"workers"`
+	Enabled      bool       `mapstructure:"enabled"`
+	InputDir     string     `mapstructure:"input_dir"`
+	OutputCSV    string     `mapstructure:"output_csv"`
+	OutputFormat string     `mapstructure:"output_format" validate:"omitempty,oneof=parquet csv jsonl tables postgres duckdb neo4j arrow clickhouse elasticsearch"`
+	Workers      int        `mapstructure:"workers"`
+	SniffContent bool       `mapstructure:"sniff_content"`
+	Streaming    bool       `mapstructure:"streaming"`
+	FromArchives bool       `mapstructure:"from_archives"`
+	IndexPath    string     `mapstructure:"index_path"`
+	RawXML       RawXML     `mapstructure:"raw_xml"`
+	Fields       []string   `mapstructure:"fields"        validate:"dive,oneof=title abstract applicants inventors ipc publication_date npl_citations family_id application_number filing_date priority_claims earliest_priority_date cpc_hierarchy citation_provenance"`
+	Columns      []string   `mapstructure:"columns"       validate:"unique,dive,oneof=patent_id status cpc_list citations family_patents title abstract applicants inventors ipc publication_date npl_citations family_id application_number filing_date priority_claims earliest_priority_date cpc_hierarchy citation_provenance revision"`
+	DateFormat   string     `mapstructure:"date_format"   validate:"oneof=yyyymmdd iso"`
+	Privacy      Privacy    `mapstructure:"privacy"`
+	Descriptor   Descriptor `mapstructure:"descriptor"`
+	Rotation     Rotation   `mapstructure:"rotation"`
+	Buckets      Buckets    `mapstructure:"buckets"`
+	// XPathFields adds a column per name, filled with the text of the nodes its XPath
+	// expression selects relative to each exchange-document.
+	XPathFields map[string]string `mapstructure:"xpath_fields" validate:"dive,keys,required,endkeys,required"`
+	// Resume continues the output of an interrupted run from the checkpoint in the
+	// manifest, skipping the XML files already in it.
+	Resume bool `mapstructure:"resume"`
+	// Profile times the extractors on a sample of the XML files.
+	Profile Profile `mapstructure:"profile"`
+	// FamilyMembers writes the family of each record as a long-format table next to the
+	// output, from the same parse.
+	FamilyMembers FamilyMembers `mapstructure:"family_members"`
+	// Families writes one row per family of the whole corpus next to the output, from the
+	// same parse.
+	Families Families `mapstructure:"families"`
+	// CitationGraph writes the citation network next to the output, from the same parse.
+	CitationGraph CitationGraph `mapstructure:"citation_graph"`
+	// Kafka publishes every record to a topic as it is parsed, next to the output.
+	Kafka Kafka `mapstructure:"kafka"`
+	// Quarantine sets aside the XML files that fail to parse instead of failing the run.
+	Quarantine Quarantine `mapstructure:"quarantine"`
+	// Contract declares expectations on the records of a run; a violation fails it.
+	Contract Contract `mapstructure:"contract"`
+	// Classifications selects the schemes of the cpc_list and ipc_list columns.
+	Classifications Classifications `mapstructure:"classifications"`
+	// MergeAbstracts fills the abstract of each record from a separate abstracts product.
+	MergeAbstracts MergeAbstracts `mapstructure:"merge_abstracts"`
+	// Dedup decides which record of a publication in several deliveries is kept.
+	Dedup Dedup `mapstructure:"dedup"`
+	// Filter keeps only the exchange-documents of the given countries and kind codes.
+	Filter DocumentFilter `mapstructure:"filter"`
+	// Postgres is the database loaded by the postgres output format.
+	Postgres Postgres `mapstructure:"postgres"`
+	// DuckDB configures the Parquet export of the duckdb output format.
+	DuckDB DuckDB `mapstructure:"duckdb"`
+	// ClickHouse is the table loaded by the clickhouse output format.
+	ClickHouse ClickHouse `mapstructure:"clickhouse"`
+	// Elasticsearch is the index loaded by the elasticsearch output format.
+	Elasticsearch Elasticsearch `mapstructure:"elasticsearch"`
+}
+
+// Elasticsearch indexes the records into Index of the Elasticsearch or OpenSearch
+// cluster at URL with the _bulk API, BatchRows documents per request. CreateIndex creates
+// a missing index with the mapping of the sink; documents rejected as temporary failures
+// are retried up to Retries times. APIKey takes precedence over User and Password.
+type Elasticsearch struct {
+	URL         string        `mapstructure:"url"          validate:"omitempty,url"`
+	Index       string        `mapstructure:"index"`
+	CreateIndex bool          `mapstructure:"create_index"`
+	User        string        `mapstructure:"user"`
+	Password    string        `mapstructure:"password"     json:"-"`
+	APIKey      string        `mapstructure:"api_key"      json:"-"`
+	BatchRows   int           `mapstructure:"batch_rows"   validate:"min=1"`
+	Retries     int           `mapstructure:"retries"      validate:"min=0"`
+	Timeout     time.Duration `mapstructure:"timeout"      validate:"min=0"`
+}
+
+// ClickHouse inserts the records into Database.Table through the HTTP interface at URL
+// (e.g. http://localhost:8123), BatchRows per INSERT. The table is created if missing.
+type ClickHouse struct {
+	URL       string        `mapstructure:"url"        validate:"omitempty,url"`
+	Database  string        `mapstructure:"database"`
+	Table     string        `mapstructure:"table"`
+	User      string        `mapstructure:"user"`
+	Password  string        `mapstructure:"password"   json:"-"`
+	BatchRows int           `mapstructure:"batch_rows" validate:"min=1"`
+	Timeout   time.Duration `mapstructure:"timeout"    validate:"min=0"`
+}
+
+// DuckDB exports the patents table to ExportDir as Parquet partitioned by PartitionBy
+// (country from the patent id, year from publication_date) once the run is loaded.
+// LockWait is how long a run waits for another process to release the database file.
+type DuckDB struct {
+	PartitionBy []string      `mapstructure:"partition_by" validate:"dive,oneof=country year"`
+	ExportDir   string        `mapstructure:"export_dir"`
+	LockWait    time.Duration `mapstructure:"lock_wait"    validate:"min=0"`
+}
+
+// Postgres loads the records into Schema with COPY, one transaction per run. Replace
+// truncates the tables before loading; otherwise rows are appended.
+type Postgres struct {
+	DSN     string `mapstructure:"dsn"     json:"-"`
+	Schema  string `mapstructure:"schema"`
+	Replace bool   `mapstructure:"replace"`
+}
+
+// Classifications lists the patent-classification schemes written to cpc_list and,
+// with ipc in fields, to ipc_list. IPCR also reads the classifications-ipcr block.
+// Prefixes keeps only the symbols starting with one of them (e.g. Y02, H01M); Normalize
+// writes symbols in the compact form of the scheme tables (H01M10/0525).
+type Classifications struct {
+	CPC       []string `mapstructure:"cpc"`
+	IPC       []string `mapstructure:"ipc"`
+	Prefixes  []string `mapstructure:"prefixes"  validate:"dive,required"`
+	Normalize bool     `mapstructure:"normalize"`
+}
+
+// MergeAbstracts joins the abstracts parsed from the XML below InputDir (the extracted
+// abstracts product) onto the records by patent id while they are written.
+type MergeAbstracts struct {
+	Enabled  bool   `mapstructure:"enabled"`
+	InputDir string `mapstructure:"input_dir" validate:"required_if=Enabled true"`
+}
+
+// Dedup handles a patent_id found in several deliveries, e.g. a corrected publication.
+// keep_first keeps the record of the earliest delivery, keep_latest the record of the
+// latest one; keep_all keeps every record and adds a revision column with the
+// publication date of its delivery. Deliveries are looked up in the manifest.
+type Dedup struct {
+	Policy string `mapstructure:"policy" validate:"oneof=off keep_first keep_latest keep_all"`
+}
+
+// DocumentFilter skips the exchange-documents whose country or kind attribute is not
+// listed, before anything is extracted from them. An empty list keeps every value;
+// codes are matched case-insensitively.
+type DocumentFilter struct {
+	Countries []string `mapstructure:"countries" validate:"dive,required"`
+	Kinds     []string `mapstructure:"kinds"     validate:"dive,required"`
+}
+
+// Contract is checked against the records a parse wrote once its output is complete:
+// at least MinRecords records, at most the given share (0-1) of records with an empty
+// column in MaxNullRate, and, when Countries is set, only patent ids from those offices.
+type Contract struct {
+	MinRecords  int64              `mapstructure:"min_records"   validate:"min=0"`
+	MaxNullRate map[string]float64 `mapstructure:"max_null_rate" validate:"dive,keys,oneof=status cpc_list citations family_patents titles abstracts applicants inventors ipc_list publication_date npl_citations family_id application_number filing_date priority_claims earliest_priority_date cpc_hierarchy citation_provenance,endkeys,min=0,max=1"`
+	Countries   []string           `mapstructure:"countries"     validate:"dive,len=2,uppercase"`
+}
+
+// FamilyMembers writes a CSV at Path with one row per family member of each record
+// (family_id, patent_id, member_id), the record itself included.
+type FamilyMembers struct {
+	Enabled bool   `mapstructure:"enabled"`
+	Path    string `mapstructure:"path"    validate:"required_if=Enabled true"`
+}
+
+// Families writes a CSV at Path with one row per family of the corpus (family_id, size,
+// members), the members sorted and joined with ';'.
+type Families struct {
+	Enabled bool   `mapstructure:"enabled"`
+	Path    string `mapstructure:"path"    validate:"required_if=Enabled true"`
+}
+
+// CitationGraph writes one edge per citation category at Path (citing_id, cited_id,
+// category, cited_phase), as a CSV edge list or as directed GraphML.
+type CitationGraph struct {
+	Enabled bool   `mapstructure:"enabled"`
+	Format  string `mapstructure:"format"  validate:"oneof=edgelist graphml"`
+	Path    string `mapstructure:"path"    validate:"required_if=Enabled true"`
+}
+
+// Kafka publishes every record as a JSON message to Topic on the cluster of Brokers
+// (host:port), keyed by its patent_id or family_id, or without a key (none). The client
+// batches messages up to MaxMessageBytes per partition with Compression; Write waits for
+// the acknowledgement of every BatchRows messages, by all in-sync replicas or the leader
+// only (Acks). Messages failing with a retriable error are resent up to Retries times.
+type Kafka struct {
+	Enabled         bool          `mapstructure:"enabled"`
+	Brokers         []string      `mapstructure:"brokers"           validate:"required_if=Enabled true,dive,hostname_port"`
+	Topic           string        `mapstructure:"topic"             validate:"required_if=Enabled true"`
+	Key             string        `mapstructure:"key"               validate:"oneof=patent_id family_id none"`
+	Acks            string        `mapstructure:"acks"              validate:"oneof=all leader"`
+	Compression     string        `mapstructure:"compression"       validate:"oneof=none gzip snappy lz4 zstd"`
+	BatchRows       int           `mapstructure:"batch_rows"        validate:"min=1"`
+	MaxMessageBytes int           `mapstructure:"max_message_bytes" validate:"min=1024"`
+	Retries         int           `mapstructure:"retries"           validate:"min=0"`
+	Timeout         time.Duration `mapstructure:"timeout"           validate:"min=0"`
+	TLS             bool          `mapstructure:"tls"`
+	SASL            KafkaSASL     `mapstructure:"sasl"`
+}
+
+// KafkaSASL authenticates the Kafka producer with PLAIN or SCRAM. The password can be
+// supplied via EPO_PARSE_KAFKA_SASL_PASSWORD.
+type KafkaSASL struct {
+	Mechanism string `mapstructure:"mechanism" validate:"oneof=none plain scram-sha-256 scram-sha-512"`
+	User      string `mapstructure:"user"      validate:"required_unless=Mechanism none"`
+	Password  string `mapstructure:"password"  json:"-"`
+}
+
+// Quarantine copies each XML file that fails to parse into Dir (or moves it, with Move)
+// and appends a row to Report, <Dir>/errors.csv by default; the parse continues with the
+// next file. Entries of archives read with from_archives are always copied.
+type Quarantine struct {
+	Enabled bool   `mapstructure:"enabled"`
+	Dir     string `mapstructure:"dir"     validate:"required_if=Enabled true"`
+	Move    bool   `mapstructure:"move"`
+	Report  string `mapstructure:"report"`
+}
+
+// Profile measures the time spent per extractor (classifications, citations, family,
+// fields, ipc) on every SampleEvery-th XML file and logs the breakdown when the parse
+// ends; Report also writes it as JSON.
+type Profile struct {
+	Enabled     bool   `mapstructure:"enabled"`
+	SampleEvery int    `mapstructure:"sample_every" validate:"min=1"`
+	Report      string `mapstructure:"report"`
+}
+
+// Buckets spreads the output over Count files by a hash of the patent family, so a later
+// incremental run only rewrites the buckets of the families it brings; 0 writes a single
+// file. Supported for the parquet and jsonl formats.
+type Buckets struct {
+	Count int `mapstructure:"count" validate:"min=0,max=4096"`
+}
+
+// Rotation splits the output into numbered parts once a part reaches either limit;
+// 0 disables a limit. The byte limit applies to csv and jsonl only.
+type Rotation struct {
+	MaxRowsPerFile  int `mapstructure:"max_rows_per_file"  validate:"min=0"`
+	MaxBytesPerFile int `mapstructure:"max_bytes_per_file" validate:"min=0"`
+}
+
+// Descriptor writes a machine-readable <output>.dataset.json next to each dataset.
+type Descriptor struct {
+	Enabled bool   `mapstructure:"enabled"`
+	License string `mapstructure:"license"`
 }
 
-func Load(cfgFile string) (Config, error) {
+// Privacy scrubs person-level fields (inventor names) before records are written.
+// hash replaces each name with a salted SHA-256 so records stay joinable; drop removes them.
+type Privacy struct {
+	Inventors string `mapstructure:"inventors" validate:"omitempty,oneof=keep hash drop"`
+	Salt      string `mapstructure:"salt"      validate:"required_if=Inventors hash"      json:"-"`
+}
+
+// RawXML exports the original exchange-document fragment of each record as JSONL.
+type RawXML struct {
+	Enabled     bool   `mapstructure:"enabled"`
+	Path        string `mapstructure:"path"        validate:"required_if=Enabled true"`
+	Compression string `mapstructure:"compression" validate:"omitempty,oneof=none zstd"`
+}
+
+// ParseInputDir is the directory scanned for XML files; it defaults to the download directory.
+func (c Config) ParseInputDir() string {
+	if c.Parse.InputDir != "" {
+		return c.Parse.InputDir
+	}
+	return c.Download.Directory
+}
+
+// Pipeline controls how a full run schedules its stages. With Streaming, each item is
+// extracted and parsed as soon as it is downloaded; QueueSize bounds the items waiting
+// between two stages and the archives extracted at once.
+type Pipeline struct {
+	Streaming bool `mapstructure:"streaming"`
+	QueueSize int  `mapstructure:"queue_size" validate:"min=1"`
+	// ConcurrentProducts runs the entries of products at the same time instead of in order.
+	ConcurrentProducts bool `mapstructure:"concurrent_products"`
+	// ShutdownGrace is how long the tasks in flight may take to finish after SIGINT or
+	// SIGTERM before they are cancelled; 0 waits for them. A second signal cancels at once.
+	ShutdownGrace time.Duration `mapstructure:"shutdown_grace" validate:"min=0"`
+	// OnError is what a stage does once some of its items failed: fail stops the run after
+	// the stage, continue keeps the output of the items that succeeded and runs the next
+	// stage. Either way the failed items are listed in the stage results.
+	OnError string `mapstructure:"on_error" validate:"oneof=fail continue"`
+}
+
+// ContinueOnError reports whether the stages go on past the items that failed.
+func (p Pipeline) ContinueOnError() bool {
+	return p.OnError == "continue"
+}
+
+// Scratch is where temporary files go that do not belong next to an output, such as
+// nested archives too large to hold in memory. MaxBytes caps the space they take at
+// once (0 is unlimited); SpillAboveBytes is the size above which they go to disk.
+type Scratch struct {
+	Dir             string `mapstructure:"dir"`
+	MaxBytes        int    `mapstructure:"max_bytes"         validate:"min=0"`
+	SpillAboveBytes int    `mapstructure:"spill_above_bytes" validate:"min=0"`
+}
+
+// Reload re-reads the config file on SIGHUP and, with Watch, whenever its modification
+// time changes (checked every Interval). Only the settings in Reloadable are applied to a
+// running pipeline; a reload that changes any other setting is rejected as a whole.
+type Reload struct {
+	Watch    bool          `mapstructure:"watch"`
+	Interval time.Duration `mapstructure:"interval" validate:"min=0"`
+}
+
+// Notify posts a summary of each run to Webhooks when it ends, so unattended runs report
+// their outcome. Timeout bounds each request.
+type Notify struct {
+	Webhooks []Webhook     `mapstructure:"webhooks" validate:"dive"`
+	Timeout  time.Duration `mapstructure:"timeout"  validate:"min=0"`
+}
+
+// Webhook is one notification target. Format json posts the run summary as is; slack posts
+// a message for a Slack incoming webhook. On lists the run outcomes it is sent for, all of
+// them when empty.
+type Webhook struct {
+	URL     string            `mapstructure:"url"     validate:"required,url"                                          json:"-"`
+	Format  string            `mapstructure:"format"  validate:"omitempty,oneof=json slack"`
+	On      []string          `mapstructure:"on"      validate:"dive,oneof=success partial failure interrupted"`
+	Headers map[string]string `mapstructure:"headers"                                                                  json:"-"`
+}
+
+// Storage configures the object store behind paths given as s3://bucket/prefix URIs,
+// which download.directory, parse.input_dir and parse.output_csv accept.
+type Storage struct {
+	S3 S3 `mapstructure:"s3"`
+}
+
+// S3 addresses AWS S3 or, with Endpoint and PathStyle, an S3-compatible store such as
+// MinIO. Credentials come from the standard AWS chain. Uploads are multipart with parts
+// of PartSize bytes, UploadConcurrency of them in flight per object.
+type S3 struct {
+	Region            string `mapstructure:"region"`
+	Endpoint          string `mapstructure:"endpoint"           validate:"omitempty,url"`
+	PathStyle         bool   `mapstructure:"path_style"`
+	PartSize          int    `mapstructure:"part_size"          validate:"min=5242880"`
+	UploadConcurrency int    `mapstructure:"upload_concurrency" validate:"min=1"`
+}
+
+// UsesS3 reports whether any of the paths of the download, extract and parse stages is
+// an S3 URI.
+func (c Config) UsesS3() bool {
+	return isS3(c.Download.Directory) || isS3(c.ParseInputDir()) || isS3(c.Parse.OutputCSV)
+}
+
+func isS3(path string) bool {
+	return strings.HasPrefix(path, "s3://")
+}
+
+// ProgressWriter is where progress bars are drawn; io.Discard when log.progress is off.
+func (c Config) ProgressWriter() io.Writer {
+	if !c.Log.Progress {
+		return io.Discard
+	}
+	return os.Stdout
+}
+
+// State configures the persistent pipeline manifest used to skip completed steps.
+type State struct {
+	Enabled bool   `mapstructure:"enabled"`
+	Path    string `mapstructure:"path"    validate:"required_if=Enabled true"`
+	// Incremental only processes deliveries published after the manifest's last delivery.
+	Incremental bool `mapstructure:"incremental"`
+}
+
+// Snapshot labels artifacts with the data currency of the newest delivery, e.g. docdb_2024w37.
+// The label replaces {snapshot} in parse output paths and is recorded in the manifest.
+type Snapshot struct {
+	Prefix string `mapstructure:"prefix"`
+	Format string `mapstructure:"format" validate:"omitempty,oneof=week date"`
+	Label  string `mapstructure:"label"`
+}
+
+type Serve struct {
+	GRPCAddress    string   `mapstructure:"grpc_address"`
+	// APIKeys restricts access to clients presenting one of the keys. Without keys the
+	// server only starts on a loopback address, unless AllowAnonymous is set.
+	APIKeys        []APIKey `mapstructure:"api_keys"        validate:"dive"`
+	AllowAnonymous bool     `mapstructure:"allow_anonymous"`
+	// AccessLog is an append-only JSONL file recording every call and the record it accessed.
+	AccessLog      string   `mapstructure:"access_log"`
+	TLS            ServeTLS `mapstructure:"tls"`
+}
+
+// ServeTLS serves gRPC over TLS with the certificate chain and key in PEM files, so that
+// API keys do not travel in plaintext.
+type ServeTLS struct {
+	CertFile string `mapstructure:"cert_file" validate:"required_with=KeyFile"`
+	KeyFile  string `mapstructure:"key_file"  validate:"required_with=CertFile"`
+}
+
+type APIKey struct {
+	Name string `mapstructure:"name" validate:"required"`
+	Key  string `mapstructure:"key"  validate:"required,min=16" json:"-"`
+}
+
+// Load reads the config from cfgFile (or the nearest ProjectFile, or the default search
+// paths) on top of the named built-in template, if any. Relative paths in a ProjectFile
+// are resolved against its directory. Flags registered with RegisterFlags and changed in flags
+// override the file and EPO_* environment variables; flags may be nil.
+func Load(cfgFile, template string, flags *pflag.FlagSet) (Config, error) {
+	return load(cfgFile, template, flags, nil)
+}
+
+// LoadSettings is Load for programs embedding the processor: settings maps dotted keys
+// (e.g. "parse.output_format") to values that override every other source.
+func LoadSettings(cfgFile, template string, settings map[string]any) (Config, error) {
+	return load(cfgFile, template, nil, settings)
+}
+
+func load(cfgFile, template string, flags *pflag.FlagSet, settings map[string]any) (Config, error) {
 	v := viper.New()
 	v.AutomaticEnv()
 	v.SetEnvPrefix("EPO")
 	v.SetEnvKeyReplacer(strings.NewReplacer(".", "_", "-", "_"))
 
 	// Flexible file loading
+	if cfgFile == "" {
+		if project, ok := FindProject("."); ok {
+			cfgFile = project
+		}
+	}
+	root := projectRoot(cfgFile)
 	if cfgFile != "" {
 		v.SetConfigFile(cfgFile)
 	} else {
@@ -86,6 +649,21 @@ func Load(cfgFile string) (Config, error) {
 	// Defaults
 	v.SetDefault("log.log_level", "info")
 	v.SetDefault("log.log_dir", "logs")
+	v.SetDefault("log.progress", true)
+	v.SetDefault("log.progress_interval", 50*time.Millisecond)
+	v.SetDefault("log.progress_max_items", 6)
+	v.SetDefault("log.explain", "")
+	v.SetDefault("scratch.dir", "")
+	v.SetDefault("scratch.max_bytes", 0)
+	v.SetDefault("scratch.spill_above_bytes", 64<<20)
+	v.SetDefault("reload.watch", false)
+	v.SetDefault("reload.interval", 10*time.Second)
+	v.SetDefault("notify.timeout", 10*time.Second)
+	v.SetDefault("storage.s3.region", "")
+	v.SetDefault("storage.s3.endpoint", "")
+	v.SetDefault("storage.s3.path_style", false)
+	v.SetDefault("storage.s3.part_size", 64<<20)
+	v.SetDefault("storage.s3.upload_concurrency", 4)
 	v.SetDefault("telemetry.enabled", true)
 	v.SetDefault("telemetry.exporter", "otlp")
 	v.SetDefault("telemetry.endpoint", "localhost:4317")
@@ -96,9 +674,152 @@ func Load(cfgFile string) (Config, error) {
 	v.SetDefault("server.max_retries", 3)
 	v.SetDefault("server.concurrent_downloads", 5)
 	v.SetDefault("server.product_id", 3)
+	v.SetDefault("server.adaptive_concurrency.enabled", false)
+	v.SetDefault("server.adaptive_concurrency.min_concurrency", 1)
+	v.SetDefault("server.adaptive_concurrency.error_threshold", 0.2)
+	v.SetDefault("server.adaptive_concurrency.decrease_factor", 0.5)
+	v.SetDefault("server.adaptive_concurrency.window", 20)
+	v.SetDefault("server.compression", false)
+	v.SetDefault("server.network.ip_family", "auto")
+	v.SetDefault("server.metadata_cache.enabled", false)
+	v.SetDefault("server.metadata_cache.dir", ".cache/metadata")
+	v.SetDefault("server.metadata_cache.offline", false)
+	v.SetDefault("server.rate_limit.requests_per_second", 0.0)
+	v.SetDefault("server.rate_limit.burst", 1)
+	v.SetDefault("server.throttle.default_wait", 30*time.Second)
+	v.SetDefault("server.throttle.max_wait", 15*time.Minute)
+	v.SetDefault("server.network.connect_timeout", 10*time.Second)
+	v.SetDefault("server.network.read_timeout", 60*time.Second)
+	v.SetDefault("server.network.fallback_delay", 300*time.Millisecond)
+	v.SetDefault("server.network.proxy.url", "")
+	v.SetDefault("server.network.proxy.no_proxy", []string{})
+	v.SetDefault("server.network.proxy.username", "")
+	v.SetDefault("server.network.proxy.password", "")
+	v.SetDefault("server.network.tls.ca_files", []string{})
+	v.SetDefault("server.network.tls.cert_file", "")
+	v.SetDefault("server.network.tls.key_file", "")
+	v.SetDefault("server.network.tls.insecure_skip_verify", false)
+	v.SetDefault("server.auth.type", "none")
+	v.SetDefault("server.auth.token", "")
+	v.SetDefault("server.auth.api_key", "")
+	v.SetDefault("server.auth.api_key_header", "X-API-Key")
+	v.SetDefault("server.auth.token_url", "")
+	v.SetDefault("server.auth.client_id", "")
+	v.SetDefault("server.auth.client_secret", "")
 	v.SetDefault("download.directory", "data")
+	v.SetDefault("download.resume_partial", true)
+	v.SetDefault("download.disk_space.check", "warn")
+	v.SetDefault("download.order", "product")
+	v.SetDefault("download.path_template", "{item_name}")
+	v.SetDefault("download.stall_timeout", 60*time.Second)
+	v.SetDefault("download.disk_space.extract_ratio", 5.0)
+	v.SetDefault("extract.path_policy", "strict")
+	v.SetDefault("parse.output_format", "parquet")
+	v.SetDefault("parse.streaming", true)
+	v.SetDefault("parse.raw_xml.compression", "zstd")
+	v.SetDefault("parse.privacy.inventors", "keep")
+	v.SetDefault("parse.descriptor.enabled", true)
+	v.SetDefault("parse.rotation.max_rows_per_file", 0)
+	v.SetDefault("parse.classifications.cpc", []string{"CPCI"})
+	v.SetDefault("parse.classifications.ipc", []string{"IPCR", "IPC"})
+	v.SetDefault("parse.classifications.prefixes", []string{})
+	v.SetDefault("parse.classifications.normalize", false)
+	v.SetDefault("parse.merge_abstracts.enabled", false)
+	v.SetDefault("parse.merge_abstracts.input_dir", "")
+	v.SetDefault("parse.dedup.policy", "off")
+	v.SetDefault("parse.filter.countries", []string{})
+	v.SetDefault("parse.filter.kinds", []string{})
+	v.SetDefault("parse.rotation.max_bytes_per_file", 0)
+	v.SetDefault("parse.buckets.count", 0)
+	v.SetDefault("parse.resume", false)
+	v.SetDefault("parse.profile.enabled", false)
+	v.SetDefault("parse.profile.sample_every", 10)
+	v.SetDefault("parse.profile.report", "")
+	v.SetDefault("parse.contract.min_records", 0)
+	v.SetDefault("parse.contract.max_null_rate", map[string]float64{})
+	v.SetDefault("parse.contract.countries", []string{})
+	v.SetDefault("parse.family_members.enabled", false)
+	v.SetDefault("parse.family_members.path", "./family_members.csv")
+	v.SetDefault("parse.families.enabled", false)
+	v.SetDefault("parse.families.path", "./families.csv")
+	v.SetDefault("parse.citation_graph.enabled", false)
+	v.SetDefault("parse.citation_graph.format", "edgelist")
+	v.SetDefault("parse.citation_graph.path", "./citations.csv")
+	v.SetDefault("parse.kafka.enabled", false)
+	v.SetDefault("parse.kafka.brokers", []string{})
+	v.SetDefault("parse.kafka.topic", "")
+	v.SetDefault("parse.kafka.key", "patent_id")
+	v.SetDefault("parse.kafka.acks", "all")
+	v.SetDefault("parse.kafka.compression", "none")
+	v.SetDefault("parse.kafka.batch_rows", 1000)
+	v.SetDefault("parse.kafka.max_message_bytes", 1000000)
+	v.SetDefault("parse.kafka.retries", 3)
+	v.SetDefault("parse.kafka.timeout", 30*time.Second)
+	v.SetDefault("parse.kafka.tls", false)
+	v.SetDefault("parse.kafka.sasl.mechanism", "none")
+	v.SetDefault("parse.kafka.sasl.user", "")
+	v.SetDefault("parse.kafka.sasl.password", "")
+	v.SetDefault("parse.quarantine.enabled", false)
+	v.SetDefault("parse.quarantine.dir", "./quarantine")
+	v.SetDefault("parse.quarantine.move", false)
+	v.SetDefault("parse.quarantine.report", "")
+	v.SetDefault("parse.privacy.salt", "")
+	v.SetDefault("parse.date_format", "yyyymmdd")
+	v.SetDefault("parse.columns", []string{})
+	v.SetDefault("parse.xpath_fields", map[string]string{})
+	v.SetDefault("parse.postgres.dsn", "")
+	v.SetDefault("parse.postgres.schema", "public")
+	v.SetDefault("parse.postgres.replace", false)
+	v.SetDefault("parse.duckdb.partition_by", []string{})
+	v.SetDefault("parse.duckdb.export_dir", "")
+	v.SetDefault("parse.duckdb.lock_wait", 5*time.Minute)
+	v.SetDefault("parse.clickhouse.url", "")
+	v.SetDefault("parse.clickhouse.database", "default")
+	v.SetDefault("parse.clickhouse.table", "patents")
+	v.SetDefault("parse.clickhouse.user", "")
+	v.SetDefault("parse.clickhouse.password", "")
+	v.SetDefault("parse.clickhouse.batch_rows", 100000)
+	v.SetDefault("parse.clickhouse.timeout", 5*time.Minute)
+	v.SetDefault("parse.elasticsearch.url", "")
+	v.SetDefault("parse.elasticsearch.index", "patents")
+	v.SetDefault("parse.elasticsearch.create_index", true)
+	v.SetDefault("parse.elasticsearch.user", "")
+	v.SetDefault("parse.elasticsearch.password", "")
+	v.SetDefault("parse.elasticsearch.api_key", "")
+	v.SetDefault("parse.elasticsearch.batch_rows", 1000)
+	v.SetDefault("parse.elasticsearch.retries", 3)
+	v.SetDefault("parse.elasticsearch.timeout", time.Minute)
+	v.SetDefault("serve.grpc_address", "localhost:50051")
+	v.SetDefault("serve.allow_anonymous", false)
+	v.SetDefault("serve.tls.cert_file", "")
+	v.SetDefault("serve.tls.key_file", "")
+	v.SetDefault("state.path", "data/.epo-manifest.db")
+	v.SetDefault("snapshot.prefix", "docdb")
+	v.SetDefault("snapshot.format", "week")
+	v.SetDefault("pipeline.queue_size", 4)
+	v.SetDefault("pipeline.concurrent_products", false)
+	v.SetDefault("pipeline.shutdown_grace", time.Minute)
+	v.SetDefault("pipeline.on_error", "fail")
+	v.SetDefault("source.type", "api")
+	v.SetDefault("source.input", "")
 
-	err := v.ReadInConfig()
+	if flags != nil {
+		if err := bindFlags(v, flags); err != nil {
+			return Config{}, err
+		}
+	}
+	for key, value := range settings {
+		v.Set(key, value)
+	}
+
+	read := v.ReadInConfig
+	if template != "" {
+		if err := applyTemplate(v, template); err != nil {
+			return Config{}, err
+		}
+		read = v.MergeInConfig
+	}
+	err := read()
 	if err != nil {
 		if _, ok := err.(viper.ConfigFileNotFoundError); !ok {
 			return Config{}, fmt.Errorf("config read error: %w", err)
@@ -110,13 +831,210 @@ func Load(cfgFile string) (Config, error) {
 	if err := v.UnmarshalExact(&cfg); err != nil {
 		return Config{}, fmt.Errorf("unmarshal error: %w", err)
 	}
+	cfg.file = v.ConfigFileUsed()
+	cfg.resolvePaths(root)
+	if err := cfg.applySource(); err != nil {
+		return Config{}, err
+	}
+
+	if err := check(&cfg); err != nil {
+		return Config{}, err
+	}
+	if err := resolveProducts(v, &cfg, root); err != nil {
+		return Config{}, err
+	}
+	return cfg, nil
+}
+
+// check validates cfg and rejects combinations of settings that cannot work together.
+// builtinColumns are the names the record outputs already use, which parse.xpath_fields
+// cannot take.
+var builtinColumns = []string{
+	"patent_id", "status", "cpc_list", "citations", "family_patents", "titles", "abstracts",
+	"applicants", "inventors", "ipc_list", "publication_date", "npl_citations", "family_id",
+	"application_number", "filing_date", "priority_claims", "earliest_priority_date", "cpc_hierarchy",
+	"citation_provenance", "extra",
+	"title", "abstract", "ipc",
+}
+
+var xpathFieldName = regexp.MustCompile(`^[a-z][a-z0-9_]*$`)
 
+var pathPlaceholder = regexp.MustCompile(`\{[^}]*\}`)
+
+// checkPathTemplate accepts a download.path_template that names each item by its item
+// name, uses only known placeholders and stays below the download directory.
+func checkPathTemplate(tmpl string) error {
+	if !strings.Contains(tmpl, "{item_name}") {
+		return fmt.Errorf("download.path_template must contain {item_name}")
+	}
+	for _, p := range pathPlaceholder.FindAllString(tmpl, -1) {
+		if !slices.Contains([]string{
+			"{item_name}", "{delivery_id}", "{delivery_name}", "{delivery_publication_date}", "{product_id}",
+		}, p) {
+			return fmt.Errorf("download.path_template: unknown placeholder %s", p)
+		}
+	}
+	if strings.Contains(tmpl, `\`) {
+		return fmt.Errorf("download.path_template separates directories with /, on every platform")
+	}
+	if path.IsAbs(tmpl) || filepath.IsAbs(tmpl) || filepath.VolumeName(tmpl) != "" ||
+		slices.Contains(strings.Split(tmpl, "/"), "..") {
+		return fmt.Errorf("download.path_template must be a relative path below download.directory")
+	}
+	return nil
+}
+
+func check(cfg *Config) error {
 	validate := validator.New()
-	if err := validate.Struct(&cfg); err != nil {
-		return Config{}, fmt.Errorf("validation failed: %w", err)
+	if err := validate.Struct(cfg); err != nil {
+		return fmt.Errorf("validation failed: %w", err)
 	}
 	if cfg.Telemetry.Enabled && cfg.Telemetry.Exporter == "otlp" && cfg.Telemetry.Endpoint == "" {
-		return Config{}, fmt.Errorf("telemetry.endpoint is required when using otlp exporter")
+		return fmt.Errorf("telemetry.endpoint is required when using otlp exporter")
 	}
-	return cfg, nil
+	if err := checkPathTemplate(cfg.Download.PathTemplate); err != nil {
+		return err
+	}
+	if proxy := cfg.Server.Network.Proxy.URL; proxy != "" {
+		u, err := url.Parse(proxy)
+		if err != nil || !slices.Contains([]string{"http", "https", "socks5", "socks5h"}, u.Scheme) {
+			return fmt.Errorf("server.network.proxy.url must be an http, https, socks5 or socks5h URL")
+		}
+	}
+	if cfg.Parse.RawXML.Enabled && cfg.Parse.Privacy.Inventors != "" &&
+		cfg.Parse.Privacy.Inventors != "keep" {
+		return fmt.Errorf(
+			"parse.raw_xml exports unscrubbed inventor data; disable it when parse.privacy.inventors is %s",
+			cfg.Parse.Privacy.Inventors,
+		)
+	}
+	if cfg.Parse.Rotation.MaxBytesPerFile > 0 &&
+		(cfg.Parse.OutputFormat == "" || cfg.Parse.OutputFormat == "parquet") {
+		return fmt.Errorf(
+			"parse.rotation.max_bytes_per_file is not supported for parquet output; use max_rows_per_file",
+		)
+	}
+	if cfg.Parse.Buckets.Count > 0 {
+		switch {
+		case !slices.Contains([]string{"", "parquet", "jsonl"}, cfg.Parse.OutputFormat):
+			return fmt.Errorf("parse.buckets needs the parquet or jsonl output format")
+		case cfg.Parse.Rotation.MaxRowsPerFile > 0 || cfg.Parse.Rotation.MaxBytesPerFile > 0:
+			return fmt.Errorf("parse.buckets cannot be combined with parse.rotation")
+		}
+	}
+	if cfg.Parse.Resume {
+		switch {
+		case cfg.Parse.FamilyMembers.Enabled || cfg.Parse.Families.Enabled || cfg.Parse.CitationGraph.Enabled:
+			return fmt.Errorf(
+				"parse.resume cannot be combined with parse.family_members, parse.families or parse.citation_graph")
+		case !cfg.State.Enabled:
+			return fmt.Errorf("parse.resume needs state.enabled to keep the checkpoint")
+		case !slices.Contains([]string{"csv", "jsonl"}, cfg.Parse.OutputFormat) ||
+			cfg.Parse.Rotation.MaxRowsPerFile > 0 || cfg.Parse.Rotation.MaxBytesPerFile > 0 ||
+			cfg.Parse.Buckets.Count > 0:
+			return fmt.Errorf("parse.resume needs a single csv or jsonl output, without rotation or buckets")
+		}
+	}
+	if (cfg.Parse.OutputFormat == "tables" || cfg.Parse.OutputFormat == "neo4j") &&
+		(cfg.Parse.Rotation.MaxRowsPerFile > 0 || cfg.Parse.Rotation.MaxBytesPerFile > 0) {
+		return fmt.Errorf("parse.rotation is not supported for the %s output format", cfg.Parse.OutputFormat)
+	}
+	if cfg.Parse.OutputFormat == "postgres" {
+		switch {
+		case cfg.Parse.Postgres.DSN == "":
+			return fmt.Errorf("parse.postgres.dsn is required for the postgres output format")
+		case cfg.Parse.Rotation.MaxRowsPerFile > 0 || cfg.Parse.Rotation.MaxBytesPerFile > 0:
+			return fmt.Errorf("parse.rotation is not supported for the postgres output format")
+		}
+	}
+	if cfg.Parse.OutputFormat == "clickhouse" {
+		switch {
+		case cfg.Parse.ClickHouse.URL == "":
+			return fmt.Errorf("parse.clickhouse.url is required for the clickhouse output format")
+		case cfg.Parse.Rotation.MaxRowsPerFile > 0 || cfg.Parse.Rotation.MaxBytesPerFile > 0:
+			return fmt.Errorf("parse.rotation is not supported for the clickhouse output format")
+		}
+	}
+	if cfg.Parse.Kafka.Enabled && cfg.Parse.Kafka.Key == "family_id" &&
+		!slices.Contains(cfg.Parse.Fields, "family_id") {
+		return fmt.Errorf("parse.kafka.key family_id needs family_id in parse.fields")
+	}
+	if cfg.Parse.OutputFormat == "elasticsearch" {
+		switch {
+		case cfg.Parse.Elasticsearch.URL == "":
+			return fmt.Errorf("parse.elasticsearch.url is required for the elasticsearch output format")
+		case cfg.Parse.Elasticsearch.Index == "":
+			return fmt.Errorf("parse.elasticsearch.index is required for the elasticsearch output format")
+		case cfg.Parse.Rotation.MaxRowsPerFile > 0 || cfg.Parse.Rotation.MaxBytesPerFile > 0:
+			return fmt.Errorf("parse.rotation is not supported for the elasticsearch output format")
+		}
+	}
+	if cfg.Parse.OutputFormat == "duckdb" {
+		switch {
+		case cfg.Parse.Rotation.MaxRowsPerFile > 0 || cfg.Parse.Rotation.MaxBytesPerFile > 0:
+			return fmt.Errorf("parse.rotation is not supported for the duckdb output format")
+		case len(cfg.Parse.DuckDB.PartitionBy) > 0 && cfg.Parse.DuckDB.ExportDir == "":
+			return fmt.Errorf("parse.duckdb.export_dir is required with parse.duckdb.partition_by")
+		case slices.Contains(cfg.Parse.DuckDB.PartitionBy, "year") &&
+			!slices.Contains(cfg.Parse.Fields, "publication_date"):
+			return fmt.Errorf("parse.duckdb.partition_by year needs publication_date in parse.fields")
+		}
+	}
+	for name := range cfg.Parse.XPathFields {
+		if !xpathFieldName.MatchString(name) || slices.Contains(builtinColumns, name) {
+			return fmt.Errorf(
+				"parse.xpath_fields.%s must be a lowercase name of letters, digits and _ "+
+					"that is not a built-in column", name,
+			)
+		}
+	}
+	if len(cfg.Parse.Columns) > 0 {
+		if cfg.Parse.OutputFormat != "csv" {
+			return fmt.Errorf("parse.columns needs the csv output format")
+		}
+		fixed := []string{"patent_id", "status", "cpc_list", "citations", "family_patents"}
+		for _, column := range cfg.Parse.Columns {
+			if _, ok := cfg.Parse.XPathFields[column]; ok {
+				continue
+			}
+			if column == "revision" {
+				if cfg.Parse.Dedup.Policy != "keep_all" {
+					return fmt.Errorf("parse.columns lists revision, which needs parse.dedup.policy keep_all")
+				}
+				continue
+			}
+			if !slices.Contains(fixed, column) && !slices.Contains(cfg.Parse.Fields, column) {
+				return fmt.Errorf("parse.columns lists %s, which needs %s in parse.fields", column, column)
+			}
+		}
+	}
+	if policy := cfg.Parse.Dedup.Policy; (policy == "keep_first" || policy == "keep_latest") &&
+		(cfg.Pipeline.Streaming || cfg.Parse.Resume) {
+		return fmt.Errorf("parse.dedup.policy %s cannot be combined with pipeline.streaming or parse.resume", policy)
+	}
+	if cfg.Parse.MergeAbstracts.Enabled && !slices.Contains(cfg.Parse.Fields, "abstract") {
+		return fmt.Errorf("parse.merge_abstracts needs abstract in parse.fields")
+	}
+	if cfg.Parse.FromArchives && (cfg.Parse.IndexPath != "" || cfg.Parse.RawXML.Enabled) {
+		return fmt.Errorf(
+			"parse.index_path and parse.raw_xml need extracted files; disable them with parse.from_archives",
+		)
+	}
+	if isS3(cfg.Download.Directory) && cfg.Extract.Enabled && !cfg.Parse.FromArchives {
+		return fmt.Errorf("archives in S3 cannot be extracted; set parse.from_archives or disable extract")
+	}
+	if isS3(cfg.ParseInputDir()) &&
+		(cfg.Parse.IndexPath != "" || cfg.Parse.RawXML.Enabled || cfg.Parse.Quarantine.Enabled) {
+		return fmt.Errorf("parse.index_path, parse.raw_xml and parse.quarantine need a local parse input")
+	}
+	if isS3(cfg.Parse.OutputCSV) {
+		switch {
+		case !slices.Contains([]string{"", "parquet", "csv", "jsonl"}, cfg.Parse.OutputFormat):
+			return fmt.Errorf("an S3 output needs the parquet, csv or jsonl output format")
+		case cfg.Parse.Rotation.MaxRowsPerFile > 0 || cfg.Parse.Rotation.MaxBytesPerFile > 0 ||
+			cfg.Parse.Buckets.Count > 0 || cfg.Parse.Resume:
+			return fmt.Errorf("an S3 output cannot be combined with parse.rotation, parse.buckets or parse.resume")
+		}
+	}
+	return nil
 }
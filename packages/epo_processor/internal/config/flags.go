@@ -0,0 +1,151 @@
+package config
+
+import (
+	"fmt"
+	"reflect"
+	"strings"
+	"time"
+
+	"github.com/spf13/pflag"
+	"github.com/spf13/viper"
+)
+
+// flagKeyAnnotation marks the flags registered by RegisterFlags with their config key.
+const flagKeyAnnotation = "config_key"
+
+// flagAliases are short names for frequently changed keys. parse declares its own
+// --input for the XML directory, which takes precedence there; --source-input works on
+// every command.
+var flagAliases = map[string]string{
+	"log-level":        "log.log_level",
+	"download-dir":     "download.directory",
+	"explain":          "log.explain",
+	"resume":           "parse.resume",
+	"profile":          "parse.profile.enabled",
+	"metadata-offline": "server.metadata_cache.offline",
+	"source":           "source.type",
+	"input":            "source.input",
+	"source-input":     "source.input",
+}
+
+// negatedFlags disable a stage, e.g. --no-extract sets extract.enabled to false.
+var negatedFlags = map[string]string{
+	"no-download": "download.enabled",
+	"no-extract":  "extract.enabled",
+	"no-parse":    "parse.enabled",
+}
+
+var durationType = reflect.TypeOf(time.Duration(0))
+
+// RegisterFlags adds one flag per config field, named after its key with '-' for '_'
+// (e.g. --parse.output-format), plus the aliases above. Secrets (json:"-") and list of
+// struct fields are left to the config file and EPO_* environment variables.
+func RegisterFlags(fs *pflag.FlagSet) {
+	for _, f := range configFields(reflect.TypeOf(Config{}), "") {
+		name := strings.ReplaceAll(f.key, "_", "-")
+		addFlag(fs, name, f.key, f.typ)
+	}
+	for name, key := range flagAliases {
+		if f, ok := fieldByKey(key); ok {
+			addFlag(fs, name, key, f.typ)
+		}
+	}
+	for name, key := range negatedFlags {
+		fs.Bool(name, false, "Sets "+key+" to false")
+	}
+}
+
+// bindFlags makes flags set on the command line override every other source. Binding
+// also registers each key with v, so EPO_* variables apply to keys without a default.
+func bindFlags(v *viper.Viper, fs *pflag.FlagSet) error {
+	var err error
+	bind := func(f *pflag.Flag) {
+		if err == nil {
+			if bindErr := v.BindPFlag(f.Annotations[flagKeyAnnotation][0], f); bindErr != nil {
+				err = fmt.Errorf("bind flag --%s: %w", f.Name, bindErr)
+			}
+		}
+	}
+	var aliases []*pflag.Flag
+	fs.VisitAll(func(f *pflag.Flag) {
+		if key, ok := negatedFlags[f.Name]; ok {
+			if f.Changed && f.Value.String() == "true" {
+				v.Set(key, false)
+			}
+			return
+		}
+		if len(f.Annotations[flagKeyAnnotation]) != 1 {
+			return
+		}
+		if _, ok := flagAliases[f.Name]; ok {
+			aliases = append(aliases, f)
+			return
+		}
+		bind(f)
+	})
+	// an alias replaces the canonical flag's binding only when it was given
+	for _, f := range aliases {
+		if f.Changed {
+			bind(f)
+		}
+	}
+	return err
+}
+
+func addFlag(fs *pflag.FlagSet, name, key string, typ reflect.Type) {
+	usage := fmt.Sprintf("Overrides %s (env EPO_%s)",
+		key, strings.ToUpper(strings.ReplaceAll(key, ".", "_")))
+	switch {
+	case typ == durationType:
+		fs.Duration(name, 0, usage)
+	case typ.Kind() == reflect.String:
+		fs.String(name, "", usage)
+	case typ.Kind() == reflect.Bool:
+		fs.Bool(name, false, usage)
+	case typ.Kind() == reflect.Int:
+		fs.Int(name, 0, usage)
+	case typ.Kind() == reflect.Float64:
+		fs.Float64(name, 0, usage)
+	case typ.Kind() == reflect.Slice && typ.Elem().Kind() == reflect.String:
+		fs.StringSlice(name, nil, usage)
+	default:
+		return
+	}
+	_ = fs.SetAnnotation(name, flagKeyAnnotation, []string{key})
+}
+
+type configField struct {
+	key string
+	typ reflect.Type
+}
+
+// configFields lists the scalar and string list fields of t by their dotted config key.
+func configFields(t reflect.Type, prefix string) []configField {
+	var fields []configField
+	for i := 0; i < t.NumField(); i++ {
+		f := t.Field(i)
+		name := f.Tag.Get("mapstructure")
+		if name == "" || f.Tag.Get("json") == "-" {
+			continue
+		}
+		key := prefix + name
+		switch {
+		case f.Type.Kind() == reflect.Struct:
+			fields = append(fields, configFields(f.Type, key+".")...)
+		case f.Type.Kind() == reflect.Map,
+			f.Type.Kind() == reflect.Slice && f.Type.Elem().Kind() != reflect.String:
+		default:
+			fields = append(fields, configField{key: key, typ: f.Type})
+		}
+	}
+	return fields
+}
+
+func fieldByKey(key string) (configField, bool) {
+	for _, f := range configFields(reflect.TypeOf(Config{}), "") {
+		if f.key == key {
+			return f, true
+		}
+	}
+	return configField{}, false
+}
@@ -0,0 +1,128 @@
+package config
+
+import (
+	"fmt"
+	"path/filepath"
+
+	"github.com/spf13/viper"
+)
+
+// Product is one entry of products. A full run processes each entry with the rest of
+// the config, using its own product id, download directory, output and manifest.
+// Settings overrides any other key for this product only, e.g. server.max_retries,
+// server.auth or download.filter; everything it leaves out is inherited.
+type Product struct {
+	Name        string         `mapstructure:"name"         validate:"required"`
+	ProductID   int            `mapstructure:"product_id"   validate:"required"`
+	DownloadDir string         `mapstructure:"download_dir" validate:"required"`
+	Output      string         `mapstructure:"output"       validate:"required"`
+	Settings    map[string]any `mapstructure:"settings"`
+
+	// resolved is the global config with Settings merged in, set by Load.
+	resolved *Config
+}
+
+// ForProduct returns the config of a run over p: the global config with p.Settings
+// merged in, p's product id, download directory and output. Its manifest lives in
+// p.DownloadDir under the file name of state.path, and its XML input is the download
+// directory.
+func (c Config) ForProduct(p Product) Config {
+	if p.resolved != nil {
+		c = *p.resolved
+	}
+	c.Server.ProductID = p.ProductID
+	c.Download.Directory = p.DownloadDir
+	c.Parse.InputDir = ""
+	c.Parse.OutputCSV = p.Output
+	c.State.Path = filepath.Join(p.DownloadDir, filepath.Base(c.State.Path))
+	c.Products = nil
+	return c
+}
+
+// resolveProducts merges the settings of every product over the effective global
+// values in v (defaults, file, environment and flags) and checks the result, so a typo
+// in a product's settings fails at load time like one in the global config. Relative
+// paths are resolved against the project root, if any.
+func resolveProducts(v *viper.Viper, cfg *Config, root string) error {
+	if len(cfg.Products) == 0 {
+		return nil
+	}
+	global := v.AllSettings()
+	delete(global, "products")
+	for i := range cfg.Products {
+		p := &cfg.Products[i]
+		if len(p.Settings) == 0 {
+			continue
+		}
+		if _, ok := p.Settings["products"]; ok {
+			return fmt.Errorf("product %s: settings cannot contain products", p.Name)
+		}
+		pv := viper.New()
+		if err := pv.MergeConfigMap(global); err != nil {
+			return fmt.Errorf("product %s: %w", p.Name, err)
+		}
+		if err := pv.MergeConfigMap(p.Settings); err != nil {
+			return fmt.Errorf("product %s: settings: %w", p.Name, err)
+		}
+		var resolved Config
+		if err := pv.UnmarshalExact(&resolved); err != nil {
+			return fmt.Errorf("product %s: settings: %w", p.Name, err)
+		}
+		resolved.resolvePaths(root)
+		if err := check(&resolved); err != nil {
+			return fmt.Errorf("product %s: %w", p.Name, err)
+		}
+		p.resolved = &resolved
+	}
+	return checkProducts(*cfg)
+}
+
+// checkProducts rejects product entries that would share files with each other. Only a
+// DuckDB database may be shared, as its writers are serialized.
+func checkProducts(cfg Config) error {
+	seen := make(map[string]string)
+	formats := make(map[string]string)
+	type loader struct {
+		name    string
+		replace bool
+	}
+	loaders := make(map[string]loader)
+	for _, p := range cfg.Products {
+		resolved := cfg.ForProduct(p)
+		if resolved.Parse.IndexPath != "" || resolved.Parse.RawXML.Enabled ||
+			resolved.Parse.FamilyMembers.Enabled || resolved.Parse.Families.Enabled ||
+			resolved.Parse.CitationGraph.Enabled {
+			return fmt.Errorf(
+				"product %s: parse.index_path, parse.raw_xml, parse.family_members, parse.families "+
+					"and parse.citation_graph are shared files; disable them with products",
+				p.Name,
+			)
+		}
+		if resolved.Parse.OutputFormat == "postgres" {
+			target := resolved.Parse.Postgres.DSN + " " + resolved.Parse.Postgres.Schema
+			prev, ok := loaders[target]
+			if ok && (prev.replace || resolved.Parse.Postgres.Replace) {
+				return fmt.Errorf(
+					"products %q and %q load into the same postgres schema; "+
+						"parse.postgres.replace would drop the rows of the other",
+					prev.name, p.Name,
+				)
+			}
+			loaders[target] = loader{name: p.Name, replace: resolved.Parse.Postgres.Replace}
+		}
+		output := filepath.Clean(p.Output)
+		keys := []string{"name " + p.Name, "download_dir " + filepath.Clean(p.DownloadDir)}
+		shared := formats[output] == "duckdb" && resolved.Parse.OutputFormat == "duckdb"
+		if !shared {
+			keys = append(keys, "output "+output)
+		}
+		formats[output] = resolved.Parse.OutputFormat
+		for _, key := range keys {
+			if other, ok := seen[key]; ok {
+				return fmt.Errorf("products %q and %q have the same %s", other, p.Name, key)
+			}
+			seen[key] = p.Name
+		}
+	}
+	return nil
+}
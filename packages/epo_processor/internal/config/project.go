@@ -0,0 +1,128 @@
+package config
+
+import (
+	"errors"
+	"fmt"
+	"os"
+	"path/filepath"
+)
+
+// ProjectFile marks a project directory. Relative paths in it are resolved against the
+// directory rather than the working directory, so a project can be moved between
+// machines as a whole.
+const ProjectFile = "processor.yaml"
+
+// projectDirs are the directories projectTemplate refers to.
+var projectDirs = []string{"logs", "data", "output", "state", "scratch"}
+
+const projectTemplate = `# epo-processor project. Relative paths are resolved against this directory; run
+# epo-processor from here or any subdirectory, or pass --config %[1]s.
+log:
+  log_dir: logs
+  explain: "" # e.g. logs/skipped.jsonl
+server:
+  product_id: 3
+download:
+  directory: data
+parse:
+  output_csv: output/patents.parquet
+state:
+  enabled: true
+  path: state/manifest.db
+scratch:
+  dir: scratch
+`
+
+// InitProject lays out a project below dir: a ProjectFile and the directories it refers
+// to. An existing ProjectFile is only replaced with overwrite.
+func InitProject(dir string, overwrite bool) (string, error) {
+	path := filepath.Join(dir, ProjectFile)
+	if _, err := os.Stat(path); err == nil && !overwrite {
+		return "", fmt.Errorf("%s already exists", path)
+	} else if err != nil && !errors.Is(err, os.ErrNotExist) {
+		return "", err
+	}
+	for _, sub := range projectDirs {
+		if err := os.MkdirAll(filepath.Join(dir, sub), 0o755); err != nil {
+			return "", fmt.Errorf("create project directory: %w", err)
+		}
+	}
+	if err := os.WriteFile(path, fmt.Appendf(nil, projectTemplate, ProjectFile), 0o644); err != nil {
+		return "", fmt.Errorf("write %s: %w", ProjectFile, err)
+	}
+	return path, nil
+}
+
+// FindProject returns the ProjectFile in dir or the nearest parent directory.
+func FindProject(dir string) (string, bool) {
+	dir, err := filepath.Abs(dir)
+	if err != nil {
+		return "", false
+	}
+	for {
+		path := filepath.Join(dir, ProjectFile)
+		if info, err := os.Stat(path); err == nil && !info.IsDir() {
+			return path, true
+		}
+		parent := filepath.Dir(dir)
+		if parent == dir {
+			return "", false
+		}
+		dir = parent
+	}
+}
+
+// projectRoot returns the project directory when cfgFile is a ProjectFile, else "".
+func projectRoot(cfgFile string) string {
+	if filepath.Base(cfgFile) != ProjectFile {
+		return ""
+	}
+	abs, err := filepath.Abs(cfgFile)
+	if err != nil {
+		return ""
+	}
+	return filepath.Dir(abs)
+}
+
+// resolvePaths makes the relative path settings of c relative to root.
+func (c *Config) resolvePaths(root string) {
+	if root == "" {
+		return
+	}
+	paths := []*string{
+		&c.Log.LogDir,
+		&c.Log.Explain,
+		&c.Download.Directory,
+		&c.Source.Input,
+		&c.Download.HUPD.Filename,
+		&c.Parse.InputDir,
+		&c.Parse.OutputCSV,
+		&c.Parse.IndexPath,
+		&c.Parse.RawXML.Path,
+		&c.Parse.MergeAbstracts.InputDir,
+		&c.Parse.DuckDB.ExportDir,
+		&c.Parse.Profile.Report,
+		&c.Parse.FamilyMembers.Path,
+		&c.Parse.Families.Path,
+		&c.Parse.CitationGraph.Path,
+		&c.Parse.Quarantine.Dir,
+		&c.Parse.Quarantine.Report,
+		&c.State.Path,
+		&c.Scratch.Dir,
+		&c.Server.MetadataCache.Dir,
+		&c.Serve.AccessLog,
+		&c.Server.Network.TLS.CertFile,
+		&c.Server.Network.TLS.KeyFile,
+	}
+	for i := range c.Server.Network.TLS.CAFiles {
+		paths = append(paths, &c.Server.Network.TLS.CAFiles[i])
+	}
+	for i := range c.Products {
+		paths = append(paths, &c.Products[i].DownloadDir, &c.Products[i].Output)
+	}
+	for _, p := range paths {
+		if *p != "" && !filepath.IsAbs(*p) && !isS3(*p) {
+			*p = filepath.Join(root, *p)
+		}
+	}
+}
@@ -0,0 +1,133 @@
+package config
+
+import (
+	"context"
+	"fmt"
+	"os"
+	"os/signal"
+	"reflect"
+	"slices"
+	"strings"
+	"syscall"
+	"time"
+
+	"go.uber.org/zap"
+)
+
+// Reloadable lists the settings a reload may change while the pipeline runs.
+var Reloadable = []string{
+	"log.log_level",
+	"server.concurrent_downloads",
+}
+
+// File returns the config file c was read from, or "" when it came from defaults,
+// environment and flags alone.
+func (c Config) File() string {
+	return c.file
+}
+
+// Watch calls load on SIGHUP and, with reload.watch, when the config file changes, until
+// ctx is done. A config that loads and differs from the current one only in Reloadable
+// settings is passed to apply and becomes the current one; otherwise the reload is
+// logged and dropped, and the pipeline keeps running on the current config.
+func Watch(
+	ctx context.Context,
+	current Config,
+	load func() (Config, error),
+	apply func(Config),
+	logger *zap.SugaredLogger,
+) {
+	hup := make(chan os.Signal, 1)
+	signal.Notify(hup, syscall.SIGHUP)
+	defer signal.Stop(hup)
+
+	var tick <-chan time.Time
+	modTime := fileModTime(current.file)
+	if current.Reload.Watch && current.file != "" && current.Reload.Interval > 0 {
+		ticker := time.NewTicker(current.Reload.Interval)
+		defer ticker.Stop()
+		tick = ticker.C
+	}
+	for {
+		select {
+		case <-ctx.Done():
+			return
+		case <-hup:
+			logger.Infow("Reloading config", "trigger", "SIGHUP", "file", current.file)
+		case <-tick:
+			mt := fileModTime(current.file)
+			if mt.Equal(modTime) {
+				continue
+			}
+			modTime = mt
+			logger.Infow("Reloading config", "trigger", "file changed", "file", current.file)
+		}
+		next, err := load()
+		if err != nil {
+			logger.Errorw("Config reload rejected", "err", err)
+			continue
+		}
+		changed := Changed(current, next)
+		if fixed := slices.DeleteFunc(slices.Clone(changed), func(key string) bool {
+			return slices.Contains(Reloadable, key)
+		}); len(fixed) > 0 {
+			logger.Errorw("Config reload rejected, settings cannot change while running",
+				"settings", fixed, "reloadable", Reloadable)
+			continue
+		}
+		if len(changed) == 0 {
+			logger.Infow("Config unchanged")
+			continue
+		}
+		apply(next)
+		current = next
+		logger.Infow("Config reloaded", "settings", changed)
+	}
+}
+
+// Changed returns the keys of the settings that differ between a and b, such as
+// "server.concurrent_downloads" or "products[1].output"; a changed map or list of values
+// counts as one setting.
+func Changed(a, b Config) []string {
+	var keys []string
+	diff(reflect.ValueOf(a), reflect.ValueOf(b), "", &keys)
+	return keys
+}
+
+func diff(a, b reflect.Value, prefix string, keys *[]string) {
+	// lists of structs, like products, are compared by their exported fields only
+	if a.Kind() == reflect.Slice && a.Type().Elem().Kind() == reflect.Struct && a.Len() == b.Len() {
+		for i := range a.Len() {
+			diff(a.Index(i), b.Index(i), fmt.Sprintf("%s[%d]", prefix, i), keys)
+		}
+		return
+	}
+	if a.Kind() != reflect.Struct || a.Type() == reflect.TypeFor[time.Time]() {
+		if !reflect.DeepEqual(a.Interface(), b.Interface()) {
+			*keys = append(*keys, prefix)
+		}
+		return
+	}
+	for i := range a.NumField() {
+		field := a.Type().Field(i)
+		if !field.IsExported() {
+			continue
+		}
+		name, _, _ := strings.Cut(field.Tag.Get("mapstructure"), ",")
+		if name == "" {
+			name = strings.ToLower(field.Name)
+		}
+		if prefix != "" {
+			name = prefix + "." + name
+		}
+		diff(a.Field(i), b.Field(i), name, keys)
+	}
+}
+
+func fileModTime(path string) time.Time {
+	info, err := os.Stat(path)
+	if err != nil {
+		return time.Time{}
+	}
+	return info.ModTime()
+}
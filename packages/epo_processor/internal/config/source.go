@@ -0,0 +1,34 @@
+package config
+
+import "fmt"
+
+// Source is where the pipeline takes its data from. With type api (the default) items are
+// downloaded from the EPO API into download.directory. With type local the API is never
+// contacted: extract and parse work on the archives and XML files already in Input, e.g.
+// a copy obtained by other means.
+type Source struct {
+	Type  string `mapstructure:"type"  validate:"oneof=api local"`
+	Input string `mapstructure:"input" validate:"required_if=Type local"`
+}
+
+// Local reports whether the pipeline works on a local directory instead of the API.
+func (s Source) Local() bool {
+	return s.Type == "local"
+}
+
+// applySource points the stages at source.input when the source is local: the download
+// stage is disabled and extract reads the archives from, and parse the XML below, the
+// input directory. Archives are never deleted there, as they are not ours to remove.
+func (c *Config) applySource() error {
+	if !c.Source.Local() {
+		return nil
+	}
+	if len(c.Products) > 0 {
+		return fmt.Errorf("source.type local cannot be combined with products")
+	}
+	c.Download.Enabled = false
+	c.Download.HUPD.Enabled = false
+	c.Download.Directory = c.Source.Input
+	c.Extract.DeleteAfterExtract = false
+	return nil
+}
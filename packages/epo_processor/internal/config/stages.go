@@ -0,0 +1,39 @@
+package config
+
+import (
+	"fmt"
+	"slices"
+	"strings"
+)
+
+// Stages are the pipeline stages in execution order, as named by --only and --skip.
+var Stages = []string{"download", "extract", "parse"}
+
+// SelectStages enables only the stages in only, or disables those in skip, overriding
+// the <stage>.enabled keys. At most one of the two lists may be given.
+func SelectStages(c *Config, only, skip []string) error {
+	if len(only) > 0 && len(skip) > 0 {
+		return fmt.Errorf("--only and --skip cannot be combined")
+	}
+	for _, name := range append(slices.Clone(only), skip...) {
+		if !slices.Contains(Stages, name) {
+			return fmt.Errorf("unknown stage %q (want %s)", name, strings.Join(Stages, ", "))
+		}
+	}
+	enabled := map[string]*bool{
+		"download": &c.Download.Enabled,
+		"extract":  &c.Extract.Enabled,
+		"parse":    &c.Parse.Enabled,
+	}
+	switch {
+	case len(only) > 0:
+		for name, on := range enabled {
+			*on = slices.Contains(only, name)
+		}
+	case len(skip) > 0:
+		for _, name := range skip {
+			*enabled[name] = false
+		}
+	}
+	return nil
+}
@@ -0,0 +1,44 @@
+package config
+
+import (
+	"bytes"
+	"embed"
+	"fmt"
+	"io/fs"
+	"path"
+	"sort"
+	"strings"
+
+	"github.com/spf13/viper"
+)
+
+// Built-in job templates prefill the config for common products. Values from the
+// config file, environment and flags still take precedence.
+//
+//go:embed templates/*.yaml
+var templateFS embed.FS
+
+// Templates returns the names of the built-in job templates.
+func Templates() []string {
+	entries, _ := fs.ReadDir(templateFS, "templates")
+	names := make([]string, 0, len(entries))
+	for _, e := range entries {
+		names = append(names, strings.TrimSuffix(e.Name(), path.Ext(e.Name())))
+	}
+	sort.Strings(names)
+	return names
+}
+
+// applyTemplate merges the named template into v.
+func applyTemplate(v *viper.Viper, name string) error {
+	data, err := templateFS.ReadFile("templates/" + name + ".yaml")
+	if err != nil {
+		return fmt.Errorf("unknown template %q (available: %s)", name, strings.Join(Templates(), ", "))
+	}
+	tv := viper.New()
+	tv.SetConfigType("yaml")
+	if err := tv.ReadConfig(bytes.NewReader(data)); err != nil {
+		return fmt.Errorf("template %s: %w", name, err)
+	}
+	return v.MergeConfigMap(tv.AllSettings())
+}
@@ -0,0 +1,152 @@
+package contract
+
+import (
+	"fmt"
+	"maps"
+	"slices"
+	"strings"
+	"sync"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// empty reports for each column of the output whether it holds no value in a record.
+// The names are those of the Parquet and JSONL outputs.
+var empty = map[string]func(models.PatentRecord) bool{
+	"status":                 func(r models.PatentRecord) bool { return r.Status == "" },
+	"cpc_list":               func(r models.PatentRecord) bool { return len(r.CPCList) == 0 },
+	"citations":              func(r models.PatentRecord) bool { return len(r.Citations) == 0 },
+	"family_patents":         func(r models.PatentRecord) bool { return len(r.FamilyPatents) == 0 },
+	"titles":                 func(r models.PatentRecord) bool { return len(r.Titles) == 0 },
+	"abstracts":              func(r models.PatentRecord) bool { return len(r.Abstracts) == 0 },
+	"applicants":             func(r models.PatentRecord) bool { return len(r.Applicants) == 0 },
+	"inventors":              func(r models.PatentRecord) bool { return len(r.Inventors) == 0 },
+	"ipc_list":               func(r models.PatentRecord) bool { return len(r.IPCList) == 0 },
+	"publication_date":       func(r models.PatentRecord) bool { return r.PublicationDate == "" },
+	"npl_citations":          func(r models.PatentRecord) bool { return len(r.NPLCitations) == 0 },
+	"family_id":              func(r models.PatentRecord) bool { return r.FamilyID == "" },
+	"application_number":     func(r models.PatentRecord) bool { return r.ApplicationNumber == "" },
+	"filing_date":            func(r models.PatentRecord) bool { return r.FilingDate == "" },
+	"priority_claims":        func(r models.PatentRecord) bool { return len(r.PriorityClaims) == 0 },
+	"earliest_priority_date": func(r models.PatentRecord) bool { return r.EarliestPriorityDate == "" },
+	"cpc_hierarchy":          func(r models.PatentRecord) bool { return len(r.CPCHierarchy) == 0 },
+	"citation_provenance":    func(r models.PatentRecord) bool { return len(r.CitationProvenance) == 0 },
+}
+
+// EmptyColumns counts for each column the records in which it holds no value.
+func EmptyColumns(records []models.PatentRecord) map[string]int {
+	counts := make(map[string]int, len(empty))
+	for _, r := range records {
+		for column, isEmpty := range empty {
+			if isEmpty(r) {
+				counts[column]++
+			}
+		}
+	}
+	return counts
+}
+
+// Violations lists the expectations of parse.contract that the output of a run does not
+// meet.
+type Violations []string
+
+func (v Violations) Error() string {
+	return "data contract violated: " + strings.Join(v, "; ")
+}
+
+// Tally counts the records written by a run, their empty columns and their countries.
+// A nil Tally counts nothing.
+type Tally struct {
+	mu        sync.Mutex
+	records   int64
+	nulls     map[string]int64
+	countries map[string]int64
+}
+
+// NewTally returns a tally for c, or nil when c declares no expectations.
+func NewTally(c config.Contract) *Tally {
+	if c.MinRecords == 0 && len(c.MaxNullRate) == 0 && len(c.Countries) == 0 {
+		return nil
+	}
+	return &Tally{nulls: map[string]int64{}, countries: map[string]int64{}}
+}
+
+// Seed counts rows written before the tally started, e.g. by the run a resumed parse
+// continues. Only the record count includes them.
+func (t *Tally) Seed(rows int64) {
+	if t == nil {
+		return
+	}
+	t.mu.Lock()
+	defer t.mu.Unlock()
+	t.records += rows
+}
+
+// Add counts the records of one write.
+func (t *Tally) Add(records []models.PatentRecord) {
+	if t == nil {
+		return
+	}
+	t.mu.Lock()
+	defer t.mu.Unlock()
+	for _, r := range records {
+		t.records++
+		for column, isEmpty := range empty {
+			if isEmpty(r) {
+				t.nulls[column]++
+			}
+		}
+		t.countries[country(r.PatentID)]++
+	}
+}
+
+// Check returns the expectations of c that the counted records violate, as Violations,
+// or nil if they meet all of them.
+func (t *Tally) Check(c config.Contract) error {
+	if t == nil {
+		return nil
+	}
+	t.mu.Lock()
+	defer t.mu.Unlock()
+	var v Violations
+	if t.records < c.MinRecords {
+		v = append(v, fmt.Sprintf("%d records, expected at least %d", t.records, c.MinRecords))
+	}
+	for _, column := range slices.Sorted(maps.Keys(c.MaxNullRate)) {
+		if t.records == 0 {
+			break
+		}
+		rate := float64(t.nulls[column]) / float64(t.records)
+		if limit := c.MaxNullRate[column]; rate > limit {
+			v = append(v, fmt.Sprintf("%s is empty in %.1f%% of the records, allowed %.1f%%",
+				column, 100*rate, 100*limit))
+		}
+	}
+	if len(c.Countries) > 0 {
+		var unexpected []string
+		var count int64
+		for _, cc := range slices.Sorted(maps.Keys(t.countries)) {
+			if !slices.Contains(c.Countries, cc) {
+				unexpected = append(unexpected, cc)
+				count += t.countries[cc]
+			}
+		}
+		if len(unexpected) > 0 {
+			v = append(v, fmt.Sprintf("%d records from countries outside the allowed set: %s",
+				count, strings.Join(unexpected, ", ")))
+		}
+	}
+	if len(v) == 0 {
+		return nil
+	}
+	return v
+}
+
+// country is the publishing office of a patent id, its two-letter prefix.
+func country(patentID string) string {
+	if len(patentID) < 2 {
+		return patentID
+	}
+	return patentID[:2]
+}
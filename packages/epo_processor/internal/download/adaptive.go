@@ -0,0 +1,105 @@
+package download
+
+import (
+	"context"
+	"sync"
+
+	"go.uber.org/zap"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+)
+
+// aimdLimiter caps in-flight requests with an additive-increase/multiplicative-decrease
+// limit. Every window of finished requests it either grows the limit by one or, when the
+// share of 5xx/timeout failures exceeds the threshold, scales it down by the factor.
+type aimdLimiter struct {
+	mu        sync.Mutex
+	cond      *sync.Cond
+	limit     int
+	minLimit  int
+	maxLimit  int
+	inFlight  int
+	window    int
+	threshold float64
+	factor    float64
+	samples   int
+	failures  int
+	logger    *zap.SugaredLogger
+}
+
+func newAIMDLimiter(
+	cfg config.AdaptiveConcurrency,
+	maxLimit int,
+	logger *zap.SugaredLogger,
+) *aimdLimiter {
+	l := &aimdLimiter{
+		limit:     maxLimit,
+		minLimit:  min(max(cfg.MinConcurrency, 1), maxLimit),
+		maxLimit:  maxLimit,
+		window:    max(cfg.Window, 1),
+		threshold: cfg.ErrorThreshold,
+		factor:    cfg.DecreaseFactor,
+		logger:    logger,
+	}
+	l.cond = sync.NewCond(&l.mu)
+	return l
+}
+
+// Acquire blocks until a slot below the current limit is free or ctx is done.
+func (l *aimdLimiter) Acquire(ctx context.Context) error {
+	stop := context.AfterFunc(ctx, func() {
+		l.mu.Lock()
+		l.cond.Broadcast()
+		l.mu.Unlock()
+	})
+	defer stop()
+	l.mu.Lock()
+	defer l.mu.Unlock()
+	for l.inFlight >= l.limit {
+		if err := ctx.Err(); err != nil {
+			return err
+		}
+		l.cond.Wait()
+	}
+	l.inFlight++
+	return nil
+}
+
+// Release frees a slot and feeds the request outcome into the current window.
+func (l *aimdLimiter) Release(err error) {
+	l.mu.Lock()
+	defer l.mu.Unlock()
+	l.inFlight--
+	l.samples++
+	if isOverloadError(err) {
+		l.failures++
+	}
+	if l.samples >= l.window {
+		rate := float64(l.failures) / float64(l.samples)
+		prev := l.limit
+		if rate > l.threshold {
+			l.limit = max(l.minLimit, int(float64(l.limit)*l.factor))
+		} else if l.limit < l.maxLimit {
+			l.limit++
+		}
+		l.samples, l.failures = 0, 0
+		if l.limit != prev {
+			l.logger.Infow("Adjusted download concurrency",
+				"from", prev,
+				"to", l.limit,
+				"error_rate", rate)
+		}
+	}
+	l.cond.Broadcast()
+}
+
+// SetMax moves the upper bound of the limit, e.g. after server.concurrent_downloads was
+// reloaded. A lower bound takes effect as requests finish; in-flight ones are not stopped.
+func (l *aimdLimiter) SetMax(maxLimit int) {
+	l.mu.Lock()
+	defer l.mu.Unlock()
+	l.maxLimit = maxLimit
+	l.minLimit = min(l.minLimit, maxLimit)
+	l.limit = min(l.limit, maxLimit)
+	l.cond.Broadcast()
+}
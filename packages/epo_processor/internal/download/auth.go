@@ -0,0 +1,152 @@
+package download
+
+import (
+	"context"
+	"encoding/json"
+	"fmt"
+	"net/http"
+	"net/url"
+	"strings"
+	"sync"
+	"time"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+)
+
+// tokenRefreshMargin renews OAuth2 tokens this long before they expire so that a long
+// download never starts with a token that lapses mid-request.
+const tokenRefreshMargin = 30 * time.Second
+
+// authTransport adds EPO API credentials to every request. With OAuth2 a 401 drops the
+// cached token and retries the request once with a fresh one.
+type authTransport struct {
+	cfg    config.Auth
+	base   http.RoundTripper
+	tokens *clientCredentials
+}
+
+func newAuthTransport(cfg config.Auth, base http.RoundTripper) http.RoundTripper {
+	switch cfg.Type {
+	case "", "none":
+		return base
+	case "oauth2":
+		return &authTransport{
+			cfg:    cfg,
+			base:   base,
+			tokens: &clientCredentials{cfg: cfg, client: &http.Client{Transport: base}},
+		}
+	default:
+		return &authTransport{cfg: cfg, base: base}
+	}
+}
+
+func (t *authTransport) RoundTrip(req *http.Request) (*http.Response, error) {
+	authed, err := t.authorize(req)
+	if err != nil {
+		return nil, err
+	}
+	resp, err := t.base.RoundTrip(authed)
+	if err != nil || t.tokens == nil || resp.StatusCode != http.StatusUnauthorized {
+		return resp, err
+	}
+	replayable := req.Body == nil || req.GetBody != nil
+	if !replayable {
+		return resp, nil
+	}
+	resp.Body.Close()
+	t.tokens.invalidate()
+	retry := req.Clone(req.Context())
+	if req.GetBody != nil {
+		if retry.Body, err = req.GetBody(); err != nil {
+			return nil, err
+		}
+	}
+	if authed, err = t.authorize(retry); err != nil {
+		return nil, err
+	}
+	return t.base.RoundTrip(authed)
+}
+
+// authorize returns a copy of req carrying the configured credentials.
+func (t *authTransport) authorize(req *http.Request) (*http.Request, error) {
+	out := req.Clone(req.Context())
+	switch t.cfg.Type {
+	case "bearer":
+		out.Header.Set("Authorization", "Bearer "+t.cfg.Token)
+	case "api_key":
+		out.Header.Set(t.cfg.APIKeyHeader, t.cfg.APIKey)
+	case "oauth2":
+		token, err := t.tokens.token(req.Context())
+		if err != nil {
+			return nil, err
+		}
+		out.Header.Set("Authorization", "Bearer "+token)
+	}
+	return out, nil
+}
+
+// clientCredentials caches an OAuth2 client-credentials access token and refreshes it
+// shortly before expiry. Concurrent downloads share one token.
+type clientCredentials struct {
+	cfg     config.Auth
+	client  *http.Client
+	mu      sync.Mutex
+	access  string
+	expires time.Time
+}
+
+type tokenResponse struct {
+	AccessToken string `json:"access_token"`
+	TokenType   string `json:"token_type"`
+	ExpiresIn   int64  `json:"expires_in"`
+}
+
+func (c *clientCredentials) token(ctx context.Context) (string, error) {
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	if c.access != "" && time.Now().Add(tokenRefreshMargin).Before(c.expires) {
+		return c.access, nil
+	}
+	form := url.Values{"grant_type": {"client_credentials"}}
+	if len(c.cfg.Scopes) > 0 {
+		form.Set("scope", strings.Join(c.cfg.Scopes, " "))
+	}
+	req, err := http.NewRequestWithContext(
+		ctx,
+		http.MethodPost,
+		c.cfg.TokenURL,
+		strings.NewReader(form.Encode()),
+	)
+	if err != nil {
+		return "", err
+	}
+	req.Header.Set("Content-Type", "application/x-www-form-urlencoded")
+	req.SetBasicAuth(url.QueryEscape(c.cfg.ClientID), url.QueryEscape(c.cfg.ClientSecret))
+	resp, err := c.client.Do(req)
+	if err != nil {
+		return "", fmt.Errorf("oauth2 token request: %w", err)
+	}
+	defer resp.Body.Close()
+	if resp.StatusCode != http.StatusOK {
+		return "", fmt.Errorf("oauth2 token request: %w", &HTTPStatusError{StatusCode: resp.StatusCode})
+	}
+	var tok tokenResponse
+	if err := json.NewDecoder(resp.Body).Decode(&tok); err != nil {
+		return "", fmt.Errorf("oauth2 token response: %w", err)
+	}
+	if tok.AccessToken == "" {
+		return "", fmt.Errorf("oauth2 token response without access_token")
+	}
+	c.access = tok.AccessToken
+	c.expires = time.Now().Add(time.Duration(tok.ExpiresIn) * time.Second)
+	if tok.ExpiresIn <= 0 {
+		c.expires = time.Now().Add(time.Hour)
+	}
+	return c.access, nil
+}
+
+func (c *clientCredentials) invalidate() {
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	c.access = ""
+}
@@ -0,0 +1,64 @@
+package download
+
+import (
+	"context"
+	"fmt"
+
+	"github.com/IBM/fp-go/v2/array"
+	F "github.com/IBM/fp-go/v2/function"
+	IOE "github.com/IBM/fp-go/v2/ioeither"
+	Http "github.com/IBM/fp-go/v2/ioeither/http"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// ProductInfo is a product the EPO API offers.
+type ProductInfo struct {
+	ID          uint32 `json:"id"`
+	Name        string `json:"name"`
+	Description string `json:"description,omitempty"`
+}
+
+// DeliveryInfo is a delivery of a product with the number and total size of its items.
+// Published is the publication date (YYYY-MM-DD), empty when the API has none.
+type DeliveryInfo struct {
+	ID        uint32 `json:"id"`
+	Name      string `json:"name"`
+	Published string `json:"published,omitempty"`
+	Items     int    `json:"items"`
+	Size      int64  `json:"size"`
+}
+
+// Products lists the products the EPO API offers.
+func (downloader *Downloader) Products(ctx context.Context) IOE.IOEither[error, []ProductInfo] {
+	client := Http.MakeClient(downloader.httpClient())
+	return F.Pipe2(
+		Http.MakeGetRequest(fmt.Sprintf("%s/products", downloader.Cfg.Server.BaseURL)),
+		Http.ReadJSON[[]models.Product](client),
+		IOE.Map[error](array.Map(func(p models.Product) ProductInfo {
+			return ProductInfo{ID: p.Id, Name: p.Name, Description: p.Description}
+		})),
+	)
+}
+
+// Deliveries lists every delivery of server.product_id, following the pagination of the
+// product. Unlike a download it ignores download.filter and the incremental watermark.
+func (downloader *Downloader) Deliveries(ctx context.Context) IOE.IOEither[error, []DeliveryInfo] {
+	client := Http.MakeClient(downloader.httpClient())
+	url := fmt.Sprintf("%s/products/%d", downloader.Cfg.Server.BaseURL, downloader.Cfg.Server.ProductID)
+	return F.Pipe1(
+		downloader.fetchProductPages(ctx, client, url, map[string]bool{url: true}),
+		IOE.Map[error](func(p models.Product) []DeliveryInfo {
+			return array.Map(func(d models.Delivery) DeliveryInfo {
+				info := DeliveryInfo{ID: d.DeliveryID, Name: d.DeliveryName, Items: len(d.Items)}
+				if published, err := d.PublishedAt(); err == nil {
+					info.Published = published.Format("2006-01-02")
+				}
+				for _, item := range d.Items {
+					info.Size += parseFileSize(item.FileSize)
+				}
+				return info
+			})(p.Deliveries)
+		}),
+	)
+}
@@ -0,0 +1,92 @@
+package download
+
+import (
+	"compress/gzip"
+	"compress/zlib"
+	"fmt"
+	"io"
+	"net/http"
+	"strings"
+
+	"github.com/klauspost/compress/zstd"
+)
+
+// acceptEncoding lists the transfer encodings decoded by compressionTransport.
+const acceptEncoding = "zstd, gzip, deflate"
+
+// compressionTransport negotiates compressed responses and hands callers the decoded
+// body, so byte counts, progress and checksums always refer to the decoded size.
+type compressionTransport struct {
+	base http.RoundTripper
+}
+
+func newCompressionTransport(enabled bool, base http.RoundTripper) http.RoundTripper {
+	if !enabled {
+		return base
+	}
+	return &compressionTransport{base: base}
+}
+
+func (t *compressionTransport) RoundTrip(req *http.Request) (*http.Response, error) {
+	if req.Header.Get("Accept-Encoding") != "" || req.Header.Get("Range") != "" {
+		return t.base.RoundTrip(req)
+	}
+	out := req.Clone(req.Context())
+	out.Header.Set("Accept-Encoding", acceptEncoding)
+	resp, err := t.base.RoundTrip(out)
+	if err != nil {
+		return nil, err
+	}
+	encoding := strings.ToLower(strings.TrimSpace(resp.Header.Get("Content-Encoding")))
+	if encoding == "" || encoding == "identity" {
+		return resp, nil
+	}
+	body, err := decodeBody(encoding, resp.Body)
+	if err != nil {
+		resp.Body.Close()
+		return nil, err
+	}
+	resp.Body = body
+	resp.Header.Del("Content-Encoding")
+	resp.Header.Del("Content-Length")
+	resp.ContentLength = -1
+	resp.Uncompressed = true
+	return resp, nil
+}
+
+// decodedBody closes the decoder together with the underlying response body.
+type decodedBody struct {
+	io.Reader
+	closeDecoder func()
+	body         io.Closer
+}
+
+func (b *decodedBody) Close() error {
+	b.closeDecoder()
+	return b.body.Close()
+}
+
+func decodeBody(encoding string, body io.ReadCloser) (io.ReadCloser, error) {
+	switch encoding {
+	case "gzip", "x-gzip":
+		r, err := gzip.NewReader(body)
+		if err != nil {
+			return nil, fmt.Errorf("gzip response: %w", err)
+		}
+		return &decodedBody{Reader: r, closeDecoder: func() { r.Close() }, body: body}, nil
+	case "deflate":
+		r, err := zlib.NewReader(body)
+		if err != nil {
+			return nil, fmt.Errorf("deflate response: %w", err)
+		}
+		return &decodedBody{Reader: r, closeDecoder: func() { r.Close() }, body: body}, nil
+	case "zstd":
+		r, err := zstd.NewReader(body, zstd.WithDecoderConcurrency(1))
+		if err != nil {
+			return nil, fmt.Errorf("zstd response: %w", err)
+		}
+		return &decodedBody{Reader: r, closeDecoder: r.Close, body: body}, nil
+	default:
+		return nil, fmt.Errorf("unsupported content encoding %q", encoding)
+	}
+}
@@ -0,0 +1,98 @@
+package download
+
+import (
+	"context"
+	"crypto/tls"
+	"errors"
+	"net"
+	"net/http"
+	"sort"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+)
+
+// dialer applies server.network: static host overrides, address-family restrictions and,
+// for the prefer_* families, a sequential fallback across the resolved addresses where
+// each attempt is bounded by the connect timeout. With the auto family the standard
+// Happy Eyeballs race between A and AAAA records is used.
+type dialer struct {
+	net      net.Dialer
+	resolver *net.Resolver
+	family   string
+	hosts    map[string]string
+}
+
+func newDialer(cfg config.Network) *dialer {
+	hosts := make(map[string]string, len(cfg.Hosts))
+	for _, h := range cfg.Hosts {
+		hosts[h.Host] = h.Address
+	}
+	return &dialer{
+		net: net.Dialer{
+			Timeout:       cfg.ConnectTimeout,
+			FallbackDelay: cfg.FallbackDelay,
+		},
+		resolver: net.DefaultResolver,
+		family:   cfg.IPFamily,
+		hosts:    hosts,
+	}
+}
+
+// newBaseTransport returns the default transport dialing through server.network and
+// its proxy, waiting at most the read timeout for a response; tlsConfig replaces the
+// default TLS settings unless nil.
+func newBaseTransport(cfg config.Network, tlsConfig *tls.Config) http.RoundTripper {
+	t := http.DefaultTransport.(*http.Transport).Clone()
+	t.DialContext = newDialer(cfg).DialContext
+	t.ResponseHeaderTimeout = cfg.ReadTimeout
+	t.Proxy = proxyFunc(cfg.Proxy)
+	if tlsConfig != nil {
+		t.TLSClientConfig = tlsConfig.Clone()
+	}
+	return t
+}
+
+func (d *dialer) DialContext(ctx context.Context, network, addr string) (net.Conn, error) {
+	host, port, err := net.SplitHostPort(addr)
+	if err != nil {
+		return nil, err
+	}
+	if override, ok := d.hosts[host]; ok {
+		host = override
+	}
+	switch d.family {
+	case "ipv4":
+		return d.net.DialContext(ctx, "tcp4", net.JoinHostPort(host, port))
+	case "ipv6":
+		return d.net.DialContext(ctx, "tcp6", net.JoinHostPort(host, port))
+	case "prefer_ipv4", "prefer_ipv6":
+		return d.dialPreferred(ctx, host, port)
+	default:
+		return d.net.DialContext(ctx, network, net.JoinHostPort(host, port))
+	}
+}
+
+// dialPreferred tries the preferred family first and falls back to the other one when
+// a connection attempt fails or times out.
+func (d *dialer) dialPreferred(ctx context.Context, host, port string) (net.Conn, error) {
+	addrs, err := d.resolver.LookupIPAddr(ctx, host)
+	if err != nil {
+		return nil, err
+	}
+	wantV4 := d.family == "prefer_ipv4"
+	sort.SliceStable(addrs, func(i, j int) bool {
+		return (addrs[i].IP.To4() != nil) == wantV4 && (addrs[j].IP.To4() != nil) != wantV4
+	})
+	var errs []error
+	for _, addr := range addrs {
+		conn, err := d.net.DialContext(ctx, "tcp", net.JoinHostPort(addr.IP.String(), port))
+		if err == nil {
+			return conn, nil
+		}
+		errs = append(errs, err)
+		if ctx.Err() != nil {
+			break
+		}
+	}
+	return nil, errors.Join(errs...)
+}
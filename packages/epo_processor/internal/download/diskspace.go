@@ -0,0 +1,95 @@
+package download
+
+import (
+	"errors"
+	"fmt"
+	"os"
+	"path/filepath"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/storage"
+)
+
+// ErrDiskSpace is returned when download.disk_space.check is abort and the queued items
+// do not fit on the filesystem of the download directory.
+var ErrDiskSpace = errors.New("not enough disk space")
+
+// checkDiskSpace compares the space items need against the free space of the download
+// directory, before any of them is downloaded. A shortfall is logged, or returned with
+// download.disk_space.check abort. A directory in S3 is not checked.
+func (downloader *Downloader) checkDiskSpace(items []DownloadFile) error {
+	cfg := downloader.Cfg.Download.DiskSpace
+	dir := downloader.Cfg.Download.Directory
+	if cfg.Check == "off" || storage.IsS3(dir) {
+		return nil
+	}
+	need := downloader.requiredSpace(items)
+	if need == 0 {
+		return nil
+	}
+	free, err := freeSpace(existingParent(dir))
+	if err != nil {
+		downloader.Logger.Warnw("Cannot determine free disk space, skipping the check", "dir", dir, "err", err)
+		return nil
+	}
+	downloader.Logger.Infow("Disk space", "dir", dir, "needed", FormatBytes(need), "free", FormatBytes(free))
+	if need <= free {
+		return nil
+	}
+	err = fmt.Errorf("%w in %s: the run needs about %s, %s is free",
+		ErrDiskSpace, dir, FormatBytes(need), FormatBytes(free))
+	if cfg.Check == "abort" {
+		return err
+	}
+	downloader.Logger.Warnw("The run may fill the disk", "err", err)
+	return nil
+}
+
+// requiredSpace estimates the bytes items still take up in the download directory: the
+// part of each item that is not on disk yet and, when extract unpacks the archives next
+// to them, download.disk_space.extract_ratio times as much for the extracted files.
+func (downloader *Downloader) requiredSpace(items []DownloadFile) int64 {
+	var need int64
+	for _, f := range items {
+		if fi, err := os.Stat(f.filePath); err == nil && fi.Size() == f.expectedSize {
+			continue
+		}
+		remaining := f.expectedSize
+		if fi, err := os.Stat(f.partPath()); err == nil {
+			remaining -= fi.Size()
+		}
+		need += max(remaining, 0)
+	}
+	if downloader.Cfg.Extract.Enabled && !downloader.Cfg.Parse.FromArchives {
+		need += int64(float64(need) * downloader.Cfg.Download.DiskSpace.ExtractRatio)
+	}
+	return need
+}
+
+// existingParent returns dir or, if it does not exist yet, its closest parent that does.
+func existingParent(dir string) string {
+	dir = filepath.Clean(dir)
+	for {
+		if _, err := os.Stat(dir); err == nil {
+			return dir
+		}
+		parent := filepath.Dir(dir)
+		if parent == dir {
+			return dir
+		}
+		dir = parent
+	}
+}
+
+// FormatBytes renders n in binary units, e.g. 1.5 GiB.
+func FormatBytes(n int64) string {
+	const unit = 1 << 10
+	if n < unit {
+		return fmt.Sprintf("%d B", n)
+	}
+	div, exp := int64(unit), 0
+	for m := n / unit; m >= unit; m /= unit {
+		div *= unit
+		exp++
+	}
+	return fmt.Sprintf("%.1f %ciB", float64(n)/float64(div), "KMGTPE"[exp])
+}
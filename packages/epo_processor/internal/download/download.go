@@ -3,12 +3,15 @@ package download
 import (
 	"context"
 	"crypto/sha1"
+	"crypto/tls"
 	"encoding/hex"
+	"errors"
 	"fmt"
 	"io"
 	"math"
 	"net/http"
 	"os"
+	"path"
 	"path/filepath"
 	"regexp"
 	"strconv"
@@ -30,9 +33,16 @@ import (
 	"go.opentelemetry.io/otel/metric"
 	"go.opentelemetry.io/otel/trace"
 	"go.uber.org/zap"
+	"golang.org/x/sync/errgroup"
 
 	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/explain"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/failures"
 	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/shutdown"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/snapshot"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/state"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/storage"
 	T "github.com/Qubut/IP-Claim/packages/epo_processor/internal/typing"
 )
 
@@ -49,6 +59,16 @@ type Downloader struct {
 	downloadFilesFailed     metric.Int64Counter
 	downloadBytesTotal      metric.Int64Counter
 	downloadFileDuration    metric.Int64Histogram
+	limiter                 *aimdLimiter
+	throttle                throttle
+	bucket                  *tokenBucket
+	files                   *gate
+	tlsConfig               *tls.Config
+	Manifest                *state.Manifest
+	Explain                 *explain.Report
+	Storage                 *storage.S3
+	snapshot                string
+	newest                  time.Time
 }
 
 type DownloadFile struct {
@@ -57,6 +77,8 @@ type DownloadFile struct {
 	expectedSize int64
 	checksum     string
 	url          string
+	// published is the publication date of the delivery (YYYY-MM-DD), empty if unknown
+	published string
 }
 
 func NewDownloader(
@@ -70,9 +92,27 @@ func NewDownloader(
 		Tracer: tracer,
 		Logger: logger,
 		Meter:  meter,
+		files:  newGate(cfg.Server.ConcurrentDownloads),
+	}
+	tlsConfig, err := newTLSConfig(cfg.Server.Network.TLS)
+	if err != nil {
+		return nil, err
+	}
+	d.tlsConfig = tlsConfig
+	if cfg.Server.Network.TLS.InsecureSkipVerify {
+		logger.Warnw("TLS certificate verification is disabled (server.network.tls.insecure_skip_verify)")
+	}
+	if cfg.Server.AdaptiveConcurrency.Enabled {
+		d.limiter = newAIMDLimiter(
+			cfg.Server.AdaptiveConcurrency,
+			cfg.Server.ConcurrentDownloads,
+			logger,
+		)
+	}
+	if cfg.Server.RateLimit.RequestsPerSecond > 0 {
+		d.bucket = newTokenBucket(cfg.Server.RateLimit)
 	}
 
-	var err error
 	d.downloadSessionDuration, err = d.Meter.Int64Histogram(
 		"download.session.duration",
 		metric.WithDescription("Duration of bulk download session"),
@@ -132,7 +172,7 @@ func (downloader *Downloader) DownloadHupd(ctx context.Context) IOE.IOEither[err
 		downloader.Logger.Infow("Starting downloading HUPD dataset",
 			"hupd_url", downloader.Cfg.Download.HUPD.URL,
 			"hupd_filename", downloader.Cfg.Download.HUPD.Filename)
-		client := Http.MakeClient(&http.Client{})
+		client := Http.MakeClient(&http.Client{Transport: newBaseTransport(downloader.Cfg.Server.Network, downloader.tlsConfig)})
 		select {
 		case <-ctx.Done():
 			return IOE.Left[int64](ctx.Err())
@@ -177,6 +217,35 @@ func (downloader *Downloader) DownloadHupd(ctx context.Context) IOE.IOEither[err
 }
 
 func (downloader *Downloader) FetchEPOFiles(ctx context.Context) IOE.IOEither[error, []int64] {
+	return downloader.fetchEPOFiles(ctx, nil)
+}
+
+// StreamEPOFiles downloads like FetchEPOFiles and sends the path of every item to
+// downloaded as soon as it is on disk, so later stages can start on it. The caller
+// closes downloaded once the returned program has run.
+func (downloader *Downloader) StreamEPOFiles(
+	ctx context.Context,
+	downloaded chan<- string,
+) IOE.IOEither[error, []int64] {
+	return downloader.fetchEPOFiles(ctx, func(path string) error {
+		select {
+		case downloaded <- path:
+			return nil
+		case <-ctx.Done():
+			return ctx.Err()
+		case <-shutdown.Stopping(ctx):
+			// the next stage no longer takes items; the file stays for the next run
+			return shutdown.ErrInterrupted
+		}
+	})
+}
+
+// fetchEPOFiles runs a download session; onDone, when set, is called for every item
+// that finished or was skipped.
+func (downloader *Downloader) fetchEPOFiles(
+	ctx context.Context,
+	onDone func(path string) error,
+) IOE.IOEither[error, []int64] {
 	ctx, span := downloader.Tracer.Start(ctx, "download.session", trace.WithAttributes(
 		attribute.Int("product_id", downloader.Cfg.Server.ProductID),
 		attribute.String("base_url", downloader.Cfg.Server.BaseURL),
@@ -198,7 +267,7 @@ func (downloader *Downloader) FetchEPOFiles(ctx context.Context) IOE.IOEither[er
 		func(total tuple.Tuple2[int64, int]) IOE.IOEither[error, T.Unit] {
 			downloader.progress = progressbar.NewOptions64(
 				total.F1,
-				progressbar.OptionSetWriter(os.Stdout),
+				progressbar.OptionSetWriter(downloader.Cfg.ProgressWriter()),
 				progressbar.OptionSetWidth(60),
 				progressbar.OptionSetDescription(
 					"[0/"+strconv.Itoa(total.F2)+"] Downloading files...",
@@ -207,7 +276,7 @@ func (downloader *Downloader) FetchEPOFiles(ctx context.Context) IOE.IOEither[er
 				progressbar.OptionShowIts(),
 				progressbar.OptionSetElapsedTime(true),
 				progressbar.OptionSetPredictTime(true),
-				progressbar.OptionThrottle(50*time.Millisecond),
+				progressbar.OptionThrottle(downloader.Cfg.Log.ProgressInterval),
 				progressbar.OptionSetRenderBlankState(true),
 				progressbar.OptionUseANSICodes(true),
 			)
@@ -215,34 +284,16 @@ func (downloader *Downloader) FetchEPOFiles(ctx context.Context) IOE.IOEither[er
 			return IOE.Of[error](T.Unit{})
 		},
 	)
-	timeout := F.Ternary(
-		func(t time.Duration) bool { return t > 0 },
-		F.Constant1[time.Duration](
-			time.Duration(downloader.Cfg.Server.Timeout)*time.Second,
-		),
-		F.Constant1[time.Duration](30*time.Second),
-	)(
-		downloader.Cfg.Server.Timeout,
-	)
 	var completed atomic.Int64
-	client := Http.MakeClient(&http.Client{Timeout: timeout})
-	url := fmt.Sprintf(
-		"%s/products/%d",
-		downloader.Cfg.Server.BaseURL,
-		downloader.Cfg.Server.ProductID,
-	)
-	request := Http.MakeGetRequest(url)
-	semaphore := make(chan struct{}, downloader.Cfg.Server.ConcurrentDownloads)
+	client := Http.MakeClient(downloader.httpClient())
+	items := Http.MakeClient(downloader.downloadClient())
 	download := func(downloadFile DownloadFile) IOE.IOEither[error, int64] {
 		select {
 		case <-ctx.Done():
 			return IOE.Left[int64](ctx.Err())
 		default:
-			acquire := IOE.FromIO[error](
-				func() DownloadFile { semaphore <- T.Unit{}; return downloadFile },
-			)
-			use := F.Flow2(
-				F.Curry3(downloader.DownloadEPOFile)(ctx)(client),
+			return F.Flow2(
+				F.Curry3(downloader.DownloadEPOFile)(ctx)(items),
 				IOE.Chain(func(size int64) IOE.IOEither[error, int64] {
 					completed.Add(1)
 					desc := fmt.Sprintf(
@@ -251,14 +302,14 @@ func (downloader *Downloader) FetchEPOFiles(ctx context.Context) IOE.IOEither[er
 						downloader.total,
 					)
 					downloader.progress.Describe(desc)
+					if onDone != nil {
+						if err := onDone(downloadFile.filePath); err != nil {
+							return IOE.Left[int64](err)
+						}
+					}
 					return IOE.Of[error](size)
 				}),
-			)
-			release := func(_ DownloadFile, _ ET.Either[error, int64]) IOE.IOEither[error, T.Unit] {
-				<-semaphore
-				return IOE.Of[error](T.Unit{})
-			}
-			return IOE.Bracket(acquire, use, release)
+			)(downloadFile)
 		}
 	}
 	cleanUp := func(_ []int64) IOE.IOEither[error, T.Unit] {
@@ -277,49 +328,46 @@ func (downloader *Downloader) FetchEPOFiles(ctx context.Context) IOE.IOEither[er
 		fmt.Fprintln(os.Stderr)
 		return IOE.Of[error](T.Unit{})
 	}
-	program := F.Pipe6(
-		request,
-		Http.ReadJSON[models.Product](client),
-		IOE.Chain(func(p models.Product) IOE.IOEither[error, []DownloadFile] {
-			select {
-			case <-ctx.Done():
-				return IOE.Left[[]DownloadFile](ctx.Err())
-			default:
-				items := array.MonadChain(
-					p.Deliveries,
-					func(delivery models.Delivery) []DownloadFile {
-						return array.MonadMap(delivery.Items, func(item models.Item) DownloadFile {
-							size := parseFileSize(item.FileSize)
-							return DownloadFile{
-								filename: item.ItemName,
-								filePath: filepath.Join(
-									downloader.Cfg.Download.Directory,
-									item.ItemName,
-								),
-								expectedSize: size,
-								checksum:     item.FileChecksum,
-								url: fmt.Sprintf(
-									"%s/products/%d/delivery/%d/item/%d/download",
-									downloader.Cfg.Server.BaseURL,
-									p.Id,
-									delivery.DeliveryID,
-									item.ItemId,
-								),
-							}
-						})
-					},
-				)
-				downloader.downloadFilesTotal.Add(ctx, int64(len(items)),
-					metric.WithAttributes(
-						attribute.Int("product_id", downloader.Cfg.Server.ProductID),
-					),
-				)
-				return IOE.Of[error](items)
-			}
+	// every failed item is collected under its name, so a checksum mismatch or an HTTP
+	// error is reported for the item it happened to
+	failed := &failures.Collector{}
+	program := F.Pipe11(
+		downloader.fetchDownloadFiles(ctx, client),
+		IOE.Chain(func(items []DownloadFile) IOE.IOEither[error, []DownloadFile] {
+			return IOE.TryCatchError(func() ([]DownloadFile, error) {
+				return downloader.enqueue(items)
+			})
+		}),
+		IOE.Map[error](downloader.schedule),
+		IOE.Tap(func(items []DownloadFile) IOE.IOEither[error, T.Unit] {
+			return IOE.TryCatchError(func() (T.Unit, error) {
+				return T.Unit{}, downloader.checkDiskSpace(items)
+			})
+		}),
+		IOE.Tap(func(items []DownloadFile) IOE.IOEither[error, T.Unit] {
+			downloader.downloadFilesTotal.Add(ctx, int64(len(items)),
+				metric.WithAttributes(
+					attribute.Int("product_id", downloader.Cfg.Server.ProductID),
+				),
+			)
+			return IOE.Of[error](T.Unit{})
 		}),
 		IOE.Tap(addProgressBar),
-		IOE.Chain(IOE.TraverseArrayPar(download)),
+		IOE.Chain(traverseBounded(
+			maxConcurrentDownloads,
+			collected(failed, downloader.gated(ctx, downloader.tracked(download))),
+		)),
+		IOE.MapLeft[[]int64](func(err error) error {
+			if failed.Len() == 0 || errors.Is(err, context.Canceled) {
+				return err
+			}
+			return failed.Err()
+		}),
 		IOE.Tap(cleanUp),
+		IOE.Tap(func(_ []int64) IOE.IOEither[error, T.Unit] {
+			downloader.advanceWatermark()
+			return IOE.Of[error](T.Unit{})
+		}),
 		IOE.Tap(func(sizes []int64) IOE.IOEither[error, T.Unit] {
 			durationMs := time.Since(startTime).Milliseconds()
 			status := "success"
@@ -345,6 +393,192 @@ func (downloader *Downloader) FetchEPOFiles(ctx context.Context) IOE.IOEither[er
 	}
 }
 
+// httpClient builds the client for metadata fetches, each bounded by server.timeout and
+// answered from server.metadata_cache when enabled.
+func (downloader *Downloader) httpClient() *http.Client {
+	client := downloader.downloadClient()
+	client.Timeout = downloader.Cfg.Server.Timeout
+	client.Transport = newMetadataCache(downloader.Cfg.Server.MetadataCache, client.Transport, downloader.Logger)
+	return client
+}
+
+// downloadClient builds the client for item downloads. It carries the configured EPO API
+// credentials, negotiates compressed transfers and keeps to server.rate_limit. A download may take far longer than
+// server.timeout, so only the connect and read timeouts of server.network and
+// download.stall_timeout bound it.
+func (downloader *Downloader) downloadClient() *http.Client {
+	return &http.Client{
+		Transport: newAuthTransport(
+			downloader.Cfg.Server.Auth,
+			newCompressionTransport(
+				downloader.Cfg.Server.Compression,
+				newRateLimitTransport(
+					downloader.bucket,
+					newBaseTransport(downloader.Cfg.Server.Network, downloader.tlsConfig),
+				),
+			),
+		),
+	}
+}
+
+// fetchDownloadFiles fetches the configured product, applies download.filter and
+// flattens the remaining deliveries into the list of items to download.
+func (downloader *Downloader) fetchDownloadFiles(
+	ctx context.Context,
+	client Http.Client,
+) IOE.IOEither[error, []DownloadFile] {
+	return F.Pipe1(
+		downloader.fetchProduct(ctx, client, downloader.incremental()),
+		IOE.Map[error](func(selected models.Product) []DownloadFile {
+			downloader.newest, _ = snapshot.Latest(selected)
+			downloader.recordSnapshot(selected)
+			return downloader.productFiles(selected)
+		}),
+	)
+}
+
+// fetchProduct fetches the configured product, following its pages, and applies
+// download.filter; incremental additionally drops deliveries up to the manifest watermark.
+func (downloader *Downloader) fetchProduct(
+	ctx context.Context,
+	client Http.Client,
+	incremental bool,
+) IOE.IOEither[error, models.Product] {
+	url := fmt.Sprintf(
+		"%s/products/%d",
+		downloader.Cfg.Server.BaseURL,
+		downloader.Cfg.Server.ProductID,
+	)
+	return F.Pipe1(
+		downloader.fetchProductPages(ctx, client, url, map[string]bool{url: true}),
+		IOE.Chain(func(p models.Product) IOE.IOEither[error, models.Product] {
+			select {
+			case <-ctx.Done():
+				return IOE.Left[models.Product](ctx.Err())
+			default:
+			}
+			filter, err := newProductFilter(downloader.Cfg.Download.Filter)
+			if err != nil {
+				return IOE.Left[models.Product](err)
+			}
+			if incremental {
+				filter.after = downloader.Manifest.Watermark()
+				downloader.Logger.Infow("Incremental run", "after", filter.after)
+			}
+			return IOE.Of[error](filter.apply(p, func(kind, name, reason string) {
+				downloader.Explain.Skip(explain.StageDownload, kind, name, reason)
+			}))
+		}),
+	)
+}
+
+// recordSnapshot derives the snapshot label from the newest delivery of p and records
+// it in the manifest.
+func (downloader *Downloader) recordSnapshot(p models.Product) {
+	latest, ok := snapshot.Latest(p)
+	if !ok && downloader.Cfg.Snapshot.Label == "" {
+		downloader.Logger.Warnw("No delivery publication date, snapshot label unknown", "product", p.Name)
+		return
+	}
+	downloader.snapshot = snapshot.Label(downloader.Cfg.Snapshot, latest)
+	downloader.Logger.Infow("Snapshot", "label", downloader.snapshot, "published", latest)
+	if downloader.Manifest == nil {
+		return
+	}
+	if err := downloader.Manifest.SetSnapshotLabel(downloader.snapshot); err != nil {
+		downloader.Logger.Warnw("Failed to record snapshot label", "err", err)
+	}
+}
+
+// incremental reports whether only deliveries newer than the manifest watermark are fetched.
+func (downloader *Downloader) incremental() bool {
+	return downloader.Cfg.State.Incremental && downloader.Manifest != nil
+}
+
+// advanceWatermark records the newest delivery of a run once all its items downloaded.
+func (downloader *Downloader) advanceWatermark() {
+	if !downloader.incremental() || downloader.newest.IsZero() {
+		return
+	}
+	if err := downloader.Manifest.AdvanceWatermark(downloader.newest); err != nil {
+		downloader.Logger.Warnw("Failed to record last delivery", "err", err)
+	}
+}
+
+// NewDeliveries lists the names of the deliveries a download would fetch now: those
+// passing download.filter and, in incremental mode, published after the watermark.
+// Nothing is downloaded.
+func (downloader *Downloader) NewDeliveries(ctx context.Context) IOE.IOEither[error, []string] {
+	client := Http.MakeClient(downloader.httpClient())
+	return F.Pipe1(
+		downloader.fetchProduct(ctx, client, downloader.incremental()),
+		IOE.Map[error](func(p models.Product) []string {
+			names := make([]string, 0, len(p.Deliveries))
+			for _, d := range p.Deliveries {
+				if len(d.Items) > 0 {
+					names = append(names, d.DeliveryName)
+				}
+			}
+			return names
+		}),
+	)
+}
+
+// Snapshot returns the label of the newest delivery seen by the last FetchEPOFiles run.
+func (downloader *Downloader) Snapshot() string {
+	return downloader.snapshot
+}
+
+func (downloader *Downloader) productFiles(p models.Product) []DownloadFile {
+	return array.MonadChain(
+		p.Deliveries,
+		func(delivery models.Delivery) []DownloadFile {
+			return array.MonadMap(delivery.Items, func(item models.Item) DownloadFile {
+				size := parseFileSize(item.FileSize)
+				name := downloader.itemPath(delivery, item)
+				var published string
+				if t, err := delivery.PublishedAt(); err == nil {
+					published = t.Format("2006-01-02")
+				}
+				return DownloadFile{
+					filename: name,
+					filePath: storage.Join(
+						downloader.Cfg.Download.Directory,
+						name,
+					),
+					expectedSize: size,
+					checksum:     item.FileChecksum,
+					url: fmt.Sprintf(
+						"%s/products/%d/delivery/%d/item/%d/download",
+						downloader.Cfg.Server.BaseURL,
+						p.Id,
+						delivery.DeliveryID,
+						item.ItemId,
+					),
+					published: published,
+				}
+			})
+		},
+	)
+}
+
+// itemPath expands download.path_template for item of delivery. The result is the path of
+// the item relative to the download directory, with forward slashes, and its name in the
+// manifest; with the default template it is the item name.
+func (downloader *Downloader) itemPath(delivery models.Delivery, item models.Item) string {
+	published := "undated"
+	if t, err := delivery.PublishedAt(); err == nil {
+		published = t.Format("2006-01-02")
+	}
+	return path.Clean(strings.NewReplacer(
+		"{item_name}", item.ItemName,
+		"{delivery_id}", strconv.FormatUint(uint64(delivery.DeliveryID), 10),
+		"{delivery_name}", delivery.DeliveryName,
+		"{delivery_publication_date}", published,
+		"{product_id}", strconv.Itoa(downloader.Cfg.Server.ProductID),
+	).Replace(downloader.Cfg.Download.PathTemplate))
+}
+
 func parseFileSize(s string) int64 {
 	s = strings.TrimSpace(s)
 	if s == "" {
@@ -377,6 +611,10 @@ func parseFileSize(s string) int64 {
 		scale := int64(math.Pow10(len(decimalPart)))
 		return (decimal * multiplier) / scale
 	}))
+	// most sizes have no fractional part to add
+	if decimalPart == "" {
+		decimal = option.Some[int64](0)
+	}
 	total := option.Sequence2(add)(whole, decimal)
 	return option.MonadGetOrElse(total, func() int64 { return 0 })
 }
@@ -417,25 +655,31 @@ func (downloader *Downloader) DownloadEPOFile(
 		return IOE.Left[int64](ctx.Err())
 	default:
 	}
+	if downloader.alreadyDownloaded(ctx, f) {
+		span.AddEvent("manifest_marks_downloaded")
+		downloader.Explain.Skip(explain.StageDownload, explain.KindItem, f.filename,
+			"manifest records it as downloaded")
+		return downloader.skipFile(ctx, span, f)
+	}
 	if downloader.Cfg.Download.SkipExists {
-		verify := verifyChecksum(f.checksum, f.filePath)
-		if ET.IsRight(verify()) {
-			span.SetAttributes(attribute.Bool("skipped", true))
+		if downloader.intact(ctx, f) {
 			span.AddEvent("file_already_exists_and_valid")
-			if downloader.progress != nil {
-				_ = downloader.progress.Add64(f.expectedSize)
-			}
-			downloader.downloadFilesSuccess.Add(ctx, 1,
-				metric.WithAttributes(
-					attribute.Int("product_id", downloader.Cfg.Server.ProductID),
-					attribute.String("method", "skip"),
-					attribute.Bool("skipped", true),
-				),
-			)
-			return IOE.Of[error](f.expectedSize)
+			downloader.Explain.Skip(explain.StageDownload, explain.KindItem, f.filename,
+				"file exists with a valid checksum")
+			downloader.recordItem(f.filename, func(it *state.ItemState) {
+				it.Download = state.StatusDone
+				it.Checksum = state.StatusOK
+				it.Error = ""
+			})
+			return downloader.skipFile(ctx, span, f)
 		}
 		span.AddEvent("existing_file_invalid_or_missing")
-		_ = os.Remove(f.filePath)
+		if !storage.IsS3(f.filePath) {
+			_ = os.Remove(f.filePath)
+		}
+	}
+	if !downloader.Cfg.Download.ResumePartial {
+		_ = os.Remove(f.partPath())
 	}
 	policy := retry.Monoid.Concat(
 		retry.LimitRetries(uint(downloader.Cfg.Server.MaxRetries)),
@@ -446,31 +690,23 @@ func (downloader *Downloader) DownloadEPOFile(
 		case <-ctx.Done():
 			return IOE.Left[int64](ctx.Err())
 		default:
-			return IOE.Bracket(
-				client.Do(Http.MakeGetRequest(f.url)),
-				func(resp *http.Response) IOE.IOEither[error, int64] {
-					if resp.StatusCode != http.StatusOK {
-						return IOE.Left[int64](fmt.Errorf("bad status: %d", resp.StatusCode))
-					}
-					return IOE.Bracket(
-						file.Create(f.filePath),
-						func(f *os.File) IOE.IOEither[error, int64] {
-							var writer io.Writer = f
-							if downloader.progress != nil {
-								writer = io.MultiWriter(f, downloader.progress)
-							}
-							return IOE.TryCatchError(func() (int64, error) {
-								return io.Copy(writer, resp.Body)
-							})
-						},
-						func(f *os.File, _ ET.Either[error, int64]) IOE.IOEither[error, any] {
-							return IOE.TryCatchError(func() (any, error) { return nil, f.Close() })
-						},
-					)
-				},
-				func(resp *http.Response, _ ET.Either[error, int64]) IOE.IOEither[error, any] {
-					return IOE.TryCatchError(func() (any, error) { return nil, resp.Body.Close() })
-				},
+			offset := partialSize(f.partPath())
+			// sum is the SHA-1 receive computed while writing, when it wrote the whole file
+			var sum string
+			return F.Pipe1(
+				downloader.throttled(ctx, f, downloader.limited(ctx, IOE.Bracket(
+					client.Do(rangeRequest(ctx, f.url, offset)),
+					func(resp *http.Response) IOE.IOEither[error, int64] {
+						downloader.watchStall(resp)
+						return downloader.receive(ctx, resp, f, offset, &sum)
+					},
+					func(resp *http.Response, _ ET.Either[error, int64]) IOE.IOEither[error, any] {
+						return IOE.TryCatchError(func() (any, error) { return nil, resp.Body.Close() })
+					},
+				))),
+				IOE.Chain(func(size int64) IOE.IOEither[error, int64] {
+					return downloader.commit(f, size, sum)
+				}),
 			)
 		}
 	}
@@ -493,8 +729,19 @@ func (downloader *Downloader) DownloadEPOFile(
 			attribute.String("status", "success"),
 			attribute.Bool("skipped", false),
 		))
+		downloader.recordItem(f.filename, func(it *state.ItemState) {
+			it.Download = state.StatusDone
+			it.Error = ""
+			if downloader.verifies(f) {
+				it.Checksum = state.StatusOK
+			}
+		})
 		return IOE.Of[error](T.Unit{})
 	}), IOE.TapLeft[int64](func(result error) IOE.IOEither[error, T.Unit] {
+		downloader.recordItem(f.filename, func(it *state.ItemState) {
+			it.Download = state.StatusFailed
+			it.Error = result.Error()
+		})
 		durationMs := time.Since(startTime).Milliseconds()
 		downloader.downloadFilesFailed.Add(ctx, 1, metric.WithAttributes(
 			attribute.String("error", fmt.Sprintf("%v", result)),
@@ -507,6 +754,249 @@ func (downloader *Downloader) DownloadEPOFile(
 	return result
 }
 
+// partPath is where f is written until its download is complete and verified.
+func (f DownloadFile) partPath() string {
+	return f.filePath + ".part"
+}
+
+// partialSize is the size of a partial download left at path, or 0 if there is none.
+func partialSize(path string) int64 {
+	fi, err := os.Stat(path)
+	if err != nil {
+		return 0
+	}
+	return fi.Size()
+}
+
+// rangeRequest requests url, asking only for the bytes from offset on when a partial
+// download exists.
+func rangeRequest(ctx context.Context, url string, offset int64) IOE.IOEither[error, *http.Request] {
+	return IOE.TryCatchError(func() (*http.Request, error) {
+		req, err := http.NewRequestWithContext(ctx, http.MethodGet, url, nil)
+		if err == nil && offset > 0 {
+			req.Header.Set("Range", fmt.Sprintf("bytes=%d-", offset))
+		}
+		return req, err
+	})
+}
+
+// receive writes the body of resp to the .part file of f and returns its total size.
+// A 206 answer continuing at offset is appended; a 200 answer restarts the file, for
+// servers that ignore ranges. A 416 means the partial file is already complete. A file
+// written from the start is hashed on the way to disk and its SHA-1 stored in sum, so
+// commit does not have to read it again.
+func (downloader *Downloader) receive(
+	ctx context.Context,
+	resp *http.Response,
+	f DownloadFile,
+	offset int64,
+	sum *string,
+) IOE.IOEither[error, int64] {
+	if storage.IsS3(f.filePath) {
+		return downloader.upload(ctx, resp, f)
+	}
+	flags := os.O_CREATE | os.O_WRONLY | os.O_TRUNC
+	switch {
+	case offset > 0 && resp.StatusCode == http.StatusRequestedRangeNotSatisfiable:
+		return IOE.Of[error](offset)
+	case offset > 0 && resp.StatusCode == http.StatusPartialContent &&
+		strings.HasPrefix(resp.Header.Get("Content-Range"), fmt.Sprintf("bytes %d-", offset)):
+		flags = os.O_WRONLY | os.O_APPEND
+		downloader.Logger.Infow("Resuming partial download", "item", f.filename, "offset", offset)
+		if downloader.progress != nil {
+			_ = downloader.progress.Add64(offset)
+		}
+	case resp.StatusCode == http.StatusOK:
+		offset = 0
+	default:
+		return IOE.Left[int64](statusError(resp))
+	}
+	return IOE.Bracket(
+		IOE.TryCatchError(func() (*os.File, error) {
+			if err := os.MkdirAll(filepath.Dir(f.partPath()), 0o755); err != nil {
+				return nil, err
+			}
+			return os.OpenFile(f.partPath(), flags, 0o644)
+		}),
+		func(out *os.File) IOE.IOEither[error, int64] {
+			writers := []io.Writer{out}
+			if downloader.progress != nil {
+				writers = append(writers, downloader.progress)
+			}
+			h := sha1.New()
+			if offset == 0 {
+				writers = append(writers, h)
+			}
+			return IOE.TryCatchError(func() (int64, error) {
+				n, err := io.Copy(io.MultiWriter(writers...), resp.Body)
+				if err == nil && offset == 0 {
+					*sum = hex.EncodeToString(h.Sum(nil))
+				}
+				return offset + n, err
+			})
+		},
+		func(out *os.File, _ ET.Either[error, int64]) IOE.IOEither[error, any] {
+			return IOE.TryCatchError(func() (any, error) { return nil, out.Close() })
+		},
+	)
+}
+
+// upload streams the body of resp to the S3 object of f. Objects cannot be appended to,
+// so the upload always starts over; the object only appears once the multipart upload
+// completes, and is deleted again if its SHA-1 does not match.
+func (downloader *Downloader) upload(
+	ctx context.Context,
+	resp *http.Response,
+	f DownloadFile,
+) IOE.IOEither[error, int64] {
+	if resp.StatusCode != http.StatusOK {
+		return IOE.Left[int64](statusError(resp))
+	}
+	return IOE.TryCatchError(func() (int64, error) {
+		h := sha1.New()
+		var tee io.Writer = h
+		if downloader.progress != nil {
+			tee = io.MultiWriter(h, downloader.progress)
+		}
+		size, err := downloader.Storage.Upload(ctx, f.filePath, io.TeeReader(resp.Body, tee))
+		if err != nil {
+			return size, err
+		}
+		if !downloader.verifies(f) {
+			return size, nil
+		}
+		if err := checksumMismatch(f.checksum, hex.EncodeToString(h.Sum(nil))); err != nil {
+			return size, errors.Join(err, downloader.Storage.Remove(ctx, f.filePath))
+		}
+		return size, nil
+	})
+}
+
+// commit verifies the finished .part file of f and renames it to the item's path, so
+// the item name only ever refers to a complete archive. sum is the SHA-1 computed while
+// the file was written; a resumed file, written in part by an earlier attempt, has none
+// and is read again. A file failing the checksum is removed, and the retry starts over.
+// S3 objects were verified by upload.
+func (downloader *Downloader) commit(f DownloadFile, size int64, sum string) IOE.IOEither[error, int64] {
+	if storage.IsS3(f.filePath) {
+		return IOE.Of[error](size)
+	}
+	part := f.partPath()
+	if downloader.verifies(f) {
+		err := checksumMismatch(f.checksum, sum)
+		if sum == "" {
+			_, err = ET.UnwrapError(verifyChecksum(f.checksum, part)())
+		}
+		if err != nil {
+			_ = os.Remove(part)
+			return IOE.Left[int64](err)
+		}
+	}
+	if err := os.Rename(part, f.filePath); err != nil {
+		return IOE.Left[int64](fmt.Errorf("move %s into place: %w", part, err))
+	}
+	return IOE.Of[error](size)
+}
+
+// verifies reports whether the SHA-1 of f is checked before it is moved into place.
+func (downloader *Downloader) verifies(f DownloadFile) bool {
+	return downloader.Cfg.Download.VerifySHA1 && f.checksum != ""
+}
+
+// skipFile accounts for an item that does not need to be downloaded again.
+func (downloader *Downloader) skipFile(
+	ctx context.Context,
+	span trace.Span,
+	f DownloadFile,
+) IOE.IOEither[error, int64] {
+	span.SetAttributes(attribute.Bool("skipped", true))
+	if downloader.progress != nil {
+		_ = downloader.progress.Add64(f.expectedSize)
+	}
+	downloader.downloadFilesSuccess.Add(ctx, 1,
+		metric.WithAttributes(
+			attribute.Int("product_id", downloader.Cfg.Server.ProductID),
+			attribute.String("method", "skip"),
+			attribute.Bool("skipped", true),
+		),
+	)
+	return IOE.Of[error](f.expectedSize)
+}
+
+// alreadyDownloaded reports whether the manifest records f as downloaded and the archive
+// is either still on disk or has already been extracted (and possibly deleted).
+func (downloader *Downloader) alreadyDownloaded(ctx context.Context, f DownloadFile) bool {
+	if downloader.Manifest == nil {
+		return false
+	}
+	it, ok := downloader.Manifest.Item(f.filename)
+	if !ok || it.Download != state.StatusDone {
+		return false
+	}
+	if it.Extract == state.StatusDone {
+		return true
+	}
+	return downloader.exists(ctx, f)
+}
+
+// exists reports whether the archive of f is in the download directory.
+func (downloader *Downloader) exists(ctx context.Context, f DownloadFile) bool {
+	if storage.IsS3(f.filePath) {
+		_, ok, err := downloader.Storage.Stat(ctx, f.filePath)
+		return err == nil && ok
+	}
+	_, err := os.Stat(f.filePath)
+	return err == nil
+}
+
+// intact reports whether the archive of f exists with the published checksum. An S3
+// object is only kept once its upload passed the checksum, so it is checked for
+// existence alone instead of being read back.
+func (downloader *Downloader) intact(ctx context.Context, f DownloadFile) bool {
+	if storage.IsS3(f.filePath) {
+		return downloader.exists(ctx, f)
+	}
+	return ET.IsRight(verifyChecksum(f.checksum, f.filePath)())
+}
+
+// recordItem updates the manifest entry of name when state tracking is enabled.
+func (downloader *Downloader) recordItem(name string, fn func(*state.ItemState)) {
+	if downloader.Manifest == nil {
+		return
+	}
+	if err := downloader.Manifest.UpdateItem(name, fn); err != nil {
+		downloader.Logger.Warnw("Failed to update manifest", "item", name, "err", err)
+	}
+}
+
+// limited runs io under the adaptive concurrency limiter when it is enabled and
+// reports the outcome back so the limit can shrink or grow.
+func (downloader *Downloader) limited(
+	ctx context.Context,
+	action IOE.IOEither[error, int64],
+) IOE.IOEither[error, int64] {
+	if downloader.limiter == nil {
+		return action
+	}
+	return func() ET.Either[error, int64] {
+		if err := downloader.limiter.Acquire(ctx); err != nil {
+			return ET.Left[int64](err)
+		}
+		res := action()
+		_, err := ET.UnwrapError(res)
+		downloader.limiter.Release(err)
+		return res
+	}
+}
+
+// checksumMismatch returns an error when the SHA-1 actual differs from expected.
+func checksumMismatch(expected, actual string) error {
+	if actual == expected {
+		return nil
+	}
+	return fmt.Errorf("checksum mismatch: expected %s, got %s", expected, actual)
+}
+
 func verifyChecksum(expectedChecksum, filePath string) IOE.IOEither[error, string] {
 	h := sha1.New()
 	acquire := file.Open(filePath)
@@ -514,13 +1004,10 @@ func verifyChecksum(expectedChecksum, filePath string) IOE.IOEither[error, strin
 		if _, err := io.Copy(h, f); err != nil {
 			return IOE.Left[string](err)
 		}
-		actual := hex.EncodeToString(h.Sum(nil))
-		if actual == expectedChecksum {
-			return IOE.Right[error](filePath)
+		if err := checksumMismatch(expectedChecksum, hex.EncodeToString(h.Sum(nil))); err != nil {
+			return IOE.Left[string](err)
 		}
-		return IOE.Left[string](
-			fmt.Errorf("checksum mismatch: expected %s, got %s", expectedChecksum, actual),
-		)
+		return IOE.Right[error](filePath)
 	}
 	release := func(f *os.File, _ ET.Either[error, string]) IOE.IOEither[error, any] {
 		return IOE.TryCatchError(func() (any, error) {
@@ -529,3 +1016,49 @@ func verifyChecksum(expectedChecksum, filePath string) IOE.IOEither[error, strin
 	}
 	return IOE.Bracket(acquire, use, release)
 }
+
+// collected records the outcome of every item of download in c, under its file name.
+func collected(
+	c *failures.Collector,
+	download func(DownloadFile) IOE.IOEither[error, int64],
+) func(DownloadFile) IOE.IOEither[error, int64] {
+	return func(f DownloadFile) IOE.IOEither[error, int64] {
+		return func() ET.Either[error, int64] {
+			res := download(f)()
+			if _, err := ET.UnwrapError(res); err != nil {
+				c.Fail(f.filename, err)
+			} else {
+				c.Succeed()
+			}
+			return res
+		}
+	}
+}
+
+// traverseBounded behaves like IOE.TraverseArrayPar but never keeps more than
+// limit goroutines in flight, so large products do not spawn one goroutine per item.
+// Results keep the input order; all item errors are joined, an interruption only once.
+func traverseBounded[A, B any](
+	limit int,
+	f func(A) IOE.IOEither[error, B],
+) func([]A) IOE.IOEither[error, []B] {
+	return func(items []A) IOE.IOEither[error, []B] {
+		return IOE.TryCatchError(func() ([]B, error) {
+			results := make([]B, len(items))
+			errs := make([]error, len(items))
+			var g errgroup.Group
+			g.SetLimit(max(limit, 1))
+			for i, item := range items {
+				g.Go(func() error {
+					results[i], errs[i] = ET.UnwrapError(f(item)())
+					return nil
+				})
+			}
+			_ = g.Wait()
+			if err := shutdown.Join(errs...); err != nil {
+				return nil, err
+			}
+			return results, nil
+		})
+	}
+}
@@ -0,0 +1,58 @@
+package download
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"net"
+	"net/http"
+	"strconv"
+	"strings"
+	"time"
+)
+
+// HTTPStatusError is returned when the EPO API answers with a non-200 status.
+// RetryAfter is the wait the answer asked for in its Retry-After header, if any.
+type HTTPStatusError struct {
+	StatusCode int
+	RetryAfter time.Duration
+}
+
+func (e *HTTPStatusError) Error() string {
+	return fmt.Sprintf("bad status: %d", e.StatusCode)
+}
+
+// statusError returns the HTTPStatusError for the answer resp.
+func statusError(resp *http.Response) *HTTPStatusError {
+	return &HTTPStatusError{StatusCode: resp.StatusCode, RetryAfter: retryAfter(resp)}
+}
+
+// retryAfter parses the Retry-After header of resp, given in seconds or as an HTTP date.
+// It returns 0 without a valid header.
+func retryAfter(resp *http.Response) time.Duration {
+	v := strings.TrimSpace(resp.Header.Get("Retry-After"))
+	if secs, err := strconv.Atoi(v); err == nil && secs > 0 {
+		return time.Duration(secs) * time.Second
+	}
+	if t, err := http.ParseTime(v); err == nil {
+		return max(time.Until(t), 0)
+	}
+	return 0
+}
+
+// isOverloadError reports whether err signals server-side pressure (5xx, 429 or a
+// timeout) rather than a problem with the individual item.
+func isOverloadError(err error) bool {
+	if err == nil {
+		return false
+	}
+	var statusErr *HTTPStatusError
+	if errors.As(err, &statusErr) {
+		return statusErr.StatusCode >= 500 || statusErr.StatusCode == http.StatusTooManyRequests
+	}
+	if errors.Is(err, context.DeadlineExceeded) || errors.Is(err, ErrStalled) {
+		return true
+	}
+	var netErr net.Error
+	return errors.As(err, &netErr) && netErr.Timeout()
+}
@@ -0,0 +1,103 @@
+package download
+
+import (
+	"fmt"
+	"path"
+	"time"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/explain"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// productFilter narrows a product to the deliveries and items selected by download.filter.
+type productFilter struct {
+	since    time.Time
+	until    time.Time
+	patterns []string
+	// after excludes deliveries published at or before it (incremental mode).
+	after time.Time
+}
+
+func newProductFilter(cfg config.Filter) (productFilter, error) {
+	var f productFilter
+	var err error
+	if cfg.Since != "" {
+		if f.since, err = time.Parse(time.DateOnly, cfg.Since); err != nil {
+			return f, fmt.Errorf("invalid filter since %q: %w", cfg.Since, err)
+		}
+	}
+	if cfg.Until != "" {
+		if f.until, err = time.Parse(time.DateOnly, cfg.Until); err != nil {
+			return f, fmt.Errorf("invalid filter until %q: %w", cfg.Until, err)
+		}
+		// until is inclusive
+		f.until = f.until.AddDate(0, 0, 1)
+	}
+	for _, pattern := range cfg.Items {
+		if _, err := path.Match(pattern, ""); err != nil {
+			return f, fmt.Errorf("invalid item pattern %q: %w", pattern, err)
+		}
+	}
+	f.patterns = cfg.Items
+	return f, nil
+}
+
+// apply returns p with only the matching deliveries and items. Deliveries left without
+// items are dropped. Every excluded delivery and item is passed to skipped with the reason.
+func (f productFilter) apply(p models.Product, skipped func(kind, name, reason string)) models.Product {
+	deliveries := make([]models.Delivery, 0, len(p.Deliveries))
+	for _, d := range p.Deliveries {
+		if reason := f.excludeDelivery(d); reason != "" {
+			skipped(explain.KindDelivery, d.DeliveryName, reason)
+			continue
+		}
+		items := make([]models.Item, 0, len(d.Items))
+		for _, item := range d.Items {
+			if f.includeItem(item.ItemName) {
+				items = append(items, item)
+			} else {
+				skipped(explain.KindItem, item.ItemName, "matches no download.filter.items pattern")
+			}
+		}
+		if len(items) == 0 {
+			continue
+		}
+		d.Items = items
+		deliveries = append(deliveries, d)
+	}
+	p.Deliveries = deliveries
+	return p
+}
+
+// excludeDelivery returns why d was not published within the window, or "" if it was.
+// Without a window every delivery matches; with one, deliveries of unknown date are excluded.
+func (f productFilter) excludeDelivery(d models.Delivery) string {
+	if f.since.IsZero() && f.until.IsZero() && f.after.IsZero() {
+		return ""
+	}
+	published, err := d.PublishedAt()
+	switch {
+	case err != nil:
+		return "publication date unknown with a date filter"
+	case !f.after.IsZero() && !published.After(f.after):
+		return "published before the incremental watermark"
+	case !f.since.IsZero() && published.Before(f.since):
+		return "published before download.filter.since"
+	case !f.until.IsZero() && !published.Before(f.until):
+		return "published after download.filter.until"
+	}
+	return ""
+}
+
+func (f productFilter) includeItem(name string) bool {
+	if len(f.patterns) == 0 {
+		return true
+	}
+	for _, pattern := range f.patterns {
+		if ok, _ := path.Match(pattern, name); ok {
+			return true
+		}
+	}
+	return false
+}
@@ -0,0 +1,10 @@
+//go:build !linux && !darwin && !freebsd
+
+package download
+
+import "errors"
+
+// freeSpace is not implemented on this platform, so the disk space check is skipped.
+func freeSpace(string) (int64, error) {
+	return 0, errors.New("free disk space is not available on this platform")
+}
@@ -0,0 +1,93 @@
+package download
+
+import (
+	"context"
+	"sync"
+
+	ET "github.com/IBM/fp-go/v2/either"
+	IOE "github.com/IBM/fp-go/v2/ioeither"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/shutdown"
+)
+
+// maxConcurrentDownloads is the upper bound of server.concurrent_downloads; a session
+// starts this many workers and lets the gate decide how many of them may download.
+const maxConcurrentDownloads = 30
+
+// gate caps the files downloaded at once. Unlike the worker limit of traverseBounded its
+// size can change while a session runs.
+type gate struct {
+	mu    sync.Mutex
+	cond  *sync.Cond
+	size  int
+	inUse int
+}
+
+func newGate(size int) *gate {
+	g := &gate{size: size}
+	g.cond = sync.NewCond(&g.mu)
+	return g
+}
+
+// Acquire blocks until fewer than size files are in progress or ctx is done.
+func (g *gate) Acquire(ctx context.Context) error {
+	stop := context.AfterFunc(ctx, func() {
+		g.mu.Lock()
+		g.cond.Broadcast()
+		g.mu.Unlock()
+	})
+	defer stop()
+	g.mu.Lock()
+	defer g.mu.Unlock()
+	for g.inUse >= g.size {
+		if err := ctx.Err(); err != nil {
+			return err
+		}
+		g.cond.Wait()
+	}
+	g.inUse++
+	return nil
+}
+
+func (g *gate) Release() {
+	g.mu.Lock()
+	defer g.mu.Unlock()
+	g.inUse--
+	g.cond.Broadcast()
+}
+
+func (g *gate) Resize(size int) {
+	g.mu.Lock()
+	defer g.mu.Unlock()
+	g.size = size
+	g.cond.Broadcast()
+}
+
+// SetConcurrency changes the number of files downloaded at once, and the ceiling of the
+// adaptive limiter, for the running and all later sessions.
+func (downloader *Downloader) SetConcurrency(n int) {
+	downloader.files.Resize(n)
+	if downloader.limiter != nil {
+		downloader.limiter.SetMax(n)
+	}
+}
+
+// gated runs the download of one file once the gate lets it through. Once the run is
+// draining, files that have not started fail with shutdown.ErrInterrupted.
+func (downloader *Downloader) gated(
+	ctx context.Context,
+	download func(DownloadFile) IOE.IOEither[error, int64],
+) func(DownloadFile) IOE.IOEither[error, int64] {
+	return func(f DownloadFile) IOE.IOEither[error, int64] {
+		return func() ET.Either[error, int64] {
+			if err := downloader.files.Acquire(ctx); err != nil {
+				return ET.Left[int64](err)
+			}
+			defer downloader.files.Release()
+			if shutdown.Interrupted(ctx) {
+				return ET.Left[int64](shutdown.ErrInterrupted)
+			}
+			return download(f)()
+		}
+	}
+}
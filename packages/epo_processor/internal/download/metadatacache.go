@@ -0,0 +1,157 @@
+package download
+
+import (
+	"bytes"
+	"crypto/sha256"
+	"encoding/hex"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"net/http"
+	"os"
+	"path/filepath"
+	"time"
+
+	"go.uber.org/zap"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+)
+
+// ErrNotCached is returned in offline mode for metadata that is not in the cache.
+var ErrNotCached = errors.New("not in the metadata cache")
+
+// cachedResponse describes a metadata response kept by metadataCache next to its body.
+type cachedResponse struct {
+	URL          string    `json:"url"`
+	ETag         string    `json:"etag,omitempty"`
+	LastModified string    `json:"last_modified,omitempty"`
+	Fetched      time.Time `json:"fetched"`
+}
+
+// metadataCache keeps the product metadata responses in server.metadata_cache.dir and
+// revalidates them with If-None-Match and If-Modified-Since. A 304 answer is served from
+// the cache, so an unchanged product costs one small request per page. In offline mode
+// the cache answers without contacting the API.
+type metadataCache struct {
+	base    http.RoundTripper
+	dir     string
+	offline bool
+	logger  *zap.SugaredLogger
+}
+
+func newMetadataCache(cfg config.MetadataCache, base http.RoundTripper, logger *zap.SugaredLogger) http.RoundTripper {
+	if !cfg.Enabled {
+		return base
+	}
+	return &metadataCache{base: base, dir: cfg.Dir, offline: cfg.Offline, logger: logger}
+}
+
+func (c *metadataCache) RoundTrip(req *http.Request) (*http.Response, error) {
+	if req.Method != http.MethodGet {
+		return c.base.RoundTrip(req)
+	}
+	key := c.key(req.URL.String())
+	meta, body, cached := c.load(key)
+	if c.offline {
+		if !cached {
+			return nil, fmt.Errorf("offline: %s %w", req.URL, ErrNotCached)
+		}
+		return cachedReply(req, body), nil
+	}
+	out := req
+	if cached {
+		out = req.Clone(req.Context())
+		if meta.ETag != "" {
+			out.Header.Set("If-None-Match", meta.ETag)
+		}
+		if meta.LastModified != "" {
+			out.Header.Set("If-Modified-Since", meta.LastModified)
+		}
+	}
+	resp, err := c.base.RoundTrip(out)
+	if err != nil {
+		return nil, err
+	}
+	switch {
+	case cached && resp.StatusCode == http.StatusNotModified:
+		resp.Body.Close()
+		c.logger.Debugw("Product metadata not modified", "url", req.URL.String())
+		return cachedReply(req, body), nil
+	case resp.StatusCode != http.StatusOK:
+		return resp, nil
+	}
+	data, err := io.ReadAll(resp.Body)
+	resp.Body.Close()
+	if err != nil {
+		return nil, err
+	}
+	resp.Body = io.NopCloser(bytes.NewReader(data))
+	if err := c.store(key, cachedResponse{
+		URL:          req.URL.String(),
+		ETag:         resp.Header.Get("ETag"),
+		LastModified: resp.Header.Get("Last-Modified"),
+		Fetched:      time.Now().UTC(),
+	}, data); err != nil {
+		c.logger.Warnw("Failed to cache product metadata", "url", req.URL.String(), "err", err)
+	}
+	return resp, nil
+}
+
+// key names the cache files of url.
+func (c *metadataCache) key(url string) string {
+	sum := sha256.Sum256([]byte(url))
+	return filepath.Join(c.dir, hex.EncodeToString(sum[:16]))
+}
+
+func (c *metadataCache) load(key string) (cachedResponse, []byte, bool) {
+	var meta cachedResponse
+	raw, err := os.ReadFile(key + ".meta.json")
+	if err != nil || json.Unmarshal(raw, &meta) != nil {
+		return meta, nil, false
+	}
+	body, err := os.ReadFile(key + ".json")
+	if err != nil {
+		return meta, nil, false
+	}
+	return meta, body, true
+}
+
+// store writes the body before its description, so that a description never refers to
+// a body that was not written.
+func (c *metadataCache) store(key string, meta cachedResponse, body []byte) error {
+	if err := os.MkdirAll(c.dir, 0o755); err != nil {
+		return err
+	}
+	raw, err := json.MarshalIndent(meta, "", "  ")
+	if err != nil {
+		return err
+	}
+	if err := writeFileAtomic(key+".json", body); err != nil {
+		return err
+	}
+	return writeFileAtomic(key+".meta.json", raw)
+}
+
+func writeFileAtomic(path string, data []byte) error {
+	tmp := path + ".tmp"
+	if err := os.WriteFile(tmp, data, 0o644); err != nil {
+		return err
+	}
+	return os.Rename(tmp, path)
+}
+
+// cachedReply answers req with a cached body.
+func cachedReply(req *http.Request, body []byte) *http.Response {
+	return &http.Response{
+		Status:        "200 OK",
+		StatusCode:    http.StatusOK,
+		Proto:         "HTTP/1.1",
+		ProtoMajor:    1,
+		ProtoMinor:    1,
+		Header:        http.Header{"Content-Type": []string{"application/json"}},
+		Body:          io.NopCloser(bytes.NewReader(body)),
+		ContentLength: int64(len(body)),
+		Request:       req,
+	}
+}
@@ -0,0 +1,81 @@
+package download
+
+import (
+	"context"
+	"fmt"
+	"net/url"
+	"strconv"
+
+	F "github.com/IBM/fp-go/v2/function"
+	IOE "github.com/IBM/fp-go/v2/ioeither"
+	Http "github.com/IBM/fp-go/v2/ioeither/http"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// fetchProductPages requests the product metadata at pageURL and follows its pagination,
+// concatenating the deliveries of all pages into the first page. seen guards against a
+// server whose next links loop back to a page already fetched.
+func (downloader *Downloader) fetchProductPages(
+	ctx context.Context,
+	client Http.Client,
+	pageURL string,
+	seen map[string]bool,
+) IOE.IOEither[error, models.Product] {
+	return F.Pipe2(
+		Http.MakeGetRequest(pageURL),
+		Http.ReadJSON[models.Product](client),
+		IOE.Chain(func(page models.Product) IOE.IOEither[error, models.Product] {
+			next, err := nextPageURL(pageURL, page)
+			page.Links, page.Page, page.TotalPages = nil, 0, 0
+			switch {
+			case err != nil:
+				return IOE.Left[models.Product](err)
+			case next == "":
+				return IOE.Of[error](page)
+			case seen[next]:
+				return IOE.Left[models.Product](
+					fmt.Errorf("product pagination loops back to %s", next))
+			}
+			select {
+			case <-ctx.Done():
+				return IOE.Left[models.Product](ctx.Err())
+			default:
+			}
+			seen[next] = true
+			downloader.Logger.Debugw("Fetching next product page",
+				"url", next, "deliveries_so_far", len(page.Deliveries))
+			return F.Pipe1(
+				downloader.fetchProductPages(ctx, client, next, seen),
+				IOE.Map[error](func(rest models.Product) models.Product {
+					page.Deliveries = append(page.Deliveries, rest.Deliveries...)
+					return page
+				}),
+			)
+		}),
+	)
+}
+
+// nextPageURL returns the URL of the page following p, which was fetched from current,
+// or "" on the last page. A next link, absolute or relative to current, takes precedence;
+// otherwise page and totalPages advance the page query parameter of current.
+func nextPageURL(current string, p models.Product) (string, error) {
+	base, err := url.Parse(current)
+	if err != nil {
+		return "", err
+	}
+	if p.Links != nil && p.Links.Next != "" {
+		next, err := url.Parse(p.Links.Next)
+		if err != nil {
+			return "", fmt.Errorf("invalid next page link %q: %w", p.Links.Next, err)
+		}
+		return base.ResolveReference(next).String(), nil
+	}
+	if p.Page <= 0 || p.Page >= p.TotalPages {
+		return "", nil
+	}
+	query := base.Query()
+	query.Set("page", strconv.Itoa(p.Page+1))
+	base.RawQuery = query.Encode()
+	return base.String(), nil
+}
@@ -0,0 +1,211 @@
+package download
+
+import (
+	"context"
+	"encoding/json"
+	"net/http"
+	"net/http/httptest"
+	"slices"
+	"strconv"
+	"strings"
+	"sync/atomic"
+	"testing"
+
+	ET "github.com/IBM/fp-go/v2/either"
+	Http "github.com/IBM/fp-go/v2/ioeither/http"
+	"go.uber.org/zap"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// pagedServer serves pages[i] for ?page=i+1 (the first page without a page parameter) and
+// counts the requests. A nil page answers 500.
+func pagedServer(t *testing.T, pages []*models.Product) (*httptest.Server, *atomic.Int32) {
+	t.Helper()
+	var requests atomic.Int32
+	srv := httptest.NewServer(http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		requests.Add(1)
+		n := 1
+		if p := r.URL.Query().Get("page"); p != "" {
+			var err error
+			if n, err = strconv.Atoi(p); err != nil || n < 1 || n > len(pages) {
+				http.NotFound(w, r)
+				return
+			}
+		}
+		if pages[n-1] == nil {
+			http.Error(w, "internal error", http.StatusInternalServerError)
+			return
+		}
+		w.Header().Set("Content-Type", "application/json")
+		_ = json.NewEncoder(w).Encode(pages[n-1])
+	}))
+	t.Cleanup(srv.Close)
+	return srv, &requests
+}
+
+func fetchPages(t *testing.T, srv *httptest.Server) (models.Product, error) {
+	t.Helper()
+	d := &Downloader{Logger: zap.NewNop().Sugar()}
+	url := srv.URL + "/products/3"
+	return ET.UnwrapError(d.fetchProductPages(
+		context.Background(), Http.MakeClient(srv.Client()), url, map[string]bool{url: true})())
+}
+
+func deliveries(ids ...uint32) []models.Delivery {
+	out := make([]models.Delivery, len(ids))
+	for i, id := range ids {
+		out[i] = models.Delivery{DeliveryID: id, DeliveryName: "delivery-" + strconv.Itoa(int(id))}
+	}
+	return out
+}
+
+func deliveryIDs(p models.Product) []uint32 {
+	ids := make([]uint32, len(p.Deliveries))
+	for i, d := range p.Deliveries {
+		ids[i] = d.DeliveryID
+	}
+	return ids
+}
+
+func TestFetchProductPages(t *testing.T) {
+	tests := []struct {
+		name     string
+		pages    []*models.Product
+		want     []uint32
+		requests int32
+	}{
+		{
+			name:     "single response",
+			pages:    []*models.Product{{Id: 3, Deliveries: deliveries(1, 2)}},
+			want:     []uint32{1, 2},
+			requests: 1,
+		},
+		{
+			name: "page numbers",
+			pages: []*models.Product{
+				{Id: 3, Deliveries: deliveries(1, 2), Page: 1, TotalPages: 3},
+				{Id: 3, Deliveries: deliveries(3), Page: 2, TotalPages: 3},
+				{Id: 3, Deliveries: deliveries(4, 5), Page: 3, TotalPages: 3},
+			},
+			want:     []uint32{1, 2, 3, 4, 5},
+			requests: 3,
+		},
+		{
+			name: "relative next links",
+			pages: []*models.Product{
+				{Id: 3, Deliveries: deliveries(1), Links: &models.Links{Next: "3?page=2"}},
+				{Id: 3, Deliveries: deliveries(2), Links: &models.Links{Next: "/products/3?page=3"}},
+				{Id: 3, Deliveries: deliveries(3)},
+			},
+			want:     []uint32{1, 2, 3},
+			requests: 3,
+		},
+		{
+			name: "empty page in between",
+			pages: []*models.Product{
+				{Id: 3, Deliveries: deliveries(1), Page: 1, TotalPages: 3},
+				{Id: 3, Deliveries: []models.Delivery{}, Page: 2, TotalPages: 3},
+				{Id: 3, Deliveries: deliveries(2), Page: 3, TotalPages: 3},
+			},
+			want:     []uint32{1, 2},
+			requests: 3,
+		},
+		{
+			name: "empty last page",
+			pages: []*models.Product{
+				{Id: 3, Deliveries: deliveries(1, 2), Page: 1, TotalPages: 2},
+				{Id: 3, Page: 2, TotalPages: 2},
+			},
+			want:     []uint32{1, 2},
+			requests: 2,
+		},
+	}
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			srv, requests := pagedServer(t, tt.pages)
+			got, err := fetchPages(t, srv)
+			if err != nil {
+				t.Fatalf("fetchProductPages: %v", err)
+			}
+			if ids := deliveryIDs(got); !slices.Equal(ids, tt.want) {
+				t.Errorf("deliveries = %v, want %v", ids, tt.want)
+			}
+			if got.Links != nil || got.Page != 0 || got.TotalPages != 0 {
+				t.Errorf("pagination fields left in the merged product: %+v", got)
+			}
+			if n := requests.Load(); n != tt.requests {
+				t.Errorf("requests = %d, want %d", n, tt.requests)
+			}
+		})
+	}
+}
+
+func TestFetchProductPagesErrors(t *testing.T) {
+	tests := []struct {
+		name    string
+		pages   []*models.Product
+		wantErr string
+	}{
+		{
+			name: "server error mid-way",
+			pages: []*models.Product{
+				{Id: 3, Deliveries: deliveries(1), Page: 1, TotalPages: 3},
+				nil,
+				{Id: 3, Deliveries: deliveries(3), Page: 3, TotalPages: 3},
+			},
+		},
+		{
+			name: "next link past the last page",
+			pages: []*models.Product{
+				{Id: 3, Deliveries: deliveries(1), Links: &models.Links{Next: "?page=7"}},
+			},
+		},
+		{
+			name: "loop",
+			pages: []*models.Product{
+				{Id: 3, Deliveries: deliveries(1), Links: &models.Links{Next: "?page=2"}},
+				{Id: 3, Deliveries: deliveries(2), Links: &models.Links{Next: "3"}},
+			},
+			wantErr: "loops back",
+		},
+	}
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			srv, _ := pagedServer(t, tt.pages)
+			_, err := fetchPages(t, srv)
+			if err == nil {
+				t.Fatal("fetchProductPages succeeded, want an error")
+			}
+			if !strings.Contains(err.Error(), tt.wantErr) {
+				t.Errorf("error = %v, want it to mention %q", err, tt.wantErr)
+			}
+		})
+	}
+}
+
+func TestNextPageURL(t *testing.T) {
+	const current = "https://publication-bdds.apps.epo.org/bdds/bdds-bff-service/prod/api/products/3"
+	tests := []struct {
+		name string
+		page models.Product
+		want string
+	}{
+		{"no pagination", models.Product{}, ""},
+		{"last page", models.Product{Page: 2, TotalPages: 2}, ""},
+		{"next page number", models.Product{Page: 1, TotalPages: 2}, current + "?page=2"},
+		{"absolute link", models.Product{Links: &models.Links{Next: "https://example.org/p?c=x"}}, "https://example.org/p?c=x"},
+		{"relative link", models.Product{Links: &models.Links{Next: "3?cursor=abc"}}, current + "?cursor=abc"},
+	}
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			got, err := nextPageURL(current, tt.page)
+			if err != nil {
+				t.Fatalf("nextPageURL: %v", err)
+			}
+			if got != tt.want {
+				t.Errorf("nextPageURL = %q, want %q", got, tt.want)
+			}
+		})
+	}
+}
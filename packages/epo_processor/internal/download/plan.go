@@ -0,0 +1,88 @@
+package download
+
+import (
+	"context"
+	"strconv"
+
+	F "github.com/IBM/fp-go/v2/function"
+	IOE "github.com/IBM/fp-go/v2/ioeither"
+	Http "github.com/IBM/fp-go/v2/ioeither/http"
+	"go.opentelemetry.io/otel/attribute"
+	"go.opentelemetry.io/otel/trace"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/state"
+)
+
+// Actions of a PlanItem.
+const (
+	PlanDownload = "download"
+	PlanSkip     = "skip"
+)
+
+// PlanItem is a product item a download would fetch or skip, and why it would skip it.
+type PlanItem struct {
+	Delivery string `json:"delivery"`
+	Item     string `json:"item"`
+	Path     string `json:"path"`
+	Size     int64  `json:"size"`
+	Action   string `json:"action"`
+	Reason   string `json:"reason,omitempty"`
+}
+
+// Plan lists the items a download would handle now, with the deliveries they belong to,
+// without downloading anything. It selects items like FetchEPOFiles: download.filter,
+// the incremental watermark and download.max_attempts apply. With download.skip_exists an
+// existing file is planned as skipped; the download still checks its SHA-1 first and
+// fetches it again if it does not match.
+func (downloader *Downloader) Plan(ctx context.Context) IOE.IOEither[error, []PlanItem] {
+	ctx, span := downloader.Tracer.Start(ctx, "download.plan", trace.WithAttributes(
+		attribute.Int("product_id", downloader.Cfg.Server.ProductID),
+	))
+	defer span.End()
+	client := Http.MakeClient(downloader.httpClient())
+	return F.Pipe1(
+		downloader.fetchProduct(ctx, client, downloader.incremental()),
+		IOE.Map[error](func(p models.Product) []PlanItem {
+			var plan []PlanItem
+			for _, d := range p.Deliveries {
+				files := downloader.productFiles(models.Product{Id: p.Id, Deliveries: []models.Delivery{d}})
+				for _, f := range files {
+					item := PlanItem{
+						Delivery: d.DeliveryName,
+						Item:     f.filename,
+						Path:     f.filePath,
+						Size:     f.expectedSize,
+						Action:   PlanDownload,
+					}
+					if reason := downloader.planSkip(ctx, f); reason != "" {
+						item.Action = PlanSkip
+						item.Reason = reason
+					}
+					plan = append(plan, item)
+				}
+			}
+			return plan
+		}),
+	)
+}
+
+// planSkip returns why the download would skip f, or "" when it would fetch it.
+func (downloader *Downloader) planSkip(ctx context.Context, f DownloadFile) string {
+	if downloader.alreadyDownloaded(ctx, f) {
+		return "manifest records it as downloaded"
+	}
+	if downloader.Manifest != nil && downloader.Cfg.Download.MaxAttempts > 0 {
+		it, _ := downloader.Manifest.Item(f.filename)
+		if it.Download != state.StatusDone && failedAttempts(it) >= downloader.Cfg.Download.MaxAttempts {
+			return "gave up after " + strconv.Itoa(failedAttempts(it)) + " failed attempts"
+		}
+	}
+	if !downloader.Cfg.Download.SkipExists {
+		return ""
+	}
+	if downloader.exists(ctx, f) {
+		return "file exists"
+	}
+	return ""
+}
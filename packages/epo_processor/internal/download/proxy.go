@@ -0,0 +1,40 @@
+package download
+
+import (
+	"fmt"
+	"net/http"
+	"net/url"
+	"strings"
+
+	"golang.org/x/net/http/httpproxy"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+)
+
+// proxyFunc selects the proxy of each request from server.network.proxy. Without a URL the
+// HTTP_PROXY, HTTPS_PROXY and NO_PROXY environment variables apply, as before. The URL may
+// use the http, https, socks5 or socks5h scheme; Username and Password, when set, replace
+// any credentials in it. The config check has validated the URL; should it still not
+// parse, every request fails with the parse error rather than bypassing the proxy.
+func proxyFunc(cfg config.Proxy) func(*http.Request) (*url.URL, error) {
+	if cfg.URL == "" {
+		return http.ProxyFromEnvironment
+	}
+	proxyURL, err := url.Parse(cfg.URL)
+	if err != nil {
+		return func(*http.Request) (*url.URL, error) {
+			return nil, fmt.Errorf("invalid server.network.proxy.url: %w", err)
+		}
+	}
+	if cfg.Username != "" {
+		proxyURL.User = url.UserPassword(cfg.Username, cfg.Password)
+	}
+	proxies := httpproxy.Config{
+		HTTPProxy:  proxyURL.String(),
+		HTTPSProxy: proxyURL.String(),
+		NoProxy:    strings.Join(cfg.NoProxy, ","),
+	}.ProxyFunc()
+	return func(req *http.Request) (*url.URL, error) {
+		return proxies(req.URL)
+	}
+}
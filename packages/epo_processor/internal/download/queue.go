@@ -0,0 +1,132 @@
+package download
+
+import (
+	"cmp"
+	"context"
+	"errors"
+	"fmt"
+	"slices"
+	"time"
+
+	ET "github.com/IBM/fp-go/v2/either"
+	IOE "github.com/IBM/fp-go/v2/ioeither"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/state"
+)
+
+// errInterrupted is recorded for attempts that were still in flight when a run died.
+const errInterrupted = "interrupted"
+
+// enqueue persists the download queue in the manifest before any task starts. Items
+// left in flight by an interrupted run go back to pending, and their open attempt is
+// closed as interrupted. Items whose failed attempts reached download.max_attempts
+// are dropped from the queue; interrupted attempts do not count.
+func (downloader *Downloader) enqueue(items []DownloadFile) ([]DownloadFile, error) {
+	if downloader.Manifest == nil {
+		return items, nil
+	}
+	pending := make([]state.ItemState, 0, len(items))
+	for _, f := range items {
+		pending = append(pending, state.ItemState{Name: f.filename, Published: f.published})
+	}
+	if err := downloader.Manifest.Enqueue(pending); err != nil {
+		return nil, fmt.Errorf("persist download queue: %w", err)
+	}
+	now := time.Now().UTC()
+	err := downloader.Manifest.UpdateItems(
+		func(it state.ItemState) bool { return it.Download == state.StatusInFlight },
+		func(it *state.ItemState) {
+			it.Download = state.StatusPending
+			if n := len(it.Attempts); n > 0 && it.Attempts[n-1].Finished == nil {
+				it.Attempts[n-1].Finished = &now
+				it.Attempts[n-1].Error = errInterrupted
+			}
+		},
+	)
+	if err != nil {
+		return nil, fmt.Errorf("recover download queue: %w", err)
+	}
+	limit := downloader.Cfg.Download.MaxAttempts
+	if limit <= 0 {
+		return items, nil
+	}
+	queued := items[:0:0]
+	for _, f := range items {
+		it, _ := downloader.Manifest.Item(f.filename)
+		if it.Download != state.StatusDone && failedAttempts(it) >= limit {
+			downloader.Logger.Warnw("Giving up on item after repeated failures",
+				"item", f.filename, "attempts", len(it.Attempts), "last_error", it.Error)
+			continue
+		}
+		queued = append(queued, f)
+	}
+	return queued, nil
+}
+
+// schedule orders the queued items for download.order. largest_first starts the biggest
+// items first, so with limited concurrency the longest transfers do not start last and
+// leave the other slots idle; items of equal size keep the product order.
+func (downloader *Downloader) schedule(items []DownloadFile) []DownloadFile {
+	if downloader.Cfg.Download.Order != "largest_first" {
+		return items
+	}
+	slices.SortStableFunc(items, func(a, b DownloadFile) int {
+		return cmp.Compare(b.expectedSize, a.expectedSize)
+	})
+	return items
+}
+
+func failedAttempts(it state.ItemState) int {
+	n := 0
+	for _, a := range it.Attempts {
+		if a.Error != "" && a.Error != errInterrupted {
+			n++
+		}
+	}
+	return n
+}
+
+// tracked records the download of each queued item as an attempt: the item is marked
+// in flight while it runs, and the attempt is closed with its outcome. Items the
+// manifest already records as downloaded run untracked.
+func (downloader *Downloader) tracked(
+	download func(DownloadFile) IOE.IOEither[error, int64],
+) func(DownloadFile) IOE.IOEither[error, int64] {
+	if downloader.Manifest == nil {
+		return download
+	}
+	return func(f DownloadFile) IOE.IOEither[error, int64] {
+		return func() ET.Either[error, int64] {
+			if it, _ := downloader.Manifest.Item(f.filename); it.Download == state.StatusDone {
+				return download(f)()
+			}
+			downloader.recordItem(f.filename, func(it *state.ItemState) {
+				it.Download = state.StatusInFlight
+				it.Attempts = append(it.Attempts, state.Attempt{Started: time.Now().UTC()})
+			})
+			res := download(f)()
+			_, err := ET.UnwrapError(res)
+			msg := ""
+			if err != nil {
+				msg = err.Error()
+			}
+			downloader.recordItem(f.filename, func(it *state.ItemState) {
+				switch {
+				case errors.Is(err, context.Canceled):
+					// a cancelled run resumes the item next time without counting it as failed
+					it.Download = state.StatusPending
+					msg = errInterrupted
+				case err != nil && it.Download == state.StatusInFlight:
+					it.Download = state.StatusFailed
+					it.Error = msg
+				}
+				if n := len(it.Attempts); n > 0 {
+					finished := time.Now().UTC()
+					it.Attempts[n-1].Finished = &finished
+					it.Attempts[n-1].Error = msg
+				}
+			})
+			return res
+		}
+	}
+}
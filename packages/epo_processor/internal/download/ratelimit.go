@@ -0,0 +1,71 @@
+package download
+
+import (
+	"context"
+	"net/http"
+	"sync"
+	"time"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+)
+
+// tokenBucket spaces out requests to server.rate_limit.requests_per_second. Up to burst
+// requests may start at once after a quiet period; tokens refill continuously.
+type tokenBucket struct {
+	mu     sync.Mutex
+	rate   float64
+	burst  float64
+	tokens float64
+	last   time.Time
+}
+
+func newTokenBucket(cfg config.RateLimit) *tokenBucket {
+	burst := float64(max(cfg.Burst, 1))
+	return &tokenBucket{rate: cfg.RequestsPerSecond, burst: burst, tokens: burst, last: time.Now()}
+}
+
+// Wait blocks until a token is available or ctx is done.
+func (b *tokenBucket) Wait(ctx context.Context) error {
+	b.mu.Lock()
+	now := time.Now()
+	b.tokens = min(b.burst, b.tokens+now.Sub(b.last).Seconds()*b.rate)
+	b.last = now
+	b.tokens--
+	delay := time.Duration(-b.tokens / b.rate * float64(time.Second))
+	b.mu.Unlock()
+	if delay <= 0 {
+		return nil
+	}
+	timer := time.NewTimer(delay)
+	defer timer.Stop()
+	select {
+	case <-ctx.Done():
+		b.mu.Lock()
+		b.tokens++
+		b.mu.Unlock()
+		return ctx.Err()
+	case <-timer.C:
+		return nil
+	}
+}
+
+// rateLimitTransport takes a token of the bucket before every request, so metadata fetches
+// and item downloads of all workers share one request rate.
+type rateLimitTransport struct {
+	base   http.RoundTripper
+	bucket *tokenBucket
+}
+
+func newRateLimitTransport(bucket *tokenBucket, base http.RoundTripper) http.RoundTripper {
+	if bucket == nil {
+		return base
+	}
+	return &rateLimitTransport{base: base, bucket: bucket}
+}
+
+func (t *rateLimitTransport) RoundTrip(req *http.Request) (*http.Response, error) {
+	if err := t.bucket.Wait(req.Context()); err != nil {
+		return nil, err
+	}
+	return t.base.RoundTrip(req)
+}
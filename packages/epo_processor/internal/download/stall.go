@@ -0,0 +1,59 @@
+package download
+
+import (
+	"errors"
+	"fmt"
+	"io"
+	"net/http"
+	"sync/atomic"
+	"time"
+)
+
+// ErrStalled is returned when a download received no bytes for download.stall_timeout.
+// The item is retried like after any other failed attempt, resuming its .part file.
+var ErrStalled = errors.New("download stalled")
+
+// stallReader is a response body that is closed by a watchdog once no bytes arrived for
+// timeout, which unblocks a read hanging on a dead connection. Closing it stops the watch.
+type stallReader struct {
+	body    io.ReadCloser
+	timeout time.Duration
+	timer   *time.Timer
+	stalled atomic.Bool
+}
+
+func newStallReader(body io.ReadCloser, timeout time.Duration) *stallReader {
+	s := &stallReader{body: body, timeout: timeout}
+	s.timer = time.AfterFunc(timeout, func() {
+		s.stalled.Store(true)
+		_ = body.Close()
+	})
+	return s
+}
+
+func (s *stallReader) Read(p []byte) (int, error) {
+	n, err := s.body.Read(p)
+	if n > 0 {
+		s.timer.Reset(s.timeout)
+	}
+	if err != nil && s.stalled.Load() {
+		return n, fmt.Errorf("%w: no data for %s", ErrStalled, s.timeout)
+	}
+	return n, err
+}
+
+func (s *stallReader) Close() error {
+	s.timer.Stop()
+	if s.stalled.Load() {
+		return nil
+	}
+	return s.body.Close()
+}
+
+// watchStall makes the body of resp fail with ErrStalled once it stops delivering bytes
+// for download.stall_timeout. A timeout of 0 leaves resp alone.
+func (downloader *Downloader) watchStall(resp *http.Response) {
+	if timeout := downloader.Cfg.Download.StallTimeout; timeout > 0 {
+		resp.Body = newStallReader(resp.Body, timeout)
+	}
+}
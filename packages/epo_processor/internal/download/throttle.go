@@ -0,0 +1,87 @@
+package download
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"net/http"
+	"sync"
+	"time"
+
+	ET "github.com/IBM/fp-go/v2/either"
+	IOE "github.com/IBM/fp-go/v2/ioeither"
+)
+
+// throttle pauses all downloads of a Downloader after the EPO API answered 429 Too Many
+// Requests, so that the concurrent requests back off together instead of each asking
+// again on its own schedule. The zero value is not paused.
+type throttle struct {
+	mu    sync.Mutex
+	until time.Time
+}
+
+// hold pauses downloads for d from now, unless an earlier answer asked for longer.
+func (t *throttle) hold(d time.Duration) {
+	t.mu.Lock()
+	defer t.mu.Unlock()
+	if until := time.Now().Add(d); until.After(t.until) {
+		t.until = until
+	}
+}
+
+// wait blocks until the pause is over or ctx is done.
+func (t *throttle) wait(ctx context.Context) error {
+	t.mu.Lock()
+	d := time.Until(t.until)
+	t.mu.Unlock()
+	if d <= 0 {
+		return nil
+	}
+	timer := time.NewTimer(d)
+	defer timer.Stop()
+	select {
+	case <-ctx.Done():
+		return ctx.Err()
+	case <-timer.C:
+		return nil
+	}
+}
+
+// throttled runs attempt and, while the API answers 429 Too Many Requests, pauses all
+// downloads for the Retry-After of the answer (server.throttle.default_wait without one)
+// and runs it again. These waits are shown in the progress bar and are not failed
+// attempts; once f waited server.throttle.max_wait in total, the 429 is returned.
+func (downloader *Downloader) throttled(
+	ctx context.Context,
+	f DownloadFile,
+	attempt IOE.IOEither[error, int64],
+) IOE.IOEither[error, int64] {
+	return func() ET.Either[error, int64] {
+		cfg := downloader.Cfg.Server.Throttle
+		var waited time.Duration
+		for {
+			if err := downloader.throttle.wait(ctx); err != nil {
+				return ET.Left[int64](err)
+			}
+			res := attempt()
+			var statusErr *HTTPStatusError
+			if _, err := ET.UnwrapError(res); !errors.As(err, &statusErr) ||
+				statusErr.StatusCode != http.StatusTooManyRequests {
+				return res
+			}
+			wait := statusErr.RetryAfter
+			if wait <= 0 {
+				wait = cfg.DefaultWait
+			}
+			if cfg.MaxWait > 0 && waited+wait > cfg.MaxWait {
+				return res
+			}
+			waited += wait
+			downloader.throttle.hold(wait)
+			downloader.Logger.Warnw("Throttled by the EPO API", "item", f.filename, "wait", wait)
+			if downloader.progress != nil {
+				downloader.progress.Describe(fmt.Sprintf("Throttled by the EPO API, resuming in %s...", wait))
+			}
+		}
+	}
+}
@@ -0,0 +1,203 @@
+package download
+
+import (
+	"context"
+	"errors"
+	"os"
+
+	ET "github.com/IBM/fp-go/v2/either"
+	F "github.com/IBM/fp-go/v2/function"
+	IOE "github.com/IBM/fp-go/v2/ioeither"
+	Http "github.com/IBM/fp-go/v2/ioeither/http"
+	"go.opentelemetry.io/otel/attribute"
+	"go.opentelemetry.io/otel/trace"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/state"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/storage"
+)
+
+type VerifyStatus string
+
+const (
+	VerifyOK      VerifyStatus = "ok"
+	VerifyCorrupt VerifyStatus = "corrupt"
+	VerifyMissing VerifyStatus = "missing"
+)
+
+// VerifyResult is the outcome of re-checking one product item on disk.
+type VerifyResult struct {
+	Filename string
+	Path     string
+	Checksum string
+	Status   VerifyStatus
+	Err      error
+	// Repaired is set when the item was downloaded again by RepairEPOFiles; Status and
+	// Err then describe the new download.
+	Repaired bool
+}
+
+// errVerifyS3 rejects verify and repair for a download directory in S3, whose objects
+// are checked while they are uploaded.
+var errVerifyS3 = errors.New("verify and repair need a local download.directory")
+
+// VerifyEPOFiles re-computes the SHA-1 of every product item in the download directory
+// and compares it against the checksum published in the product metadata.
+func (downloader *Downloader) VerifyEPOFiles(
+	ctx context.Context,
+) IOE.IOEither[error, []VerifyResult] {
+	if storage.IsS3(downloader.Cfg.Download.Directory) {
+		return IOE.Left[[]VerifyResult](errVerifyS3)
+	}
+	ctx, span := downloader.Tracer.Start(ctx, "download.verify", trace.WithAttributes(
+		attribute.Int("product_id", downloader.Cfg.Server.ProductID),
+		attribute.String("directory", downloader.Cfg.Download.Directory),
+	))
+	defer span.End()
+	client := Http.MakeClient(downloader.httpClient())
+	verify := func(f DownloadFile) IOE.IOEither[error, VerifyResult] {
+		return func() ET.Either[error, VerifyResult] {
+			select {
+			case <-ctx.Done():
+				return ET.Left[VerifyResult](ctx.Err())
+			default:
+				res := verifyFile(f)
+				if res.Status != VerifyMissing {
+					downloader.recordItem(f.filename, func(it *state.ItemState) {
+						it.Checksum = state.StatusOK
+						if res.Status == VerifyCorrupt {
+							it.Checksum = state.StatusMismatch
+						}
+					})
+				}
+				return ET.Right[error](res)
+			}
+		}
+	}
+	return F.Pipe1(
+		downloader.fetchDownloadFiles(ctx, client),
+		IOE.Chain(traverseBounded(downloader.Cfg.Server.ConcurrentDownloads, verify)),
+	)
+}
+
+// RepairEPOFiles verifies the download directory like VerifyEPOFiles, deletes corrupt
+// items and downloads corrupt and missing items again. Items missing because they
+// were extracted with delete_after_extract are left alone. Repaired items are verified
+// again and go back to pending extraction and parsing in the manifest.
+func (downloader *Downloader) RepairEPOFiles(
+	ctx context.Context,
+) IOE.IOEither[error, []VerifyResult] {
+	if storage.IsS3(downloader.Cfg.Download.Directory) {
+		return IOE.Left[[]VerifyResult](errVerifyS3)
+	}
+	ctx, span := downloader.Tracer.Start(ctx, "download.repair", trace.WithAttributes(
+		attribute.Int("product_id", downloader.Cfg.Server.ProductID),
+		attribute.String("directory", downloader.Cfg.Download.Directory),
+	))
+	defer span.End()
+	client := Http.MakeClient(downloader.httpClient())
+	items := Http.MakeClient(downloader.downloadClient())
+	repair := func(f DownloadFile) IOE.IOEither[error, VerifyResult] {
+		return func() ET.Either[error, VerifyResult] {
+			if err := ctx.Err(); err != nil {
+				return ET.Left[VerifyResult](err)
+			}
+			res := verifyFile(f)
+			if res.Status == VerifyOK || downloader.deletedAfterExtract(f, res) {
+				return ET.Right[error](res)
+			}
+			downloader.Logger.Infow("Repairing item", "item", f.filename, "status", res.Status, "err", res.Err)
+			_ = os.Remove(f.filePath)
+			_ = os.Remove(f.partPath())
+			downloader.recordItem(f.filename, func(it *state.ItemState) {
+				it.Download = state.StatusPending
+				it.Checksum = state.StatusPending
+				it.Extract = state.StatusPending
+				it.Parse = state.StatusPending
+			})
+			download := downloader.tracked(func(f DownloadFile) IOE.IOEither[error, int64] {
+				return downloader.DownloadEPOFile(ctx, items, f)
+			})
+			if _, err := ET.UnwrapError(download(f)()); err != nil {
+				if errors.Is(err, context.Canceled) {
+					return ET.Left[VerifyResult](err)
+				}
+				res.Err = err
+				res.Repaired = true
+				return ET.Right[error](res)
+			}
+			res = verifyFile(f)
+			res.Repaired = true
+			downloader.recordItem(f.filename, func(it *state.ItemState) {
+				it.Checksum = state.StatusOK
+				if res.Status == VerifyCorrupt {
+					it.Checksum = state.StatusMismatch
+				}
+			})
+			return ET.Right[error](res)
+		}
+	}
+	return F.Pipe1(
+		downloader.fetchDownloadFiles(ctx, client),
+		IOE.Chain(traverseBounded(downloader.Cfg.Server.ConcurrentDownloads, repair)),
+	)
+}
+
+// deletedAfterExtract reports whether f is missing because it was extracted and then
+// deleted on purpose.
+func (downloader *Downloader) deletedAfterExtract(f DownloadFile, res VerifyResult) bool {
+	if res.Status != VerifyMissing || !downloader.Cfg.Extract.DeleteAfterExtract ||
+		downloader.Manifest == nil {
+		return false
+	}
+	it, _ := downloader.Manifest.Item(f.filename)
+	return it.Extract == state.StatusDone
+}
+
+// ProductItem is one item of the product metadata and where it is stored on disk.
+type ProductItem struct {
+	Name         string
+	Path         string
+	ExpectedSize int64
+	Checksum     string
+}
+
+// ProductItems lists the product items selected by download.filter without downloading
+// them. The incremental watermark is ignored so the whole corpus is listed.
+func (downloader *Downloader) ProductItems(ctx context.Context) IOE.IOEither[error, []ProductItem] {
+	ctx, span := downloader.Tracer.Start(ctx, "download.product_items", trace.WithAttributes(
+		attribute.Int("product_id", downloader.Cfg.Server.ProductID),
+	))
+	defer span.End()
+	client := Http.MakeClient(downloader.httpClient())
+	return F.Pipe1(
+		downloader.fetchProduct(ctx, client, false),
+		IOE.Map[error](func(p models.Product) []ProductItem {
+			files := downloader.productFiles(p)
+			items := make([]ProductItem, 0, len(files))
+			for _, f := range files {
+				items = append(items, ProductItem{
+					Name:         f.filename,
+					Path:         f.filePath,
+					ExpectedSize: f.expectedSize,
+					Checksum:     f.checksum,
+				})
+			}
+			return items
+		}),
+	)
+}
+
+func verifyFile(f DownloadFile) VerifyResult {
+	res := VerifyResult{Filename: f.filename, Path: f.filePath, Checksum: f.checksum, Status: VerifyOK}
+	if _, err := os.Stat(f.filePath); err != nil {
+		res.Status = VerifyMissing
+		res.Err = err
+		return res
+	}
+	if _, err := ET.UnwrapError(verifyChecksum(f.checksum, f.filePath)()); err != nil {
+		res.Status = VerifyCorrupt
+		res.Err = err
+	}
+	return res
+}
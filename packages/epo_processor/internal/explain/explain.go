@@ -0,0 +1,111 @@
+// Package explain records why the pipeline left out a delivery, item, archive entry or
+// file, so that a patent missing from the output can be traced to the step that dropped it.
+package explain
+
+import (
+	"encoding/json"
+	"fmt"
+	"os"
+	"path/filepath"
+	"sync"
+	"time"
+
+	"go.uber.org/zap"
+)
+
+const (
+	StageDownload = "download"
+	StageExtract  = "extract"
+	StageParse    = "parse"
+)
+
+const (
+	KindDelivery = "delivery"
+	KindItem     = "item"
+	KindArchive  = "archive"
+	KindEntry    = "entry"
+	KindFile     = "file"
+)
+
+// Entry is one line of the skip report.
+type Entry struct {
+	Time    time.Time `json:"time"`
+	Product int       `json:"product"`
+	Stage   string    `json:"stage"`
+	Kind    string    `json:"kind"`
+	Name    string    `json:"name"`
+	Reason  string    `json:"reason"`
+}
+
+// Report appends an Entry per skip to a JSONL file and logs it at debug level. A nil
+// *Report ignores skips, so callers need not check whether explain mode is on.
+// Reports of several products may share a file; entries carry the product id.
+type Report struct {
+	mu      sync.Mutex
+	file    *os.File
+	enc     *json.Encoder
+	product int
+	logger  *zap.SugaredLogger
+	counts  map[string]int
+}
+
+// Open appends to the skip report at path, creating it and its directory if needed.
+func Open(path string, product int, logger *zap.SugaredLogger) (*Report, error) {
+	if err := os.MkdirAll(filepath.Dir(path), 0o755); err != nil {
+		return nil, fmt.Errorf("create skip report directory: %w", err)
+	}
+	f, err := os.OpenFile(path, os.O_CREATE|os.O_WRONLY|os.O_APPEND, 0o644)
+	if err != nil {
+		return nil, fmt.Errorf("open skip report: %w", err)
+	}
+	return &Report{
+		file:    f,
+		enc:     json.NewEncoder(f),
+		product: product,
+		logger:  logger,
+		counts:  make(map[string]int),
+	}, nil
+}
+
+// Skip records that name (of the given kind) was left out by stage for reason.
+func (r *Report) Skip(stage, kind, name, reason string) {
+	if r == nil {
+		return
+	}
+	r.mu.Lock()
+	defer r.mu.Unlock()
+	r.counts[stage+"/"+reason]++
+	r.logger.Debugw("Skipped", "stage", stage, "kind", kind, "name", name, "reason", reason)
+	entry := Entry{
+		Time:    time.Now().UTC(),
+		Product: r.product,
+		Stage:   stage,
+		Kind:    kind,
+		Name:    name,
+		Reason:  reason,
+	}
+	if err := r.enc.Encode(entry); err != nil {
+		r.logger.Warnw("Failed to write skip report", "err", err)
+	}
+}
+
+// Counts returns the number of skips so far per "stage/reason".
+func (r *Report) Counts() map[string]int {
+	if r == nil {
+		return nil
+	}
+	r.mu.Lock()
+	defer r.mu.Unlock()
+	counts := make(map[string]int, len(r.counts))
+	for k, v := range r.counts {
+		counts[k] = v
+	}
+	return counts
+}
+
+func (r *Report) Close() error {
+	if r == nil {
+		return nil
+	}
+	return r.file.Close()
+}
@@ -3,8 +3,8 @@ package extract
 import (
 	"archive/tar"
 	"archive/zip"
-	"compress/gzip"
 	"context"
+	"errors"
 	"fmt"
 	"io"
 	"os"
@@ -22,6 +22,9 @@ import (
 	"go.uber.org/zap"
 
 	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/explain"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/failures"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/state"
 	T "github.com/Qubut/IP-Claim/packages/epo_processor/internal/typing"
 )
 
@@ -32,6 +35,12 @@ const (
 	TarType     ArchiveType = "tar"
 	TarGzType   ArchiveType = "tar.gz"
 	TgzType     ArchiveType = "tgz"
+	TarZstType  ArchiveType = "tar.zst"
+	TarBz2Type  ArchiveType = "tar.bz2"
+	TarXzType   ArchiveType = "tar.xz"
+	ZstType     ArchiveType = "zst"
+	Bz2Type     ArchiveType = "bz2"
+	XzType      ArchiveType = "xz"
 	UnknownType ArchiveType = "unknown"
 )
 
@@ -51,6 +60,8 @@ type Extractor struct {
 	archivesFailed  metric.Int64Counter
 	bytesTotal      metric.Int64Counter
 	fileDuration    metric.Int64Histogram
+	Manifest        *state.Manifest
+	Explain         *explain.Report
 }
 
 func NewExtractor(
@@ -134,13 +145,13 @@ func (e *Extractor) ExtractAll(ctx context.Context, dir string) IOE.IOEither[err
 	e.Logger.Infow("Starting extraction in directory", "dir", dir, "deleteAfter", e.DeleteAfter)
 
 	e.progress = progressbar.NewOptions64(-1,
-		progressbar.OptionSetWriter(os.Stdout),
+		progressbar.OptionSetWriter(e.Cfg.ProgressWriter()),
 		progressbar.OptionSetWidth(60),
 		progressbar.OptionSetDescription("[0 extracted] Finding archive files..."),
 		progressbar.OptionSpinnerType(14),
 		progressbar.OptionSetElapsedTime(true),
 		progressbar.OptionSetPredictTime(true),
-		progressbar.OptionThrottle(50*time.Millisecond),
+		progressbar.OptionThrottle(e.Cfg.Log.ProgressInterval),
 		progressbar.OptionSetRenderBlankState(true),
 		progressbar.OptionUseANSICodes(true),
 	)
@@ -176,15 +187,28 @@ func (e *Extractor) ExtractAll(ctx context.Context, dir string) IOE.IOEither[err
 				fmt.Sprintf("[0 extracted] Processing %d archive files...", len(archiveFiles)),
 			)
 
+			// each archive is extracted even when others fail; the failures are returned
+			// together, each under its archive
+			failed := &failures.Collector{}
 			traverse := IOE.TraverseArrayPar(func(archivePath string) IOE.IOEither[error, T.Unit] {
-				select {
-				case <-ctx.Done():
-					return IOE.Left[T.Unit](ctx.Err())
-				default:
-					return e.processSingleArchive(ctx, archivePath)
-				}
+				return function.Pipe2(
+					e.extractItem(ctx, archivePath),
+					IOE.Tap(func(_ T.Unit) IOE.IOEither[error, T.Unit] {
+						failed.Succeed()
+						return IOE.Of[error](T.Unit{})
+					}),
+					IOE.TapLeft[T.Unit](func(err error) IOE.IOEither[error, T.Unit] {
+						failed.Fail(archivePath, err)
+						return IOE.Of[error](T.Unit{})
+					}),
+				)
 			})
-			return traverse(archiveFiles)
+			return IOE.MapLeft[[]T.Unit](func(err error) error {
+				if failed.Len() == 0 || errors.Is(err, context.Canceled) {
+					return err
+				}
+				return failed.Err()
+			})(traverse(archiveFiles))
 		}),
 		IOE.Map[error](func(_ []T.Unit) T.Unit {
 			durationMs := time.Since(startTime).Milliseconds()
@@ -211,6 +235,53 @@ func (e *Extractor) ExtractAll(ctx context.Context, dir string) IOE.IOEither[err
 	)
 }
 
+// ExtractArchive extracts one downloaded item and returns the directory holding its
+// contents. Files that are not archives are returned unchanged.
+func (e *Extractor) ExtractArchive(ctx context.Context, archivePath string) IOE.IOEither[error, string] {
+	archiveType := detectArchiveType(archivePath)
+	if archiveType == UnknownType {
+		return IOE.Of[error](archivePath)
+	}
+	return function.Pipe1(
+		e.extractItem(ctx, archivePath),
+		IOE.Map[error](func(_ T.Unit) string {
+			return destination(archivePath, archiveType)
+		}),
+	)
+}
+
+// extractItem extracts a top-level archive unless the manifest records it as extracted.
+func (e *Extractor) extractItem(ctx context.Context, archivePath string) IOE.IOEither[error, T.Unit] {
+	select {
+	case <-ctx.Done():
+		return IOE.Left[T.Unit](ctx.Err())
+	default:
+	}
+	name := e.itemName(archivePath)
+	if e.alreadyExtracted(name) {
+		e.Logger.Infow("Skipping archive already extracted", "archive", archivePath)
+		e.Explain.Skip(explain.StageExtract, explain.KindArchive, name, "manifest records it as extracted")
+		return IOE.Right[error](T.Unit{})
+	}
+	return function.Pipe2(
+		e.processSingleArchive(ctx, archivePath),
+		IOE.Tap(func(_ T.Unit) IOE.IOEither[error, T.Unit] {
+			e.recordItem(name, func(it *state.ItemState) {
+				it.Extract = state.StatusDone
+				it.Error = ""
+			})
+			return IOE.Of[error](T.Unit{})
+		}),
+		IOE.TapLeft[T.Unit](func(err error) IOE.IOEither[error, T.Unit] {
+			e.recordItem(name, func(it *state.ItemState) {
+				it.Extract = state.StatusFailed
+				it.Error = err.Error()
+			})
+			return IOE.Of[error](T.Unit{})
+		}),
+	)
+}
+
 func (e *Extractor) ProcessArchiveFile(archivePath string) IOE.IOEither[error, T.Unit] {
 	ctx := context.Background()
 	return e.processSingleArchive(ctx, archivePath)
@@ -220,18 +291,15 @@ func (e *Extractor) processSingleArchive(
 	ctx context.Context,
 	archivePath string,
 ) IOE.IOEither[error, T.Unit] {
-	archiveType := getArchiveType(archivePath)
+	archiveType := detectArchiveType(archivePath)
 	ctx, span := e.Tracer.Start(ctx, "process.archive", trace.WithAttributes(
 		attribute.String("archive_path", archivePath),
 		attribute.String("archive_type", string(archiveType)),
 	))
 	defer span.End()
 	startTime := time.Now()
-	baseName := strings.TrimSuffix(filepath.Base(archivePath), filepath.Ext(archivePath))
-	if archiveType == TarGzType || archiveType == TgzType {
-		baseName = strings.TrimSuffix(baseName, ".tar") // Remove .tar for .tar.gz/.tgz
-	}
-	destDir := filepath.Join(filepath.Dir(archivePath), baseName)
+	baseName := trimArchiveExt(filepath.Base(archivePath), archiveType)
+	destDir := destination(archivePath, archiveType)
 	e.Logger.Infow("Processing archive file",
 		"archive", archivePath,
 		"baseName", baseName,
@@ -252,7 +320,7 @@ func (e *Extractor) processSingleArchive(
 			}
 			e.Logger.Infow("Extracting main archive", "archive", archivePath, "dest", destDir)
 			e.currentArchive = archivePath
-			e.progress.Describe(fmt.Sprintf("Extracting %s", filepath.Base(archivePath)))
+			e.describe(fmt.Sprintf("Extracting %s", filepath.Base(archivePath)))
 			return T.Unit{}, e.extractToDir(archivePath, destDir, archiveType)
 		}),
 		IOE.Chain(func(_ T.Unit) IOE.IOEither[error, T.Unit] {
@@ -261,7 +329,7 @@ func (e *Extractor) processSingleArchive(
 				return IOE.Left[T.Unit](ctx.Err())
 			default:
 			}
-			e.progress.Describe(fmt.Sprintf("Extracting nested archives in %s", baseName))
+			e.describe(fmt.Sprintf("Extracting nested archives in %s", baseName))
 			return e.extractAllArchivesInDir(ctx, destDir)
 		}),
 		IOE.Chain(func(_ T.Unit) IOE.IOEither[error, T.Unit] {
@@ -301,21 +369,77 @@ func (e *Extractor) processSingleArchive(
 	)
 }
 
+// destination is the directory an archive extracts into.
+func destination(archivePath string, archiveType ArchiveType) string {
+	if archiveType.isSingleFile() {
+		// a compressed file decompresses next to itself
+		return filepath.Dir(archivePath)
+	}
+	baseName := trimArchiveExt(filepath.Base(archivePath), archiveType)
+	return filepath.Join(filepath.Dir(archivePath), baseName)
+}
+
+// itemName is the manifest name of the downloaded item at archivePath: its path below the
+// download directory with forward slashes, as download.path_template laid it out.
+func (e *Extractor) itemName(archivePath string) string {
+	rel, err := filepath.Rel(e.Cfg.Download.Directory, archivePath)
+	if err != nil || strings.HasPrefix(rel, "..") {
+		return filepath.Base(archivePath)
+	}
+	return filepath.ToSlash(rel)
+}
+
+func (e *Extractor) alreadyExtracted(name string) bool {
+	if e.Manifest == nil {
+		return false
+	}
+	it, ok := e.Manifest.Item(name)
+	return ok && it.Extract == state.StatusDone
+}
+
+// recordItem updates the manifest entry of name when state tracking is enabled.
+func (e *Extractor) recordItem(name string, fn func(*state.ItemState)) {
+	if e.Manifest == nil {
+		return
+	}
+	if err := e.Manifest.UpdateItem(name, fn); err != nil {
+		e.Logger.Warnw("Failed to update manifest", "item", name, "err", err)
+	}
+}
+
+// IsArchive reports whether path has the suffix of an archive type the extractor handles.
+func IsArchive(path string) bool {
+	return getArchiveType(path) != UnknownType
+}
+
+// ExtractedPath returns where the archive at path is extracted to: the directory named
+// after it, or the decompressed file next to a compressed single file.
+func ExtractedPath(path string) string {
+	t := getArchiveType(path)
+	if t.isSingleFile() {
+		return filepath.Join(filepath.Dir(path), trimArchiveExt(filepath.Base(path), t))
+	}
+	return destination(path, t)
+}
+
 func getArchiveType(path string) ArchiveType {
 	lower := strings.ToLower(path)
-	if strings.HasSuffix(lower, ".zip") {
-		return ZipType
-	} else if strings.HasSuffix(lower, ".tar.gz") {
-		return TarGzType
-	} else if strings.HasSuffix(lower, ".tgz") {
-		return TgzType
-	} else if strings.HasSuffix(lower, ".tar") {
-		return TarType
+	for _, s := range archiveSuffixes {
+		if strings.HasSuffix(lower, s.suffix) {
+			return s.kind
+		}
 	}
 	return UnknownType
 }
 
+// findArchiveFiles lists the downloaded archives in dir. They lie as many directories
+// deep as download.path_template has separators; the directories next to them hold
+// extracted archives and are not searched.
 func (e *Extractor) findArchiveFiles(dir string) ([]string, error) {
+	return e.findArchivesAt(dir, strings.Count(e.Cfg.Download.PathTemplate, "/"))
+}
+
+func (e *Extractor) findArchivesAt(dir string, depth int) ([]string, error) {
 	var archiveFiles []string
 
 	entries, err := os.ReadDir(dir)
@@ -324,16 +448,33 @@ func (e *Extractor) findArchiveFiles(dir string) ([]string, error) {
 	}
 
 	for _, entry := range entries {
-		if !entry.IsDir() {
-			if getArchiveType(entry.Name()) != UnknownType {
-				archiveFiles = append(archiveFiles, filepath.Join(dir, entry.Name()))
+		path := filepath.Join(dir, entry.Name())
+		switch {
+		case entry.IsDir() && depth > 0:
+			nested, err := e.findArchivesAt(path, depth-1)
+			if err != nil {
+				return nil, err
 			}
+			archiveFiles = append(archiveFiles, nested...)
+		case entry.IsDir() || depth > 0:
+			// an extracted archive, or a file outside the layout of the template
+		case detectArchiveType(path) != UnknownType:
+			archiveFiles = append(archiveFiles, path)
+		default:
+			e.Explain.Skip(explain.StageExtract, explain.KindFile, path, "not a supported archive type")
 		}
 	}
 
 	return archiveFiles, nil
 }
 
+// describe updates the progress bar, which is only shown by ExtractAll.
+func (e *Extractor) describe(desc string) {
+	if e.progress != nil {
+		e.progress.Describe(desc)
+	}
+}
+
 func (e *Extractor) updateDescription() {
 	if e.progress != nil {
 		desc := fmt.Sprintf("[%d extracted] Extracting %s", e.ExtractedFiles.Load(), e.currentFile)
@@ -373,7 +514,7 @@ func (e *Extractor) extractAllArchivesInDir(
 					return T.Unit{}, ctx.Err()
 				default:
 				}
-				archiveType := getArchiveType(archiveFile)
+				archiveType := detectArchiveType(archiveFile)
 				ctx, span := e.Tracer.Start(ctx, "extract.nested_archive", trace.WithAttributes(
 					attribute.String("archive_file", archiveFile),
 					attribute.String("archive_type", string(archiveType)),
@@ -436,7 +577,7 @@ func (e *Extractor) findAllArchiveFilesRecursive(dir string) ([]string, error) {
 			return err
 		}
 
-		if !d.IsDir() && getArchiveType(d.Name()) != UnknownType {
+		if !d.IsDir() && detectArchiveType(path) != UnknownType {
 			archiveFiles = append(archiveFiles, path)
 		}
 
@@ -447,13 +588,15 @@ func (e *Extractor) findAllArchiveFilesRecursive(dir string) ([]string, error) {
 }
 
 func (e *Extractor) extractToDir(archivePath, destDir string, archiveType ArchiveType) error {
-	switch archiveType {
-	case ZipType:
+	switch {
+	case archiveType == ZipType:
 		return e.extractZip(archivePath, destDir)
-	case TarType:
+	case archiveType == TarType:
 		return e.extractTar(archivePath, destDir)
-	case TarGzType, TgzType:
-		return e.extractTarGz(archivePath, destDir)
+	case archiveType.isTar():
+		return e.extractCompressedTar(archivePath, destDir, archiveType.codec())
+	case archiveType.isSingleFile():
+		return e.extractCompressedFile(archivePath, destDir, archiveType)
 	default:
 		return fmt.Errorf("unsupported archive type: %s", archiveType)
 	}
@@ -476,10 +619,12 @@ func (e *Extractor) extractZip(zipPath, destDir string) error {
 		e.currentFile = f.Name
 		e.updateDescription()
 
-		destPath := filepath.Join(destDir, f.Name)
-		cleanDestPath := filepath.Clean(destPath)
-		if !strings.HasPrefix(cleanDestPath, filepath.Clean(destDir)+string(filepath.Separator)) {
-			return fmt.Errorf("illegal file path: %s", destPath)
+		cleanDestPath, err := e.entryPath(destDir, f.Name)
+		if err != nil {
+			return err
+		}
+		if cleanDestPath == "" {
+			continue
 		}
 
 		if f.FileInfo().IsDir() {
@@ -549,21 +694,59 @@ func (e *Extractor) extractTar(tarPath, destDir string) error {
 	return e.extractTarReader(tr, destDir, tarPath)
 }
 
-func (e *Extractor) extractTarGz(tarGzPath, destDir string) error {
-	file, err := os.Open(tarGzPath)
+func (e *Extractor) extractCompressedTar(archivePath, destDir, codec string) error {
+	r, err := openDecompressed(archivePath, codec)
 	if err != nil {
-		return fmt.Errorf("failed to open tar.gz %s: %w", tarGzPath, err)
+		return err
 	}
-	defer file.Close()
+	tr := tar.NewReader(r)
+	if err := e.extractTarReader(tr, destDir, archivePath); err != nil {
+		r.Close()
+		return err
+	}
+	return r.Close()
+}
 
-	gzr, err := gzip.NewReader(file)
+// extractCompressedFile decompresses a single .zst/.bz2/.xz file into destDir.
+func (e *Extractor) extractCompressedFile(
+	archivePath, destDir string,
+	archiveType ArchiveType,
+) error {
+	startTime := time.Now()
+	e.currentArchive = archivePath
+	r, err := openDecompressed(archivePath, archiveType.codec())
 	if err != nil {
-		return fmt.Errorf("failed to create gzip reader for %s: %w", tarGzPath, err)
+		return err
 	}
-	defer gzr.Close()
-
-	tr := tar.NewReader(gzr)
-	return e.extractTarReader(tr, destDir, tarGzPath)
+	name := trimArchiveExt(filepath.Base(archivePath), archiveType)
+	e.currentFile = name
+	e.updateDescription()
+	destPath := filepath.Join(destDir, name)
+	destFile, err := os.Create(destPath)
+	if err != nil {
+		r.Close()
+		return fmt.Errorf("failed to create file %s: %w", destPath, err)
+	}
+	n, err := io.Copy(destFile, r)
+	destFile.Close()
+	if closeErr := r.Close(); err == nil {
+		err = closeErr
+	}
+	if err != nil {
+		os.Remove(destPath)
+		return fmt.Errorf("failed to decompress %s: %w", archivePath, err)
+	}
+	e.bytesTotal.Add(context.Background(), n)
+	e.filesTotal.Add(context.Background(), 1)
+	e.ExtractedFiles.Add(1)
+	e.fileDuration.Record(context.Background(), time.Since(startTime).Milliseconds(),
+		metric.WithAttributes(
+			attribute.String("status", "success"),
+			attribute.Bool("nested", false),
+		),
+	)
+	e.updateDescription()
+	return nil
 }
 
 func (e *Extractor) extractTarReader(tr *tar.Reader, destDir, archivePath string) error {
@@ -582,10 +765,12 @@ func (e *Extractor) extractTarReader(tr *tar.Reader, destDir, archivePath string
 		e.currentFile = header.Name
 		e.updateDescription()
 
-		destPath := filepath.Join(destDir, header.Name)
-		cleanDestPath := filepath.Clean(destPath)
-		if !strings.HasPrefix(cleanDestPath, filepath.Clean(destDir)+string(filepath.Separator)) {
-			return fmt.Errorf("illegal file path: %s", destPath)
+		cleanDestPath, err := e.entryPath(destDir, header.Name)
+		if err != nil {
+			return err
+		}
+		if cleanDestPath == "" {
+			continue
 		}
 
 		switch header.Typeflag {
@@ -618,9 +803,21 @@ func (e *Extractor) extractTarReader(tr *tar.Reader, destDir, archivePath string
 			e.filesTotal.Add(context.Background(), 1)
 			e.ExtractedFiles.Add(1)
 		case tar.TypeSymlink:
-			// Sanitize link target
-			targetPath := filepath.Clean(filepath.Join(destDir, header.Linkname))
-			if !strings.HasPrefix(targetPath, filepath.Clean(destDir)+string(filepath.Separator)) {
+			if err := os.MkdirAll(filepath.Dir(cleanDestPath), os.ModePerm); err != nil {
+				return fmt.Errorf(
+					"failed to create parent directory for %s: %w",
+					cleanDestPath,
+					err,
+				)
+			}
+			if !linkAllowed(destDir, cleanDestPath, header.Linkname) {
+				if e.Cfg.Extract.PathPolicy != PathPolicyLenient {
+					return fmt.Errorf(
+						"illegal symlink %s -> %s",
+						header.Name,
+						header.Linkname,
+					)
+				}
 				e.Logger.Warnw(
 					"Skipping illegal symlink target",
 					"symlink",
@@ -628,6 +825,8 @@ func (e *Extractor) extractTarReader(tr *tar.Reader, destDir, archivePath string
 					"target",
 					header.Linkname,
 				)
+				e.Explain.Skip(explain.StageExtract, explain.KindEntry, archivePath+"!/"+header.Name,
+					"symlink target outside the extraction directory")
 				continue
 			}
 			if err := os.Symlink(header.Linkname, cleanDestPath); err != nil {
@@ -641,6 +840,8 @@ func (e *Extractor) extractTarReader(tr *tar.Reader, destDir, archivePath string
 				"name",
 				header.Name,
 			)
+			e.Explain.Skip(explain.StageExtract, explain.KindEntry, archivePath+"!/"+header.Name,
+				fmt.Sprintf("unsupported tar entry type %q", header.Typeflag))
 			continue
 		}
 
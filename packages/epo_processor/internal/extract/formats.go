@@ -0,0 +1,202 @@
+package extract
+
+import (
+	"bufio"
+	"bytes"
+	"compress/bzip2"
+	"compress/gzip"
+	"errors"
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+	"strings"
+
+	"github.com/klauspost/compress/zstd"
+	"github.com/ulikunitz/xz"
+)
+
+// archiveSuffixes maps file extensions to archive types. Compound suffixes come first
+// so that .tar.zst is not mistaken for a plain .zst file.
+var archiveSuffixes = []struct {
+	suffix string
+	kind   ArchiveType
+}{
+	{".tar.gz", TarGzType},
+	{".tgz", TgzType},
+	{".tar.zst", TarZstType},
+	{".tar.bz2", TarBz2Type},
+	{".tar.xz", TarXzType},
+	{".zip", ZipType},
+	{".tar", TarType},
+	{".zst", ZstType},
+	{".bz2", Bz2Type},
+	{".xz", XzType},
+}
+
+// codec names the compression of a type, empty for zip and plain tar.
+func (t ArchiveType) codec() string {
+	switch t {
+	case TarGzType, TgzType:
+		return "gzip"
+	case TarZstType, ZstType:
+		return "zstd"
+	case TarBz2Type, Bz2Type:
+		return "bzip2"
+	case TarXzType, XzType:
+		return "xz"
+	default:
+		return ""
+	}
+}
+
+// isTar reports whether the type holds a tar stream, compressed or not.
+func (t ArchiveType) isTar() bool {
+	switch t {
+	case TarType, TarGzType, TgzType, TarZstType, TarBz2Type, TarXzType:
+		return true
+	default:
+		return false
+	}
+}
+
+// isSingleFile reports whether the type compresses a single file rather than an archive.
+func (t ArchiveType) isSingleFile() bool {
+	return t.codec() != "" && !t.isTar()
+}
+
+// trimArchiveExt strips the archive extension from name. Sniffed files without a known
+// extension get a suffix so the output does not overwrite the input.
+func trimArchiveExt(name string, t ArchiveType) string {
+	lower := strings.ToLower(name)
+	for _, s := range archiveSuffixes {
+		if s.kind == t && strings.HasSuffix(lower, s.suffix) {
+			return name[:len(name)-len(s.suffix)]
+		}
+	}
+	return name + ".out"
+}
+
+// detectArchiveType picks the type by extension and falls back to magic bytes for
+// files without an extension. Files with another extension, e.g. .xml, .docx or .jar,
+// which are zip files themselves, are never sniffed and stay as they are.
+func detectArchiveType(path string) ArchiveType {
+	if t := getArchiveType(path); t != UnknownType {
+		return t
+	}
+	if filepath.Ext(path) != "" {
+		return UnknownType
+	}
+	return sniffArchiveType(path)
+}
+
+var (
+	zipMagic   = []byte("PK\x03\x04")
+	gzipMagic  = []byte{0x1f, 0x8b}
+	zstdMagic  = []byte{0x28, 0xb5, 0x2f, 0xfd}
+	bzip2Magic = []byte("BZh")
+	xzMagic    = []byte{0xfd, '7', 'z', 'X', 'Z', 0x00}
+)
+
+// tarMagicOffset is where the ustar magic lives in a tar header block.
+const tarMagicOffset = 257
+
+func sniffArchiveType(path string) ArchiveType {
+	f, err := os.Open(path)
+	if err != nil {
+		return UnknownType
+	}
+	defer f.Close()
+	head := make([]byte, 512)
+	n, _ := io.ReadFull(f, head)
+	head = head[:n]
+	switch {
+	case bytes.HasPrefix(head, zipMagic):
+		return ZipType
+	case isTarHeader(head):
+		return TarType
+	case bytes.HasPrefix(head, gzipMagic):
+		// gzip-compressed single files have no dedicated type; only tarballs are handled
+		return compressedKind(path, "gzip", TarGzType, UnknownType)
+	case bytes.HasPrefix(head, zstdMagic):
+		return compressedKind(path, "zstd", TarZstType, ZstType)
+	case bytes.HasPrefix(head, bzip2Magic):
+		return compressedKind(path, "bzip2", TarBz2Type, Bz2Type)
+	case bytes.HasPrefix(head, xzMagic):
+		return compressedKind(path, "xz", TarXzType, XzType)
+	default:
+		return UnknownType
+	}
+}
+
+func isTarHeader(block []byte) bool {
+	return len(block) >= tarMagicOffset+5 &&
+		string(block[tarMagicOffset:tarMagicOffset+5]) == "ustar"
+}
+
+// compressedKind peeks into the decompressed stream to tell a tarball from a single file.
+func compressedKind(path, codec string, tarKind, fileKind ArchiveType) ArchiveType {
+	r, err := openDecompressed(path, codec)
+	if err != nil {
+		return fileKind
+	}
+	defer r.Close()
+	head := make([]byte, 512)
+	n, _ := io.ReadFull(r, head)
+	if isTarHeader(head[:n]) {
+		return tarKind
+	}
+	return fileKind
+}
+
+// openDecompressed returns the decoded contents of path.
+func openDecompressed(path, codec string) (io.ReadCloser, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return nil, fmt.Errorf("failed to open %s: %w", path, err)
+	}
+	br := bufio.NewReader(f)
+	switch codec {
+	case "gzip":
+		gzr, err := gzip.NewReader(br)
+		if err != nil {
+			f.Close()
+			return nil, fmt.Errorf("failed to create gzip reader for %s: %w", path, err)
+		}
+		return &decompressed{Reader: gzr, closers: []func() error{gzr.Close, f.Close}}, nil
+	case "zstd":
+		zr, err := zstd.NewReader(br)
+		if err != nil {
+			f.Close()
+			return nil, fmt.Errorf("failed to create zstd reader for %s: %w", path, err)
+		}
+		closeZstd := func() error { zr.Close(); return nil }
+		return &decompressed{Reader: zr, closers: []func() error{closeZstd, f.Close}}, nil
+	case "bzip2":
+		return &decompressed{Reader: bzip2.NewReader(br), closers: []func() error{f.Close}}, nil
+	case "xz":
+		xzr, err := xz.NewReader(br)
+		if err != nil {
+			f.Close()
+			return nil, fmt.Errorf("failed to create xz reader for %s: %w", path, err)
+		}
+		return &decompressed{Reader: xzr, closers: []func() error{f.Close}}, nil
+	default:
+		f.Close()
+		return nil, fmt.Errorf("unsupported compression %q", codec)
+	}
+}
+
+// decompressed closes the decoder chain in order.
+type decompressed struct {
+	io.Reader
+	closers []func() error
+}
+
+func (d *decompressed) Close() error {
+	var errs []error
+	for _, c := range d.closers {
+		errs = append(errs, c())
+	}
+	return errors.Join(errs...)
+}
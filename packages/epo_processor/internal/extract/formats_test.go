@@ -0,0 +1,94 @@
+package extract
+
+import (
+	"archive/tar"
+	"bytes"
+	"io"
+	"os"
+	"path/filepath"
+	"testing"
+
+	"github.com/ulikunitz/xz"
+)
+
+func xzCompress(t *testing.T, data []byte) []byte {
+	t.Helper()
+	var buf bytes.Buffer
+	w, err := xz.NewWriter(&buf)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if _, err := w.Write(data); err != nil {
+		t.Fatal(err)
+	}
+	if err := w.Close(); err != nil {
+		t.Fatal(err)
+	}
+	return buf.Bytes()
+}
+
+func tarball(t *testing.T, name string, data []byte) []byte {
+	t.Helper()
+	var buf bytes.Buffer
+	tw := tar.NewWriter(&buf)
+	if err := tw.WriteHeader(&tar.Header{Name: name, Mode: 0o644, Size: int64(len(data))}); err != nil {
+		t.Fatal(err)
+	}
+	if _, err := tw.Write(data); err != nil {
+		t.Fatal(err)
+	}
+	if err := tw.Close(); err != nil {
+		t.Fatal(err)
+	}
+	return buf.Bytes()
+}
+
+func TestDetectArchiveType(t *testing.T) {
+	dir := t.TempDir()
+	zipped := append([]byte("PK\x03\x04"), make([]byte, 64)...)
+	tests := []struct {
+		name string
+		data []byte
+		want ArchiveType
+	}{
+		{"delivery.zip", zipped, ZipType},
+		{"delivery", zipped, ZipType},
+		{"report.docx", zipped, UnknownType},
+		{"library.jar", zipped, UnknownType},
+		{"doc.xml", zipped, UnknownType},
+		{"bundle.tar.xz", nil, TarXzType},
+		{"bundle", xzCompress(t, tarball(t, "doc.xml", []byte("<doc/>"))), TarXzType},
+		{"single", xzCompress(t, []byte("<doc/>")), XzType},
+		{"notes", []byte("plain text"), UnknownType},
+	}
+	for _, tt := range tests {
+		t.Run(tt.name, func(t *testing.T) {
+			path := filepath.Join(dir, tt.name)
+			if err := os.WriteFile(path, tt.data, 0o644); err != nil {
+				t.Fatal(err)
+			}
+			if got := detectArchiveType(path); got != tt.want {
+				t.Errorf("detectArchiveType(%s) = %q, want %q", tt.name, got, tt.want)
+			}
+		})
+	}
+}
+
+func TestOpenDecompressedXZ(t *testing.T) {
+	path := filepath.Join(t.TempDir(), "doc.xml.xz")
+	if err := os.WriteFile(path, xzCompress(t, []byte("<doc/>")), 0o644); err != nil {
+		t.Fatal(err)
+	}
+	r, err := openDecompressed(path, "xz")
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer r.Close()
+	got, err := io.ReadAll(r)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if string(got) != "<doc/>" {
+		t.Errorf("decompressed %q, want <doc/>", got)
+	}
+}
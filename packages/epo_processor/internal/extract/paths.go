@@ -0,0 +1,143 @@
+package extract
+
+import (
+	"fmt"
+	"os"
+	"path/filepath"
+	"strings"
+)
+
+const (
+	// PathPolicyStrict fails the archive on any entry that would escape the target directory.
+	PathPolicyStrict = "strict"
+	// PathPolicyLenient strips absolute roots and ".." components and extracts the rest.
+	PathPolicyLenient = "lenient"
+)
+
+// entryPath resolves an archive entry name below destDir. It returns "" for entries
+// that name destDir itself (e.g. "./") and must be skipped. Entries below a symlink
+// extracted earlier are refused under either policy, since writing through the link
+// could land anywhere.
+func (e *Extractor) entryPath(destDir, name string) (string, error) {
+	root := filepath.Clean(destDir)
+	if e.Cfg.Extract.PathPolicy == PathPolicyLenient {
+		name = sanitizeEntryName(name)
+	} else if isAbsEntry(name) {
+		return "", fmt.Errorf("illegal file path: %s", name)
+	}
+	target := filepath.Join(root, filepath.FromSlash(name))
+	if target == root {
+		return "", nil
+	}
+	if !within(root, target) {
+		return "", fmt.Errorf("illegal file path: %s", name)
+	}
+	if symlinkBelow(root, filepath.Dir(target)) {
+		return "", fmt.Errorf("illegal file path through symlink: %s", name)
+	}
+	return target, nil
+}
+
+// symlinkBelow reports whether dir or any of its parents below root is a symlink.
+func symlinkBelow(root, dir string) bool {
+	for ; within(root, dir); dir = filepath.Dir(dir) {
+		if fi, err := os.Lstat(dir); err == nil && fi.Mode()&os.ModeSymlink != 0 {
+			return true
+		}
+	}
+	return false
+}
+
+// linkAllowed reports whether a symlink at linkPath pointing to linkName stays below destDir.
+// Targets are resolved relative to the link's own directory, as the OS does, following
+// the symlinks already extracted, so that a chain of links cannot climb out of destDir.
+func linkAllowed(destDir, linkPath, linkName string) bool {
+	if isAbsEntry(linkName) {
+		return false
+	}
+	root, err := filepath.Abs(destDir)
+	if err != nil {
+		return false
+	}
+	if root, err = filepath.EvalSymlinks(root); err != nil {
+		return false
+	}
+	parent, err := filepath.Abs(filepath.Dir(linkPath))
+	if err != nil {
+		return false
+	}
+	if parent, err = filepath.EvalSymlinks(parent); err != nil {
+		return false
+	}
+	return within(root, realPath(parent, filepath.FromSlash(linkName), 0))
+}
+
+// maxLinkHops bounds the symlinks realPath follows, as ELOOP does for the OS.
+const maxLinkHops = 40
+
+// realPath resolves the relative path name from the absolute, symlink-free dir component
+// by component, as the OS would: ".." steps up from where the symlinks before it led, not
+// from the name as written. Components that do not exist yet count as directories, but
+// ".." after one returns "", since a later entry could make it a symlink; so do links
+// that are absolute or nested too deep.
+func realPath(dir, name string, hops int) string {
+	resolved, missing := dir, false
+	for _, part := range strings.Split(name, string(filepath.Separator)) {
+		switch {
+		case part == "" || part == ".":
+			continue
+		case part == ".." && missing:
+			return ""
+		case part == "..":
+			resolved = filepath.Dir(resolved)
+			continue
+		}
+		next := filepath.Join(resolved, part)
+		fi, err := os.Lstat(next)
+		switch {
+		case err != nil:
+			missing = true
+		case fi.Mode()&os.ModeSymlink != 0:
+			link, err := os.Readlink(next)
+			if err != nil || filepath.IsAbs(link) || hops >= maxLinkHops {
+				return ""
+			}
+			if next = realPath(resolved, link, hops+1); next == "" {
+				return ""
+			}
+			if _, err := os.Lstat(next); err != nil {
+				missing = true
+			}
+		}
+		resolved = next
+	}
+	return resolved
+}
+
+func within(root, path string) bool {
+	return strings.HasPrefix(path, root+string(filepath.Separator))
+}
+
+// isAbsEntry also treats Windows-style roots as absolute, since archives built on
+// Windows may carry them regardless of the host OS.
+func isAbsEntry(name string) bool {
+	n := strings.ReplaceAll(name, "\\", "/")
+	return filepath.IsAbs(name) || strings.HasPrefix(n, "/") ||
+		len(n) >= 2 && n[1] == ':'
+}
+
+// sanitizeEntryName drops roots, drive letters, "." and ".." so the name can only
+// descend into the target directory.
+func sanitizeEntryName(name string) string {
+	var parts []string
+	for _, p := range strings.Split(strings.ReplaceAll(name, "\\", "/"), "/") {
+		switch {
+		case p == "", p == ".", p == "..":
+			continue
+		case len(p) == 2 && p[1] == ':':
+			continue
+		}
+		parts = append(parts, p)
+	}
+	return strings.Join(parts, "/")
+}
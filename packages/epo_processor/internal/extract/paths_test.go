@@ -0,0 +1,106 @@
+package extract
+
+import (
+	"os"
+	"path/filepath"
+	"testing"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+)
+
+func extractorWithPolicy(policy string) *Extractor {
+	var cfg config.Config
+	cfg.Extract.PathPolicy = policy
+	return &Extractor{Cfg: cfg}
+}
+
+func symlink(t *testing.T, target, link string) {
+	t.Helper()
+	if err := os.Symlink(target, link); err != nil {
+		t.Skipf("symlinks not supported: %v", err)
+	}
+}
+
+func TestEntryPath(t *testing.T) {
+	root := t.TempDir()
+	tests := []struct {
+		policy string
+		name   string
+		want   string // below root, "" for a skipped entry
+		err    bool
+	}{
+		{PathPolicyStrict, "a/b.xml", "a/b.xml", false},
+		{PathPolicyStrict, "a/../b.xml", "b.xml", false},
+		{PathPolicyStrict, "./", "", false},
+		{PathPolicyStrict, "../evil.xml", "", true},
+		{PathPolicyStrict, "a/../../evil.xml", "", true},
+		{PathPolicyStrict, "/etc/passwd", "", true},
+		{PathPolicyStrict, `C:\evil.xml`, "", true},
+		{PathPolicyLenient, "../evil.xml", "evil.xml", false},
+		{PathPolicyLenient, "/etc/passwd", "etc/passwd", false},
+		{PathPolicyLenient, "C:/a/../b.xml", "a/b.xml", false},
+		{PathPolicyLenient, "../", "", false},
+	}
+	for _, tt := range tests {
+		t.Run(tt.policy+" "+tt.name, func(t *testing.T) {
+			got, err := extractorWithPolicy(tt.policy).entryPath(root, tt.name)
+			if tt.err {
+				if err == nil {
+					t.Fatalf("entryPath(%q) = %q, want an error", tt.name, got)
+				}
+				return
+			}
+			if err != nil {
+				t.Fatalf("entryPath(%q): %v", tt.name, err)
+			}
+			want := ""
+			if tt.want != "" {
+				want = filepath.Join(root, filepath.FromSlash(tt.want))
+			}
+			if got != want {
+				t.Errorf("entryPath(%q) = %q, want %q", tt.name, got, want)
+			}
+		})
+	}
+}
+
+func TestEntryPathThroughSymlink(t *testing.T) {
+	root, outside := t.TempDir(), t.TempDir()
+	symlink(t, outside, filepath.Join(root, "link"))
+	for _, policy := range []string{PathPolicyStrict, PathPolicyLenient} {
+		if got, err := extractorWithPolicy(policy).entryPath(root, "link/evil.xml"); err == nil {
+			t.Errorf("%s: entryPath wrote through a symlink to %q", policy, got)
+		}
+	}
+}
+
+func TestLinkAllowed(t *testing.T) {
+	root := t.TempDir()
+	dir := filepath.Join(root, "a")
+	if err := os.MkdirAll(filepath.Join(dir, "b"), 0o755); err != nil {
+		t.Fatal(err)
+	}
+	// a/up points back to root, so ".." after it leaves root although the name stays inside
+	symlink(t, "..", filepath.Join(dir, "up"))
+	tests := []struct {
+		target string
+		want   bool
+	}{
+		{"b", true},
+		{"../a/b", true},
+		{"up", false}, // root itself is not below root
+		{"up/a", true},
+		{"../../outside", false},
+		{"/etc", false},
+		{`C:\Windows`, false},
+		{"up/../outside", false},
+		{"new/../b", false}, // a later entry could make new a symlink
+	}
+	for _, tt := range tests {
+		t.Run(tt.target, func(t *testing.T) {
+			if got := linkAllowed(root, filepath.Join(dir, "link"), tt.target); got != tt.want {
+				t.Errorf("linkAllowed(%q) = %v, want %v", tt.target, got, tt.want)
+			}
+		})
+	}
+}
@@ -0,0 +1,112 @@
+// Package failures collects the items a stage could not process. A stage with failed
+// items ends with a *Partial naming each of them; with pipeline.on_error continue the run
+// goes on past it and reports the failures at the end.
+package failures
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"sync"
+)
+
+// ExitCode is the exit status of a run in which some items failed while the others
+// succeeded. A run that failed outright exits with 1.
+const ExitCode = 2
+
+// Failure is an item that failed and the reason it failed for.
+type Failure struct {
+	Item   string `json:"item"`
+	Reason string `json:"reason"`
+
+	err error
+}
+
+// Partial is the error of a stage that finished with failed items.
+type Partial struct {
+	Succeeded int
+	Failures  []Failure
+}
+
+func (p *Partial) Error() string {
+	first := p.Failures[0]
+	return fmt.Sprintf("%d of %d items failed, first %s: %s",
+		len(p.Failures), p.Succeeded+len(p.Failures), first.Item, first.Reason)
+}
+
+// Unwrap returns the errors of the failed items, so errors.Is and errors.As see them.
+func (p *Partial) Unwrap() []error {
+	errs := make([]error, len(p.Failures))
+	for i, f := range p.Failures {
+		errs[i] = f.err
+	}
+	return errs
+}
+
+// Total reports whether every item of the stage failed.
+func (p *Partial) Total() bool {
+	return p.Succeeded == 0
+}
+
+// IsPartial reports whether err only failed in part: every error it wraps or joins is a
+// *Partial of a stage in which some items succeeded.
+func IsPartial(err error) bool {
+	switch e := err.(type) {
+	case *Partial:
+		return !e.Total()
+	case interface{ Unwrap() []error }:
+		for _, err := range e.Unwrap() {
+			if !IsPartial(err) {
+				return false
+			}
+		}
+		return true
+	case interface{ Unwrap() error }:
+		return IsPartial(e.Unwrap())
+	default:
+		return false
+	}
+}
+
+// Collector counts the items of a stage as they succeed or fail. It is safe for
+// concurrent use; the zero value is ready to use.
+type Collector struct {
+	mu        sync.Mutex
+	succeeded int
+	failures  []Failure
+}
+
+// Succeed counts an item that succeeded.
+func (c *Collector) Succeed() {
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	c.succeeded++
+}
+
+// Fail records item as failed with err. An item stopped by a cancelled or interrupted
+// run has not failed and is not recorded.
+func (c *Collector) Fail(item string, err error) {
+	if errors.Is(err, context.Canceled) {
+		return
+	}
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	c.failures = append(c.failures, Failure{Item: item, Reason: err.Error(), err: err})
+}
+
+// Len returns the number of failed items.
+func (c *Collector) Len() int {
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	return len(c.failures)
+}
+
+// Err returns a *Partial with the failed items, or nil when none failed.
+func (c *Collector) Err() error {
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	if len(c.failures) == 0 {
+		return nil
+	}
+	return &Partial{Succeeded: c.succeeded, Failures: append([]Failure(nil), c.failures...)}
+}
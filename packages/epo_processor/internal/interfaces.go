@@ -5,18 +5,36 @@ import (
 
 	"github.com/IBM/fp-go/v2/ioeither"
 
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/download"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/parse"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/sink"
 	T "github.com/Qubut/IP-Claim/packages/epo_processor/internal/typing"
 )
 
 type DownloaderInterface interface {
 	FetchEPOFiles(ctx context.Context) ioeither.IOEither[error, []int64]
+	StreamEPOFiles(ctx context.Context, downloaded chan<- string) ioeither.IOEither[error, []int64]
 	DownloadHupd(ctx context.Context) ioeither.IOEither[error, int64]
+	VerifyEPOFiles(ctx context.Context) ioeither.IOEither[error, []download.VerifyResult]
+	RepairEPOFiles(ctx context.Context) ioeither.IOEither[error, []download.VerifyResult]
+	ProductItems(ctx context.Context) ioeither.IOEither[error, []download.ProductItem]
+	Plan(ctx context.Context) ioeither.IOEither[error, []download.PlanItem]
+	NewDeliveries(ctx context.Context) ioeither.IOEither[error, []string]
+	Products(ctx context.Context) ioeither.IOEither[error, []download.ProductInfo]
+	Deliveries(ctx context.Context) ioeither.IOEither[error, []download.DeliveryInfo]
+	Snapshot() string
+	SetConcurrency(n int)
 }
 
 type ExtractorInterface interface {
 	ExtractAll(ctx context.Context, dir string) ioeither.IOEither[error, T.Unit]
+	ExtractArchive(ctx context.Context, archivePath string) ioeither.IOEither[error, string]
 }
 
 type ParserInterface interface {
-	ParseAllToParquet(ctx context.Context, downloadDir, outputCSV string, maxWorkers int64) error
+	ParseAll(ctx context.Context, inputDir, outputPath string, maxWorkers int64) error
+	ParseStream(ctx context.Context, inputs <-chan string, outputPath string, maxWorkers int64) error
+	ParseInto(ctx context.Context, inputDir string, out sink.Sink, maxWorkers int64) error
+	CitationStats() parse.CitationStats
+	Inspect(ctx context.Context, path string) (parse.Inspection, error)
 }
@@ -0,0 +1,115 @@
+package mockserver
+
+import (
+	"bytes"
+	"encoding/xml"
+	"fmt"
+	"math/rand/v2"
+)
+
+var (
+	cpcSymbols = []string{
+		"H04L 9/32", "G06F 16/245", "A61K 31/4439", "B60L 53/14",
+		"G06N 3/08", "H01M 10/0525", "C07D 401/04", "F03D 7/02",
+	}
+	citedCountries = []string{"EP", "US", "WO", "DE", "JP", "CN"}
+	categories     = []string{"X", "Y", "A", "D"}
+	surnames       = []string{"MUELLER", "DUPONT", "ROSSI", "SMITH", "TANAKA", "NOVAK"}
+	givenNames     = []string{"Anna", "Jean", "Marco", "Emily", "Hiro", "Petra"}
+	titleWords     = []string{
+		"method", "system", "device", "compound", "battery", "network", "control", "sensor",
+	}
+)
+
+// exchangeDocuments renders docs DOCDB exchange-documents starting at doc-number first.
+// Every element the parser reads is present: CPC classifications, citations with
+// categories, family members, titles, abstracts, applicants and inventors.
+func exchangeDocuments(rng *rand.Rand, first, docs int) []byte {
+	var buf bytes.Buffer
+	buf.WriteString(xml.Header)
+	buf.WriteString(`<exch:exchange-documents xmlns:exch="http://www.epo.org/exchange">` + "\n")
+	for i := 0; i < docs; i++ {
+		writeDocument(&buf, rng, first+i)
+	}
+	buf.WriteString("</exch:exchange-documents>\n")
+	return buf.Bytes()
+}
+
+func writeDocument(buf *bytes.Buffer, rng *rand.Rand, number int) {
+	docNumber := fmt.Sprintf("%07d", number)
+	kind := pick(rng, []string{"A1", "A2", "B1"})
+	fmt.Fprintf(buf,
+		`<exch:exchange-document system="ops.epo.org" country="EP" doc-number="%s" kind="%s" status="n">`+"\n",
+		docNumber, kind)
+	buf.WriteString("<exch:bibliographic-data>\n")
+	fmt.Fprintf(buf, `<exch:invention-title lang="en">%s</exch:invention-title>`+"\n", escape(title(rng)))
+	fmt.Fprintf(buf, `<exch:invention-title lang="de">%s</exch:invention-title>`+"\n", escape(title(rng)))
+
+	buf.WriteString("<exch:patent-classifications>\n")
+	for j, n := 0, 1+rng.IntN(3); j < n; j++ {
+		buf.WriteString("<exch:patent-classification>")
+		buf.WriteString(`<exch:classification-scheme office="EP" scheme="CPCI"/>`)
+		fmt.Fprintf(buf, "<exch:classification-symbol>%s</exch:classification-symbol>",
+			escape(pick(rng, cpcSymbols)))
+		buf.WriteString("</exch:patent-classification>\n")
+	}
+	buf.WriteString("</exch:patent-classifications>\n")
+
+	buf.WriteString("<exch:parties>\n<exch:applicants>\n")
+	fmt.Fprintf(buf,
+		`<exch:applicant sequence="1" data-format="docdb"><exch:applicant-name><name>%s</name></exch:applicant-name></exch:applicant>`+"\n",
+		escape(pick(rng, surnames)+" "+pick(rng, []string{"AG", "SA", "GMBH", "INC"})))
+	buf.WriteString("</exch:applicants>\n<exch:inventors>\n")
+	for j, n := 0, 1+rng.IntN(3); j < n; j++ {
+		fmt.Fprintf(buf,
+			`<exch:inventor sequence="%d" data-format="docdb"><exch:inventor-name><name>%s</name></exch:inventor-name></exch:inventor>`+"\n",
+			j+1, escape(pick(rng, surnames)+" "+pick(rng, givenNames)))
+	}
+	buf.WriteString("</exch:inventors>\n</exch:parties>\n")
+
+	buf.WriteString("<exch:references-cited>\n")
+	for j, n := 0, rng.IntN(4); j < n; j++ {
+		fmt.Fprintf(buf, `<exch:citation sequence="%d">`, j+1)
+		buf.WriteString(`<patcit num="1"><document-id document-id-type="docdb">`)
+		fmt.Fprintf(buf, "<country>%s</country><doc-number>%d</doc-number><kind>A</kind>",
+			pick(rng, citedCountries), 5000000+rng.IntN(4000000))
+		buf.WriteString("</document-id></patcit>")
+		fmt.Fprintf(buf, "<exch:category>%s</exch:category>", pick(rng, categories))
+		buf.WriteString("</exch:citation>\n")
+	}
+	buf.WriteString("</exch:references-cited>\n")
+	buf.WriteString("</exch:bibliographic-data>\n")
+
+	fmt.Fprintf(buf, `<exch:abstract lang="en"><p>%s</p></exch:abstract>`+"\n",
+		escape("A "+title(rng)+" is disclosed."))
+
+	buf.WriteString("<exch:patent-family>\n")
+	fmt.Fprintf(buf, "%s\n", familyMember("EP", docNumber, kind))
+	if rng.IntN(2) == 0 {
+		fmt.Fprintf(buf, "%s\n", familyMember("US", fmt.Sprintf("%d", 10000000+rng.IntN(900000)), "B2"))
+	}
+	buf.WriteString("</exch:patent-family>\n")
+	buf.WriteString("</exch:exchange-document>\n")
+}
+
+func familyMember(country, number, kind string) string {
+	return fmt.Sprintf(
+		`<exch:family-member><exch:publication-reference data-format="docdb">`+
+			`<document-id><country>%s</country><doc-number>%s</doc-number><kind>%s</kind></document-id>`+
+			`</exch:publication-reference></exch:family-member>`,
+		country, number, kind)
+}
+
+func title(rng *rand.Rand) string {
+	return pick(rng, titleWords) + " and " + pick(rng, titleWords) + " for " + pick(rng, titleWords)
+}
+
+func pick(rng *rand.Rand, values []string) string {
+	return values[rng.IntN(len(values))]
+}
+
+func escape(s string) string {
+	var buf bytes.Buffer
+	_ = xml.EscapeText(&buf, []byte(s))
+	return buf.String()
+}
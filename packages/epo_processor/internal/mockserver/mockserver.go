@@ -0,0 +1,248 @@
+// Package mockserver serves a synthetic EPO publication API with generated DOCDB-like
+// archives, so the pipeline can run end to end without credentials or real data.
+package mockserver
+
+import (
+	"archive/zip"
+	"bytes"
+	"context"
+	"crypto/sha1"
+	"encoding/hex"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"math/rand/v2"
+	"net"
+	"net/http"
+	"strconv"
+	"time"
+
+	"go.uber.org/zap"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// Options sizes the synthetic product. The same options always produce byte-identical
+// archives, so checksums stay stable across restarts.
+type Options struct {
+	Address          string
+	ProductID        uint32
+	Deliveries       int
+	ItemsPerDelivery int
+	DocsPerItem      int
+	// FirstDelivery is the publication date of the oldest delivery; later deliveries
+	// follow weekly.
+	FirstDelivery time.Time
+	// Latency delays every response, to exercise timeouts and progress reporting.
+	Latency time.Duration
+	// PageSize splits the product metadata into pages of this many deliveries, linked
+	// by links.next and the page query parameter; 0 returns all deliveries at once.
+	PageSize int
+}
+
+// Server is the mock publication API.
+type Server struct {
+	opts    Options
+	logger  *zap.SugaredLogger
+	product models.Product
+	// archives holds the item downloads keyed by delivery and item id.
+	archives map[[2]uint32][]byte
+}
+
+// New generates the product metadata and all item archives up front.
+func New(opts Options, logger *zap.SugaredLogger) (*Server, error) {
+	if opts.Deliveries < 1 || opts.ItemsPerDelivery < 1 || opts.DocsPerItem < 1 {
+		return nil, fmt.Errorf("deliveries, items and documents per item must be positive")
+	}
+	s := &Server{
+		opts:     opts,
+		logger:   logger,
+		archives: make(map[[2]uint32][]byte),
+		product: models.Product{
+			Id:          opts.ProductID,
+			Name:        "DOCDB mock",
+			Description: "Synthetic DOCDB-like bibliographic data",
+		},
+	}
+	rng := rand.New(rand.NewPCG(uint64(opts.ProductID), 1))
+	docNumber := 1000000
+	for d := 0; d < opts.Deliveries; d++ {
+		published := opts.FirstDelivery.AddDate(0, 0, 7*d)
+		delivery := models.Delivery{
+			DeliveryID:                  uint32(d + 1),
+			DeliveryName:                "DOCDB " + published.Format("2006-01-02"),
+			DeliveryPublicationDatetime: published.Format(time.RFC3339),
+		}
+		for i := 0; i < opts.ItemsPerDelivery; i++ {
+			name := fmt.Sprintf("docdb_xml_%s_%03d.zip", published.Format("20060102"), i+1)
+			data, err := itemArchive(name, rng, docNumber, opts.DocsPerItem)
+			if err != nil {
+				return nil, err
+			}
+			docNumber += opts.DocsPerItem
+			sum := sha1.Sum(data)
+			item := models.Item{
+				ItemId:                  uint32(i + 1),
+				ItemName:                name,
+				FileSize:                fmt.Sprintf("%.2f KB", float64(len(data))/1024),
+				FileChecksum:            hex.EncodeToString(sum[:]),
+				ItemPublicationDatetime: published.Format(time.RFC3339),
+			}
+			delivery.Items = append(delivery.Items, item)
+			s.archives[[2]uint32{delivery.DeliveryID, item.ItemId}] = data
+		}
+		s.product.Deliveries = append(s.product.Deliveries, delivery)
+	}
+	return s, nil
+}
+
+// Handler routes the product metadata and item download endpoints of the API.
+func (s *Server) Handler() http.Handler {
+	mux := http.NewServeMux()
+	mux.HandleFunc("GET /products", s.handleProducts)
+	mux.HandleFunc("GET /products/{product}", s.handleProduct)
+	mux.HandleFunc("GET /products/{product}/delivery/{delivery}/item/{item}/download", s.handleItem)
+	return s.delayed(mux)
+}
+
+// ListenAndServe serves the API on Options.Address until ctx is cancelled.
+func (s *Server) ListenAndServe(ctx context.Context) error {
+	lis, err := net.Listen("tcp", s.opts.Address)
+	if err != nil {
+		return fmt.Errorf("listen on %s: %w", s.opts.Address, err)
+	}
+	srv := &http.Server{Handler: s.Handler(), ReadHeaderTimeout: 10 * time.Second}
+	go func() {
+		<-ctx.Done()
+		shutdownCtx, cancel := context.WithTimeout(context.Background(), 5*time.Second)
+		defer cancel()
+		_ = srv.Shutdown(shutdownCtx)
+	}()
+	s.logger.Infow("Mock EPO API listening",
+		"address", lis.Addr().String(),
+		"product_id", s.opts.ProductID,
+		"items", len(s.archives))
+	if err := srv.Serve(lis); err != nil && !errors.Is(err, http.ErrServerClosed) {
+		return err
+	}
+	return nil
+}
+
+func (s *Server) delayed(next http.Handler) http.Handler {
+	if s.opts.Latency <= 0 {
+		return next
+	}
+	return http.HandlerFunc(func(w http.ResponseWriter, r *http.Request) {
+		select {
+		case <-time.After(s.opts.Latency):
+			next.ServeHTTP(w, r)
+		case <-r.Context().Done():
+		}
+	})
+}
+
+// handleProducts lists the one product of the mock, without its deliveries.
+func (s *Server) handleProducts(w http.ResponseWriter, _ *http.Request) {
+	w.Header().Set("Content-Type", "application/json")
+	products := []models.Product{{Id: s.product.Id, Name: s.product.Name, Description: s.product.Description}}
+	if err := json.NewEncoder(w).Encode(products); err != nil {
+		s.logger.Warnw("Failed to write product list", "err", err)
+	}
+}
+
+func (s *Server) handleProduct(w http.ResponseWriter, r *http.Request) {
+	if !s.knownProduct(r) {
+		http.NotFound(w, r)
+		return
+	}
+	product, ok := s.productPage(r)
+	if !ok {
+		http.NotFound(w, r)
+		return
+	}
+	w.Header().Set("Content-Type", "application/json")
+	if err := json.NewEncoder(w).Encode(product); err != nil {
+		s.logger.Warnw("Failed to write product metadata", "err", err)
+	}
+}
+
+// productPage returns the page of the product metadata selected by the page query
+// parameter (1 by default), or the whole product when pagination is off.
+func (s *Server) productPage(r *http.Request) (models.Product, bool) {
+	if s.opts.PageSize <= 0 {
+		return s.product, true
+	}
+	page := 1
+	if v := r.URL.Query().Get("page"); v != "" {
+		n, err := strconv.Atoi(v)
+		if err != nil || n < 1 {
+			return models.Product{}, false
+		}
+		page = n
+	}
+	total := (len(s.product.Deliveries) + s.opts.PageSize - 1) / s.opts.PageSize
+	if page > total {
+		return models.Product{}, false
+	}
+	p := s.product
+	start := (page - 1) * s.opts.PageSize
+	p.Deliveries = p.Deliveries[start:min(start+s.opts.PageSize, len(p.Deliveries))]
+	p.Page, p.TotalPages = page, total
+	if page < total {
+		p.Links = &models.Links{Next: fmt.Sprintf("/products/%d?page=%d", s.opts.ProductID, page+1)}
+	}
+	return p, true
+}
+
+func (s *Server) handleItem(w http.ResponseWriter, r *http.Request) {
+	if !s.knownProduct(r) {
+		http.NotFound(w, r)
+		return
+	}
+	deliveryID, err1 := strconv.ParseUint(r.PathValue("delivery"), 10, 32)
+	itemID, err2 := strconv.ParseUint(r.PathValue("item"), 10, 32)
+	data, ok := s.archives[[2]uint32{uint32(deliveryID), uint32(itemID)}]
+	if err1 != nil || err2 != nil || !ok {
+		http.NotFound(w, r)
+		return
+	}
+	w.Header().Set("Content-Type", "application/zip")
+	http.ServeContent(w, r, "", time.Time{}, bytes.NewReader(data))
+}
+
+func (s *Server) knownProduct(r *http.Request) bool {
+	id, err := strconv.ParseUint(r.PathValue("product"), 10, 32)
+	return err == nil && uint32(id) == s.opts.ProductID
+}
+
+// itemArchive builds an item like a DOCDB delivery: an outer zip holding a nested zip
+// that holds the exchange-documents XML.
+func itemArchive(name string, rng *rand.Rand, firstDoc, docs int) ([]byte, error) {
+	base := name[:len(name)-len(".zip")]
+	inner, err := zipOf(base+".xml", exchangeDocuments(rng, firstDoc, docs))
+	if err != nil {
+		return nil, err
+	}
+	return zipOf("Root/DOC/"+base+".zip", inner)
+}
+
+func zipOf(name string, content []byte) ([]byte, error) {
+	var buf bytes.Buffer
+	zw := zip.NewWriter(&buf)
+	// a fixed modification time keeps the archive bytes, and so the checksum, stable
+	w, err := zw.CreateHeader(&zip.FileHeader{
+		Name:     name,
+		Method:   zip.Deflate,
+		Modified: time.Date(2024, 1, 1, 0, 0, 0, 0, time.UTC),
+	})
+	if err != nil {
+		return nil, err
+	}
+	if _, err := w.Write(content); err != nil {
+		return nil, err
+	}
+	if err := zw.Close(); err != nil {
+		return nil, err
+	}
+	return buf.Bytes(), nil
+}
@@ -1,18 +1,33 @@
 package models
 
+import (
+	"fmt"
+	"time"
+)
+
 type Product struct {
-	Id   uint32 `json:"id"`
-	Name string `json:"name"`
-	// description string // not used
-	Deliveries []Delivery `json:"deliveries"`
+	Id          uint32     `json:"id"`
+	Name        string     `json:"name"`
+	Description string     `json:"description,omitempty"`
+	Deliveries  []Delivery `json:"deliveries"`
+	// Links, Page and TotalPages are set when the deliveries are split over several
+	// responses; a response without them holds all deliveries.
+	Links      *Links `json:"links,omitempty"`
+	Page       int    `json:"page,omitempty"`
+	TotalPages int    `json:"totalPages,omitempty"`
+}
+
+// Links points to the following page of a paginated response.
+type Links struct {
+	Next string `json:"next,omitempty"`
 }
 
 type Delivery struct {
-	DeliveryID   uint32 `json:"deliveryId"`
-	DeliveryName string `json:"deliveryName"`
-	// deliveryPublicationDatetime string // not used
-	DeliveryExpiryDatetime string `json:"deliveryExpiryDatetime,omitempty"`
-	Items                  []Item `json:"items"`
+	DeliveryID                  uint32 `json:"deliveryId"`
+	DeliveryName                string `json:"deliveryName"`
+	DeliveryPublicationDatetime string `json:"deliveryPublicationDatetime,omitempty"`
+	DeliveryExpiryDatetime      string `json:"deliveryExpiryDatetime,omitempty"`
+	Items                       []Item `json:"items"`
 }
 
 type Item struct {
@@ -22,3 +37,25 @@ type Item struct {
 	FileChecksum            string `json:"fileChecksum"`
 	ItemPublicationDatetime string `json:"itemPublicationDatetime"`
 }
+
+var datetimeLayouts = []string{
+	time.RFC3339,
+	"2006-01-02T15:04:05",
+	"2006-01-02 15:04:05",
+	"2006-01-02",
+}
+
+// ParseDatetime parses the datetime formats used by the EPO publication API.
+func ParseDatetime(s string) (time.Time, error) {
+	for _, layout := range datetimeLayouts {
+		if t, err := time.Parse(layout, s); err == nil {
+			return t, nil
+		}
+	}
+	return time.Time{}, fmt.Errorf("unrecognized datetime %q", s)
+}
+
+// PublishedAt returns the delivery publication datetime.
+func (d Delivery) PublishedAt() (time.Time, error) {
+	return ParseDatetime(d.DeliveryPublicationDatetime)
+}
@@ -0,0 +1,102 @@
+package models
+
+// Citation in references-cited
+type Citation struct {
+	CitedID    string   `parquet:"cited_id"        json:"cited_id"`
+	Categories []string `parquet:"categories,list" json:"categories"`
+	// Phase is the cited-phase attribute (SEA, EXA, ISR, APP, ...) and Office the office
+	// that cited it. Only the citation graph and the citation_provenance field write them,
+	// so the citations column keeps its schema.
+	Phase  string `parquet:"-" json:"-"`
+	Office string `parquet:"-" json:"-"`
+}
+
+// CitationProvenance tells where a citation of the citations column comes from: the
+// phase it was cited in and the office that cited it
+type CitationProvenance struct {
+	CitedID string `parquet:"cited_id" json:"cited_id"`
+	Phase   string `parquet:"phase"    json:"phase,omitempty"`
+	Office  string `parquet:"office"   json:"office,omitempty"`
+}
+
+// NPLCitation is a non-patent literature citation (nplcit) in references-cited
+type NPLCitation struct {
+	Text       string   `parquet:"text"            json:"text"`
+	DOI        string   `parquet:"doi"             json:"doi,omitempty"`
+	Categories []string `parquet:"categories,list" json:"categories"`
+}
+
+// PriorityClaim is a priority-claim of an exchange-document
+type PriorityClaim struct {
+	Country   string `parquet:"country"    json:"country"`
+	DocNumber string `parquet:"doc_number" json:"doc_number"`
+	Kind      string `parquet:"kind"       json:"kind,omitempty"`
+	Date      string `parquet:"date"       json:"date,omitempty"`
+}
+
+// ExtraField holds the values a parse.xpath_fields expression selected in a document
+type ExtraField struct {
+	Name   string   `parquet:"name"        json:"name"`
+	Values []string `parquet:"values,list" json:"values"`
+}
+
+// Optional bibliographic fields selectable via parse.fields
+const (
+	FieldTitle      = "title"
+	FieldAbstract   = "abstract"
+	FieldApplicants = "applicants"
+	FieldInventors  = "inventors"
+	FieldIPC        = "ipc"
+	// FieldPublicationDate is the date-publ attribute of the exchange-document (YYYYMMDD)
+	FieldPublicationDate = "publication_date"
+	// FieldNPLCitations holds the nplcit citations next to the patcit ones of citations
+	FieldNPLCitations = "npl_citations"
+	// FieldFamilyID is the family-id attribute of the exchange-document (DOCDB family)
+	FieldFamilyID = "family_id"
+	// FieldApplicationNumber and FieldFilingDate come from the docdb application-reference
+	FieldApplicationNumber = "application_number"
+	FieldFilingDate        = "filing_date"
+	// FieldPriorityClaims lists the priority-claims; FieldEarliestPriorityDate is the
+	// lowest of their dates (YYYYMMDD)
+	FieldPriorityClaims       = "priority_claims"
+	FieldEarliestPriorityDate = "earliest_priority_date"
+	// FieldCPCHierarchy lists the section, class, subclass and main group of the cpc_list
+	// symbols
+	FieldCPCHierarchy = "cpc_hierarchy"
+	// FieldCitationProvenance lists the phase and office of each entry of citations
+	FieldCitationProvenance = "citation_provenance"
+)
+
+// ColumnRevision is the extra column parse.dedup.policy keep_all adds: the publication
+// date of the delivery a record was parsed from (YYYY-MM-DD)
+const ColumnRevision = "revision"
+
+// LocalizedText is a text together with its lang attribute
+type LocalizedText struct {
+	Lang string `parquet:"lang" json:"lang"`
+	Text string `parquet:"text" json:"text"`
+}
+
+// PatentRecord is the flattened patent row written by the output sinks
+type PatentRecord struct {
+	PatentID             string               `parquet:"patent_id"                json:"patent_id"`
+	Status               string               `parquet:"status"                   json:"status"`
+	CPCList              []string             `parquet:"cpc_list,list"            json:"cpc_list"`
+	Citations            []Citation           `parquet:"citations,list"           json:"citations"`
+	FamilyPatents        []string             `parquet:"family_patents,list"      json:"family_patents"`
+	Titles               []LocalizedText      `parquet:"titles,list"              json:"titles,omitempty"`
+	Abstracts            []LocalizedText      `parquet:"abstracts,list"           json:"abstracts,omitempty"`
+	Applicants           []string             `parquet:"applicants,list"          json:"applicants,omitempty"`
+	Inventors            []string             `parquet:"inventors,list"           json:"inventors,omitempty"`
+	IPCList              []string             `parquet:"ipc_list,list"            json:"ipc_list,omitempty"`
+	PublicationDate      string               `parquet:"publication_date"         json:"publication_date,omitempty"`
+	NPLCitations         []NPLCitation        `parquet:"npl_citations,list"       json:"npl_citations,omitempty"`
+	FamilyID             string               `parquet:"family_id"                json:"family_id,omitempty"`
+	ApplicationNumber    string               `parquet:"application_number"       json:"application_number,omitempty"`
+	FilingDate           string               `parquet:"filing_date"              json:"filing_date,omitempty"`
+	PriorityClaims       []PriorityClaim      `parquet:"priority_claims,list"     json:"priority_claims,omitempty"`
+	EarliestPriorityDate string               `parquet:"earliest_priority_date"   json:"earliest_priority_date,omitempty"`
+	CPCHierarchy         []string             `parquet:"cpc_hierarchy,list"       json:"cpc_hierarchy,omitempty"`
+	CitationProvenance   []CitationProvenance `parquet:"citation_provenance,list" json:"citation_provenance,omitempty"`
+	Extra                []ExtraField         `parquet:"extra,list"               json:"extra,omitempty"`
+}
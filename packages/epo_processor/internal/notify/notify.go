@@ -0,0 +1,198 @@
+package notify
+
+import (
+	"bytes"
+	"context"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"net/http"
+	"net/url"
+	"slices"
+	"strings"
+	"time"
+
+	"go.uber.org/zap"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/failures"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/pipeline"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/shutdown"
+)
+
+type Status string
+
+const (
+	StatusSuccess     Status = "success"
+	StatusPartial     Status = "partial"
+	StatusFailure     Status = "failure"
+	StatusInterrupted Status = "interrupted"
+)
+
+// Event is the summary of one finished run, posted as is by json webhooks.
+type Event struct {
+	Status   Status                 `json:"status"`
+	Product  string                 `json:"product,omitempty"`
+	Snapshot string                 `json:"snapshot,omitempty"`
+	Started  time.Time              `json:"started"`
+	Duration time.Duration          `json:"duration"`
+	Counts   *pipeline.Counts       `json:"counts,omitempty"`
+	Stages   []pipeline.StageResult `json:"stages"`
+	Error    string                 `json:"error,omitempty"`
+}
+
+// NewEvent describes a run of product (empty for a single run) that started at started
+// and ended with summary and err.
+func NewEvent(product string, started time.Time, summary pipeline.Summary, err error) Event {
+	ev := Event{
+		Status:   StatusSuccess,
+		Product:  product,
+		Snapshot: summary.Snapshot,
+		Started:  started.UTC(),
+		Duration: time.Since(started),
+		Counts:   summary.Counts,
+		Stages:   summary.Stages,
+	}
+	switch {
+	case errors.Is(err, shutdown.ErrInterrupted):
+		ev.Status = StatusInterrupted
+	case failures.IsPartial(err):
+		ev.Status = StatusPartial
+	case err != nil:
+		ev.Status = StatusFailure
+	}
+	if err != nil {
+		ev.Error = err.Error()
+	}
+	return ev
+}
+
+// Notifier posts run events to the webhooks of notify.webhooks.
+type Notifier struct {
+	webhooks []config.Webhook
+	client   *http.Client
+	logger   *zap.SugaredLogger
+}
+
+// New returns a notifier for cfg, or nil when no webhook is configured. A nil Notifier
+// sends nothing.
+func New(cfg config.Notify, logger *zap.SugaredLogger) *Notifier {
+	if len(cfg.Webhooks) == 0 {
+		return nil
+	}
+	return &Notifier{
+		webhooks: cfg.Webhooks,
+		client:   &http.Client{Timeout: cfg.Timeout},
+		logger:   logger,
+	}
+}
+
+// Send posts ev to every webhook that subscribes to its status. A failed delivery is
+// logged and does not fail the run. Send does not use the context of the run, so an
+// interrupted run is still reported.
+func (n *Notifier) Send(ev Event) {
+	if n == nil {
+		return
+	}
+	for _, hook := range n.webhooks {
+		if len(hook.On) > 0 && !slices.Contains(hook.On, string(ev.Status)) {
+			continue
+		}
+		if err := n.post(hook, ev); err != nil {
+			n.logger.Warnw("Failed to send run notification",
+				"url", redact(hook.URL), "status", ev.Status, "err", err)
+		}
+	}
+}
+
+func (n *Notifier) post(hook config.Webhook, ev Event) error {
+	var payload any = ev
+	if hook.Format == "slack" {
+		payload = map[string]string{"text": slackText(ev)}
+	}
+	body, err := json.Marshal(payload)
+	if err != nil {
+		return err
+	}
+	req, err := http.NewRequestWithContext(context.Background(), http.MethodPost, hook.URL,
+		bytes.NewReader(body))
+	if err != nil {
+		return redactError(err)
+	}
+	req.Header.Set("Content-Type", "application/json")
+	for k, v := range hook.Headers {
+		req.Header.Set(k, v)
+	}
+	resp, err := n.client.Do(req)
+	if err != nil {
+		return redactError(err)
+	}
+	defer resp.Body.Close()
+	if resp.StatusCode >= 300 {
+		return fmt.Errorf("webhook returned %s", resp.Status)
+	}
+	return nil
+}
+
+// slackText renders ev as a short message in Slack mrkdwn.
+func slackText(ev Event) string {
+	var b strings.Builder
+	icon := map[Status]string{
+		StatusSuccess:     ":white_check_mark:",
+		StatusPartial:     ":warning:",
+		StatusFailure:     ":x:",
+		StatusInterrupted: ":warning:",
+	}[ev.Status]
+	fmt.Fprintf(&b, "%s *epo_processor run %s*", icon, ev.Status)
+	if ev.Product != "" {
+		fmt.Fprintf(&b, " for product `%s`", ev.Product)
+	}
+	if ev.Snapshot != "" {
+		fmt.Fprintf(&b, " (snapshot `%s`)", ev.Snapshot)
+	}
+	fmt.Fprintf(&b, " after %s", ev.Duration.Round(time.Second))
+	if c := ev.Counts; c != nil {
+		fmt.Fprintf(&b, "\nDownloaded %d, extracted %d, parsed %d, failed %d",
+			c.Downloaded, c.Extracted, c.Parsed, c.Failed)
+	}
+	for _, s := range ev.Stages {
+		fmt.Fprintf(&b, "\n• %s: %s, %d items in %s", s.Stage, s.Status, s.Items,
+			s.Duration.Round(time.Second))
+		if s.Failed > 0 {
+			fmt.Fprintf(&b, ", %d failed", s.Failed)
+		}
+	}
+	if ev.Error != "" {
+		fmt.Fprintf(&b, "\n```%s```", ev.Error)
+	}
+	return b.String()
+}
+
+// redact drops the credentials, path and query of a webhook URL, which often hold its
+// secret.
+func redact(rawURL string) string {
+	scheme, rest, ok := strings.Cut(rawURL, "://")
+	if !ok {
+		return "<invalid url>"
+	}
+	host := rest
+	if i := strings.IndexAny(host, "/?#"); i >= 0 {
+		host = host[:i]
+	}
+	if i := strings.LastIndex(host, "@"); i >= 0 {
+		host = host[i+1:]
+	}
+	return scheme + "://" + host
+}
+
+// redactError redacts the URL that net/http and net/url errors quote in their message,
+// e.g. Post "https://hooks.slack.com/services/...": dial tcp: ...
+func redactError(err error) error {
+	var urlErr *url.Error
+	if errors.As(err, &urlErr) {
+		redacted := *urlErr
+		redacted.URL = redact(urlErr.URL)
+		return &redacted
+	}
+	return err
+}
@@ -0,0 +1,111 @@
+package parse
+
+import (
+	"context"
+	"fmt"
+	"io"
+	"io/fs"
+	"os"
+	"path/filepath"
+	"strings"
+	"unicode"
+
+	"github.com/antchfx/xmlquery"
+	"go.uber.org/zap"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// abstractIndex holds the abstracts of a separately bought abstracts product, keyed by
+// patent id and, as a fallback for records whose kind code differs, by country and
+// document number.
+type abstractIndex struct {
+	byID     map[string][]models.LocalizedText
+	byNumber map[string][]models.LocalizedText
+	merged   int64
+}
+
+// loadAbstracts reads the abstract of every exchange-document in the XML files below
+// dir. The index is held in memory for the whole parse run.
+func (p *Parser) loadAbstracts(ctx context.Context, dir string) (*abstractIndex, error) {
+	index := &abstractIndex{
+		byID:     make(map[string][]models.LocalizedText),
+		byNumber: make(map[string][]models.LocalizedText),
+	}
+	err := filepath.WalkDir(dir, func(path string, d fs.DirEntry, err error) error {
+		if err != nil {
+			return err
+		}
+		if d.IsDir() || !isXMLFile(path, p.Cfg.Parse.SniffContent) {
+			return nil
+		}
+		return index.addFile(ctx, path)
+	})
+	if err != nil {
+		return nil, fmt.Errorf("load abstracts from %s: %w", dir, err)
+	}
+	p.Logger.Info("Loaded abstracts for merging",
+		zap.String("input_dir", dir),
+		zap.Int("documents", len(index.byID)))
+	return index, nil
+}
+
+func (index *abstractIndex) addFile(ctx context.Context, path string) error {
+	f, err := os.Open(path)
+	if err != nil {
+		return err
+	}
+	defer f.Close()
+	sp, err := xmlquery.CreateStreamParser(f, exchangeDocumentXPath)
+	if err != nil {
+		return err
+	}
+	for {
+		if err := ctx.Err(); err != nil {
+			return err
+		}
+		node, err := sp.Read()
+		if err == io.EOF {
+			return nil
+		}
+		if err != nil {
+			return fmt.Errorf("%s: %w", path, err)
+		}
+		abstracts := localizedTexts(node, "*[local-name()='abstract']")
+		if len(abstracts) == 0 {
+			continue
+		}
+		country, number := node.SelectAttr("country"), node.SelectAttr("doc-number")
+		index.byID[country+number+node.SelectAttr("kind")] = abstracts
+		index.byNumber[country+number] = abstracts
+	}
+}
+
+// merge fills the abstracts of records that have none with those of the index. The
+// exact patent id is preferred over a match on country and document number.
+func (index *abstractIndex) merge(records []models.PatentRecord) {
+	for i := range records {
+		r := &records[i]
+		if len(r.Abstracts) > 0 {
+			continue
+		}
+		abstracts, ok := index.byID[r.PatentID]
+		if !ok {
+			abstracts, ok = index.byNumber[withoutKind(r.PatentID)]
+		}
+		if ok {
+			r.Abstracts = abstracts
+			index.merged++
+		}
+	}
+}
+
+// withoutKind strips the kind code (e.g. A1, B) from a patent id: everything from its
+// last letter on.
+func withoutKind(patentID string) string {
+	i := strings.LastIndexFunc(patentID, unicode.IsLetter)
+	if i <= 0 {
+		return patentID
+	}
+	return patentID[:i]
+}
@@ -0,0 +1,216 @@
+package parse
+
+import (
+	"archive/zip"
+	"bytes"
+	"context"
+	"errors"
+	"fmt"
+	"io"
+	"path"
+	"strings"
+
+	ET "github.com/IBM/fp-go/v2/either"
+	"go.opentelemetry.io/otel/attribute"
+	"go.opentelemetry.io/otel/metric"
+	"go.opentelemetry.io/otel/trace"
+	"go.uber.org/zap"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/explain"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/state"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/storage"
+)
+
+// archiveEntrySeparator joins an archive path and an entry name into the key used in the
+// manifest and in error messages, e.g. data/docdb.zip!/Root/DOC/part1.zip!/part1.xml.
+const archiveEntrySeparator = "!/"
+
+func isZipFile(name string) bool {
+	return strings.EqualFold(path.Ext(name), ".zip")
+}
+
+// parseArchive parses the XML entries of a zip archive without extracting it to disk.
+// Nested zip archives are opened one at a time, see openNestedZip.
+func (p *Parser) parseArchive(
+	ctx context.Context,
+	archivePath string,
+	write func(string, []models.PatentRecord) error,
+) error {
+	ctx, span := p.Tracer.Start(ctx, "parse.zip_archive", trace.WithAttributes(
+		attribute.String("archive_path", archivePath),
+	))
+	defer span.End()
+	zr, release, err := p.openArchive(ctx, archivePath)
+	if err != nil {
+		// an archive that cannot be opened fails on its own, like an unreadable entry
+		span.RecordError(err)
+		p.failed.Fail(archivePath, fmt.Errorf("failed to open zip: %w", err))
+		return nil
+	}
+	defer release()
+	if err := p.parseZipEntries(ctx, archivePath, zr, write); err != nil {
+		span.RecordError(err)
+		return err
+	}
+	return nil
+}
+
+func (p *Parser) parseZipEntries(
+	ctx context.Context,
+	prefix string,
+	zr *zip.Reader,
+	write func(string, []models.PatentRecord) error,
+) error {
+	for _, f := range zr.File {
+		if err := ctx.Err(); err != nil {
+			return err
+		}
+		if f.FileInfo().IsDir() {
+			continue
+		}
+		key := prefix + archiveEntrySeparator + f.Name
+		switch {
+		case isZipFile(f.Name):
+			nested, release, err := p.openNestedZip(ctx, f)
+			if err != nil {
+				return fmt.Errorf("%s: %w", key, err)
+			}
+			err = p.parseZipEntries(ctx, key, nested, write)
+			if err := errors.Join(err, release()); err != nil {
+				return err
+			}
+		case strings.EqualFold(path.Ext(f.Name), ".xml"):
+			if reason := p.skipParsed(key); reason != "" {
+				p.Explain.Skip(explain.StageParse, explain.KindEntry, key, reason)
+				continue
+			}
+			p.xmlFilesTotal.Add(ctx, 1)
+			if err := p.parseZipXML(ctx, key, f, write); err != nil {
+				p.xmlFilesFailed.Add(ctx, 1, metric.WithAttributes(attribute.String("status", "failed")))
+				p.recordFile(key, state.StatusFailed)
+				var readErr *entryReadError
+				if p.quarantined != nil && ctx.Err() == nil && errors.As(err, &readErr) {
+					qerr := p.quarantined.addEntry(key, f.Open, readErr.err)
+					if qerr == nil {
+						p.Logger.Warn("Quarantined XML file", zap.String("path", key), zap.Error(readErr.err))
+						continue
+					}
+					err = errors.Join(err, qerr)
+				}
+				// an entry that cannot be read fails on its own, other errors fail the archive
+				if errors.As(err, &readErr) {
+					p.failed.Fail(key, err)
+					continue
+				}
+				return fmt.Errorf("%s: %w", key, err)
+			}
+			p.xmlFilesSuccess.Add(ctx, 1, metric.WithAttributes(attribute.String("status", "success")))
+			p.recordFile(key, state.StatusDone)
+			p.failed.Succeed()
+		default:
+			p.Explain.Skip(explain.StageParse, explain.KindEntry, key, "no .xml extension")
+		}
+	}
+	return nil
+}
+
+// openArchive opens a zip archive on disk or in S3. Reading a zip needs random access,
+// so an archive in S3 is copied to a scratch file first; release removes it.
+func (p *Parser) openArchive(ctx context.Context, archivePath string) (*zip.Reader, func() error, error) {
+	if !storage.IsS3(archivePath) {
+		zr, err := zip.OpenReader(archivePath)
+		if err != nil {
+			return nil, nil, err
+		}
+		return &zr.Reader, zr.Close, nil
+	}
+	body, size, err := p.Storage.Open(ctx, archivePath)
+	if err != nil {
+		return nil, nil, err
+	}
+	defer body.Close()
+	spill, err := p.Scratch.Spill(ctx, body, size)
+	if err != nil {
+		return nil, nil, fmt.Errorf("copy archive to scratch: %w", err)
+	}
+	zr, err := zip.NewReader(spill, size)
+	if err != nil {
+		return nil, nil, errors.Join(err, spill.Close())
+	}
+	return zr, spill.Close, nil
+}
+
+// openNestedZip opens a zip archive stored in another one. Archives up to
+// scratch.spill_above_bytes are read into memory, larger ones are copied to a scratch
+// file; release frees either once the entries have been read.
+func (p *Parser) openNestedZip(ctx context.Context, f *zip.File) (*zip.Reader, func() error, error) {
+	rc, err := f.Open()
+	if err != nil {
+		return nil, nil, err
+	}
+	defer rc.Close()
+	size := int64(f.UncompressedSize64)
+	if p.Scratch == nil || size <= int64(p.Cfg.Scratch.SpillAboveBytes) {
+		data, err := io.ReadAll(rc)
+		if err != nil {
+			return nil, nil, err
+		}
+		zr, err := zip.NewReader(bytes.NewReader(data), int64(len(data)))
+		return zr, func() error { return nil }, err
+	}
+	spill, err := p.Scratch.Spill(ctx, rc, size)
+	if err != nil {
+		return nil, nil, fmt.Errorf("spill nested archive: %w", err)
+	}
+	zr, err := zip.NewReader(spill, size)
+	if err != nil {
+		return nil, nil, errors.Join(err, spill.Close())
+	}
+	return zr, spill.Close, nil
+}
+
+// entryReadError is a failure to parse an archive entry, as opposed to writing its
+// records; only the former is quarantined.
+type entryReadError struct {
+	err error
+}
+
+func (e *entryReadError) Error() string {
+	return e.err.Error()
+}
+
+func (e *entryReadError) Unwrap() error {
+	return e.err
+}
+
+func (p *Parser) parseZipXML(
+	ctx context.Context,
+	key string,
+	f *zip.File,
+	write func(string, []models.PatentRecord) error,
+) error {
+	rc, err := f.Open()
+	if err != nil {
+		return err
+	}
+	defer rc.Close()
+	p.bytesTotal.Add(ctx, int64(f.UncompressedSize64))
+	var writeErr error
+	_, err = ET.UnwrapError(p.extractRecords(ctx, rc, func(records []models.PatentRecord) error {
+		if err := write(key, records); err != nil {
+			writeErr = err
+			return err
+		}
+		p.recordsTotal.Add(ctx, int64(len(records)))
+		p.processedRecords.Add(uint64(len(records)))
+		return nil
+	})())
+	switch {
+	case writeErr != nil:
+		return writeErr
+	case err != nil:
+		return &entryReadError{err: err}
+	}
+	return nil
+}
@@ -0,0 +1,238 @@
+package parse
+
+import (
+	"regexp"
+	"strings"
+
+	"github.com/antchfx/xmlquery"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// fieldSet holds the optional bibliographic fields enabled via parse.fields.
+type fieldSet map[string]bool
+
+func newFieldSet(fields []string) fieldSet {
+	set := fieldSet{}
+	for _, f := range fields {
+		set[f] = true
+	}
+	return set
+}
+
+// extract fills the enabled bibliographic fields of record from an exchange-document.
+func (fields fieldSet) extract(node *xmlquery.Node, record *models.PatentRecord) {
+	if len(fields) == 0 {
+		return
+	}
+	biblio := xmlquery.FindOne(node, "*[local-name()='bibliographic-data']")
+	if fields[models.FieldPublicationDate] {
+		record.PublicationDate = node.SelectAttr("date-publ")
+		if record.PublicationDate == "" && biblio != nil {
+			record.PublicationDate = publicationReferenceDate(biblio)
+		}
+	}
+	if fields[models.FieldTitle] && biblio != nil {
+		record.Titles = localizedTexts(biblio, "*[local-name()='invention-title']")
+	}
+	if fields[models.FieldAbstract] {
+		record.Abstracts = localizedTexts(node, "*[local-name()='abstract']")
+	}
+	if fields[models.FieldApplicants] && biblio != nil {
+		record.Applicants = partyNames(
+			biblio,
+			".//*[local-name()='applicants']/*[local-name()='applicant']",
+			"*[local-name()='applicant-name']/*[local-name()='name']",
+		)
+	}
+	if fields[models.FieldInventors] && biblio != nil {
+		record.Inventors = partyNames(
+			biblio,
+			".//*[local-name()='inventors']/*[local-name()='inventor']",
+			"*[local-name()='inventor-name']/*[local-name()='name']",
+		)
+	}
+	if fields[models.FieldNPLCitations] {
+		record.NPLCitations = nplCitations(node)
+	}
+	if fields[models.FieldFamilyID] {
+		record.FamilyID = node.SelectAttr("family-id")
+	}
+	if (fields[models.FieldApplicationNumber] || fields[models.FieldFilingDate]) && biblio != nil {
+		number, date := applicationReference(biblio)
+		if fields[models.FieldApplicationNumber] {
+			record.ApplicationNumber = number
+		}
+		if fields[models.FieldFilingDate] {
+			record.FilingDate = date
+		}
+	}
+	if (fields[models.FieldPriorityClaims] || fields[models.FieldEarliestPriorityDate]) && biblio != nil {
+		claims := priorityClaims(biblio)
+		if fields[models.FieldPriorityClaims] {
+			record.PriorityClaims = claims
+		}
+		if fields[models.FieldEarliestPriorityDate] {
+			record.EarliestPriorityDate = earliestPriority(claims)
+		}
+	}
+}
+
+// priorityClaims returns the priority claims of the exchange-document. DOCDB lists each
+// claim in several formats; the docdb ones are used when present.
+func priorityClaims(biblio *xmlquery.Node) []models.PriorityClaim {
+	nodes := xmlquery.Find(biblio, "*[local-name()='priority-claims']/*[local-name()='priority-claim']")
+	preferred := make([]*xmlquery.Node, 0, len(nodes))
+	for _, n := range nodes {
+		if n.SelectAttr("data-format") == "docdb" {
+			preferred = append(preferred, n)
+		}
+	}
+	if len(preferred) == 0 {
+		preferred = nodes
+	}
+	var claims []models.PriorityClaim
+	for _, n := range preferred {
+		docID := xmlquery.FindOne(n, "*[local-name()='document-id']")
+		if docID == nil {
+			continue
+		}
+		claim := models.PriorityClaim{
+			Country:   getText(docID, "*[local-name()='country']"),
+			DocNumber: getText(docID, "*[local-name()='doc-number']"),
+			Kind:      getText(docID, "*[local-name()='kind']"),
+			Date:      getText(docID, "*[local-name()='date']"),
+		}
+		if claim.Country == "" && claim.DocNumber == "" {
+			continue
+		}
+		claims = append(claims, claim)
+	}
+	return claims
+}
+
+// earliestPriority returns the lowest claim date; YYYYMMDD dates sort as strings.
+func earliestPriority(claims []models.PriorityClaim) string {
+	var earliest string
+	for _, c := range claims {
+		if c.Date != "" && (earliest == "" || c.Date < earliest) {
+			earliest = c.Date
+		}
+	}
+	return earliest
+}
+
+// publicationReferenceDate returns the date of the publication-reference, preferring its
+// docdb variant; it stands in for a missing date-publ attribute.
+func publicationReferenceDate(biblio *xmlquery.Node) string {
+	var date string
+	for _, ref := range xmlquery.Find(biblio, "*[local-name()='publication-reference']") {
+		d := getText(ref, "*[local-name()='document-id']/*[local-name()='date']")
+		if d != "" && (date == "" || ref.SelectAttr("data-format") == "docdb") {
+			date = d
+		}
+	}
+	return date
+}
+
+// applicationReference returns the application number (country and doc-number, e.g.
+// EP01234567) and the filing date (YYYYMMDD) of the application-reference, preferring
+// its docdb variant.
+func applicationReference(biblio *xmlquery.Node) (number, date string) {
+	refs := xmlquery.Find(biblio, "*[local-name()='application-reference']/*[local-name()='document-id']")
+	if len(refs) == 0 {
+		return "", ""
+	}
+	ref := refs[0]
+	for _, n := range refs {
+		if n.Parent.SelectAttr("data-format") == "docdb" || n.SelectAttr("document-id-type") == "docdb" {
+			ref = n
+			break
+		}
+	}
+	number = getText(ref, "*[local-name()='country']") + getText(ref, "*[local-name()='doc-number']")
+	return number, getText(ref, "*[local-name()='date']")
+}
+
+// doiPattern matches a DOI inside free citation text, e.g. "DOI: 10.1038/nature12373".
+var doiPattern = regexp.MustCompile(`\b10\.\d{4,9}/[^\s"<>]+`)
+
+// nplCitations returns the non-patent literature citations of references-cited. The DOI
+// comes from a doi element when the nplcit has one, else from the citation text.
+func nplCitations(node *xmlquery.Node) []models.NPLCitation {
+	var citations []models.NPLCitation
+	for _, n := range xmlquery.Find(
+		node, ".//*[local-name()='references-cited']/*[local-name()='citation']",
+	) {
+		npl := xmlquery.FindOne(n, "*[local-name()='nplcit']")
+		if npl == nil {
+			continue
+		}
+		text := strings.Join(strings.Fields(getText(npl, "*[local-name()='text']")), " ")
+		if text == "" {
+			text = strings.Join(strings.Fields(npl.InnerText()), " ")
+		}
+		doi := getText(npl, ".//*[local-name()='doi']")
+		if doi == "" {
+			doi = strings.TrimRight(doiPattern.FindString(text), ".,;)]")
+		}
+		if text == "" && doi == "" {
+			continue
+		}
+		citations = append(citations, models.NPLCitation{
+			Text:       text,
+			DOI:        doi,
+			Categories: citationCategories(n),
+		})
+	}
+	return citations
+}
+
+// localizedTexts returns one text per lang attribute, keeping the first occurrence.
+func localizedTexts(parent *xmlquery.Node, selector string) []models.LocalizedText {
+	var texts []models.LocalizedText
+	seen := make(map[string]struct{})
+	for _, n := range xmlquery.Find(parent, selector) {
+		lang := n.SelectAttr("lang")
+		if _, ok := seen[lang]; ok {
+			continue
+		}
+		text := strings.Join(strings.Fields(n.InnerText()), " ")
+		if text == "" {
+			continue
+		}
+		seen[lang] = struct{}{}
+		texts = append(texts, models.LocalizedText{Lang: lang, Text: text})
+	}
+	return texts
+}
+
+// partyNames returns the distinct names of applicants or inventors. DOCDB lists each
+// party in the normalized docdb format and as originally filed (docdba); the docdb
+// variant is preferred when present.
+func partyNames(parent *xmlquery.Node, partySelector, nameSelector string) []string {
+	parties := xmlquery.Find(parent, partySelector)
+	preferred := make([]*xmlquery.Node, 0, len(parties))
+	for _, n := range parties {
+		if n.SelectAttr("data-format") == "docdb" {
+			preferred = append(preferred, n)
+		}
+	}
+	if len(preferred) == 0 {
+		preferred = parties
+	}
+	var names []string
+	seen := make(map[string]struct{})
+	for _, n := range preferred {
+		name := getText(n, nameSelector)
+		if name == "" {
+			continue
+		}
+		if _, ok := seen[name]; ok {
+			continue
+		}
+		seen[name] = struct{}{}
+		names = append(names, name)
+	}
+	return names
+}
@@ -0,0 +1,88 @@
+package parse
+
+import (
+	"maps"
+	"sync"
+
+	"github.com/antchfx/xmlquery"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// unknownKey counts citations without an office or cited-phase attribute.
+const unknownKey = "unknown"
+
+// CitationStats counts the citations parsed in a run by the office that cited them and
+// the phase they were cited in (SEA search, EXA examination, ISR, APP applicant, ...).
+// Shifts in these distributions are an early sign of changes in the EPO export.
+type CitationStats struct {
+	Total    int64            `json:"total"`
+	ByOffice map[string]int64 `json:"by_office"`
+	ByPhase  map[string]int64 `json:"by_phase"`
+}
+
+type citationCounter struct {
+	mu    sync.Mutex
+	stats CitationStats
+}
+
+func (c *citationCounter) reset() {
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	c.stats = CitationStats{ByOffice: map[string]int64{}, ByPhase: map[string]int64{}}
+}
+
+// citingOffice is the office that cited the citation element n. DOCDB names it in
+// office, or in srep-office for search report citations.
+func citingOffice(n *xmlquery.Node) string {
+	if office := n.SelectAttr("office"); office != "" {
+		return office
+	}
+	return n.SelectAttr("srep-office")
+}
+
+// citationProvenance lists the phase and office of each citation, in the same order.
+func citationProvenance(citations []models.Citation) []models.CitationProvenance {
+	provenance := make([]models.CitationProvenance, 0, len(citations))
+	for _, c := range citations {
+		provenance = append(provenance,
+			models.CitationProvenance{CitedID: c.CitedID, Phase: c.Phase, Office: c.Office})
+	}
+	return provenance
+}
+
+// add counts the citation element n.
+func (c *citationCounter) add(n *xmlquery.Node) {
+	office := citingOffice(n)
+	phase := n.SelectAttr("cited-phase")
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	if c.stats.ByOffice == nil {
+		c.stats = CitationStats{ByOffice: map[string]int64{}, ByPhase: map[string]int64{}}
+	}
+	c.stats.Total++
+	c.stats.ByOffice[orUnknown(office)]++
+	c.stats.ByPhase[orUnknown(phase)]++
+}
+
+func (c *citationCounter) snapshot() CitationStats {
+	c.mu.Lock()
+	defer c.mu.Unlock()
+	return CitationStats{
+		Total:    c.stats.Total,
+		ByOffice: maps.Clone(c.stats.ByOffice),
+		ByPhase:  maps.Clone(c.stats.ByPhase),
+	}
+}
+
+func orUnknown(s string) string {
+	if s == "" {
+		return unknownKey
+	}
+	return s
+}
+
+// CitationStats returns the citation counts of the last parse run.
+func (p *Parser) CitationStats() CitationStats {
+	return p.citations.snapshot()
+}
@@ -0,0 +1,168 @@
+package parse
+
+import (
+	"slices"
+	"sort"
+	"strings"
+
+	"github.com/antchfx/xmlquery"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+)
+
+// schemeIPCR names the classifications-ipcr block of DOCDB in parse.classifications.ipc.
+const schemeIPCR = "IPCR"
+
+// classificationSchemes selects the patent-classification schemes written to the
+// cpc_list and ipc_list columns (parse.classifications), and how their symbols are
+// filtered and normalized.
+type classificationSchemes struct {
+	cpc       map[string]bool
+	ipc       map[string]bool
+	prefixes  []string
+	normalize bool
+}
+
+func newClassificationSchemes(cfg config.Classifications) classificationSchemes {
+	set := func(schemes []string) map[string]bool {
+		m := make(map[string]bool, len(schemes))
+		for _, s := range schemes {
+			m[strings.ToUpper(s)] = true
+		}
+		return m
+	}
+	prefixes := make([]string, 0, len(cfg.Prefixes))
+	for _, p := range cfg.Prefixes {
+		prefixes = append(prefixes, compactSymbol(p))
+	}
+	return classificationSchemes{
+		cpc:       set(cfg.CPC),
+		ipc:       set(cfg.IPC),
+		prefixes:  prefixes,
+		normalize: cfg.Normalize,
+	}
+}
+
+// add puts symbol into set, normalized when parse.classifications.normalize is set,
+// unless it matches none of parse.classifications.prefixes.
+func (s classificationSchemes) add(set map[string]struct{}, symbol string) {
+	if s.normalize {
+		symbol = normalizeSymbol(symbol)
+	}
+	if len(s.prefixes) > 0 {
+		compact := compactSymbol(symbol)
+		if !slices.ContainsFunc(s.prefixes, func(p string) bool { return strings.HasPrefix(compact, p) }) {
+			return
+		}
+	}
+	set[symbol] = struct{}{}
+}
+
+// classificationSymbols returns the sorted distinct symbols of the classifications
+// whose scheme is in schemes, or nil if there are none.
+func (s classificationSchemes) classificationSymbols(
+	classifications []PatentClassification,
+	schemes map[string]bool,
+) []string {
+	set := make(map[string]struct{})
+	for _, pc := range classifications {
+		if schemes[pc.Scheme] {
+			s.add(set, pc.ClassificationSymbol)
+		}
+	}
+	return sortedKeys(set)
+}
+
+// ipcSymbols returns the IPC symbols of an exchange-document: those of the
+// patent-classification schemes in schemes and, when IPCR is selected, the entries of
+// classifications-ipcr. Their text ("H04L   9/32   20060101AFI...") is normalized to
+// the symbol without spaces (H04L9/32).
+func (s classificationSchemes) ipcSymbols(
+	node *xmlquery.Node,
+	classifications []PatentClassification,
+	schemes map[string]bool,
+) []string {
+	symbols := s.classificationSymbols(classifications, schemes)
+	set := make(map[string]struct{}, len(symbols))
+	for _, symbol := range symbols {
+		set[symbol] = struct{}{}
+	}
+	if schemes[schemeIPCR] {
+		for _, n := range xmlquery.Find(node,
+			".//*[local-name()='classifications-ipcr']/*[local-name()='classification-ipcr']/*[local-name()='text']",
+		) {
+			fields := strings.Fields(n.InnerText())
+			if len(fields) >= 2 {
+				s.add(set, fields[0]+fields[1])
+			}
+		}
+	}
+	return sortedKeys(set)
+}
+
+// compactSymbol is symbol in upper case without whitespace, the form prefixes are
+// compared in.
+func compactSymbol(symbol string) string {
+	return strings.ToUpper(strings.Join(strings.Fields(symbol), ""))
+}
+
+// normalizeSymbol rewrites a CPC or IPC symbol to the form of the scheme tables: upper
+// case without whitespace, the main group without leading zeros and the subgroup with at
+// least two digits, e.g. "h01m  010/5" becomes H01M10/50. Symbols that do not look like
+// subclass, group and subgroup are only compacted.
+func normalizeSymbol(symbol string) string {
+	s := compactSymbol(symbol)
+	if len(s) < 5 {
+		return s
+	}
+	group, subgroup, ok := strings.Cut(s[4:], "/")
+	if !ok || group == "" || subgroup == "" {
+		return s
+	}
+	if trimmed := strings.TrimLeft(group, "0"); trimmed != "" {
+		group = trimmed
+	}
+	if len(subgroup) < 2 {
+		subgroup += "0"
+	}
+	return s[:4] + group + "/" + subgroup
+}
+
+// cpcAncestors returns the sorted distinct ancestors of the symbols, derived from their
+// structure: section (H), class (H01), subclass (H01M) and main group (H01M10/00). Symbols
+// that do not start with a subclass are skipped.
+func cpcAncestors(symbols []string) []string {
+	set := make(map[string]struct{})
+	for _, symbol := range symbols {
+		s := compactSymbol(symbol)
+		if len(s) < 4 || !isLetter(s[0]) || !isDigit(s[1]) || !isDigit(s[2]) || !isLetter(s[3]) {
+			continue
+		}
+		set[s[:1]] = struct{}{}
+		set[s[:3]] = struct{}{}
+		set[s[:4]] = struct{}{}
+		if group, _, ok := strings.Cut(s[4:], "/"); ok && group != "" {
+			if trimmed := strings.TrimLeft(group, "0"); trimmed != "" {
+				group = trimmed
+			}
+			set[s[:4]+group+"/00"] = struct{}{}
+		}
+	}
+	return sortedKeys(set)
+}
+
+func isLetter(b byte) bool { return b >= 'A' && b <= 'Z' }
+
+func isDigit(b byte) bool { return b >= '0' && b <= '9' }
+
+func sortedKeys(set map[string]struct{}) []string {
+	if len(set) == 0 {
+		return nil
+	}
+	keys := make([]string, 0, len(set))
+	for k := range set {
+		keys = append(keys, k)
+	}
+	sort.Strings(keys)
+	return keys
+}
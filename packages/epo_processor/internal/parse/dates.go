@@ -0,0 +1,32 @@
+package parse
+
+import (
+	"time"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// dateFormat renders the dates of a record as parse.date_format asks. DOCDB writes them
+// as YYYYMMDD; "iso" turns them into YYYY-MM-DD. A value that is not a valid YYYYMMDD
+// date is kept as it is.
+type dateFormat string
+
+func (f dateFormat) apply(record *models.PatentRecord) {
+	if f != "iso" {
+		return
+	}
+	record.PublicationDate = isoDate(record.PublicationDate)
+	record.FilingDate = isoDate(record.FilingDate)
+	record.EarliestPriorityDate = isoDate(record.EarliestPriorityDate)
+	for i := range record.PriorityClaims {
+		record.PriorityClaims[i].Date = isoDate(record.PriorityClaims[i].Date)
+	}
+}
+
+func isoDate(date string) string {
+	t, err := time.Parse("20060102", date)
+	if err != nil {
+		return date
+	}
+	return t.Format(time.DateOnly)
+}
@@ -0,0 +1,146 @@
+package parse
+
+import (
+	"cmp"
+	"path/filepath"
+	"slices"
+	"strings"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/extract"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/storage"
+)
+
+// Policies of parse.dedup.policy.
+const (
+	DedupOff        = "off"
+	DedupKeepFirst  = "keep_first"
+	DedupKeepLatest = "keep_latest"
+	DedupKeepAll    = "keep_all"
+)
+
+// revisionIndex maps the downloaded archives, and what they extract to, to the
+// publication date of their delivery (YYYY-MM-DD) as recorded in the manifest.
+type revisionIndex map[string]string
+
+func (p *Parser) loadRevisions() revisionIndex {
+	index := revisionIndex{}
+	if p.Manifest == nil {
+		return index
+	}
+	for _, name := range p.Manifest.Names() {
+		it, _ := p.Manifest.Item(name)
+		if it.Published == "" {
+			continue
+		}
+		archive := storage.Join(p.Cfg.Download.Directory, name)
+		index[sourceKey(archive)] = it.Published
+		if !storage.IsS3(archive) {
+			index[sourceKey(extract.ExtractedPath(archive))] = it.Published
+		}
+	}
+	return index
+}
+
+// of returns the revision of the XML file or archive entry at path: that of the closest
+// archive or extracted directory containing it, or "" when its delivery is unknown.
+func (r revisionIndex) of(path string) string {
+	path, _, _ = strings.Cut(path, archiveEntrySeparator)
+	if storage.IsS3(path) {
+		return r[path]
+	}
+	for dir := sourceKey(path); ; {
+		if rev, ok := r[dir]; ok {
+			return rev
+		}
+		parent := filepath.Dir(dir)
+		if parent == dir {
+			return ""
+		}
+		dir = parent
+	}
+}
+
+// sourceKey makes local paths comparable however the download and parse input
+// directories are spelled.
+func sourceKey(path string) string {
+	if storage.IsS3(path) {
+		return path
+	}
+	if abs, err := filepath.Abs(path); err == nil {
+		return abs
+	}
+	return filepath.Clean(path)
+}
+
+// orderSources sorts sources so that the record parse.dedup.policy keeps is usually
+// parsed first: the earliest deliveries first for keep_first, the latest for
+// keep_latest. Sources of unknown delivery count as the earliest.
+func (p *Parser) orderSources(sources []string) {
+	policy := p.Cfg.Parse.Dedup.Policy
+	if policy != DedupKeepFirst && policy != DedupKeepLatest {
+		return
+	}
+	revisions := p.loadRevisions()
+	slices.SortStableFunc(sources, func(a, b string) int {
+		if policy == DedupKeepLatest {
+			return cmp.Compare(revisions.of(b), revisions.of(a))
+		}
+		return cmp.Compare(revisions.of(a), revisions.of(b))
+	})
+}
+
+// deduper applies parse.dedup.policy to the records as they are written. It keeps the
+// revision of every patent id written in memory for the run.
+type deduper struct {
+	policy    string
+	revisions revisionIndex
+	kept      map[string]string
+	// dropped counts the records left out as duplicates; superseded those among them
+	// that the policy prefers over the record already written, which happens when
+	// workers finish the files of different deliveries out of order.
+	dropped    int64
+	superseded int64
+}
+
+// newDeduper returns nil with parse.dedup.policy off.
+func (p *Parser) newDeduper() *deduper {
+	if p.Cfg.Parse.Dedup.Policy == DedupOff {
+		return nil
+	}
+	return &deduper{
+		policy:    p.Cfg.Parse.Dedup.Policy,
+		revisions: p.loadRevisions(),
+		kept:      make(map[string]string),
+	}
+}
+
+// filter returns the records of the source key to write. keep_all writes all of them
+// with their revision; the other policies drop the patent ids already written.
+func (d *deduper) filter(key string, records []models.PatentRecord) []models.PatentRecord {
+	rev := d.revisions.of(key)
+	if d.policy == DedupKeepAll {
+		if rev == "" {
+			return records
+		}
+		for i := range records {
+			records[i].Extra = append(records[i].Extra,
+				models.ExtraField{Name: models.ColumnRevision, Values: []string{rev}})
+		}
+		return records
+	}
+	kept := make([]models.PatentRecord, 0, len(records))
+	for _, r := range records {
+		prev, seen := d.kept[r.PatentID]
+		if !seen {
+			d.kept[r.PatentID] = rev
+			kept = append(kept, r)
+			continue
+		}
+		d.dropped++
+		if d.policy == DedupKeepLatest && rev > prev || d.policy == DedupKeepFirst && rev < prev {
+			d.superseded++
+		}
+	}
+	return kept
+}
@@ -1,12 +1,14 @@
 package parse
 
+import "github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+
 type ExchangeDocument struct {
 	Country               string
 	DocNumber             string
 	Kind                  string
 	Status                string
 	PatentClassifications []PatentClassification
-	Citations             []Citation
+	Citations             []models.Citation
 	FamilyMembers         []FamilyMember
 }
 
@@ -16,12 +18,6 @@ type PatentClassification struct {
 	ClassificationSymbol string
 }
 
-// Citation in references-cited
-type Citation struct {
-	CitedID    string   `parquet:"name=cited_id, type=BYTE_ARRAY, convertedtype=UTF8"`
-	Categories []string `parquet:"name=categories, type=LIST"`
-}
-
 // FamilyMember in patent-family
 type FamilyMember struct {
 	PublicationReferences []PublicationReference
@@ -39,12 +35,3 @@ type DocumentID struct {
 	DocNumber string
 	Kind      string
 }
-
-// PatentRecord is the patent schema for Parquet output
-type PatentRecord struct {
-	PatentID      string     `parquet:"name=patent_id, type=BYTE_ARRAY, convertedtype=UTF8"`
-	Status        string     `parquet:"name=status, type=BYTE_ARRAY, convertedtype=UTF8"`
-	CPCList       []string   `parquet:"name=cpc_list, type=LIST"`
-	Citations     []Citation `parquet:"name=citations, type=LIST"`
-	FamilyPatents []string   `parquet:"name=family_patents, type=LIST"`
-}
@@ -0,0 +1,55 @@
+package parse
+
+import (
+	"strings"
+
+	"github.com/antchfx/xmlquery"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+)
+
+// documentFilter selects the exchange-documents of parse.filter by their country and
+// kind attributes; an empty set accepts every value.
+type documentFilter struct {
+	countries map[string]bool
+	kinds     map[string]bool
+}
+
+func newDocumentFilter(cfg config.DocumentFilter) documentFilter {
+	set := func(codes []string) map[string]bool {
+		if len(codes) == 0 {
+			return nil
+		}
+		m := make(map[string]bool, len(codes))
+		for _, c := range codes {
+			m[strings.ToUpper(c)] = true
+		}
+		return m
+	}
+	return documentFilter{countries: set(cfg.Countries), kinds: set(cfg.Kinds)}
+}
+
+// keep reports whether the exchange-document node passes the filter. It only reads two
+// attributes, so a skipped document costs nothing beyond being read.
+func (f documentFilter) keep(node *xmlquery.Node) bool {
+	if f.countries != nil && !f.countries[strings.ToUpper(node.SelectAttr("country"))] {
+		return false
+	}
+	return f.kinds == nil || f.kinds[strings.ToUpper(node.SelectAttr("kind"))]
+}
+
+// selectDocuments drops the nodes the filter rejects and counts them.
+func (p *Parser) selectDocuments(nodes []*xmlquery.Node) []*xmlquery.Node {
+	if p.filter.countries == nil && p.filter.kinds == nil {
+		return nodes
+	}
+	kept := nodes[:0]
+	for _, node := range nodes {
+		if p.filter.keep(node) {
+			kept = append(kept, node)
+			continue
+		}
+		p.filtered.Add(1)
+	}
+	return kept
+}
@@ -0,0 +1,134 @@
+package parse
+
+import (
+	"bufio"
+	"encoding/json"
+	"encoding/xml"
+	"errors"
+	"fmt"
+	"io"
+	"os"
+	"sync"
+)
+
+// IndexEntry locates the raw XML of one exchange-document inside a parsed file.
+type IndexEntry struct {
+	PatentID string `json:"patent_id"`
+	File     string `json:"file"`
+	Offset   int64  `json:"offset"`
+	Length   int64  `json:"length"`
+}
+
+// recordIndex appends IndexEntry lines (JSONL) to the configured index file.
+type recordIndex struct {
+	mu   sync.Mutex
+	file *os.File
+	enc  *json.Encoder
+}
+
+func openRecordIndex(path string) (*recordIndex, error) {
+	f, err := os.Create(path)
+	if err != nil {
+		return nil, fmt.Errorf("failed to create record index: %w", err)
+	}
+	return &recordIndex{file: f, enc: json.NewEncoder(f)}, nil
+}
+
+func (idx *recordIndex) add(entries []IndexEntry) error {
+	idx.mu.Lock()
+	defer idx.mu.Unlock()
+	for i := range entries {
+		if err := idx.enc.Encode(&entries[i]); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+func (idx *recordIndex) Close() error {
+	return idx.file.Close()
+}
+
+// indexXMLFile scans path with a token decoder and returns the byte range of every
+// exchange-document element.
+func indexXMLFile(path string) ([]IndexEntry, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return nil, err
+	}
+	defer f.Close()
+	dec := xml.NewDecoder(bufio.NewReader(f))
+	var entries []IndexEntry
+	var current *IndexEntry
+	for {
+		offset := dec.InputOffset()
+		tok, err := dec.RawToken()
+		if err == io.EOF {
+			return entries, nil
+		}
+		if err != nil {
+			return nil, fmt.Errorf("index %s: %w", path, err)
+		}
+		switch t := tok.(type) {
+		case xml.StartElement:
+			if t.Name.Local == "exchange-document" && current == nil {
+				current = &IndexEntry{
+					PatentID: attr(t, "country") + attr(t, "doc-number") + attr(t, "kind"),
+					File:     path,
+					Offset:   offset,
+				}
+			}
+		case xml.EndElement:
+			if t.Name.Local == "exchange-document" && current != nil {
+				current.Length = dec.InputOffset() - current.Offset
+				entries = append(entries, *current)
+				current = nil
+			}
+		}
+	}
+}
+
+func attr(el xml.StartElement, name string) string {
+	for _, a := range el.Attr {
+		if a.Name.Local == name {
+			return a.Value
+		}
+	}
+	return ""
+}
+
+// LookupRecord finds patentID in the JSONL index at indexPath and returns the raw
+// exchange-document XML it points to.
+func LookupRecord(indexPath, patentID string) ([]byte, error) {
+	f, err := os.Open(indexPath)
+	if err != nil {
+		return nil, fmt.Errorf("open record index: %w", err)
+	}
+	defer f.Close()
+	dec := json.NewDecoder(f)
+	for {
+		var e IndexEntry
+		if err := dec.Decode(&e); err != nil {
+			if errors.Is(err, io.EOF) {
+				return nil, fmt.Errorf("patent %s not found in index", patentID)
+			}
+			return nil, fmt.Errorf("read record index: %w", err)
+		}
+		if e.PatentID == patentID {
+			return readRange(e.File, e.Offset, e.Length)
+		}
+	}
+}
+
+func readRange(path string, offset, length int64) ([]byte, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return nil, err
+	}
+	defer f.Close()
+	buf := make([]byte, length)
+	if _, err := f.ReadAt(buf, offset); err != nil {
+		return nil, fmt.Errorf("read %s at %d: %w", path, offset, err)
+	}
+	return buf, nil
+}
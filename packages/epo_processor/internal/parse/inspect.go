@@ -0,0 +1,50 @@
+package parse
+
+import (
+	"context"
+
+	ET "github.com/IBM/fp-go/v2/either"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/contract"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// Inspection is what the parser extracts from a single XML file, with the counts that
+// tell why a column comes out empty.
+type Inspection struct {
+	File            string                `json:"file"`
+	Documents       int                   `json:"documents"`
+	Classifications int                   `json:"classifications"`
+	Citations       int                   `json:"citations"`
+	NPLCitations    int                   `json:"npl_citations"`
+	FamilyMembers   int                   `json:"family_members"`
+	Empty           map[string]int        `json:"empty"`
+	Records         []models.PatentRecord `json:"records"`
+}
+
+// Inspect parses the XML file at path with the configured fields, classification schemes
+// and XPath columns, and writes nothing. Empty counts the records per column without a
+// value; classifications count the CPC and IPC symbols.
+func (p *Parser) Inspect(ctx context.Context, path string) (Inspection, error) {
+	var records []models.PatentRecord
+	_, err := ET.UnwrapError(p.processSingleXML(ctx, path, func(batch []models.PatentRecord) error {
+		records = append(records, batch...)
+		return nil
+	})())
+	if err != nil {
+		return Inspection{}, err
+	}
+	in := Inspection{
+		File:      path,
+		Documents: len(records),
+		Empty:     contract.EmptyColumns(records),
+		Records:   records,
+	}
+	for _, r := range records {
+		in.Classifications += len(r.CPCList) + len(r.IPCList)
+		in.Citations += len(r.Citations)
+		in.NPLCitations += len(r.NPLCitations)
+		in.FamilyMembers += len(r.FamilyPatents)
+	}
+	return in, nil
+}
@@ -2,7 +2,9 @@ package parse
 
 import (
 	"context"
+	"errors"
 	"fmt"
+	"io"
 	"io/fs"
 	"os"
 	"path/filepath"
@@ -17,9 +19,9 @@ import (
 	F "github.com/IBM/fp-go/v2/function"
 	IO "github.com/IBM/fp-go/v2/io"
 	IOE "github.com/IBM/fp-go/v2/ioeither"
+	"github.com/IBM/fp-go/v2/ioeither/file"
 	"github.com/IBM/fp-go/v2/option"
 	"github.com/antchfx/xmlquery"
-	"github.com/parquet-go/parquet-go"
 	"github.com/schollz/progressbar/v3"
 	"go.opentelemetry.io/otel/attribute"
 	"go.opentelemetry.io/otel/metric"
@@ -28,6 +30,16 @@ import (
 	"golang.org/x/sync/semaphore"
 
 	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/contract"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/explain"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/failures"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/scratch"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/shutdown"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/sink"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/snapshot"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/state"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/storage"
 )
 
 type Parser struct {
@@ -44,6 +56,29 @@ type Parser struct {
 	recordsTotal     metric.Int64Counter
 	bytesTotal       metric.Int64Counter
 	fileDuration     metric.Int64Histogram
+	Manifest         *state.Manifest
+	Explain          *explain.Report
+	Scratch          *scratch.Space
+	Storage          *storage.S3
+	fields           fieldSet
+	schemes          classificationSchemes
+	scrubber         scrubber
+	dates            dateFormat
+	xpaths           xpathFields
+	filter           documentFilter
+	citations        citationCounter
+	profile          *profiler
+	tally            *contract.Tally
+	// filtered counts the exchange-documents of a run that parse.filter skipped
+	filtered atomic.Int64
+	// resumed is the checkpoint a --resume run continues from; checkpointing is set while
+	// a parse records a checkpoint after every file.
+	resumed       *state.ParseCheckpoint
+	checkpointing bool
+	// quarantined takes the files that fail to parse during a run with parse.quarantine
+	quarantined *quarantine
+	// failed counts the files of a run that were parsed and names those that failed
+	failed *failures.Collector
 }
 
 func NewParser(
@@ -58,9 +93,21 @@ func NewParser(
 		Tracer:           tracer,
 		Meter:            meter,
 		processedRecords: &atomic.Uint64{},
+		fields:           newFieldSet(cfg.Parse.Fields),
+		schemes:          newClassificationSchemes(cfg.Parse.Classifications),
+		scrubber:         newScrubber(cfg.Parse.Privacy),
+		dates:            dateFormat(cfg.Parse.DateFormat),
+		filter:           newDocumentFilter(cfg.Parse.Filter),
+	}
+	if cfg.Parse.Profile.Enabled {
+		p.profile = newProfiler(cfg.Parse.Profile.SampleEvery)
 	}
 
 	var err error
+	p.xpaths, err = newXPathFields(cfg.Parse.XPathFields)
+	if err != nil {
+		return nil, err
+	}
 	p.sessionDuration, err = meter.Int64Histogram(
 		"parse.session.duration",
 		metric.WithDescription("Duration of the full parsing session"),
@@ -123,14 +170,40 @@ func NewParser(
 	return p, nil
 }
 
-func (p *Parser) ParseAllToParquet(
+func (p *Parser) ParseAll(
+	ctx context.Context,
+	downloadDir, outputPath string,
+	maxWorkers int64,
+) error {
+	return p.parseDir(ctx, downloadDir, outputPath, nil, maxWorkers)
+}
+
+// ParseInto parses the XML files under inputDir like ParseAll, but writes the records to
+// out instead of the configured output, and closes it. No checkpoint, side outputs or
+// descriptor are written, and the manifest items are not marked as parsed.
+func (p *Parser) ParseInto(ctx context.Context, inputDir string, out sink.Sink, maxWorkers int64) error {
+	return p.parseDir(ctx, inputDir, "", out, maxWorkers)
+}
+
+// parseDir parses the sources under downloadDir into out, or into the configured output
+// at outputPath when out is nil.
+func (p *Parser) parseDir(
 	ctx context.Context,
-	downloadDir, outputParquet string,
+	downloadDir, outputPath string,
+	out sink.Sink,
 	maxWorkers int64,
 ) error {
+	label := snapshot.Resolve(p.Cfg.Snapshot, p.Manifest)
+	p.resumed = nil
+	if out == nil {
+		outputPath = snapshot.Expand(outputPath, label)
+		p.resumed = p.resumeFrom(outputPath)
+	}
 	ctx, sessionSpan := p.Tracer.Start(ctx, "parse.session", trace.WithAttributes(
 		attribute.String("download_dir", downloadDir),
-		attribute.String("output_parquet", outputParquet),
+		attribute.String("output", outputPath),
+		attribute.String("output_format", p.Cfg.Parse.OutputFormat),
+		attribute.String("snapshot", label),
 		attribute.Int64("max_workers", maxWorkers),
 	))
 	defer sessionSpan.End()
@@ -139,153 +212,441 @@ func (p *Parser) ParseAllToParquet(
 	p.Logger.Info(
 		"Starting parsing session",
 		zap.String("download_dir", downloadDir),
-		zap.String("output_parquet", outputParquet),
+		zap.String("output", outputPath),
+		zap.String("output_format", p.Cfg.Parse.OutputFormat),
+		zap.String("snapshot", label),
 	)
 	ctxFind, findSpan := p.Tracer.Start(ctx, "parse.find_xml_files")
-	var xmlFiles []string
-	err := filepath.WalkDir(downloadDir, func(path string, d fs.DirEntry, err error) error {
-		if ctxFind.Err() != nil {
-			return ctxFind.Err()
+	sources, err := p.findSources(ctxFind, downloadDir)
+	findSpan.End()
+	if err != nil {
+		sessionSpan.RecordError(err)
+		return fmt.Errorf("failed to walk directory: %w", err)
+	}
+
+	p.orderSources(sources)
+	p.Logger.Info("Found XML files", zap.Int("count", len(sources)))
+	sessionSpan.AddEvent(
+		"xml_files_found",
+		trace.WithAttributes(attribute.Int("count", len(sources))),
+	)
+	queue := make(chan string, len(sources))
+	for _, source := range sources {
+		queue <- source
+	}
+	close(queue)
+	return p.parseSources(ctx, parseRun{
+		span:       sessionSpan,
+		start:      startTime,
+		label:      label,
+		outputPath: outputPath,
+		inputDir:   downloadDir,
+		maxWorkers: maxWorkers,
+		total:      len(sources),
+		out:        out,
+	}, queue)
+}
+
+// ParseStream parses into a single output while its inputs are still being produced:
+// every path received from inputs (an extracted directory or a downloaded file) is
+// scanned and its XML files are handed to the workers right away. The output is
+// finalized once inputs is closed and all files are parsed.
+func (p *Parser) ParseStream(
+	ctx context.Context,
+	inputs <-chan string,
+	outputPath string,
+	maxWorkers int64,
+) error {
+	var input string
+	var ok bool
+	// the snapshot label is recorded by the downloader before the first item arrives
+	select {
+	case <-ctx.Done():
+		return ctx.Err()
+	case input, ok = <-inputs:
+	}
+	label := snapshot.Resolve(p.Cfg.Snapshot, p.Manifest)
+	outputPath = snapshot.Expand(outputPath, label)
+	p.resumed = p.resumeFrom(outputPath)
+	ctx, sessionSpan := p.Tracer.Start(ctx, "parse.session", trace.WithAttributes(
+		attribute.String("output", outputPath),
+		attribute.String("output_format", p.Cfg.Parse.OutputFormat),
+		attribute.String("snapshot", label),
+		attribute.Int64("max_workers", maxWorkers),
+		attribute.Bool("streaming", true),
+	))
+	defer sessionSpan.End()
+	p.Logger.Info(
+		"Starting streaming parsing session",
+		zap.String("output", outputPath),
+		zap.String("output_format", p.Cfg.Parse.OutputFormat),
+		zap.String("snapshot", label),
+	)
+
+	queue := make(chan string, maxWorkers)
+	go func() {
+		defer close(queue)
+		for ok {
+			sources, err := p.findSources(ctx, input)
+			if err != nil {
+				return
+			}
+			for _, source := range sources {
+				select {
+				case queue <- source:
+				case <-ctx.Done():
+					return
+				}
+			}
+			select {
+			case input, ok = <-inputs:
+			case <-ctx.Done():
+				return
+			}
+		}
+	}()
+	return p.parseSources(ctx, parseRun{
+		span:       sessionSpan,
+		start:      time.Now(),
+		label:      label,
+		outputPath: outputPath,
+		inputDir:   p.Cfg.ParseInputDir(),
+		maxWorkers: maxWorkers,
+		total:      -1,
+	}, queue)
+}
+
+// findSources lists the XML files below root, and the zip archives when
+// parse.from_archives is set. root may also be a single file.
+func (p *Parser) findSources(ctx context.Context, root string) ([]string, error) {
+	if storage.IsS3(root) {
+		return p.findObjects(ctx, root)
+	}
+	var sources []string
+	err := filepath.WalkDir(root, func(path string, d fs.DirEntry, err error) error {
+		if ctx.Err() != nil {
+			return ctx.Err()
 		}
 		if err != nil {
 			p.Logger.Warn("Error accessing path", zap.String("path", path), zap.Error(err))
 			return nil
 		}
-		if !d.IsDir() && strings.EqualFold(filepath.Ext(path), ".xml") {
-			xmlFiles = append(xmlFiles, path)
+		if d.IsDir() {
+			return nil
+		}
+		if p.Cfg.Parse.FromArchives && isZipFile(path) {
+			sources = append(sources, path)
+			return nil
 		}
+		if !isXMLFile(path, p.Cfg.Parse.SniffContent) {
+			p.Explain.Skip(explain.StageParse, explain.KindFile, path, p.notXMLReason())
+			return nil
+		}
+		if reason := p.skipParsed(path); reason != "" {
+			p.Explain.Skip(explain.StageParse, explain.KindFile, path, reason)
+			return nil
+		}
+		sources = append(sources, path)
 		return nil
 	})
-	findSpan.End()
+	return sources, err
+}
+
+// findObjects is findSources for an S3 prefix or object. Objects are recognized by their
+// extension alone; parse.sniff_content does not apply to them.
+func (p *Parser) findObjects(ctx context.Context, root string) ([]string, error) {
+	objects, err := p.Storage.List(ctx, root)
 	if err != nil {
-		sessionSpan.RecordError(err)
-		return fmt.Errorf("failed to walk directory: %w", err)
+		return nil, err
+	}
+	var sources []string
+	for _, o := range objects {
+		if p.Cfg.Parse.FromArchives && isZipFile(o.URI) {
+			sources = append(sources, o.URI)
+			continue
+		}
+		if !isXMLFile(o.URI, false) {
+			p.Explain.Skip(explain.StageParse, explain.KindFile, o.URI, p.notXMLReason())
+			continue
+		}
+		if reason := p.skipParsed(o.URI); reason != "" {
+			p.Explain.Skip(explain.StageParse, explain.KindFile, o.URI, reason)
+			continue
+		}
+		sources = append(sources, o.URI)
 	}
+	return sources, nil
+}
 
-	p.xmlFilesTotal.Add(ctx, int64(len(xmlFiles)))
-	p.Logger.Info("Found XML files", zap.Int("count", len(xmlFiles)))
-	sessionSpan.AddEvent(
-		"xml_files_found",
-		trace.WithAttributes(attribute.Int("count", len(xmlFiles))),
-	)
+// parseRun describes one parse session; total is -1 when the number of sources is
+// not known up front. out, when set, replaces the configured output.
+type parseRun struct {
+	span       trace.Span
+	start      time.Time
+	label      string
+	outputPath string
+	inputDir   string
+	maxWorkers int64
+	total      int
+	out        sink.Sink
+}
 
-	p.progress = progressbar.NewOptions(len(xmlFiles),
-		progressbar.OptionSetWriter(os.Stdout),
+// parseSources parses every path received from sources with up to run.maxWorkers
+// workers and writes all records to a single output.
+func (p *Parser) parseSources(ctx context.Context, run parseRun, sources <-chan string) error {
+	sessionSpan := run.span
+	p.citations.reset()
+	p.profile.reset()
+	p.filtered.Store(0)
+	p.failed = &failures.Collector{}
+	p.tally = contract.NewTally(p.Cfg.Parse.Contract)
+	p.progress = progressbar.NewOptions(run.total,
+		progressbar.OptionSetWriter(p.Cfg.ProgressWriter()),
 		progressbar.OptionSetWidth(60),
 		progressbar.OptionSetDescription("[0 processed] Parsing XML files..."),
 		progressbar.OptionSpinnerType(14),
 		progressbar.OptionSetElapsedTime(true),
 		progressbar.OptionSetPredictTime(true),
-		progressbar.OptionThrottle(50*time.Millisecond),
+		progressbar.OptionThrottle(p.Cfg.Log.ProgressInterval),
 		progressbar.OptionSetRenderBlankState(true),
 		progressbar.OptionUseANSICodes(true),
 	)
-	file, err := os.Create(outputParquet)
+	var abstracts *abstractIndex
+	if p.Cfg.Parse.MergeAbstracts.Enabled {
+		var err error
+		abstracts, err = p.loadAbstracts(ctx, p.Cfg.Parse.MergeAbstracts.InputDir)
+		if err != nil {
+			sessionSpan.RecordError(err)
+			return err
+		}
+	}
+	meta := p.datasetMetadata(run.label, run.inputDir, run.maxWorkers)
+	// an output in S3 is written to the scratch directory and uploaded once complete
+	outputPath := run.outputPath
+	if storage.IsS3(outputPath) {
+		outputPath = filepath.Join(p.Scratch.Dir(), filepath.Base(outputPath))
+	}
+	out := run.out
+	var err error
+	// rows counts the records in the output for the checkpoint
+	var rows int64
+	switch {
+	case out != nil:
+	case p.resumed != nil:
+		rows = p.resumed.Rows
+		p.tally.Seed(rows)
+		p.Logger.Info("Resuming parse",
+			zap.String("output", run.outputPath),
+			zap.Int("files", len(p.resumed.Files)),
+			zap.Int64("rows", rows))
+		out, err = sink.Append(outputPath, p.Cfg.Parse, meta,
+			sink.Checkpoint{Offset: p.resumed.Offset, Rows: rows})
+	default:
+		out, err = sink.New(outputPath, p.Cfg.Parse, meta)
+	}
 	if err != nil {
 		sessionSpan.RecordError(err)
-		return fmt.Errorf("failed to create Parquet file: %w", err)
+		return err
 	}
-	defer file.Close()
-	writer := parquet.NewGenericWriter[PatentRecord](file)
-	defer writer.Close()
+	closed := false
+	defer func() {
+		if !closed {
+			_ = sink.Abort(out)
+		}
+	}()
+	p.checkpointing = p.Manifest != nil && run.out == nil && sink.Resumable(p.Cfg.Parse)
+	if p.checkpointing && p.resumed == nil {
+		offset, err := sink.Mark(out)
+		if err == nil {
+			err = p.Manifest.StartCheckpoint(run.outputPath, offset)
+		}
+		if err != nil {
+			sessionSpan.RecordError(err)
+			return fmt.Errorf("start parse checkpoint: %w", err)
+		}
+	}
+	var index *recordIndex
+	if p.Cfg.Parse.IndexPath != "" {
+		index, err = openRecordIndex(snapshot.Expand(p.Cfg.Parse.IndexPath, run.label))
+		if err != nil {
+			sessionSpan.RecordError(err)
+			return err
+		}
+		defer index.Close()
+	}
+	var raw *rawXMLWriter
+	if p.Cfg.Parse.RawXML.Enabled {
+		rawCfg := p.Cfg.Parse.RawXML
+		rawCfg.Path = snapshot.Expand(rawCfg.Path, run.label)
+		raw, err = openRawXMLWriter(rawCfg)
+		if err != nil {
+			sessionSpan.RecordError(err)
+			return err
+		}
+		defer raw.Close()
+	}
+	if p.Cfg.Parse.Quarantine.Enabled {
+		p.quarantined, err = openQuarantine(p.Cfg.Parse.Quarantine)
+		if err != nil {
+			sessionSpan.RecordError(err)
+			return err
+		}
+		defer func() {
+			if n := p.quarantined.Count(); n > 0 {
+				p.Logger.Warn("Quarantined XML files that failed to parse",
+					zap.Int("files", n), zap.String("dir", p.Cfg.Parse.Quarantine.Dir))
+			}
+			if err := p.quarantined.Close(); err != nil {
+				p.Logger.Warn("Failed to close quarantine report", zap.Error(err))
+			}
+			p.quarantined = nil
+		}()
+	}
+	dedup := p.newDeduper()
 	var writeMu sync.Mutex
-	safeWrite := func(rows []PatentRecord) error {
+	// safeWrite writes the records of the XML file key. With a checkpoint they are flushed
+	// and the file recorded under the same lock, so the checkpoint covers exactly the files
+	// whose records are on disk.
+	safeWrite := func(key string, records []models.PatentRecord) error {
 		writeMu.Lock()
 		defer writeMu.Unlock()
-		_, err := writer.Write(rows)
-		return err
+		if dedup != nil {
+			records = dedup.filter(key, records)
+		}
+		if abstracts != nil {
+			abstracts.merge(records)
+		}
+		if err := out.Write(records); err != nil {
+			return err
+		}
+		p.tally.Add(records)
+		if !p.checkpointing {
+			return nil
+		}
+		rows += int64(len(records))
+		offset, err := sink.Mark(out)
+		if err != nil {
+			return err
+		}
+		return p.Manifest.AdvanceCheckpoint(key, offset, rows)
 	}
-	sem := semaphore.NewWeighted(maxWorkers)
+	sem := semaphore.NewWeighted(run.maxWorkers)
 	var wg sync.WaitGroup
 	errChan := make(chan error, 1)
 	var processedFiles atomic.Int64
+	found := 0
 
-	for _, xmlPath := range xmlFiles {
-		select {
-		case <-ctx.Done():
-			p.Logger.Warn("Parsing cancelled")
-			return ctx.Err()
-		default:
+	parseFile := func(path string) {
+		ctxFile, fileSpan := p.Tracer.Start(ctx, "parse.xml_file", trace.WithAttributes(
+			attribute.String("xml_path", path),
+		))
+		defer fileSpan.End()
+		fileStart := time.Now()
+		// the records go to the output batch by batch while the file is read; a failed
+		// write stops the run, a failed read only this file
+		var writeErr error
+		count, err := ET.UnwrapError(p.processSingleXML(ctxFile, path, func(records []models.PatentRecord) error {
+			if err := safeWrite(path, records); err != nil {
+				writeErr = err
+				return err
+			}
+			p.recordsTotal.Add(ctxFile, int64(len(records)))
+			p.processedRecords.Add(uint64(len(records)))
+			return nil
+		})())
+		if err != nil {
+			fileSpan.RecordError(err)
+			p.xmlFilesFailed.Add(
+				ctxFile,
+				1,
+				metric.WithAttributes(attribute.String("status", "failed")),
+			)
 		}
+		switch {
+		case writeErr != nil:
+			select {
+			case errChan <- writeErr:
+			default:
+			}
+			p.recordFile(path, state.StatusFailed)
+			p.updateProgress()
+			return
+		case err != nil:
+			if p.quarantined != nil && ctx.Err() == nil {
+				qerr := p.quarantined.addFile(path, err)
+				if qerr == nil {
+					p.Logger.Warn("Quarantined XML file", zap.String("path", path), zap.Error(err))
+					p.recordFile(path, state.StatusFailed)
+					p.updateProgress()
+					return
+				}
+				err = errors.Join(err, qerr)
+			}
+			p.failed.Fail(path, err)
+			p.recordFile(path, state.StatusFailed)
+			p.updateProgress()
+			return
+		}
+		fileSpan.AddEvent(
+			"records_processed",
+			trace.WithAttributes(attribute.Int64("count", int64(count))),
+		)
+		p.xmlFilesSuccess.Add(
+			ctxFile,
+			1,
+			metric.WithAttributes(attribute.String("status", "success")),
+		)
+		durationMs := time.Since(fileStart).Milliseconds()
+		p.fileDuration.Record(
+			ctxFile,
+			durationMs,
+			metric.WithAttributes(attribute.String("status", "success")),
+		)
+		if index != nil || raw != nil {
+			if err := exportRecordLocations(path, index, raw); err != nil {
+				p.Logger.Warn("Failed to index XML file", zap.String("path", path), zap.Error(err))
+			}
+		}
+		p.recordFile(path, state.StatusDone)
+		p.failed.Succeed()
+		processedFiles.Add(1)
+		p.updateProgress()
+		if p.processedRecords.Load()%100 == 0 {
+			p.Logger.Info("Processed records", zap.Uint64("total", p.processedRecords.Load()))
+		}
+	}
+	parseZip := func(path string) {
+		if err := p.parseArchive(ctx, path, safeWrite); err != nil {
+			select {
+			case errChan <- fmt.Errorf("failed to process %s: %w", path, err):
+			default:
+			}
+		}
+		p.updateProgress()
+	}
+
+	// once the run drains, no new files are started; the output keeps the records of
+	// those in flight and is flushed without being finalized
+	for source := range sources {
+		if shutdown.Interrupted(ctx) {
+			break
+		}
+		found++
 		wg.Add(1)
 		if err := sem.Acquire(ctx, 1); err != nil {
-			return err
+			wg.Done()
+			break
 		}
 		go func(path string) {
 			defer wg.Done()
 			defer sem.Release(1)
-			ctxFile, fileSpan := p.Tracer.Start(ctx, "parse.xml_file", trace.WithAttributes(
-				attribute.String("xml_path", path),
-			))
-			defer fileSpan.End()
-			fileStart := time.Now()
-			records := p.processSingleXML(ctxFile, path)()
-			if ET.IsLeft(records) {
-				_, err := ET.UnwrapError(records)
-				fileSpan.RecordError(err)
-				p.xmlFilesFailed.Add(
-					ctxFile,
-					1,
-					metric.WithAttributes(attribute.String("status", "failed")),
-				)
-				select {
-				case errChan <- fmt.Errorf("failed to process %s: %w", path, err):
-				default:
-				}
-				p.updateProgress()
+			if p.Cfg.Parse.FromArchives && isZipFile(path) {
+				parseZip(path)
 				return
 			}
-			res := F.Pipe3(
-				records,
-				ET.Chain(func(records []PatentRecord) ET.Either[error, uint64] {
-					if err := safeWrite(records); err != nil {
-						return ET.Left[uint64](err)
-					}
-					count := uint64(len(records))
-					p.recordsTotal.Add(ctxFile, int64(count))
-					p.processedRecords.Add(count)
-					fileSpan.AddEvent(
-						"records_processed",
-						trace.WithAttributes(attribute.Int64("count", int64(count))),
-					)
-					return ET.Right[error](count)
-				}),
-				ET.MapLeft[uint64](func(err error) error {
-					fileSpan.RecordError(err)
-					p.xmlFilesFailed.Add(
-						ctxFile,
-						1,
-						metric.WithAttributes(attribute.String("status", "failed")),
-					)
-					select {
-					case errChan <- err:
-					default:
-					}
-					return err
-				}),
-				ET.Map[error](func(_ uint64) uint64 { return 0 }),
-			)
-			if ET.IsLeft(res) {
-				p.updateProgress()
-				return
-			}
-			p.xmlFilesSuccess.Add(
-				ctxFile,
-				1,
-				metric.WithAttributes(attribute.String("status", "success")),
-			)
-			durationMs := time.Since(fileStart).Milliseconds()
-			p.fileDuration.Record(
-				ctxFile,
-				durationMs,
-				metric.WithAttributes(attribute.String("status", "success")),
-			)
-			processedFiles.Add(1)
-			p.updateProgress()
-			if p.processedRecords.Load()%100 == 0 {
-				p.Logger.Info("Processed records", zap.Uint64("total", p.processedRecords.Load()))
-			}
-		}(xmlPath)
+			p.xmlFilesTotal.Add(ctx, 1)
+			parseFile(path)
+		}(source)
 	}
 
 	wg.Wait()
@@ -294,10 +655,62 @@ func (p *Parser) ParseAllToParquet(
 		sessionSpan.RecordError(err)
 		return err
 	}
+	if err := ctx.Err(); err != nil {
+		return err
+	}
+	if shutdown.Interrupted(ctx) {
+		p.Logger.Warn("Parsing interrupted", zap.Int64("files", processedFiles.Load()))
+		return shutdown.ErrInterrupted
+	}
+	// the output lacks the records of the failed files; only pipeline.on_error continue
+	// keeps it
+	if n := p.failed.Len(); n > 0 {
+		if !p.Cfg.Pipeline.ContinueOnError() {
+			err := p.failed.Err()
+			sessionSpan.RecordError(err)
+			return err
+		}
+		p.Logger.Warn("Skipped XML files that failed to parse", zap.Int("files", n))
+	}
+	closed = true
+	if err := out.Close(); err != nil {
+		sessionSpan.RecordError(err)
+		return fmt.Errorf("failed to finalize output: %w", err)
+	}
+	// items stay unparsed in the manifest, so the next run parses them again
+	if err := p.tally.Check(p.Cfg.Parse.Contract); err != nil {
+		sessionSpan.RecordError(err)
+		p.Logger.Error("Output violates the data contract",
+			zap.String("output", run.outputPath), zap.Error(err))
+		return err
+	}
+	if outputPath != run.outputPath {
+		if err := p.publish(ctx, outputPath, run.outputPath); err != nil {
+			sessionSpan.RecordError(err)
+			return err
+		}
+	}
+	if p.Manifest != nil && run.out == nil {
+		err := p.Manifest.UpdateItems(
+			func(it state.ItemState) bool {
+				return it.Extract == state.StatusDone ||
+					p.Cfg.Parse.FromArchives && it.Download == state.StatusDone
+			},
+			func(it *state.ItemState) { it.Parse = state.StatusDone },
+		)
+		if err != nil {
+			p.Logger.Warn("Failed to update manifest", zap.Error(err))
+		}
+		if p.checkpointing {
+			if err := p.Manifest.ClearCheckpoint(); err != nil {
+				p.Logger.Warn("Failed to update manifest", zap.Error(err))
+			}
+		}
+	}
 
-	durationMs := time.Since(startTime).Milliseconds()
+	durationMs := time.Since(run.start).Milliseconds()
 	status := "success"
-	if len(xmlFiles) == 0 {
+	if found == 0 {
 		status = "empty"
 	}
 	p.sessionDuration.Record(
@@ -305,13 +718,108 @@ func (p *Parser) ParseAllToParquet(
 		durationMs,
 		metric.WithAttributes(attribute.String("status", status)),
 	)
-	p.Logger.Info("Parsing completed", zap.Uint64("total_records", p.processedRecords.Load()))
+	if abstracts != nil {
+		p.Logger.Info("Merged abstracts", zap.Int64("records", abstracts.merged))
+	}
+	if n := p.filtered.Load(); n > 0 {
+		p.Logger.Info("Skipped documents outside parse.filter", zap.Int64("documents", n))
+	}
+	if dedup != nil && dedup.dropped > 0 {
+		p.Logger.Info("Dropped duplicate records",
+			zap.String("policy", dedup.policy), zap.Int64("records", dedup.dropped))
+		if dedup.superseded > 0 {
+			p.Logger.Warn("Kept a record the dedup policy does not prefer, as it was written first",
+				zap.String("policy", dedup.policy), zap.Int64("records", dedup.superseded))
+		}
+	}
+	citations := p.citations.snapshot()
+	p.Logger.Info("Parsing completed",
+		zap.Uint64("total_records", p.processedRecords.Load()),
+		zap.Int64("citations", citations.Total),
+		zap.Any("citations_by_office", citations.ByOffice),
+		zap.Any("citations_by_phase", citations.ByPhase),
+	)
+	if err := p.reportProfile(); err != nil {
+		p.Logger.Warn("Failed to write extractor profile", zap.Error(err))
+	}
 	if p.progress != nil {
 		p.progress.Describe("Parsing complete")
 		_ = p.progress.Finish()
 		p.progress = nil
 	}
-	return nil
+	return p.failed.Err()
+}
+
+// datasetMetadata describes the inputs and settings of a parse run for the dataset descriptor.
+func (p *Parser) datasetMetadata(label, inputDir string, workers int64) sink.Metadata {
+	sources := []string{inputDir}
+	if p.Manifest != nil {
+		if names := p.Manifest.Names(); len(names) > 0 {
+			sources = names
+		}
+	}
+	return sink.Metadata{
+		Snapshot: label,
+		Sources:  sources,
+		Parameters: map[string]any{
+			"product_id":    p.Cfg.Server.ProductID,
+			"input_dir":     inputDir,
+			"output_format": p.Cfg.Parse.OutputFormat,
+			"fields":        p.Cfg.Parse.Fields,
+			"streaming":     p.Cfg.Parse.Streaming,
+			"workers":       workers,
+			"incremental":   p.Cfg.State.Incremental,
+			"privacy":       p.Cfg.Parse.Privacy.Inventors,
+			"filter":        p.Cfg.Download.Filter,
+		},
+	}
+}
+
+// recordFile stores the parse status of an XML file when state tracking is enabled.
+func (p *Parser) recordFile(path string, st state.Status) {
+	if p.Manifest == nil || st == state.StatusDone && p.checkpointing {
+		// done files are recorded along with the checkpoint, see safeWrite
+		return
+	}
+	if err := p.Manifest.SetFileStatus(path, st); err != nil {
+		p.Logger.Warn("Failed to update manifest", zap.String("path", path), zap.Error(err))
+	}
+}
+
+// skipParsed returns why path can be skipped because an earlier run parsed it: into an
+// earlier incremental output, or into the output a resumed run continues. It returns ""
+// when path has to be parsed.
+func (p *Parser) skipParsed(path string) string {
+	switch {
+	case p.resumed != nil && p.resumed.Files[path]:
+		return "in the output of the interrupted run being resumed"
+	case p.Cfg.State.Incremental && p.Manifest != nil &&
+		p.Manifest.FileStatus(path) == state.StatusDone:
+		return "parsed by an earlier incremental run"
+	}
+	return ""
+}
+
+// resumeFrom returns the checkpoint to continue writing outputPath from with
+// parse.resume, or nil to start the output over.
+func (p *Parser) resumeFrom(outputPath string) *state.ParseCheckpoint {
+	if !p.Cfg.Parse.Resume || p.Manifest == nil {
+		return nil
+	}
+	cp, ok := p.Manifest.ParseCheckpoint()
+	if !ok || cp.Output != outputPath {
+		p.Logger.Warn("No parse checkpoint for the output, starting over", zap.String("output", outputPath))
+		return nil
+	}
+	return &cp
+}
+
+// notXMLReason explains why isXMLFile rejected a file under the current settings.
+func (p *Parser) notXMLReason() string {
+	if p.Cfg.Parse.SniffContent {
+		return "no .xml extension and no XML content"
+	}
+	return "no .xml extension (parse.sniff_content is off)"
 }
 
 func (p *Parser) updateProgress() {
@@ -320,43 +828,104 @@ func (p *Parser) updateProgress() {
 	}
 }
 
+// exchangeDocumentXPath selects exchange documents regardless of namespace prefix.
+const exchangeDocumentXPath = "//*[local-name()='exchange-document']"
+
+// processSingleXML passes the records of the XML file at xmlPath to emit, in batches
+// while the file is read with parse.streaming, and returns their number.
 func (p *Parser) processSingleXML(
 	ctx context.Context,
 	xmlPath string,
-) IOE.IOEither[error, []PatentRecord] {
+	emit func([]models.PatentRecord) error,
+) IOE.IOEither[error, int] {
 	ctx, span := p.Tracer.Start(
 		ctx,
 		"parse.process_xml",
 		trace.WithAttributes(attribute.String("xml_path", xmlPath)),
 	)
 	defer span.End()
-	open := IOE.Eitherize1(os.Open)(xmlPath)
-	records := F.Pipe4(
-		open,
-		IOE.Tap(func(f *os.File) IOE.IOEither[error, int64] {
-			select {
-			case <-ctx.Done():
-				return IOE.Left[int64](ctx.Err())
-			default:
-			}
-			fi, err := f.Stat()
-			if err != nil {
-				return IOE.Left[int64](err)
-			}
-			size := fi.Size()
-			p.bytesTotal.Add(ctx, size)
-			span.AddEvent("file_size", trace.WithAttributes(attribute.Int64("bytes", size)))
-			return IOE.Right[error](size)
-		}),
-		IOE.Chain(func(f *os.File) IOE.IOEither[error, *xmlquery.Node] {
-			select {
-			case <-ctx.Done():
-				return IOE.Left[*xmlquery.Node](ctx.Err())
-			default:
-			}
-			return IOE.TryCatchError(func() (*xmlquery.Node, error) {
-				return xmlquery.Parse(f)
-			})
+	if storage.IsS3(xmlPath) {
+		return p.processObject(ctx, xmlPath, emit)
+	}
+	use := func(f *os.File) IOE.IOEither[error, int] {
+		return F.Pipe1(
+			IOE.TryCatchError(func() (int64, error) {
+				select {
+				case <-ctx.Done():
+					return 0, ctx.Err()
+				default:
+				}
+				fi, err := f.Stat()
+				if err != nil {
+					return 0, err
+				}
+				size := fi.Size()
+				p.bytesTotal.Add(ctx, size)
+				span.AddEvent("file_size", trace.WithAttributes(attribute.Int64("bytes", size)))
+				return size, nil
+			}),
+			IOE.Chain(func(_ int64) IOE.IOEither[error, int] {
+				return p.extractRecords(ctx, f, emit)
+			}),
+		)
+	}
+	release := func(f *os.File, _ ET.Either[error, int]) IOE.IOEither[error, any] {
+		return IOE.TryCatchError(func() (any, error) { return nil, f.Close() })
+	}
+	return IOE.Bracket(file.Open(xmlPath), use, release)
+}
+
+// processObject reads the records of an XML object in S3 while it is downloaded.
+func (p *Parser) processObject(
+	ctx context.Context,
+	uri string,
+	emit func([]models.PatentRecord) error,
+) IOE.IOEither[error, int] {
+	return func() ET.Either[error, int] {
+		body, size, err := p.Storage.Open(ctx, uri)
+		if err != nil {
+			return ET.Left[int](err)
+		}
+		defer body.Close()
+		p.bytesTotal.Add(ctx, size)
+		return p.extractRecords(ctx, body, emit)()
+	}
+}
+
+// extractRecords passes the records of one XML file to emit, read with the stream parser
+// or, with parse.streaming off, the DOM parser, and profiles the extractors when the file
+// is sampled. It returns the number of records.
+func (p *Parser) extractRecords(
+	ctx context.Context,
+	r io.Reader,
+	emit func([]models.PatentRecord) error,
+) IOE.IOEither[error, int] {
+	return func() ET.Either[error, int] {
+		prof := p.profile.sample()
+		defer p.profile.done(prof)
+		if p.Cfg.Parse.Streaming {
+			return p.streamRecords(ctx, r, prof, emit)()
+		}
+		records, err := ET.UnwrapError(p.domRecords(ctx, r, prof)())
+		if err == nil {
+			err = emit(records)
+		}
+		if err != nil {
+			return ET.Left[int](err)
+		}
+		return ET.Right[error](len(records))
+	}
+}
+
+// domRecords loads the whole document into memory before extracting records.
+func (p *Parser) domRecords(
+	ctx context.Context,
+	r io.Reader,
+	prof *fileProfile,
+) IOE.IOEither[error, []models.PatentRecord] {
+	return F.Pipe3(
+		IOE.TryCatchError(func() (*xmlquery.Node, error) {
+			return xmlquery.Parse(r)
 		}),
 		IOE.Chain(func(doc *xmlquery.Node) IOE.IOEither[error, []*xmlquery.Node] {
 			select {
@@ -365,33 +934,102 @@ func (p *Parser) processSingleXML(
 			default:
 			}
 			return IOE.TryCatchError(func() ([]*xmlquery.Node, error) {
-				return xmlquery.QueryAll(doc, "//*[local-name()='exchange-document']")
+				return xmlquery.QueryAll(doc, exchangeDocumentXPath)
 			})
 		}),
-		IOE.Chain(IOE.TraverseArray(func(node *xmlquery.Node) IOE.IOEither[error, PatentRecord] {
+		IOE.Map[error](p.selectDocuments),
+		IOE.Chain(IOE.TraverseArray(func(node *xmlquery.Node) IOE.IOEither[error, models.PatentRecord] {
 			select {
 			case <-ctx.Done():
-				return IOE.Left[PatentRecord](ctx.Err())
+				return IOE.Left[models.PatentRecord](ctx.Err())
 			default:
-				res, err := exchangeDocumentFromNode(node)
+				res, err := exchangeDocumentFromNode(node, p.fields, p.schemes, &p.citations, prof)
 				if err != nil {
-					return IOE.Left[PatentRecord](err)
+					return IOE.Left[models.PatentRecord](err)
 				}
+				p.xpaths.extract(node, &res)
+				p.scrubber.scrub(&res)
+				p.dates.apply(&res)
 				return IOE.Right[error](res)
 			}
 		})),
 	)
-	return records
 }
 
-func exchangeDocumentFromNode(node *xmlquery.Node) (PatentRecord, error) {
+// streamBatchDocs is the number of records streamRecords collects before passing them on.
+const streamBatchDocs = 500
+
+// streamRecords reads one exchange-document at a time and passes the records to emit in
+// batches of streamBatchDocs, so memory stays bounded by a batch and the largest
+// document: the stream parser drops each subtree once the next one is read. With a parse
+// checkpoint the records of the file are passed at once, since the checkpoint records
+// whole files. The last batch is passed even when empty.
+func (p *Parser) streamRecords(
+	ctx context.Context,
+	r io.Reader,
+	prof *fileProfile,
+	emit func([]models.PatentRecord) error,
+) IOE.IOEither[error, int] {
+	return IOE.TryCatchError(func() (int, error) {
+		sp, err := xmlquery.CreateStreamParser(r, exchangeDocumentXPath)
+		if err != nil {
+			return 0, err
+		}
+		count := 0
+		var records []models.PatentRecord
+		for {
+			if err := ctx.Err(); err != nil {
+				return count, err
+			}
+			node, err := sp.Read()
+			if err == io.EOF {
+				return count + len(records), emit(records)
+			}
+			if err != nil {
+				return count, err
+			}
+			if !p.filter.keep(node) {
+				p.filtered.Add(1)
+				continue
+			}
+			rec, err := exchangeDocumentFromNode(node, p.fields, p.schemes, &p.citations, prof)
+			if err != nil {
+				return count, err
+			}
+			p.xpaths.extract(node, &rec)
+			p.scrubber.scrub(&rec)
+			p.dates.apply(&rec)
+			records = append(records, rec)
+			if len(records) >= streamBatchDocs && !p.checkpointing {
+				if err := emit(records); err != nil {
+					return count, err
+				}
+				count += len(records)
+				records = nil
+			}
+		}
+	})
+}
+
+func exchangeDocumentFromNode(
+	node *xmlquery.Node,
+	fields fieldSet,
+	schemes classificationSchemes,
+	citationCounts *citationCounter,
+	prof *fileProfile,
+) (models.PatentRecord, error) {
 	country := node.SelectAttr("country")
 	docNumber := node.SelectAttr("doc-number")
 	kind := node.SelectAttr("kind")
 	status := node.SelectAttr("status")
 	if country == "" || docNumber == "" || kind == "" || status == "" {
-		return PatentRecord{}, fmt.Errorf("missing required attributes")
+		return models.PatentRecord{}, &documentError{
+			id:  country + docNumber + kind,
+			err: fmt.Errorf("missing required attributes"),
+		}
 	}
+	prof.document()
+	start := prof.now()
 	classifications := F.Pipe2(
 		IOE.TryCatchError(func() ([]*xmlquery.Node, error) {
 			return xmlquery.QueryAll(node, ".//*[local-name()='patent-classification']")
@@ -424,23 +1062,14 @@ func exchangeDocumentFromNode(node *xmlquery.Node) (PatentRecord, error) {
 			return IO.Of([]PatentClassification{})
 		}),
 	)()
+	prof.track(extractClassifications, start)
+	start = prof.now()
 	citations := F.Pipe2(
 		IOE.TryCatchError(func() ([]*xmlquery.Node, error) {
 			return xmlquery.QueryAll(node, ".//*[local-name()='references-cited']/*[local-name()='citation']")
 		}),
-		IOE.Chain(IOE.TraverseArray(func(n *xmlquery.Node) IOE.IOEither[error, Citation] {
-			categories := F.Pipe2(
-				xmlquery.Find(
-					n,
-					"*[local-name()='category'] | *[local-name()='rel-passage']/*[local-name()='category']",
-				),
-				array.Map(func(c *xmlquery.Node) string {
-					return strings.TrimSpace(c.InnerText())
-				}),
-				array.Filter(func(s string) bool {
-					return s != ""
-				}),
-			)
+		IOE.Chain(IOE.TraverseArray(func(n *xmlquery.Node) IOE.IOEither[error, models.Citation] {
+			categories := citationCategories(n)
 			citedID := F.Pipe2(
 				option.FromNillable(
 					xmlquery.FindOne(n, "*[local-name()='patcit']/*[local-name()='document-id']"),
@@ -456,12 +1085,22 @@ func exchangeDocumentFromNode(node *xmlquery.Node) (PatentRecord, error) {
 				}),
 				option.GetOrElse(func() string { return "" }),
 			)
-			return IOE.Right[error](Citation{CitedID: citedID, Categories: categories})
+			if citedID != "" {
+				citationCounts.add(n)
+			}
+			return IOE.Right[error](models.Citation{
+				CitedID:    citedID,
+				Categories: categories,
+				Phase:      n.SelectAttr("cited-phase"),
+				Office:     citingOffice(n),
+			})
 		})),
-		IOE.GetOrElse(func(_ error) IO.IO[[]Citation] {
-			return IO.Of([]Citation{})
+		IOE.GetOrElse(func(_ error) IO.IO[[]models.Citation] {
+			return IO.Of([]models.Citation{})
 		}),
 	)()
+	prof.track(extractCitations, start)
+	start = prof.now()
 	familyMembers := F.Pipe2(
 		IOE.TryCatchError(func() ([]*xmlquery.Node, error) {
 			return xmlquery.QueryAll(
@@ -513,6 +1152,7 @@ func exchangeDocumentFromNode(node *xmlquery.Node) (PatentRecord, error) {
 			return IO.Of([]FamilyMember{})
 		}),
 	)()
+	prof.track(extractFamily, start)
 	doc := ExchangeDocument{
 		Country:               country,
 		DocNumber:             docNumber,
@@ -523,19 +1163,8 @@ func exchangeDocumentFromNode(node *xmlquery.Node) (PatentRecord, error) {
 		FamilyMembers:         familyMembers,
 	}
 	patentID := doc.Country + doc.DocNumber + doc.Kind
-	cpcSet := make(map[string]struct{})
-	for _, pc := range doc.PatentClassifications {
-		if pc.Scheme == "CPCI" {
-			symbol := pc.ClassificationSymbol
-			cpcSet[symbol] = struct{}{}
-		}
-	}
-	var cpcList []string
-	for symbol := range cpcSet {
-		cpcList = append(cpcList, symbol)
-	}
-	sort.Strings(cpcList)
-	filteredCitations := array.Filter(func(c Citation) bool {
+	cpcList := schemes.classificationSymbols(doc.PatentClassifications, schemes.cpc)
+	filteredCitations := array.Filter(func(c models.Citation) bool {
 		return c.CitedID != ""
 	})(doc.Citations)
 	familySet := make(map[string]struct{})
@@ -554,13 +1183,44 @@ func exchangeDocumentFromNode(node *xmlquery.Node) (PatentRecord, error) {
 		familyList = append(familyList, fid)
 	}
 	sort.Strings(familyList)
-	return PatentRecord{
+	record := models.PatentRecord{
 		PatentID:      patentID,
 		Status:        doc.Status,
 		CPCList:       cpcList,
 		Citations:     filteredCitations,
 		FamilyPatents: familyList,
-	}, nil
+	}
+	start = prof.now()
+	fields.extract(node, &record)
+	if fields[models.FieldCPCHierarchy] {
+		record.CPCHierarchy = cpcAncestors(cpcList)
+	}
+	if fields[models.FieldCitationProvenance] {
+		record.CitationProvenance = citationProvenance(filteredCitations)
+	}
+	prof.track(extractFields, start)
+	if fields[models.FieldIPC] {
+		start = prof.now()
+		record.IPCList = schemes.ipcSymbols(node, doc.PatentClassifications, schemes.ipc)
+		prof.track(extractIPC, start)
+	}
+	return record, nil
+}
+
+// citationCategories returns the search report categories (X, Y, A, ...) of a citation.
+func citationCategories(n *xmlquery.Node) []string {
+	return F.Pipe2(
+		xmlquery.Find(
+			n,
+			"*[local-name()='category'] | *[local-name()='rel-passage']/*[local-name()='category']",
+		),
+		array.Map(func(c *xmlquery.Node) string {
+			return strings.TrimSpace(c.InnerText())
+		}),
+		array.Filter(func(s string) bool {
+			return s != ""
+		}),
+	)
 }
 
 func getText(parent *xmlquery.Node, selector string) string {
@@ -0,0 +1,40 @@
+package parse
+
+import (
+	"crypto/sha256"
+	"encoding/hex"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// scrubber removes or pseudonymizes person-level fields of a record according to
+// parse.privacy. Applicants are usually companies and are left untouched.
+type scrubber struct {
+	inventors string
+	salt      []byte
+}
+
+func newScrubber(cfg config.Privacy) scrubber {
+	return scrubber{inventors: cfg.Inventors, salt: []byte(cfg.Salt)}
+}
+
+func (s scrubber) scrub(record *models.PatentRecord) {
+	switch s.inventors {
+	case "drop":
+		record.Inventors = nil
+	case "hash":
+		for i, name := range record.Inventors {
+			record.Inventors[i] = s.hash(name)
+		}
+	}
+}
+
+// hash returns a salted SHA-256 digest so the same inventor maps to the same value
+// within datasets sharing a salt.
+func (s scrubber) hash(value string) string {
+	h := sha256.New()
+	h.Write(s.salt)
+	h.Write([]byte(value))
+	return hex.EncodeToString(h.Sum(nil))
+}
@@ -0,0 +1,191 @@
+package parse
+
+import (
+	"encoding/json"
+	"fmt"
+	"os"
+	"sync"
+	"sync/atomic"
+	"time"
+
+	"go.uber.org/zap"
+)
+
+// Extractors timed by the profiler, in the order they run on an exchange-document.
+const (
+	extractClassifications = "classifications"
+	extractCitations       = "citations"
+	extractFamily          = "family"
+	extractFields          = "fields"
+	extractIPC             = "ipc"
+	// extractOther is the rest of the file time: reading and decoding the XML, locating
+	// the exchange-documents and scrubbing the records.
+	extractOther = "other"
+)
+
+var extractors = []string{
+	extractClassifications,
+	extractCitations,
+	extractFamily,
+	extractFields,
+	extractIPC,
+}
+
+// ExtractorProfile is the time spent in one extractor across the profiled files.
+type ExtractorProfile struct {
+	Name    string  `json:"name"`
+	Seconds float64 `json:"seconds"`
+	// Share is the fraction of the profiled file time spent in the extractor.
+	Share float64 `json:"share"`
+}
+
+// ProfileReport breaks the parse time of the sampled XML files down by extractor, to see
+// which XPath queries are worth optimizing.
+type ProfileReport struct {
+	Files      int64              `json:"files"`
+	Documents  int64              `json:"documents"`
+	Seconds    float64            `json:"seconds"`
+	Extractors []ExtractorProfile `json:"extractors"`
+}
+
+// profiler accumulates the extractor times of every sampleEvery-th file. A nil profiler
+// samples nothing.
+type profiler struct {
+	sampleEvery int64
+	seen        atomic.Int64
+	mu          sync.Mutex
+	files       int64
+	documents   int64
+	total       time.Duration
+	times       map[string]time.Duration
+}
+
+func newProfiler(sampleEvery int) *profiler {
+	return &profiler{sampleEvery: int64(max(sampleEvery, 1)), times: map[string]time.Duration{}}
+}
+
+// fileProfile holds the extractor times of one file; it is used by a single goroutine.
+// Its methods are no-ops on nil, so files that are not sampled pay nothing.
+type fileProfile struct {
+	start     time.Time
+	documents int64
+	times     map[string]time.Duration
+}
+
+func (p *profiler) reset() {
+	if p == nil {
+		return
+	}
+	p.mu.Lock()
+	defer p.mu.Unlock()
+	p.seen.Store(0)
+	p.files, p.documents, p.total = 0, 0, 0
+	p.times = map[string]time.Duration{}
+}
+
+// sample starts the profile of the next file, or returns nil if the file is not sampled.
+func (p *profiler) sample() *fileProfile {
+	if p == nil || (p.seen.Add(1)-1)%p.sampleEvery != 0 {
+		return nil
+	}
+	return &fileProfile{start: time.Now(), times: map[string]time.Duration{}}
+}
+
+// done adds the file profile f to the totals.
+func (p *profiler) done(f *fileProfile) {
+	if p == nil || f == nil {
+		return
+	}
+	elapsed := time.Since(f.start)
+	p.mu.Lock()
+	defer p.mu.Unlock()
+	p.files++
+	p.documents += f.documents
+	p.total += elapsed
+	for name, d := range f.times {
+		p.times[name] += d
+	}
+}
+
+func (f *fileProfile) now() time.Time {
+	if f == nil {
+		return time.Time{}
+	}
+	return time.Now()
+}
+
+// track adds the time since start to the extractor name.
+func (f *fileProfile) track(name string, start time.Time) {
+	if f == nil {
+		return
+	}
+	f.times[name] += time.Since(start)
+}
+
+func (f *fileProfile) document() {
+	if f != nil {
+		f.documents++
+	}
+}
+
+func (p *profiler) report() ProfileReport {
+	p.mu.Lock()
+	defer p.mu.Unlock()
+	r := ProfileReport{Files: p.files, Documents: p.documents, Seconds: p.total.Seconds()}
+	share := func(d time.Duration) float64 {
+		if p.total == 0 {
+			return 0
+		}
+		return float64(d) / float64(p.total)
+	}
+	other := p.total
+	for _, name := range extractors {
+		d := p.times[name]
+		other -= d
+		r.Extractors = append(r.Extractors,
+			ExtractorProfile{Name: name, Seconds: d.Seconds(), Share: share(d)})
+	}
+	other = max(other, 0)
+	r.Extractors = append(r.Extractors,
+		ExtractorProfile{Name: extractOther, Seconds: other.Seconds(), Share: share(other)})
+	return r
+}
+
+// ProfileReport returns the extractor profile of the last parse run; ok is false if
+// profiling is disabled.
+func (p *Parser) ProfileReport() (ProfileReport, bool) {
+	if p.profile == nil {
+		return ProfileReport{}, false
+	}
+	return p.profile.report(), true
+}
+
+// reportProfile logs the extractor profile of the run and writes it to parse.profile.report.
+func (p *Parser) reportProfile() error {
+	report, ok := p.ProfileReport()
+	if !ok {
+		return nil
+	}
+	breakdown := make(map[string]string, len(report.Extractors))
+	for _, e := range report.Extractors {
+		breakdown[e.Name] = fmt.Sprintf("%.3fs (%.1f%%)", e.Seconds, 100*e.Share)
+	}
+	p.Logger.Info("Extractor profile",
+		zap.Int64("files", report.Files),
+		zap.Int64("documents", report.Documents),
+		zap.Float64("seconds", report.Seconds),
+		zap.Any("extractors", breakdown),
+	)
+	path := p.Cfg.Parse.Profile.Report
+	if path == "" {
+		return nil
+	}
+	data, err := json.MarshalIndent(report, "", "  ")
+	if err != nil {
+		return err
+	}
+	if err := os.WriteFile(path, append(data, '\n'), 0o644); err != nil {
+		return fmt.Errorf("write profile report: %w", err)
+	}
+	return nil
+}
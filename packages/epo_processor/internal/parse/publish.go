@@ -0,0 +1,44 @@
+package parse
+
+import (
+	"bytes"
+	"context"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"os"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/sink"
+)
+
+// publish uploads an output finished in the scratch directory to uri in S3, with its
+// dataset descriptor pointed at uri, and removes the local files.
+func (p *Parser) publish(ctx context.Context, local, uri string) error {
+	f, err := os.Open(local)
+	if err != nil {
+		return fmt.Errorf("publish output: %w", err)
+	}
+	_, err = p.Storage.Upload(ctx, uri, f)
+	if err := errors.Join(err, f.Close(), os.Remove(local)); err != nil {
+		return fmt.Errorf("publish output: %w", err)
+	}
+	if !p.Cfg.Parse.Descriptor.Enabled {
+		return nil
+	}
+	desc, err := sink.ReadDescriptor(local)
+	if err != nil {
+		return fmt.Errorf("publish descriptor: %w", err)
+	}
+	desc.Path = uri
+	for i := range desc.Partitions {
+		desc.Partitions[i].Path = uri
+	}
+	data, err := json.MarshalIndent(desc, "", "  ")
+	if err != nil {
+		return err
+	}
+	if _, err := p.Storage.Upload(ctx, uri+sink.DescriptorSuffix, bytes.NewReader(data)); err != nil {
+		return fmt.Errorf("publish descriptor: %w", err)
+	}
+	return os.Remove(local + sink.DescriptorSuffix)
+}
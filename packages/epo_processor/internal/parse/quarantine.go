@@ -0,0 +1,176 @@
+package parse
+
+import (
+	"encoding/csv"
+	"errors"
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+	"strconv"
+	"strings"
+	"sync"
+	"time"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+)
+
+var quarantineHeader = []string{"time", "file", "quarantined_as", "document_id", "error"}
+
+// documentError is a failure to read one exchange-document, with the id of the document
+// as far as its attributes give it.
+type documentError struct {
+	id  string
+	err error
+}
+
+func (e *documentError) Error() string {
+	return fmt.Sprintf("document %s: %v", e.id, e.err)
+}
+
+func (e *documentError) Unwrap() error {
+	return e.err
+}
+
+// quarantine keeps the XML files that fail to parse out of the way of the run: each is
+// copied, or moved, into the quarantine directory and reported in errors.csv, and the
+// parse goes on with the next file.
+type quarantine struct {
+	dir    string
+	move   bool
+	mu     sync.Mutex
+	file   *os.File
+	writer *csv.Writer
+	count  int
+}
+
+func openQuarantine(cfg config.Quarantine) (*quarantine, error) {
+	if err := os.MkdirAll(cfg.Dir, 0o755); err != nil {
+		return nil, fmt.Errorf("failed to create quarantine directory: %w", err)
+	}
+	report := cfg.Report
+	if report == "" {
+		report = filepath.Join(cfg.Dir, "errors.csv")
+	}
+	f, err := os.OpenFile(report, os.O_CREATE|os.O_APPEND|os.O_WRONLY, 0o644)
+	if err != nil {
+		return nil, fmt.Errorf("failed to open quarantine report: %w", err)
+	}
+	q := &quarantine{dir: cfg.Dir, move: cfg.Move, file: f, writer: csv.NewWriter(f)}
+	if fi, err := f.Stat(); err == nil && fi.Size() == 0 {
+		err = q.writer.Write(quarantineHeader)
+		if err == nil {
+			q.writer.Flush()
+			err = q.writer.Error()
+		}
+		if err != nil {
+			return nil, errors.Join(err, f.Close())
+		}
+	}
+	return q, nil
+}
+
+// addFile quarantines the XML file at path, which failed with cause.
+func (q *quarantine) addFile(path string, cause error) error {
+	q.mu.Lock()
+	defer q.mu.Unlock()
+	dest := q.destination(filepath.Base(path))
+	var err error
+	if q.move {
+		err = os.Rename(path, dest)
+	}
+	if !q.move || err != nil {
+		err = copyFile(path, dest)
+		if err == nil && q.move {
+			err = os.Remove(path)
+		}
+	}
+	if err != nil {
+		return fmt.Errorf("quarantine %s: %w", path, err)
+	}
+	return q.report(path, dest, cause)
+}
+
+// addEntry quarantines the archive entry key by copying what open returns; the archive
+// itself is left in place.
+func (q *quarantine) addEntry(key string, open func() (io.ReadCloser, error), cause error) error {
+	q.mu.Lock()
+	defer q.mu.Unlock()
+	archive, entry, _ := strings.Cut(key, archiveEntrySeparator)
+	name := strings.TrimSuffix(filepath.Base(archive), filepath.Ext(archive)) + "_" +
+		strings.ReplaceAll(entry, "/", "_")
+	dest := q.destination(name)
+	err := func() error {
+		rc, err := open()
+		if err != nil {
+			return err
+		}
+		defer rc.Close()
+		return writeFile(dest, rc)
+	}()
+	if err != nil {
+		return fmt.Errorf("quarantine %s: %w", key, err)
+	}
+	return q.report(key, dest, cause)
+}
+
+// destination is a free path for name in the quarantine directory; a file quarantined
+// by an earlier run is not overwritten.
+func (q *quarantine) destination(name string) string {
+	dest := filepath.Join(q.dir, name)
+	ext := filepath.Ext(name)
+	for i := 1; ; i++ {
+		if _, err := os.Stat(dest); errors.Is(err, os.ErrNotExist) {
+			return dest
+		}
+		dest = filepath.Join(q.dir, strings.TrimSuffix(name, ext)+"."+strconv.Itoa(i)+ext)
+	}
+}
+
+func (q *quarantine) report(source, dest string, cause error) error {
+	var docErr *documentError
+	var docID string
+	if errors.As(cause, &docErr) {
+		docID = docErr.id
+	}
+	q.count++
+	row := []string{time.Now().UTC().Format(time.RFC3339), source, dest, docID, cause.Error()}
+	if err := q.writer.Write(row); err != nil {
+		return err
+	}
+	q.writer.Flush()
+	return q.writer.Error()
+}
+
+// Count returns the number of files quarantined by this run.
+func (q *quarantine) Count() int {
+	q.mu.Lock()
+	defer q.mu.Unlock()
+	return q.count
+}
+
+func (q *quarantine) Close() error {
+	q.writer.Flush()
+	return errors.Join(q.writer.Error(), q.file.Close())
+}
+
+func copyFile(src, dest string) error {
+	f, err := os.Open(src)
+	if err != nil {
+		return err
+	}
+	defer f.Close()
+	return writeFile(dest, f)
+}
+
+func writeFile(dest string, r io.Reader) error {
+	out, err := os.Create(dest)
+	if err != nil {
+		return err
+	}
+	_, err = io.Copy(out, r)
+	if err := errors.Join(err, out.Close()); err != nil {
+		return errors.Join(err, os.Remove(dest))
+	}
+	return nil
+}
@@ -0,0 +1,100 @@
+package parse
+
+import (
+	"encoding/base64"
+	"encoding/json"
+	"fmt"
+	"os"
+	"sync"
+
+	"github.com/klauspost/compress/zstd"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+)
+
+// rawXMLRecord is one JSONL line of the raw XML export. Data holds the fragment as-is
+// for encoding "xml", or base64 of the zstd-compressed fragment for "zstd+base64".
+type rawXMLRecord struct {
+	PatentID string `json:"patent_id"`
+	Encoding string `json:"encoding"`
+	Data     string `json:"data"`
+}
+
+// rawXMLWriter stores the original exchange-document fragment of every record so
+// future extractors can run without keeping the extracted corpus around.
+type rawXMLWriter struct {
+	mu   sync.Mutex
+	file *os.File
+	enc  *json.Encoder
+	zstd *zstd.Encoder
+}
+
+func openRawXMLWriter(cfg config.RawXML) (*rawXMLWriter, error) {
+	f, err := os.Create(cfg.Path)
+	if err != nil {
+		return nil, fmt.Errorf("failed to create raw XML export: %w", err)
+	}
+	w := &rawXMLWriter{file: f, enc: json.NewEncoder(f)}
+	if cfg.Compression == "zstd" {
+		w.zstd, err = zstd.NewWriter(nil)
+		if err != nil {
+			f.Close()
+			return nil, err
+		}
+	}
+	return w, nil
+}
+
+func (w *rawXMLWriter) addFromFile(path string, entries []IndexEntry) error {
+	f, err := os.Open(path)
+	if err != nil {
+		return err
+	}
+	defer f.Close()
+	for _, e := range entries {
+		buf := make([]byte, e.Length)
+		if _, err := f.ReadAt(buf, e.Offset); err != nil {
+			return fmt.Errorf("read %s at %d: %w", path, e.Offset, err)
+		}
+		rec := rawXMLRecord{PatentID: e.PatentID, Encoding: "xml", Data: string(buf)}
+		if w.zstd != nil {
+			rec.Encoding = "zstd+base64"
+			rec.Data = base64.StdEncoding.EncodeToString(w.zstd.EncodeAll(buf, nil))
+		}
+		if err := w.write(&rec); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+func (w *rawXMLWriter) write(rec *rawXMLRecord) error {
+	w.mu.Lock()
+	defer w.mu.Unlock()
+	return w.enc.Encode(rec)
+}
+
+func (w *rawXMLWriter) Close() error {
+	if w.zstd != nil {
+		_ = w.zstd.Close()
+	}
+	return w.file.Close()
+}
+
+// exportRecordLocations locates the exchange-documents of path once and feeds the
+// offset index and the raw XML export, whichever are enabled.
+func exportRecordLocations(path string, index *recordIndex, raw *rawXMLWriter) error {
+	entries, err := indexXMLFile(path)
+	if err != nil {
+		return err
+	}
+	if index != nil {
+		if err := index.add(entries); err != nil {
+			return err
+		}
+	}
+	if raw != nil {
+		return raw.addFromFile(path, entries)
+	}
+	return nil
+}
@@ -0,0 +1,46 @@
+package parse
+
+import (
+	"bytes"
+	"io"
+	"os"
+	"path/filepath"
+	"strings"
+)
+
+var utf8BOM = []byte{0xEF, 0xBB, 0xBF}
+
+// isXMLFile reports whether path should be parsed. Any casing of the .xml extension is
+// accepted; with sniff enabled, files with other extensions are accepted when their content
+// starts with an XML declaration or an exchange-document(s) root element.
+func isXMLFile(path string, sniff bool) bool {
+	if strings.EqualFold(filepath.Ext(path), ".xml") {
+		return true
+	}
+	return sniff && sniffXML(path)
+}
+
+func sniffXML(path string) bool {
+	f, err := os.Open(path)
+	if err != nil {
+		return false
+	}
+	defer f.Close()
+	buf := make([]byte, 512)
+	n, _ := io.ReadFull(f, buf)
+	head := bytes.TrimLeft(bytes.TrimPrefix(buf[:n], utf8BOM), " \t\r\n")
+	if bytes.HasPrefix(head, []byte("<?xml")) {
+		return true
+	}
+	if !bytes.HasPrefix(head, []byte("<")) {
+		return false
+	}
+	name := head[1:]
+	if end := bytes.IndexAny(name, " \t\r\n/>"); end >= 0 {
+		name = name[:end]
+	}
+	if colon := bytes.IndexByte(name, ':'); colon >= 0 {
+		name = name[colon+1:]
+	}
+	return bytes.HasPrefix(name, []byte("exchange-document"))
+}
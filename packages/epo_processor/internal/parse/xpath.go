@@ -0,0 +1,49 @@
+package parse
+
+import (
+	"fmt"
+	"maps"
+	"slices"
+	"strings"
+
+	"github.com/antchfx/xmlquery"
+	"github.com/antchfx/xpath"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// xpathField is a column of parse.xpath_fields: the text of the nodes its expression
+// selects in an exchange-document.
+type xpathField struct {
+	name string
+	expr *xpath.Expr
+}
+
+// xpathFields are the custom columns of a parse, in name order.
+type xpathFields []xpathField
+
+func newXPathFields(exprs map[string]string) (xpathFields, error) {
+	fields := make(xpathFields, 0, len(exprs))
+	for _, name := range slices.Sorted(maps.Keys(exprs)) {
+		expr, err := xpath.Compile(exprs[name])
+		if err != nil {
+			return nil, fmt.Errorf("parse.xpath_fields.%s: %w", name, err)
+		}
+		fields = append(fields, xpathField{name: name, expr: expr})
+	}
+	return fields, nil
+}
+
+// extract adds one entry per custom column to record, holding the whitespace-normalized
+// text of each selected node (element or attribute) that is not empty.
+func (fields xpathFields) extract(node *xmlquery.Node, record *models.PatentRecord) {
+	for _, f := range fields {
+		values := []string{}
+		for _, n := range xmlquery.QuerySelectorAll(node, f.expr) {
+			if text := strings.Join(strings.Fields(n.InnerText()), " "); text != "" {
+				values = append(values, text)
+			}
+		}
+		record.Extra = append(record.Extra, models.ExtraField{Name: f.name, Values: values})
+	}
+}
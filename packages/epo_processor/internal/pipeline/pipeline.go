@@ -0,0 +1,227 @@
+package pipeline
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"sync"
+	"time"
+
+	ET "github.com/IBM/fp-go/v2/either"
+	"go.opentelemetry.io/otel"
+	"go.opentelemetry.io/otel/attribute"
+	"go.opentelemetry.io/otel/trace"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/failures"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/parse"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/shutdown"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/snapshot"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/state"
+)
+
+type Stage string
+
+const (
+	StageDownload Stage = "download"
+	StageExtract  Stage = "extract"
+	StageParse    Stage = "parse"
+)
+
+const (
+	StatusStarted   = "started"
+	StatusCompleted = "completed"
+	StatusFailed    = "failed"
+	// StatusPartial marks a stage in which some items failed and the others succeeded.
+	StatusPartial = "partial"
+	// StatusInterrupted marks a stage that stopped early on SIGINT or SIGTERM.
+	StatusInterrupted = "interrupted"
+)
+
+// Event is a progress notification emitted as a run moves through its stages.
+type Event struct {
+	Stage   Stage     `json:"stage"`
+	Status  string    `json:"status"`
+	Message string    `json:"message,omitempty"`
+	Time    time.Time `json:"time"`
+}
+
+// StageResult records how a single stage of a run ended. When items of the stage failed,
+// Items counts those that succeeded and Failures names the others with their reasons.
+type StageResult struct {
+	Stage    Stage              `json:"stage"`
+	Status   string             `json:"status"`
+	Items    int                `json:"items"`
+	Failed   int                `json:"failed"`
+	Failures []failures.Failure `json:"failures,omitempty"`
+	Error    string             `json:"error,omitempty"`
+	Duration time.Duration      `json:"duration"`
+}
+
+// Summary collects the stage results of a run in execution order, and the citation
+// counts of its parse stage.
+type Summary struct {
+	Snapshot  string               `json:"snapshot,omitempty"`
+	Stages    []StageResult        `json:"stages"`
+	Citations *parse.CitationStats `json:"citations,omitempty"`
+	// Counts is set when state tracking is enabled.
+	Counts *Counts `json:"counts,omitempty"`
+}
+
+// Counts are the items a run downloaded, extracted and parsed, from the manifest before
+// and after it. Failed counts the failed download, extract and parse steps in the manifest
+// once the run ended, including failures of earlier runs that were not retried.
+type Counts struct {
+	Downloaded int `json:"downloaded"`
+	Extracted  int `json:"extracted"`
+	Parsed     int `json:"parsed"`
+	Failed     int `json:"failed"`
+}
+
+// Run executes the stages enabled in cfg in order, stopping at the first failure.
+// With pipeline.streaming the stages overlap per item instead. With pipeline.on_error
+// continue a stage in which only some items failed does not stop the run; its
+// *failures.Partial is returned once the run ends. emit may be nil.
+func Run(
+	ctx context.Context,
+	cfg config.Config,
+	services *internal.Services,
+	emit func(Event),
+) (Summary, error) {
+	if services.Manifest == nil {
+		return run(ctx, cfg, services, emit)
+	}
+	before := services.Manifest.Summary()
+	summary, err := run(ctx, cfg, services, emit)
+	after := services.Manifest.Summary()
+	summary.Counts = &Counts{
+		Downloaded: after.Download[state.StatusDone] - before.Download[state.StatusDone],
+		Extracted:  after.Extract[state.StatusDone] - before.Extract[state.StatusDone],
+		Parsed:     after.Parse[state.StatusDone] - before.Parse[state.StatusDone],
+		Failed: after.Download[state.StatusFailed] + after.Extract[state.StatusFailed] +
+			after.Parse[state.StatusFailed],
+	}
+	return summary, err
+}
+
+func run(
+	ctx context.Context,
+	cfg config.Config,
+	services *internal.Services,
+	emit func(Event),
+) (summary Summary, err error) {
+	if emit == nil {
+		emit = func(Event) {}
+	}
+	var mu sync.Mutex
+	// partial holds the errors of the stages the run went on after; the error that
+	// stopped the run, if any, comes first
+	var partial []error
+	defer func() {
+		err = errors.Join(append([]error{err}, partial...)...)
+	}()
+	// the session spans of the services become children of the stage spans
+	tracer := otel.Tracer(cfg.Telemetry.ServiceName)
+	ctx, span := tracer.Start(ctx, "pipeline.run", trace.WithAttributes(
+		attribute.Int("product_id", cfg.Server.ProductID),
+		attribute.Bool("streaming", cfg.Pipeline.Streaming),
+	))
+	defer span.End()
+	stage := func(ctx context.Context, s Stage, run func(ctx context.Context) (int, error)) error {
+		ctx, span := tracer.Start(ctx, "pipeline."+string(s))
+		defer span.End()
+		emit(Event{Stage: s, Status: StatusStarted, Time: time.Now()})
+		start := time.Now()
+		items, err := run(ctx)
+		res := StageResult{Stage: s, Status: StatusCompleted, Items: items, Duration: time.Since(start)}
+		if err != nil {
+			res.Status = StatusFailed
+			res.Error = err.Error()
+			span.RecordError(err)
+		}
+		var failed *failures.Partial
+		if errors.As(err, &failed) {
+			res.Items = failed.Succeeded
+			res.Failed = len(failed.Failures)
+			res.Failures = failed.Failures
+			if !failed.Total() {
+				res.Status = StatusPartial
+			}
+		}
+		if errors.Is(err, shutdown.ErrInterrupted) {
+			res.Status = StatusInterrupted
+		}
+		span.SetAttributes(
+			attribute.String("status", res.Status),
+			attribute.Int("items", res.Items),
+			attribute.Int("failed", res.Failed),
+		)
+		mu.Lock()
+		summary.Stages = append(summary.Stages, res)
+		if res.Status == StatusPartial && cfg.Pipeline.ContinueOnError() {
+			partial = append(partial, fmt.Errorf("%s: %w", s, err))
+			err = nil
+		}
+		mu.Unlock()
+		emit(Event{Stage: s, Status: res.Status, Message: res.Error, Time: time.Now()})
+		if err != nil {
+			return fmt.Errorf("%s: %w", s, err)
+		}
+		return nil
+	}
+
+	if cfg.Pipeline.Streaming && cfg.Download.Enabled {
+		err := runStreaming(ctx, cfg, services, stage)
+		if cfg.Parse.Enabled {
+			summary.Citations = citationStats(services)
+		}
+		summary.Snapshot = services.Downloader.Snapshot()
+		if summary.Snapshot == "" {
+			summary.Snapshot = snapshot.Resolve(cfg.Snapshot, services.Manifest)
+		}
+		return summary, err
+	}
+	if cfg.Download.Enabled {
+		err := stage(ctx, StageDownload, func(ctx context.Context) (int, error) {
+			sizes, err := ET.UnwrapError(services.Downloader.FetchEPOFiles(ctx)())
+			return len(sizes), err
+		})
+		summary.Snapshot = services.Downloader.Snapshot()
+		if err != nil {
+			return summary, err
+		}
+	}
+	if summary.Snapshot == "" {
+		summary.Snapshot = snapshot.Resolve(cfg.Snapshot, services.Manifest)
+	}
+	if cfg.Extract.Enabled && !cfg.Parse.FromArchives {
+		err := stage(ctx, StageExtract, func(ctx context.Context) (int, error) {
+			_, err := ET.UnwrapError(services.Extractor.ExtractAll(ctx, cfg.Download.Directory)())
+			return 0, err
+		})
+		if err != nil {
+			return summary, err
+		}
+	}
+	if cfg.Parse.Enabled {
+		err := stage(ctx, StageParse, func(ctx context.Context) (int, error) {
+			return 0, services.Parser.ParseAll(
+				ctx,
+				cfg.ParseInputDir(),
+				cfg.Parse.OutputCSV,
+				int64(cfg.Parse.Workers),
+			)
+		})
+		summary.Citations = citationStats(services)
+		if err != nil {
+			return summary, err
+		}
+	}
+	return summary, nil
+}
+
+func citationStats(services *internal.Services) *parse.CitationStats {
+	stats := services.Parser.CitationStats()
+	return &stats
+}
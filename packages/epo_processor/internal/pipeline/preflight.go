@@ -0,0 +1,63 @@
+package pipeline
+
+import (
+	"errors"
+	"io/fs"
+	"path/filepath"
+	"strings"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/extract"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/storage"
+)
+
+// errFound stops a directory walk at the first match.
+var errFound = errors.New("found")
+
+// Preflight warns about enabled stages whose input is produced by a disabled stage
+// and is not on disk yet, e.g. parsing with extract skipped and no XML files present.
+func Preflight(cfg config.Config) []string {
+	var warnings []string
+	if cfg.Download.Enabled {
+		return nil
+	}
+	extracts := cfg.Extract.Enabled && !cfg.Parse.FromArchives
+	if extracts && !hasFile(cfg.Download.Directory, extract.IsArchive) {
+		warnings = append(warnings,
+			"extract runs without download, but "+cfg.Download.Directory+" holds no archives")
+	}
+	if !cfg.Parse.Enabled || extracts {
+		return warnings
+	}
+	switch {
+	case cfg.Parse.FromArchives && !hasFile(cfg.Download.Directory, extract.IsArchive):
+		warnings = append(warnings,
+			"parse runs without download, but "+cfg.Download.Directory+" holds no archives")
+	case !cfg.Parse.FromArchives && !hasFile(cfg.ParseInputDir(), isXML):
+		warnings = append(warnings,
+			"parse runs without extract, but "+cfg.ParseInputDir()+" holds no XML files")
+	}
+	return warnings
+}
+
+func isXML(name string) bool {
+	return strings.EqualFold(filepath.Ext(name), ".xml")
+}
+
+// hasFile reports whether dir contains a file, at any depth, whose name matches. A
+// prefix in S3 is assumed to, as listing it is left to the stage.
+func hasFile(dir string, match func(string) bool) bool {
+	if storage.IsS3(dir) {
+		return true
+	}
+	err := filepath.WalkDir(dir, func(path string, d fs.DirEntry, err error) error {
+		if err != nil {
+			return err
+		}
+		if !d.IsDir() && match(d.Name()) {
+			return errFound
+		}
+		return nil
+	})
+	return errors.Is(err, errFound)
+}
@@ -0,0 +1,164 @@
+package pipeline
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"io"
+	"strings"
+	"sync"
+	"time"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/shutdown"
+)
+
+// ProductSummary is the outcome of the run over one entry of products.
+type ProductSummary struct {
+	Product string `json:"product"`
+	Summary
+	Error string `json:"error,omitempty"`
+
+	err error
+}
+
+// Err returns the error the product failed with, nil if it succeeded.
+func (s ProductSummary) Err() error {
+	return s.err
+}
+
+// RunProducts runs the pipeline once per entry of cfg.Products, each with the services
+// newServices builds for its config. Products run in order, or all at once with
+// pipeline.concurrent_products; then the per-stage progress bars give way to one shared
+// line showing the current stage of the running products. A failed product does not stop the
+// others; the failures are returned together.
+func RunProducts(
+	ctx context.Context,
+	cfg config.Config,
+	newServices func(config.Config) (*internal.Services, error),
+) ([]ProductSummary, error) {
+	summaries := make([]ProductSummary, len(cfg.Products))
+	errs := make([]error, len(cfg.Products))
+	run := func(i int, productCfg config.Config, emit func(Event)) {
+		name := cfg.Products[i].Name
+		summaries[i].Product = name
+		services, err := newServices(productCfg)
+		if err == nil {
+			summaries[i].Summary, err = Run(ctx, productCfg, services, emit)
+			err = errors.Join(err, services.Close())
+		}
+		if err != nil {
+			summaries[i].Error = err.Error()
+			summaries[i].err = err
+			errs[i] = fmt.Errorf("product %s: %w", name, err)
+		}
+	}
+	if !cfg.Pipeline.ConcurrentProducts {
+		for i, p := range cfg.Products {
+			if err := ctx.Err(); err != nil {
+				return summaries[:i], err
+			}
+			if shutdown.Interrupted(ctx) {
+				return summaries[:i], shutdown.Join(append(errs[:i:i], shutdown.ErrInterrupted)...)
+			}
+			run(i, cfg.ForProduct(p), nil)
+		}
+		return summaries, errors.Join(errs...)
+	}
+	display := newProductDisplay(cfg.ProgressWriter(), cfg.Log, cfg.Products)
+	var wg sync.WaitGroup
+	for i, p := range cfg.Products {
+		productCfg := cfg.ForProduct(p)
+		productCfg.Log.Progress = false
+		wg.Add(1)
+		go func() {
+			defer wg.Done()
+			run(i, productCfg, func(ev Event) { display.update(i, ev) })
+			display.done(i)
+		}()
+	}
+	wg.Wait()
+	display.finish()
+	return summaries, errors.Join(errs...)
+}
+
+// productDisplay redraws a single status line with the latest stage event per product.
+// Redraws are throttled to log.progress_interval, and at most log.progress_max_items
+// running products are named; the rest are summed up as "N others in progress".
+type productDisplay struct {
+	mu       sync.Mutex
+	out      io.Writer
+	interval time.Duration
+	maxItems int
+	names    []string
+	status   []string
+	running  []bool
+	finished int
+	drawn    time.Time
+}
+
+func newProductDisplay(out io.Writer, log config.Log, products []config.Product) *productDisplay {
+	d := &productDisplay{
+		out:      out,
+		interval: log.ProgressInterval,
+		maxItems: max(log.ProgressMaxItems, 1),
+		status:   make([]string, len(products)),
+		running:  make([]bool, len(products)),
+	}
+	for i, p := range products {
+		d.names = append(d.names, p.Name)
+		d.status[i] = "waiting"
+	}
+	return d
+}
+
+func (d *productDisplay) update(i int, ev Event) {
+	d.mu.Lock()
+	defer d.mu.Unlock()
+	d.status[i] = string(ev.Stage) + " " + ev.Status
+	d.running[i] = true
+	// failures are always drawn so the line never hides one
+	if ev.Status != StatusFailed && time.Since(d.drawn) < d.interval {
+		return
+	}
+	d.draw()
+}
+
+// done records that product i has finished all its stages.
+func (d *productDisplay) done(i int) {
+	d.mu.Lock()
+	defer d.mu.Unlock()
+	d.running[i] = false
+	d.finished++
+	d.draw()
+}
+
+func (d *productDisplay) draw() {
+	d.drawn = time.Now()
+	var parts []string
+	others := 0
+	for i, name := range d.names {
+		if !d.running[i] {
+			continue
+		}
+		if len(parts) == d.maxItems {
+			others++
+			continue
+		}
+		parts = append(parts, name+": "+d.status[i])
+	}
+	if others > 0 {
+		parts = append(parts, fmt.Sprintf("%d others in progress", others))
+	}
+	parts = append(parts, fmt.Sprintf("[%d/%d products finished]", d.finished, len(d.names)))
+	fmt.Fprintf(d.out, "\r\033[K%s", strings.Join(parts, " | "))
+}
+
+func (d *productDisplay) finish() {
+	d.mu.Lock()
+	defer d.mu.Unlock()
+	if d.out != io.Discard {
+		fmt.Fprintln(d.out)
+	}
+}
@@ -0,0 +1,166 @@
+package pipeline
+
+import (
+	"context"
+	"errors"
+
+	ET "github.com/IBM/fp-go/v2/either"
+	"golang.org/x/sync/errgroup"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/failures"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/shutdown"
+)
+
+// runStreaming runs the enabled stages concurrently. Downloaded items are passed to
+// the extract stage and extracted directories to the parse stage over channels of
+// pipeline.queue_size, so a slow stage holds back the one before it instead of
+// letting finished items pile up on disk. The first failing stage cancels the others.
+// When the run is interrupted each stage stops taking items and closes its channel once
+// the items in flight are done, so the stages drain one after the other.
+func runStreaming(
+	ctx context.Context,
+	cfg config.Config,
+	services *internal.Services,
+	stage func(ctx context.Context, s Stage, run func(ctx context.Context) (int, error)) error,
+) error {
+	g, ctx := errgroup.WithContext(ctx)
+	size := cfg.Pipeline.QueueSize
+	downloaded := make(chan string, size)
+	// a channel is only closed when its stage succeeded: closing it after a failure could
+	// let the next stage finalize a partial result before the group context is cancelled
+	g.Go(func() error {
+		err := stage(ctx, StageDownload, func(ctx context.Context) (int, error) {
+			sizes, err := ET.UnwrapError(services.Downloader.StreamEPOFiles(ctx, downloaded)())
+			return len(sizes), err
+		})
+		if err == nil || errors.Is(err, shutdown.ErrInterrupted) {
+			close(downloaded)
+		}
+		return drained(err)
+	})
+
+	parseInput := (<-chan string)(downloaded)
+	if cfg.Extract.Enabled && !cfg.Parse.FromArchives {
+		extracted := make(chan string, size)
+		parseInput = extracted
+		g.Go(func() error {
+			err := stage(ctx, StageExtract, func(ctx context.Context) (int, error) {
+				return extractStream(ctx, cfg, services, downloaded, extracted)
+			})
+			if err == nil || errors.Is(err, shutdown.ErrInterrupted) {
+				close(extracted)
+			}
+			return drained(err)
+		})
+	}
+
+	if cfg.Parse.Enabled {
+		g.Go(func() error {
+			return drained(stage(ctx, StageParse, func(ctx context.Context) (int, error) {
+				return 0, services.Parser.ParseStream(
+					ctx,
+					parseInput,
+					cfg.Parse.OutputCSV,
+					int64(cfg.Parse.Workers),
+				)
+			}))
+		})
+	} else {
+		g.Go(func() error {
+			for {
+				select {
+				case _, ok := <-parseInput:
+					if !ok {
+						return nil
+					}
+				case <-ctx.Done():
+					return ctx.Err()
+				}
+			}
+		})
+	}
+	if err := g.Wait(); err != nil {
+		return err
+	}
+	if shutdown.Interrupted(ctx) {
+		return shutdown.ErrInterrupted
+	}
+	return nil
+}
+
+// drained hides the interruption of a stage from the group, which would otherwise cancel
+// the stages still finishing their items; runStreaming reports it once all are done.
+func drained(err error) error {
+	if errors.Is(err, shutdown.ErrInterrupted) {
+		return nil
+	}
+	return err
+}
+
+// extractStream extracts up to pipeline.queue_size archives at once as they arrive on in
+// and, when parse is enabled, passes the extracted directories on to out. A failed archive
+// stops the stage unless pipeline.on_error is continue.
+func extractStream(
+	ctx context.Context,
+	cfg config.Config,
+	services *internal.Services,
+	in <-chan string,
+	out chan<- string,
+) (int, error) {
+	g, gctx := errgroup.WithContext(ctx)
+	g.SetLimit(cfg.Pipeline.QueueSize)
+	forward := cfg.Parse.Enabled
+	failed := &failures.Collector{}
+	count := 0
+	for {
+		var archivePath string
+		var ok bool
+		select {
+		case archivePath, ok = <-in:
+		case <-gctx.Done():
+		case <-shutdown.Stopping(ctx):
+		}
+		if !ok {
+			break
+		}
+		count++
+		g.Go(func() error {
+			dir, err := ET.UnwrapError(services.Extractor.ExtractArchive(gctx, archivePath)())
+			if err != nil {
+				failed.Fail(archivePath, err)
+				if cfg.Pipeline.ContinueOnError() && !errors.Is(err, context.Canceled) {
+					return nil
+				}
+				return err
+			}
+			failed.Succeed()
+			if !forward {
+				return nil
+			}
+			select {
+			case out <- dir:
+				return nil
+			case <-gctx.Done():
+				return gctx.Err()
+			case <-shutdown.Stopping(ctx):
+				// parse takes no more items; the archive is extracted for the next run
+				return nil
+			}
+		})
+	}
+	if err := g.Wait(); err != nil {
+		if failed.Len() == 0 || errors.Is(err, context.Canceled) {
+			return count, err
+		}
+		return count, failed.Err()
+	}
+	if err := ctx.Err(); err != nil {
+		return count, err
+	}
+	if shutdown.Interrupted(ctx) {
+		return count, shutdown.ErrInterrupted
+	}
+	return count, failed.Err()
+}
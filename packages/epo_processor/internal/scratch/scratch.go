@@ -0,0 +1,176 @@
+// Package scratch hands out temporary files below scratch.dir and bounds the space they
+// take. Each process works in its own run-<pid>-* directory, so the directories of
+// crashed runs can be told apart from those of running ones and removed at startup.
+package scratch
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"io"
+	"io/fs"
+	"os"
+	"path/filepath"
+	"strconv"
+	"strings"
+	"sync/atomic"
+	"syscall"
+
+	"go.uber.org/zap"
+	"golang.org/x/sync/semaphore"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+)
+
+const runPrefix = "run-"
+
+// Space is the scratch directory of one run. Files reserve their size against the quota
+// before they are written and release it when closed; a reservation that does not fit
+// waits for other files to be closed.
+type Space struct {
+	dir   string
+	max   int64
+	quota *semaphore.Weighted
+	used  atomic.Int64
+}
+
+// Open removes the run directories left by processes that are no longer alive and
+// creates the directory of this run below cfg.Dir (the system temp directory if empty).
+func Open(cfg config.Scratch, logger *zap.SugaredLogger) (*Space, error) {
+	root := cfg.Dir
+	if root == "" {
+		root = filepath.Join(os.TempDir(), "epo-processor")
+	}
+	if err := os.MkdirAll(root, 0o755); err != nil {
+		return nil, fmt.Errorf("create scratch directory: %w", err)
+	}
+	removeOrphans(root, logger)
+	dir, err := os.MkdirTemp(root, fmt.Sprintf("%s%d-", runPrefix, os.Getpid()))
+	if err != nil {
+		return nil, fmt.Errorf("create scratch directory: %w", err)
+	}
+	s := &Space{dir: dir, max: int64(cfg.MaxBytes)}
+	if cfg.MaxBytes > 0 {
+		s.quota = semaphore.NewWeighted(s.max)
+	}
+	return s, nil
+}
+
+// Spill copies size bytes from r into a new scratch file and returns it positioned at
+// the start. Closing the file removes it and frees its share of the quota.
+func (s *Space) Spill(ctx context.Context, r io.Reader, size int64) (*File, error) {
+	if s.quota != nil {
+		if size > s.max {
+			return nil, fmt.Errorf("%d bytes exceed scratch.max_bytes (%d)", size, s.max)
+		}
+		if err := s.quota.Acquire(ctx, size); err != nil {
+			return nil, err
+		}
+	}
+	s.used.Add(size)
+	f, err := os.CreateTemp(s.dir, "spill-*")
+	if err != nil {
+		s.release(size)
+		return nil, err
+	}
+	file := &File{File: f, space: s, size: size}
+	if _, err := io.Copy(f, io.LimitReader(r, size)); err != nil {
+		return nil, errors.Join(err, file.Close())
+	}
+	if _, err := f.Seek(0, io.SeekStart); err != nil {
+		return nil, errors.Join(err, file.Close())
+	}
+	return file, nil
+}
+
+// Dir returns the run directory, for files that are not counted against the quota
+// because their size is not known up front. They are removed with the directory.
+func (s *Space) Dir() string {
+	return s.dir
+}
+
+// Used returns the bytes currently reserved by open scratch files.
+func (s *Space) Used() int64 {
+	return s.used.Load()
+}
+
+// Close removes the run directory with any files still in it.
+func (s *Space) Close() error {
+	return os.RemoveAll(s.dir)
+}
+
+func (s *Space) release(size int64) {
+	s.used.Add(-size)
+	if s.quota != nil {
+		s.quota.Release(size)
+	}
+}
+
+// File is a scratch file; Close deletes it.
+type File struct {
+	*os.File
+	space *Space
+	size  int64
+}
+
+func (f *File) Close() error {
+	err := errors.Join(f.File.Close(), os.Remove(f.Name()))
+	f.space.release(f.size)
+	return err
+}
+
+// removeOrphans deletes the run directories below root whose process has exited.
+func removeOrphans(root string, logger *zap.SugaredLogger) {
+	entries, err := os.ReadDir(root)
+	if err != nil {
+		logger.Warnw("Failed to list scratch directory", "dir", root, "err", err)
+		return
+	}
+	for _, entry := range entries {
+		pid, ok := runPID(entry.Name())
+		if !entry.IsDir() || !ok || pid == os.Getpid() || alive(pid) {
+			continue
+		}
+		path := filepath.Join(root, entry.Name())
+		size := dirSize(path)
+		if err := os.RemoveAll(path); err != nil {
+			logger.Warnw("Failed to remove orphaned scratch files", "dir", path, "err", err)
+			continue
+		}
+		logger.Infow("Removed orphaned scratch files", "dir", path, "bytes", size)
+	}
+}
+
+// runPID parses the process id out of a run-<pid>-<random> directory name.
+func runPID(name string) (int, bool) {
+	rest, ok := strings.CutPrefix(name, runPrefix)
+	if !ok {
+		return 0, false
+	}
+	pid, _, _ := strings.Cut(rest, "-")
+	n, err := strconv.Atoi(pid)
+	return n, err == nil
+}
+
+// alive reports whether a process with pid exists; one owned by another user counts.
+func alive(pid int) bool {
+	p, err := os.FindProcess(pid)
+	if err != nil {
+		return false
+	}
+	err = p.Signal(syscall.Signal(0))
+	return err == nil || errors.Is(err, syscall.EPERM)
+}
+
+func dirSize(dir string) int64 {
+	var size int64
+	_ = filepath.WalkDir(dir, func(_ string, d fs.DirEntry, err error) error {
+		if err == nil && !d.IsDir() {
+			if info, err := d.Info(); err == nil {
+				size += info.Size()
+			}
+		}
+		return nil
+	})
+	return size
+}
@@ -0,0 +1,178 @@
+// Package search builds a full-text index over parse outputs and answers boolean
+// queries against it, for searching a corpus without a search server.
+package search
+
+import (
+	"errors"
+	"fmt"
+	"strings"
+
+	"github.com/blevesearch/bleve/v2"
+	"github.com/blevesearch/bleve/v2/analysis/analyzer/keyword"
+	"github.com/blevesearch/bleve/v2/analysis/analyzer/standard"
+	"github.com/blevesearch/bleve/v2/mapping"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/sink"
+)
+
+// Indexed fields. id and cpc hold lowercase keywords, title and abstract analyzed text.
+const (
+	FieldID       = "id"
+	FieldTitle    = "title"
+	FieldAbstract = "abstract"
+	FieldCPC      = "cpc"
+
+	// fieldDisplay stores the first title for the results, without indexing it.
+	fieldDisplay = "display"
+)
+
+// batchDocs is the number of documents Build writes to the index at once.
+const batchDocs = 1000
+
+// Doc is an indexed publication as search results show it.
+type Doc struct {
+	PatentID string
+	Title    string
+}
+
+// Index is a bleve index with one document per patent_id.
+type Index struct {
+	index bleve.Index
+}
+
+// BuildResult counts what Build indexed.
+type BuildResult struct {
+	Documents int64  `json:"documents"`
+	Total     uint64 `json:"total"`
+}
+
+// document is what is indexed of a record.
+type document struct {
+	ID       string   `json:"id"`
+	Title    []string `json:"title"`
+	Abstract []string `json:"abstract"`
+	CPC      []string `json:"cpc"`
+	Display  string   `json:"display"`
+}
+
+// Build indexes the patent_id, titles, abstracts and CPC symbols of the records in the
+// output files ins into the index directory dir, creating it if needed. Documents are
+// keyed by patent_id, so running it again with new outputs extends the index and a
+// record indexed again, e.g. a corrected publication, replaces the earlier one. Titles
+// and abstracts are only present in outputs parsed with title and abstract in
+// parse.fields.
+func Build(dir string, ins []string) (res BuildResult, err error) {
+	index, err := bleve.Open(dir)
+	if errors.Is(err, bleve.ErrorIndexPathDoesNotExist) {
+		index, err = bleve.New(dir, indexMapping())
+	}
+	if err != nil {
+		return res, fmt.Errorf("failed to open search index: %w", err)
+	}
+	defer func() { err = errors.Join(err, index.Close()) }()
+	batch := index.NewBatch()
+	for _, in := range ins {
+		err := sink.ReadRecords(in, func(records []models.PatentRecord) error {
+			for _, r := range records {
+				if err := batch.Index(r.PatentID, newDocument(r)); err != nil {
+					return err
+				}
+				res.Documents++
+				if batch.Size() >= batchDocs {
+					if err := index.Batch(batch); err != nil {
+						return fmt.Errorf("write search index: %w", err)
+					}
+					batch.Reset()
+				}
+			}
+			return nil
+		})
+		if err != nil {
+			return res, fmt.Errorf("index %s: %w", in, err)
+		}
+	}
+	if err := index.Batch(batch); err != nil {
+		return res, fmt.Errorf("write search index: %w", err)
+	}
+	res.Total, err = index.DocCount()
+	return res, err
+}
+
+// indexMapping maps the fields of document: keywords for id and cpc, the standard
+// analyzer for title and abstract, and the stored display title.
+func indexMapping() mapping.IndexMapping {
+	keywords := bleve.NewTextFieldMapping()
+	keywords.Analyzer = keyword.Name
+	keywords.IncludeInAll = false
+	keywords.Store = false
+	text := bleve.NewTextFieldMapping()
+	text.Analyzer = standard.Name
+	text.IncludeInAll = false
+	text.Store = false
+	display := bleve.NewTextFieldMapping()
+	display.Index = false
+	display.IncludeInAll = false
+
+	doc := bleve.NewDocumentStaticMapping()
+	doc.AddFieldMappingsAt(FieldID, keywords)
+	doc.AddFieldMappingsAt(FieldCPC, keywords)
+	doc.AddFieldMappingsAt(FieldTitle, text)
+	doc.AddFieldMappingsAt(FieldAbstract, text)
+	doc.AddFieldMappingsAt(fieldDisplay, display)
+	m := bleve.NewIndexMapping()
+	m.DefaultMapping = doc
+	m.DefaultAnalyzer = standard.Name
+	return m
+}
+
+func newDocument(r models.PatentRecord) document {
+	d := document{ID: strings.ToLower(r.PatentID)}
+	for _, t := range r.Titles {
+		d.Title = append(d.Title, t.Text)
+	}
+	if len(r.Titles) > 0 {
+		d.Display = r.Titles[0].Text
+	}
+	for _, a := range r.Abstracts {
+		d.Abstract = append(d.Abstract, a.Text)
+	}
+	for _, symbol := range r.CPCList {
+		d.CPC = append(d.CPC, cpcPrefixes(symbol)...)
+	}
+	return d
+}
+
+// Open opens the index in dir for searching.
+func Open(dir string) (*Index, error) {
+	index, err := bleve.OpenUsing(dir, map[string]any{"read_only": true})
+	if err != nil {
+		return nil, fmt.Errorf("open search index: %w", err)
+	}
+	return &Index{index: index}, nil
+}
+
+// Close closes the index.
+func (idx *Index) Close() error {
+	return idx.index.Close()
+}
+
+// cpcPrefixes returns the compact, lowercase CPC symbol with its section, class,
+// subclass and main group, so that a query for any of them finds the symbol:
+// h01m10/0525 gives h, h01, h01m, h01m10 and h01m10/0525.
+func cpcPrefixes(symbol string) []string {
+	symbol = strings.ToLower(strings.ReplaceAll(symbol, " ", ""))
+	if symbol == "" {
+		return nil
+	}
+	var prefixes []string
+	for _, n := range []int{1, 3, 4} {
+		if len(symbol) > n {
+			prefixes = append(prefixes, symbol[:n])
+		}
+	}
+	if group, _, ok := strings.Cut(symbol, "/"); ok && len(group) > 4 {
+		prefixes = append(prefixes, group)
+	}
+	return append(prefixes, symbol)
+}
@@ -0,0 +1,227 @@
+package search
+
+import (
+	"fmt"
+	"regexp"
+	"slices"
+	"strings"
+
+	"github.com/blevesearch/bleve/v2"
+	bq "github.com/blevesearch/bleve/v2/search/query"
+)
+
+// cpcPattern matches query words that read as a CPC section, class, subclass, group or
+// symbol (Y02E, H01M10/0525), which an unqualified term also looks up as a CPC prefix.
+var cpcPattern = regexp.MustCompile(`^[a-hy](\d\d([a-z](\d+(/\d+)?)?)?)?$`)
+
+// Hit is a document matching a query, with its relevance score.
+type Hit struct {
+	Doc
+	Score float64
+}
+
+// Search returns up to limit documents matching query, or all of them with limit 0, the
+// best scored first. A query combines terms with AND, OR and NOT and parentheses; terms
+// without an operator between them are ANDed. A term is a word, or field:word for the
+// fields id, title, abstract and cpc; a bare word is looked up in all of them. Scores are
+// the tf-idf of the matched terms.
+func (idx *Index) Search(query string, limit int) ([]Hit, error) {
+	q, err := parseQuery(query)
+	if err != nil {
+		return nil, err
+	}
+	if limit <= 0 {
+		count, err := idx.index.DocCount()
+		if err != nil {
+			return nil, err
+		}
+		limit = int(count)
+	}
+	req := bleve.NewSearchRequestOptions(q, limit, 0, false)
+	req.Fields = []string{fieldDisplay}
+	req.SortBy([]string{"-_score", "_id"})
+	res, err := idx.index.Search(req)
+	if err != nil {
+		return nil, err
+	}
+	hits := make([]Hit, len(res.Hits))
+	for i, h := range res.Hits {
+		title, _ := h.Fields[fieldDisplay].(string)
+		hits[i] = Hit{Doc: Doc{PatentID: h.ID, Title: title}, Score: h.Score}
+	}
+	return hits, nil
+}
+
+// parseQuery translates a query into a bleve query.
+func parseQuery(query string) (bq.Query, error) {
+	p := &queryParser{tokens: lexQuery(query)}
+	if len(p.tokens) == 0 {
+		return nil, fmt.Errorf("empty query")
+	}
+	q, err := p.or()
+	if err != nil {
+		return nil, err
+	}
+	if p.pos < len(p.tokens) {
+		return nil, fmt.Errorf("unexpected %q in query", p.tokens[p.pos])
+	}
+	return q, nil
+}
+
+// lexQuery splits a query into parentheses and whitespace-separated words.
+func lexQuery(query string) []string {
+	var tokens []string
+	for _, word := range strings.Fields(query) {
+		for word != "" {
+			i := strings.IndexAny(word, "()")
+			switch {
+			case i < 0:
+				tokens, word = append(tokens, word), ""
+			case i > 0:
+				tokens, word = append(tokens, word[:i]), word[i:]
+			default:
+				tokens, word = append(tokens, word[:1]), word[1:]
+			}
+		}
+	}
+	return tokens
+}
+
+// queryParser translates the query while parsing it, by recursive descent:
+//
+//	or   = and { "OR" and }
+//	and  = not { ["AND"] not }
+//	not  = "NOT" not | "(" or ")" | term
+//
+// A NOT is a set difference within its AND: the negated parts become the must-not
+// clauses of a boolean query whose must clauses are the others, or all documents when an
+// AND has only negated parts.
+type queryParser struct {
+	tokens []string
+	pos    int
+}
+
+func (p *queryParser) peek() string {
+	if p.pos < len(p.tokens) {
+		return p.tokens[p.pos]
+	}
+	return ""
+}
+
+func (p *queryParser) or() (bq.Query, error) {
+	left, err := p.and()
+	if err != nil {
+		return nil, err
+	}
+	if p.peek() != "OR" {
+		return left, nil
+	}
+	either := bleve.NewDisjunctionQuery(left)
+	for p.peek() == "OR" {
+		p.pos++
+		right, err := p.and()
+		if err != nil {
+			return nil, err
+		}
+		either.AddQuery(right)
+	}
+	return either, nil
+}
+
+func (p *queryParser) and() (bq.Query, error) {
+	var must, mustNot []bq.Query
+	for {
+		q, negated, err := p.not()
+		if err != nil {
+			return nil, err
+		}
+		if negated {
+			mustNot = append(mustNot, q)
+		} else {
+			must = append(must, q)
+		}
+		next := p.peek()
+		if next == "" || next == "OR" || next == ")" {
+			break
+		}
+		if next == "AND" {
+			p.pos++
+		}
+	}
+	if len(mustNot) == 0 && len(must) == 1 {
+		return must[0], nil
+	}
+	b := bleve.NewBooleanQuery()
+	if len(must) == 0 {
+		must = []bq.Query{bleve.NewMatchAllQuery()}
+	}
+	b.AddMust(must...)
+	if len(mustNot) > 0 {
+		b.AddMustNot(mustNot...)
+	}
+	return b, nil
+}
+
+// not returns the next operand and whether it is negated.
+func (p *queryParser) not() (bq.Query, bool, error) {
+	switch token := p.peek(); token {
+	case "":
+		return nil, false, fmt.Errorf("query ends where a term is expected")
+	case "NOT":
+		p.pos++
+		q, negated, err := p.not()
+		return q, !negated, err
+	case "(":
+		p.pos++
+		q, err := p.or()
+		if err != nil {
+			return nil, false, err
+		}
+		if p.peek() != ")" {
+			return nil, false, fmt.Errorf("missing ) in query")
+		}
+		p.pos++
+		return q, false, nil
+	case ")", "AND", "OR":
+		return nil, false, fmt.Errorf("unexpected %q in query", token)
+	default:
+		p.pos++
+		return term(token), false, nil
+	}
+}
+
+// term looks up a query word. Words that analyze into several (lithium-ion) must all
+// match, in the same field.
+func term(word string) bq.Query {
+	fields := []string{FieldID, FieldTitle, FieldAbstract, FieldCPC}
+	if field, value, ok := strings.Cut(word, ":"); ok && slices.Contains(fields, strings.ToLower(field)) {
+		fields, word = []string{strings.ToLower(field)}, value
+	}
+	word = strings.ToLower(word)
+	var queries []bq.Query
+	for _, field := range fields {
+		switch field {
+		case FieldID:
+			q := bleve.NewTermQuery(word)
+			q.SetField(field)
+			queries = append(queries, q)
+		case FieldCPC:
+			symbol := strings.ReplaceAll(word, " ", "")
+			if len(fields) > 1 && !cpcPattern.MatchString(symbol) {
+				continue
+			}
+			q := bleve.NewTermQuery(symbol)
+			q.SetField(field)
+			queries = append(queries, q)
+		default:
+			q := bleve.NewMatchQuery(word)
+			q.SetField(field)
+			q.SetOperator(bq.MatchQueryOperatorAnd)
+			queries = append(queries, q)
+		}
+	}
+	if len(queries) == 1 {
+		return queries[0]
+	}
+	return bleve.NewDisjunctionQuery(queries...)
+}
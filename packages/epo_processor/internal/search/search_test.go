@@ -0,0 +1,164 @@
+package search
+
+import (
+	"encoding/json"
+	"os"
+	"path/filepath"
+	"slices"
+	"testing"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+func record(id, title, abstract string, cpc ...string) models.PatentRecord {
+	r := models.PatentRecord{PatentID: id, CPCList: cpc}
+	if title != "" {
+		r.Titles = []models.LocalizedText{{Lang: "en", Text: title}}
+	}
+	if abstract != "" {
+		r.Abstracts = []models.LocalizedText{{Lang: "en", Text: abstract}}
+	}
+	return r
+}
+
+// writeOutput writes records as a jsonl parse output and returns its path.
+func writeOutput(t *testing.T, name string, records ...models.PatentRecord) string {
+	t.Helper()
+	path := filepath.Join(t.TempDir(), name)
+	f, err := os.Create(path)
+	if err != nil {
+		t.Fatal(err)
+	}
+	enc := json.NewEncoder(f)
+	for _, r := range records {
+		if err := enc.Encode(r); err != nil {
+			t.Fatal(err)
+		}
+	}
+	if err := f.Close(); err != nil {
+		t.Fatal(err)
+	}
+	return path
+}
+
+func buildIndex(t *testing.T, ins ...string) string {
+	t.Helper()
+	dir := filepath.Join(t.TempDir(), "patents.search")
+	if _, err := Build(dir, ins); err != nil {
+		t.Fatalf("Build: %v", err)
+	}
+	return dir
+}
+
+func searchIDs(t *testing.T, dir, query string) []string {
+	t.Helper()
+	idx, err := Open(dir)
+	if err != nil {
+		t.Fatalf("Open: %v", err)
+	}
+	defer idx.Close()
+	hits, err := idx.Search(query, 0)
+	if err != nil {
+		t.Fatalf("Search(%q): %v", query, err)
+	}
+	ids := make([]string, len(hits))
+	for i, h := range hits {
+		ids[i] = h.PatentID
+	}
+	slices.Sort(ids)
+	return ids
+}
+
+func TestSearch(t *testing.T) {
+	out := writeOutput(t, "out.jsonl",
+		record("EP1A1", "Lithium-ion battery", "An anode for batteries", "H01M 10/0525", "Y02E 60/10"),
+		record("EP2A1", "Lead acid battery", "A grid for lead batteries", "H01M 10/06"),
+		record("EP3A1", "Solar cell", "A lithium coated panel", "H01L 31/04", "Y02E 10/50"),
+		record("EP4A1", "Wind turbine", "", "F03D 1/00"),
+	)
+	dir := buildIndex(t, out)
+	tests := []struct {
+		query string
+		want  []string
+	}{
+		{"battery", []string{"EP1A1", "EP2A1"}},
+		{"battery AND Y02E", []string{"EP1A1"}},
+		{"battery Y02E", []string{"EP1A1"}},
+		{"battery OR turbine", []string{"EP1A1", "EP2A1", "EP4A1"}},
+		{"battery NOT title:lead", []string{"EP1A1"}},
+		{"NOT battery", []string{"EP3A1", "EP4A1"}},
+		{"NOT NOT battery", []string{"EP1A1", "EP2A1"}},
+		{"(battery OR solar) AND NOT lead", []string{"EP1A1", "EP3A1"}},
+		{"title:lithium", []string{"EP1A1"}},
+		{"lithium", []string{"EP1A1", "EP3A1"}},
+		{"lithium-ion", []string{"EP1A1"}},
+		{"cpc:H01M", []string{"EP1A1", "EP2A1"}},
+		{"H01M10/06", []string{"EP2A1"}},
+		{"Y02E", []string{"EP1A1", "EP3A1"}},
+		{"id:ep3a1", []string{"EP3A1"}},
+		{"EP4A1", []string{"EP4A1"}},
+		{"nothing", []string{}},
+	}
+	for _, tt := range tests {
+		t.Run(tt.query, func(t *testing.T) {
+			if got := searchIDs(t, dir, tt.query); !slices.Equal(got, tt.want) {
+				t.Errorf("Search(%q) = %v, want %v", tt.query, got, tt.want)
+			}
+		})
+	}
+}
+
+func TestSearchLimitAndTitles(t *testing.T) {
+	dir := buildIndex(t, writeOutput(t, "out.jsonl",
+		record("EP1A1", "Battery", ""),
+		record("EP2A1", "Battery battery battery", "battery"),
+	))
+	idx, err := Open(dir)
+	if err != nil {
+		t.Fatal(err)
+	}
+	defer idx.Close()
+	hits, err := idx.Search("battery", 1)
+	if err != nil {
+		t.Fatal(err)
+	}
+	if len(hits) != 1 || hits[0].PatentID != "EP2A1" || hits[0].Title != "Battery battery battery" {
+		t.Errorf("hits = %+v, want EP2A1 with its title first", hits)
+	}
+}
+
+func TestBuildReplacesReindexedRecords(t *testing.T) {
+	dir := filepath.Join(t.TempDir(), "patents.search")
+	first := writeOutput(t, "first.jsonl", record("EP1A1", "Batery", ""), record("EP2A1", "Solar cell", ""))
+	corrected := writeOutput(t, "corrected.jsonl", record("EP1A1", "Battery", ""))
+	for _, in := range []string{first, corrected, corrected} {
+		if _, err := Build(dir, []string{in}); err != nil {
+			t.Fatalf("Build(%s): %v", in, err)
+		}
+	}
+	if got := searchIDs(t, dir, "batery"); len(got) != 0 {
+		t.Errorf("stale title still found in %v", got)
+	}
+	if got := searchIDs(t, dir, "battery"); !slices.Equal(got, []string{"EP1A1"}) {
+		t.Errorf("corrected title found in %v, want [EP1A1]", got)
+	}
+	if got := searchIDs(t, dir, "NOT nothing"); !slices.Equal(got, []string{"EP1A1", "EP2A1"}) {
+		t.Errorf("documents = %v, want each patent_id once", got)
+	}
+}
+
+func TestParseQueryErrors(t *testing.T) {
+	for _, query := range []string{"", "  ", "battery AND", "(battery", "battery)", "OR battery", "NOT"} {
+		if _, err := parseQuery(query); err == nil {
+			t.Errorf("parseQuery(%q) succeeded, want an error", query)
+		}
+	}
+}
+
+func TestCPCPrefixes(t *testing.T) {
+	got := cpcPrefixes("H01M 10/0525")
+	want := []string{"h", "h01", "h01m", "h01m10", "h01m10/0525"}
+	if !slices.Equal(got, want) {
+		t.Errorf("cpcPrefixes = %v, want %v", got, want)
+	}
+}
@@ -0,0 +1,209 @@
+package server
+
+import (
+	"context"
+	"crypto/subtle"
+	"encoding/json"
+	"fmt"
+	"os"
+	"strings"
+	"sync"
+	"time"
+
+	"google.golang.org/grpc"
+	"google.golang.org/grpc/codes"
+	"google.golang.org/grpc/metadata"
+	"google.golang.org/grpc/peer"
+	"google.golang.org/grpc/status"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+)
+
+// apiKeyHeader carries the client key; "authorization: Bearer <key>" is accepted as well.
+const apiKeyHeader = "x-api-key"
+
+// auditSubject is implemented by requests that name the record or job they access, so the
+// access log can show who looked up what.
+type auditSubject interface {
+	subject() string
+}
+
+func (r *LookupRequest) subject() string { return r.PatentID }
+func (r *JobRequest) subject() string    { return r.JobID }
+
+// accessControl authenticates calls against serve.api_keys and appends JSON lines for
+// every call to serve.access_log. With no keys configured every call is allowed as
+// "anonymous"; ServeGRPC only allows that on a loopback address or with
+// serve.allow_anonymous.
+type accessControl struct {
+	keys map[string]string // key -> client name
+	mu   sync.Mutex
+	log  *os.File
+	enc  *json.Encoder
+}
+
+// accessEntry is a line of the access log. A call is logged with the event "call" before
+// it is served and "done" with its status code afterwards; a rejected call only gets the
+// "done" line.
+type accessEntry struct {
+	Time    time.Time `json:"time"`
+	Event   string    `json:"event"`
+	Client  string    `json:"client"`
+	Peer    string    `json:"peer,omitempty"`
+	Method  string    `json:"method"`
+	Subject string    `json:"subject,omitempty"`
+	Code    string    `json:"code,omitempty"`
+}
+
+const (
+	accessCall = "call"
+	accessDone = "done"
+)
+
+func newAccessControl(cfg config.Serve) (*accessControl, error) {
+	ac := &accessControl{keys: make(map[string]string, len(cfg.APIKeys))}
+	for _, k := range cfg.APIKeys {
+		ac.keys[k.Key] = k.Name
+	}
+	if cfg.AccessLog != "" {
+		f, err := os.OpenFile(cfg.AccessLog, os.O_WRONLY|os.O_CREATE|os.O_APPEND, 0o600)
+		if err != nil {
+			return nil, fmt.Errorf("open access log: %w", err)
+		}
+		ac.log = f
+		ac.enc = json.NewEncoder(f)
+	}
+	return ac, nil
+}
+
+func (ac *accessControl) Close() error {
+	if ac.log == nil {
+		return nil
+	}
+	return ac.log.Close()
+}
+
+// authenticate returns the client name for the key presented in ctx.
+func (ac *accessControl) authenticate(ctx context.Context) (string, error) {
+	if len(ac.keys) == 0 {
+		return "anonymous", nil
+	}
+	md, _ := metadata.FromIncomingContext(ctx)
+	presented := firstValue(md, apiKeyHeader)
+	if presented == "" {
+		presented = strings.TrimPrefix(firstValue(md, "authorization"), "Bearer ")
+	}
+	if presented == "" {
+		return "", status.Error(codes.Unauthenticated, "missing API key")
+	}
+	for key, name := range ac.keys {
+		if subtle.ConstantTimeCompare([]byte(key), []byte(presented)) == 1 {
+			return name, nil
+		}
+	}
+	return "", status.Error(codes.Unauthenticated, "invalid API key")
+}
+
+func firstValue(md metadata.MD, key string) string {
+	if v := md.Get(key); len(v) > 0 {
+		return v[0]
+	}
+	return ""
+}
+
+// record appends an access log entry for event; err is the outcome of a done call.
+// Write failures are reported to the caller, and a call whose "call" entry could not be
+// written is not served, so that no call is served without leaving a trace.
+func (ac *accessControl) record(ctx context.Context, event, client, method string, req any, err error) error {
+	if ac.enc == nil {
+		return nil
+	}
+	entry := accessEntry{
+		Time:   time.Now().UTC(),
+		Event:  event,
+		Client: client,
+		Method: method,
+	}
+	if event == accessDone {
+		entry.Code = status.Code(err).String()
+	}
+	if p, ok := peer.FromContext(ctx); ok {
+		entry.Peer = p.Addr.String()
+	}
+	if s, ok := req.(auditSubject); ok {
+		entry.Subject = s.subject()
+	}
+	ac.mu.Lock()
+	defer ac.mu.Unlock()
+	if werr := ac.enc.Encode(entry); werr != nil {
+		return status.Errorf(codes.Internal, "write access log: %v", werr)
+	}
+	return nil
+}
+
+func (ac *accessControl) unaryInterceptor(
+	ctx context.Context,
+	req any,
+	info *grpc.UnaryServerInfo,
+	handler grpc.UnaryHandler,
+) (any, error) {
+	client, err := ac.authenticate(ctx)
+	if err != nil {
+		return nil, preferLogError(err, ac.record(ctx, accessDone, "unknown", info.FullMethod, req, err))
+	}
+	if err := ac.record(ctx, accessCall, client, info.FullMethod, req, nil); err != nil {
+		return nil, err
+	}
+	resp, err := handler(ctx, req)
+	if logErr := ac.record(ctx, accessDone, client, info.FullMethod, req, err); logErr != nil {
+		return nil, logErr
+	}
+	return resp, err
+}
+
+func (ac *accessControl) streamInterceptor(
+	srv any,
+	ss grpc.ServerStream,
+	info *grpc.StreamServerInfo,
+	handler grpc.StreamHandler,
+) error {
+	ctx := ss.Context()
+	client, err := ac.authenticate(ctx)
+	if err != nil {
+		return preferLogError(err, ac.record(ctx, accessDone, "unknown", info.FullMethod, nil, err))
+	}
+	// the request is only received by the handler, so the call line has no subject
+	if err := ac.record(ctx, accessCall, client, info.FullMethod, nil, nil); err != nil {
+		return err
+	}
+	wrapped := &auditedStream{ServerStream: ss}
+	err = handler(srv, wrapped)
+	if logErr := ac.record(ctx, accessDone, client, info.FullMethod, wrapped.first, err); logErr != nil {
+		return logErr
+	}
+	return err
+}
+
+// preferLogError reports a failed access log write over the call error, since serving
+// without an audit trail is the more severe problem.
+func preferLogError(err, logErr error) error {
+	if logErr != nil {
+		return logErr
+	}
+	return err
+}
+
+// auditedStream keeps the first received message so stream calls can be logged with
+// their subject.
+type auditedStream struct {
+	grpc.ServerStream
+	first any
+}
+
+func (s *auditedStream) RecvMsg(m any) error {
+	err := s.ServerStream.RecvMsg(m)
+	if err == nil && s.first == nil {
+		s.first = m
+	}
+	return err
+}
@@ -0,0 +1,329 @@
+package server
+
+import (
+	"context"
+	"crypto/tls"
+	"errors"
+	"fmt"
+	"net"
+
+	"go.uber.org/zap"
+	"google.golang.org/grpc"
+	"google.golang.org/grpc/codes"
+	"google.golang.org/grpc/credentials"
+	"google.golang.org/grpc/status"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/parse"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/pipeline"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/snapshot"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/state"
+)
+
+const serviceName = "epo_processor.v1.Pipeline"
+
+type StartRunRequest struct {
+	// Stages to run; empty means the stages enabled in the config.
+	Stages    []pipeline.Stage `json:"stages,omitempty"`
+	ProductID int              `json:"product_id,omitempty"`
+}
+
+type StartRunResponse struct {
+	JobID string `json:"job_id"`
+}
+
+type JobRequest struct {
+	JobID string `json:"job_id"`
+}
+
+type JobStatus struct {
+	JobID   string           `json:"job_id"`
+	State   string           `json:"state"`
+	Error   string           `json:"error,omitempty"`
+	Summary pipeline.Summary `json:"summary"`
+}
+
+type LookupRequest struct {
+	PatentID string `json:"patent_id"`
+}
+
+type LookupResponse struct {
+	PatentID string `json:"patent_id"`
+	XML      string `json:"xml"`
+}
+
+// PipelineServer is the gRPC surface used by external orchestrators. The messages are the
+// JSON encodings of the Go structs above (see jsonCodec), not protobuf: the service is
+// JSON over gRPC, and protoc-generated clients cannot call it. One run executes at a time;
+// StartRun fails with FAILED_PRECONDITION while another is running.
+type PipelineServer interface {
+	StartRun(ctx context.Context, req *StartRunRequest) (*StartRunResponse, error)
+	GetSummary(ctx context.Context, req *JobRequest) (*JobStatus, error)
+	CancelJob(ctx context.Context, req *JobRequest) (*JobStatus, error)
+	StreamProgress(req *JobRequest, stream grpc.ServerStream) error
+	LookupRecord(ctx context.Context, req *LookupRequest) (*LookupResponse, error)
+}
+
+// ServicesFactory builds the stage services for the config of a single run.
+type ServicesFactory func(cfg config.Config) (*internal.Services, error)
+
+type pipelineServer struct {
+	cfg         config.Config
+	newServices ServicesFactory
+	jobs        *jobRegistry
+	logger      *zap.SugaredLogger
+}
+
+func NewPipelineServer(
+	cfg config.Config,
+	newServices ServicesFactory,
+	logger *zap.SugaredLogger,
+) PipelineServer {
+	return &pipelineServer{
+		cfg:         cfg,
+		newServices: newServices,
+		jobs:        newJobRegistry(),
+		logger:      logger,
+	}
+}
+
+func (s *pipelineServer) StartRun(
+	_ context.Context,
+	req *StartRunRequest,
+) (*StartRunResponse, error) {
+	runCfg := s.cfg
+	if req.ProductID != 0 {
+		runCfg.Server.ProductID = req.ProductID
+	}
+	if len(req.Stages) > 0 {
+		runCfg.Download.Enabled, runCfg.Extract.Enabled, runCfg.Parse.Enabled = false, false, false
+		for _, st := range req.Stages {
+			switch st {
+			case pipeline.StageDownload:
+				runCfg.Download.Enabled = true
+			case pipeline.StageExtract:
+				runCfg.Extract.Enabled = true
+			case pipeline.StageParse:
+				runCfg.Parse.Enabled = true
+			default:
+				return nil, status.Errorf(codes.InvalidArgument, "unknown stage %q", st)
+			}
+		}
+	}
+	ctx, cancel := context.WithCancel(context.Background())
+	j, running := s.jobs.add(cancel)
+	if running != nil {
+		cancel()
+		return nil, status.Errorf(codes.FailedPrecondition, "job %s is still running", running.id)
+	}
+	services, err := s.newServices(runCfg)
+	if err != nil {
+		s.jobs.remove(j.id)
+		cancel()
+		return nil, status.Errorf(codes.Internal, "init services: %v", err)
+	}
+	s.logger.Infow("Starting pipeline run", "job_id", j.id, "product_id", runCfg.Server.ProductID)
+	go func() {
+		defer cancel()
+		defer func() {
+			if err := services.Close(); err != nil {
+				s.logger.Warnw("Failed to close services", "job_id", j.id, "err", err)
+			}
+		}()
+		summary, err := pipeline.Run(ctx, runCfg, services, j.publish)
+		state := JobSucceeded
+		switch {
+		case errors.Is(err, context.Canceled):
+			state = JobCancelled
+		case err != nil:
+			state = JobFailed
+		}
+		s.logger.Infow("Pipeline run finished", "job_id", j.id, "state", state, "err", err)
+		j.finish(state, summary, err)
+	}()
+	return &StartRunResponse{JobID: j.id}, nil
+}
+
+func (s *pipelineServer) GetSummary(_ context.Context, req *JobRequest) (*JobStatus, error) {
+	j, ok := s.jobs.get(req.JobID)
+	if !ok {
+		return nil, status.Errorf(codes.NotFound, "unknown job %q", req.JobID)
+	}
+	st := j.status()
+	return &st, nil
+}
+
+func (s *pipelineServer) CancelJob(_ context.Context, req *JobRequest) (*JobStatus, error) {
+	j, ok := s.jobs.get(req.JobID)
+	if !ok {
+		return nil, status.Errorf(codes.NotFound, "unknown job %q", req.JobID)
+	}
+	j.cancel()
+	st := j.status()
+	return &st, nil
+}
+
+func (s *pipelineServer) StreamProgress(req *JobRequest, stream grpc.ServerStream) error {
+	j, ok := s.jobs.get(req.JobID)
+	if !ok {
+		return status.Errorf(codes.NotFound, "unknown job %q", req.JobID)
+	}
+	next := 0
+	for {
+		events, changed, finished := j.since(next)
+		for i := range events {
+			if err := stream.SendMsg(&events[i]); err != nil {
+				return err
+			}
+		}
+		next += len(events)
+		if finished {
+			return nil
+		}
+		select {
+		case <-changed:
+		case <-stream.Context().Done():
+			return stream.Context().Err()
+		}
+	}
+}
+
+// LookupRecord returns the original exchange-document XML of a record via the parse index.
+func (s *pipelineServer) LookupRecord(_ context.Context, req *LookupRequest) (*LookupResponse, error) {
+	if s.cfg.Parse.IndexPath == "" {
+		return nil, status.Error(codes.FailedPrecondition, "no record index configured")
+	}
+	if req.PatentID == "" {
+		return nil, status.Error(codes.InvalidArgument, "patent_id is required")
+	}
+	var manifest *state.Manifest
+	if s.cfg.State.Enabled {
+		if manifest, _ = state.Open(s.cfg.State.Path); manifest != nil {
+			defer manifest.Close()
+		}
+	}
+	indexPath := snapshot.Expand(s.cfg.Parse.IndexPath, snapshot.Resolve(s.cfg.Snapshot, manifest))
+	raw, err := parse.LookupRecord(indexPath, req.PatentID)
+	if err != nil {
+		return nil, status.Errorf(codes.NotFound, "lookup %s: %v", req.PatentID, err)
+	}
+	return &LookupResponse{PatentID: req.PatentID, XML: string(raw)}, nil
+}
+
+func unaryHandler[Req, Resp any](
+	method string,
+	call func(PipelineServer, context.Context, *Req) (*Resp, error),
+) func(any, context.Context, func(any) error, grpc.UnaryServerInterceptor) (any, error) {
+	return func(
+		srv any,
+		ctx context.Context,
+		dec func(any) error,
+		interceptor grpc.UnaryServerInterceptor,
+	) (any, error) {
+		in := new(Req)
+		if err := dec(in); err != nil {
+			return nil, err
+		}
+		if interceptor == nil {
+			return call(srv.(PipelineServer), ctx, in)
+		}
+		info := &grpc.UnaryServerInfo{Server: srv, FullMethod: "/" + serviceName + "/" + method}
+		handler := func(ctx context.Context, req any) (any, error) {
+			return call(srv.(PipelineServer), ctx, req.(*Req))
+		}
+		return interceptor(ctx, in, info, handler)
+	}
+}
+
+func streamProgressHandler(srv any, stream grpc.ServerStream) error {
+	in := new(JobRequest)
+	if err := stream.RecvMsg(in); err != nil {
+		return err
+	}
+	return srv.(PipelineServer).StreamProgress(in, stream)
+}
+
+var serviceDesc = grpc.ServiceDesc{
+	ServiceName: serviceName,
+	HandlerType: (*PipelineServer)(nil),
+	Methods: []grpc.MethodDesc{
+		{
+			MethodName: "StartRun",
+			Handler:    unaryHandler("StartRun", PipelineServer.StartRun),
+		},
+		{
+			MethodName: "GetSummary",
+			Handler:    unaryHandler("GetSummary", PipelineServer.GetSummary),
+		},
+		{
+			MethodName: "CancelJob",
+			Handler:    unaryHandler("CancelJob", PipelineServer.CancelJob),
+		},
+		{
+			MethodName: "LookupRecord",
+			Handler:    unaryHandler("LookupRecord", PipelineServer.LookupRecord),
+		},
+	},
+	Streams: []grpc.StreamDesc{
+		{
+			StreamName:    "StreamProgress",
+			Handler:       streamProgressHandler,
+			ServerStreams: true,
+		},
+	},
+}
+
+// ServeGRPC serves srv on cfg.GRPCAddress until ctx is cancelled, then stops gracefully.
+// Calls are authenticated against cfg.APIKeys and recorded in cfg.AccessLog when set.
+// Without API keys it refuses to listen on anything but a loopback address unless
+// cfg.AllowAnonymous is set. With cfg.TLS the connections use TLS.
+func ServeGRPC(ctx context.Context, cfg config.Serve, srv PipelineServer) error {
+	if len(cfg.APIKeys) == 0 && !cfg.AllowAnonymous && !IsLoopback(cfg.GRPCAddress) {
+		return fmt.Errorf("refusing to serve %s without serve.api_keys; "+
+			"configure keys, bind a loopback address or set serve.allow_anonymous", cfg.GRPCAddress)
+	}
+	opts := []grpc.ServerOption{grpc.ForceServerCodec(jsonCodec{})}
+	if cfg.TLS.CertFile != "" {
+		cert, err := tls.LoadX509KeyPair(cfg.TLS.CertFile, cfg.TLS.KeyFile)
+		if err != nil {
+			return fmt.Errorf("load serve.tls certificate: %w", err)
+		}
+		opts = append(opts, grpc.Creds(credentials.NewTLS(&tls.Config{
+			Certificates: []tls.Certificate{cert},
+			MinVersion:   tls.VersionTLS12,
+		})))
+	}
+	access, err := newAccessControl(cfg)
+	if err != nil {
+		return err
+	}
+	defer access.Close()
+	lis, err := net.Listen("tcp", cfg.GRPCAddress)
+	if err != nil {
+		return fmt.Errorf("listen on %s: %w", cfg.GRPCAddress, err)
+	}
+	g := grpc.NewServer(append(opts,
+		grpc.UnaryInterceptor(access.unaryInterceptor),
+		grpc.StreamInterceptor(access.streamInterceptor),
+	)...)
+	g.RegisterService(&serviceDesc, srv)
+	go func() {
+		<-ctx.Done()
+		g.GracefulStop()
+	}()
+	return g.Serve(lis)
+}
+
+// IsLoopback reports whether the listen address addr only accepts local connections.
+func IsLoopback(addr string) bool {
+	host, _, err := net.SplitHostPort(addr)
+	if err != nil {
+		return false
+	}
+	if host == "localhost" {
+		return true
+	}
+	ip := net.ParseIP(host)
+	return ip != nil && ip.IsLoopback()
+}
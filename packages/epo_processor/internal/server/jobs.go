@@ -0,0 +1,157 @@
+package server
+
+import (
+	"context"
+	"fmt"
+	"slices"
+	"sync"
+	"sync/atomic"
+	"time"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/pipeline"
+)
+
+const (
+	JobRunning   = "running"
+	JobSucceeded = "succeeded"
+	JobFailed    = "failed"
+	JobCancelled = "cancelled"
+)
+
+// Finished jobs are kept for finishedJobTTL, and at most maxFinishedJobs of them, so that
+// a long-running server does not accumulate every run it ever started.
+const (
+	finishedJobTTL  = time.Hour
+	maxFinishedJobs = 100
+)
+
+// job tracks one pipeline run started over the API. Events are kept for the lifetime of the
+// job so late subscribers can replay them, until the registry evicts the finished job.
+type job struct {
+	id      string
+	cancel  context.CancelFunc
+	mu      sync.Mutex
+	events  []pipeline.Event
+	changed chan struct{}
+	state   string
+	summary pipeline.Summary
+	err     error
+	ended   time.Time
+}
+
+func newJob(id string, cancel context.CancelFunc) *job {
+	return &job{id: id, cancel: cancel, changed: make(chan struct{}), state: JobRunning}
+}
+
+func (j *job) publish(e pipeline.Event) {
+	j.mu.Lock()
+	defer j.mu.Unlock()
+	j.events = append(j.events, e)
+	close(j.changed)
+	j.changed = make(chan struct{})
+}
+
+func (j *job) finish(state string, summary pipeline.Summary, err error) {
+	j.mu.Lock()
+	defer j.mu.Unlock()
+	j.state = state
+	j.summary = summary
+	j.err = err
+	j.ended = time.Now()
+	close(j.changed)
+	j.changed = make(chan struct{})
+}
+
+// since returns the events after index from, a channel closed on the next change and
+// whether the job has finished.
+func (j *job) since(from int) ([]pipeline.Event, <-chan struct{}, bool) {
+	j.mu.Lock()
+	defer j.mu.Unlock()
+	events := append([]pipeline.Event(nil), j.events[from:]...)
+	return events, j.changed, j.state != JobRunning
+}
+
+func (j *job) status() JobStatus {
+	j.mu.Lock()
+	defer j.mu.Unlock()
+	st := JobStatus{JobID: j.id, State: j.state, Summary: j.summary}
+	if j.err != nil {
+		st.Error = j.err.Error()
+	}
+	return st
+}
+
+type jobRegistry struct {
+	mu   sync.Mutex
+	seq  atomic.Int64
+	jobs map[string]*job
+}
+
+func newJobRegistry() *jobRegistry {
+	return &jobRegistry{jobs: make(map[string]*job)}
+}
+
+// endedAt returns when the job finished, or the zero time while it runs.
+func (j *job) endedAt() time.Time {
+	j.mu.Lock()
+	defer j.mu.Unlock()
+	return j.ended
+}
+
+// add registers a new job, unless a job is still running, which it returns instead: runs
+// share the download directory, the outputs and the manifest, so they must not overlap.
+func (r *jobRegistry) add(cancel context.CancelFunc) (j, running *job) {
+	r.mu.Lock()
+	defer r.mu.Unlock()
+	r.evict(time.Now())
+	for _, other := range r.jobs {
+		if other.endedAt().IsZero() {
+			return nil, other
+		}
+	}
+	j = newJob(fmt.Sprintf("run-%d", r.seq.Add(1)), cancel)
+	r.jobs[j.id] = j
+	return j, nil
+}
+
+// remove drops a job that never started.
+func (r *jobRegistry) remove(id string) {
+	r.mu.Lock()
+	defer r.mu.Unlock()
+	delete(r.jobs, id)
+}
+
+// evict drops the jobs finished more than finishedJobTTL ago, then the oldest finished
+// ones beyond maxFinishedJobs. Running jobs are always kept. r.mu must be held.
+func (r *jobRegistry) evict(now time.Time) {
+	type finished struct {
+		id    string
+		ended time.Time
+	}
+	var kept []finished
+	for id, j := range r.jobs {
+		ended := j.endedAt()
+		switch {
+		case ended.IsZero():
+		case now.Sub(ended) > finishedJobTTL:
+			delete(r.jobs, id)
+		default:
+			kept = append(kept, finished{id, ended})
+		}
+	}
+	if len(kept) <= maxFinishedJobs {
+		return
+	}
+	slices.SortFunc(kept, func(a, b finished) int { return a.ended.Compare(b.ended) })
+	for _, f := range kept[:len(kept)-maxFinishedJobs] {
+		delete(r.jobs, f.id)
+	}
+}
+
+func (r *jobRegistry) get(id string) (*job, bool) {
+	r.mu.Lock()
+	defer r.mu.Unlock()
+	r.evict(time.Now())
+	j, ok := r.jobs[id]
+	return j, ok
+}
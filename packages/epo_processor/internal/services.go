@@ -1,20 +1,30 @@
 package internal
 
 import (
+	"context"
+	"errors"
+
 	"go.opentelemetry.io/otel/metric"
 	"go.opentelemetry.io/otel/trace"
 	"go.uber.org/zap"
 
 	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
 	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/download"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/explain"
 	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/extract"
 	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/parse"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/scratch"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/state"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/storage"
 )
 
 type Services struct {
 	Downloader DownloaderInterface
 	Extractor  ExtractorInterface
 	Parser     ParserInterface
+	Manifest   *state.Manifest
+	Explain    *explain.Report
+	Scratch    *scratch.Space
 }
 
 func InitServices(
@@ -35,9 +45,56 @@ func InitServices(
 	if err != nil {
 		return nil, err
 	}
+	var manifest *state.Manifest
+	if cfg.State.Enabled {
+		manifest, err = state.Open(cfg.State.Path)
+		if err != nil {
+			return nil, err
+		}
+		d.Manifest, e.Manifest, p.Manifest = manifest, manifest, manifest
+	}
+	if cfg.UsesS3() {
+		store, err := storage.NewS3(context.Background(), cfg.Storage.S3)
+		if err != nil {
+			return nil, err
+		}
+		d.Storage, p.Storage = store, store
+	}
+	space, err := scratch.Open(cfg.Scratch, logger)
+	if err != nil {
+		return nil, err
+	}
+	p.Scratch = space
+	var report *explain.Report
+	if cfg.Log.Explain != "" {
+		report, err = explain.Open(cfg.Log.Explain, cfg.Server.ProductID, logger)
+		if err != nil {
+			return nil, err
+		}
+		d.Explain, e.Explain, p.Explain = report, report, report
+	}
 	return &Services{
 		Downloader: d,
 		Extractor:  e,
 		Parser:     p,
+		Manifest:   manifest,
+		Explain:    report,
+		Scratch:    space,
 	}, nil
 }
+
+// Reload applies the settings of config.Reloadable that live in the services to a
+// running pipeline.
+func (s *Services) Reload(cfg config.Config) {
+	s.Downloader.SetConcurrency(cfg.Server.ConcurrentDownloads)
+}
+
+// Close removes the scratch files of the run, closes the skip report and compacts the
+// manifest journal.
+func (s *Services) Close() error {
+	err := errors.Join(s.Scratch.Close(), s.Explain.Close())
+	if s.Manifest != nil {
+		err = errors.Join(err, s.Manifest.Close())
+	}
+	return err
+}
@@ -0,0 +1,103 @@
+// Package shutdown stops a run in two steps. The first SIGINT or SIGTERM drains it: no
+// new downloads, archives or XML files are started, while those in flight may finish and
+// the output is flushed. A second signal, or pipeline.shutdown_grace after the first,
+// cancels the work still in flight.
+package shutdown
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"os"
+	"os/signal"
+	"syscall"
+	"time"
+
+	"go.uber.org/zap"
+)
+
+// ExitCode is the exit status of a run stopped by a signal, 128+SIGINT as shells report it.
+const ExitCode = 130
+
+// ErrInterrupted is returned by a stage that stopped early because the run was drained.
+// It wraps context.Canceled, so cancelled items are recorded as pending, not failed.
+var ErrInterrupted = fmt.Errorf("interrupted: %w", context.Canceled)
+
+type drainKey struct{}
+
+// Notify returns a context that is cancelled on the second signal, once grace has passed
+// since the first (0 waits for the work in flight) or when stop is called. Draining on it
+// reports the first signal.
+func Notify(
+	parent context.Context,
+	grace time.Duration,
+	logger *zap.SugaredLogger,
+) (ctx context.Context, stop context.CancelFunc) {
+	ctx, cancel := context.WithCancel(parent)
+	drain, startDrain := context.WithCancel(ctx)
+	signals := make(chan os.Signal, 2)
+	signal.Notify(signals, os.Interrupt, syscall.SIGTERM)
+	go func() {
+		defer signal.Stop(signals)
+		select {
+		case <-ctx.Done():
+			return
+		case sig := <-signals:
+			logger.Warnw("Finishing the tasks in flight before stopping; signal again to abort them",
+				"signal", sig.String(), "grace", grace)
+			startDrain()
+		}
+		var expired <-chan time.Time
+		if grace > 0 {
+			timer := time.NewTimer(grace)
+			defer timer.Stop()
+			expired = timer.C
+		}
+		select {
+		case <-ctx.Done():
+			return
+		case sig := <-signals:
+			logger.Warnw("Aborting the tasks in flight", "signal", sig.String())
+		case <-expired:
+			logger.Warnw("Shutdown grace period over, aborting the tasks in flight", "grace", grace)
+		}
+		cancel()
+	}()
+	return context.WithValue(ctx, drainKey{}, drain), cancel
+}
+
+// Stopping returns a channel that is closed once the run of ctx should stop taking on
+// work. Without Notify it is ctx.Done().
+func Stopping(ctx context.Context) <-chan struct{} {
+	if drain, ok := ctx.Value(drainKey{}).(context.Context); ok {
+		return drain.Done()
+	}
+	return ctx.Done()
+}
+
+// Interrupted reports whether the run of ctx was told to stop.
+func Interrupted(ctx context.Context) bool {
+	select {
+	case <-Stopping(ctx):
+		return true
+	default:
+		return false
+	}
+}
+
+// Join joins errs like errors.Join but keeps a single ErrInterrupted, so a drained
+// batch of tasks does not report every task it did not start.
+func Join(errs ...error) error {
+	var kept []error
+	interrupted := false
+	for _, err := range errs {
+		if errors.Is(err, ErrInterrupted) {
+			if interrupted {
+				continue
+			}
+			interrupted = true
+		}
+		kept = append(kept, err)
+	}
+	return errors.Join(kept...)
+}
@@ -0,0 +1,280 @@
+package sink
+
+import (
+	"errors"
+	"fmt"
+	"os"
+
+	"github.com/apache/arrow/go/v18/arrow"
+	"github.com/apache/arrow/go/v18/arrow/array"
+	"github.com/apache/arrow/go/v18/arrow/ipc"
+	"github.com/apache/arrow/go/v18/arrow/memory"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+var (
+	arrowString  = arrow.BinaryTypes.String
+	arrowStrings = arrow.ListOf(arrow.BinaryTypes.String)
+)
+
+func arrowStruct(names ...string) *arrow.StructType {
+	fields := make([]arrow.Field, 0, len(names))
+	for _, name := range names {
+		fields = append(fields, arrow.Field{Name: name, Type: arrowString})
+	}
+	return arrow.StructOf(fields...)
+}
+
+// arrowColumns mirrors the record struct, like the Parquet schema: lists of strings and
+// lists of structs keep their nesting.
+var arrowColumns = []struct {
+	field  arrow.Field
+	append func(array.Builder, models.PatentRecord)
+}{
+	{arrow.Field{Name: "patent_id", Type: arrowString}, func(b array.Builder, r models.PatentRecord) {
+		appendString(b, r.PatentID)
+	}},
+	{arrow.Field{Name: "status", Type: arrowString}, func(b array.Builder, r models.PatentRecord) {
+		appendString(b, r.Status)
+	}},
+	{arrow.Field{Name: "cpc_list", Type: arrowStrings}, func(b array.Builder, r models.PatentRecord) {
+		appendStrings(b, r.CPCList)
+	}},
+	{
+		arrow.Field{Name: "citations", Type: arrow.ListOf(arrow.StructOf(
+			arrow.Field{Name: "cited_id", Type: arrowString},
+			arrow.Field{Name: "categories", Type: arrowStrings},
+		))},
+		func(b array.Builder, r models.PatentRecord) {
+			appendStructs(b, r.Citations, func(s *array.StructBuilder, c models.Citation) {
+				appendString(s.FieldBuilder(0), c.CitedID)
+				appendStrings(s.FieldBuilder(1), c.Categories)
+			})
+		},
+	},
+	{arrow.Field{Name: "family_patents", Type: arrowStrings}, func(b array.Builder, r models.PatentRecord) {
+		appendStrings(b, r.FamilyPatents)
+	}},
+	{arrow.Field{Name: "titles", Type: arrow.ListOf(arrowStruct("lang", "text"))}, func(b array.Builder, r models.PatentRecord) {
+		appendLocalized(b, r.Titles)
+	}},
+	{arrow.Field{Name: "abstracts", Type: arrow.ListOf(arrowStruct("lang", "text"))}, func(b array.Builder, r models.PatentRecord) {
+		appendLocalized(b, r.Abstracts)
+	}},
+	{arrow.Field{Name: "applicants", Type: arrowStrings}, func(b array.Builder, r models.PatentRecord) {
+		appendStrings(b, r.Applicants)
+	}},
+	{arrow.Field{Name: "inventors", Type: arrowStrings}, func(b array.Builder, r models.PatentRecord) {
+		appendStrings(b, r.Inventors)
+	}},
+	{arrow.Field{Name: "ipc_list", Type: arrowStrings}, func(b array.Builder, r models.PatentRecord) {
+		appendStrings(b, r.IPCList)
+	}},
+	{arrow.Field{Name: "publication_date", Type: arrowString}, func(b array.Builder, r models.PatentRecord) {
+		appendString(b, r.PublicationDate)
+	}},
+	{
+		arrow.Field{Name: "npl_citations", Type: arrow.ListOf(arrow.StructOf(
+			arrow.Field{Name: "text", Type: arrowString},
+			arrow.Field{Name: "doi", Type: arrowString},
+			arrow.Field{Name: "categories", Type: arrowStrings},
+		))},
+		func(b array.Builder, r models.PatentRecord) {
+			appendStructs(b, r.NPLCitations, func(s *array.StructBuilder, c models.NPLCitation) {
+				appendString(s.FieldBuilder(0), c.Text)
+				appendString(s.FieldBuilder(1), c.DOI)
+				appendStrings(s.FieldBuilder(2), c.Categories)
+			})
+		},
+	},
+	{arrow.Field{Name: "family_id", Type: arrowString}, func(b array.Builder, r models.PatentRecord) {
+		appendString(b, r.FamilyID)
+	}},
+	{arrow.Field{Name: "application_number", Type: arrowString}, func(b array.Builder, r models.PatentRecord) {
+		appendString(b, r.ApplicationNumber)
+	}},
+	{arrow.Field{Name: "filing_date", Type: arrowString}, func(b array.Builder, r models.PatentRecord) {
+		appendString(b, r.FilingDate)
+	}},
+	{
+		arrow.Field{Name: "priority_claims", Type: arrow.ListOf(arrowStruct("country", "doc_number", "kind", "date"))},
+		func(b array.Builder, r models.PatentRecord) {
+			appendStructs(b, r.PriorityClaims, func(s *array.StructBuilder, c models.PriorityClaim) {
+				appendString(s.FieldBuilder(0), c.Country)
+				appendString(s.FieldBuilder(1), c.DocNumber)
+				appendString(s.FieldBuilder(2), c.Kind)
+				appendString(s.FieldBuilder(3), c.Date)
+			})
+		},
+	},
+	{arrow.Field{Name: "earliest_priority_date", Type: arrowString}, func(b array.Builder, r models.PatentRecord) {
+		appendString(b, r.EarliestPriorityDate)
+	}},
+	{arrow.Field{Name: "cpc_hierarchy", Type: arrowStrings}, func(b array.Builder, r models.PatentRecord) {
+		appendStrings(b, r.CPCHierarchy)
+	}},
+	{
+		arrow.Field{Name: "citation_provenance", Type: arrow.ListOf(arrowStruct("cited_id", "phase", "office"))},
+		func(b array.Builder, r models.PatentRecord) {
+			appendStructs(b, r.CitationProvenance, func(s *array.StructBuilder, c models.CitationProvenance) {
+				appendString(s.FieldBuilder(0), c.CitedID)
+				appendString(s.FieldBuilder(1), c.Phase)
+				appendString(s.FieldBuilder(2), c.Office)
+			})
+		},
+	},
+	{
+		arrow.Field{Name: "extra", Type: arrow.ListOf(arrow.StructOf(
+			arrow.Field{Name: "name", Type: arrowString},
+			arrow.Field{Name: "values", Type: arrowStrings},
+		))},
+		func(b array.Builder, r models.PatentRecord) {
+			appendStructs(b, r.Extra, func(s *array.StructBuilder, e models.ExtraField) {
+				appendString(s.FieldBuilder(0), e.Name)
+				appendStrings(s.FieldBuilder(1), e.Values)
+			})
+		},
+	},
+}
+
+// ArrowSchema is the schema of the record batches of RecordBatch and of the arrow format.
+var ArrowSchema = func() *arrow.Schema {
+	fields := make([]arrow.Field, 0, len(arrowColumns))
+	for _, c := range arrowColumns {
+		fields = append(fields, c.field)
+	}
+	return arrow.NewSchema(fields, nil)
+}()
+
+// RecordBatch converts records into an Arrow record batch with ArrowSchema, allocated
+// from mem. The caller releases it.
+func RecordBatch(mem memory.Allocator, records []models.PatentRecord) arrow.Record {
+	b := array.NewRecordBuilder(mem, ArrowSchema)
+	defer b.Release()
+	b.Reserve(len(records))
+	for _, r := range records {
+		for i, c := range arrowColumns {
+			c.append(b.Field(i), r)
+		}
+	}
+	return b.NewRecord()
+}
+
+func appendString(b array.Builder, s string) {
+	b.(*array.StringBuilder).Append(s)
+}
+
+func appendStrings(b array.Builder, values []string) {
+	lb := b.(*array.ListBuilder)
+	lb.Append(true)
+	vb := lb.ValueBuilder().(*array.StringBuilder)
+	for _, v := range values {
+		vb.Append(v)
+	}
+}
+
+func appendStructs[T any](b array.Builder, values []T, fill func(*array.StructBuilder, T)) {
+	lb := b.(*array.ListBuilder)
+	lb.Append(true)
+	sb := lb.ValueBuilder().(*array.StructBuilder)
+	for _, v := range values {
+		sb.Append(true)
+		fill(sb, v)
+	}
+}
+
+func appendLocalized(b array.Builder, texts []models.LocalizedText) {
+	appendStructs(b, texts, func(s *array.StructBuilder, t models.LocalizedText) {
+		appendString(s.FieldBuilder(0), t.Lang)
+		appendString(s.FieldBuilder(1), t.Text)
+	})
+}
+
+// arrowSink writes an Arrow IPC file (Feather v2), one record batch per Write. The
+// batches are not compressed, so readers can memory-map the file.
+type arrowSink struct {
+	file    *os.File
+	counter *countingWriter
+	writer  *ipc.FileWriter
+	mem     memory.Allocator
+}
+
+func newArrowSink(path string) (*arrowSink, error) {
+	f, err := os.Create(path)
+	if err != nil {
+		return nil, fmt.Errorf("failed to create Arrow file: %w", err)
+	}
+	mem := memory.NewGoAllocator()
+	counter := &countingWriter{w: f}
+	w, err := ipc.NewFileWriter(counter, ipc.WithSchema(ArrowSchema), ipc.WithAllocator(mem))
+	if err != nil {
+		return nil, errors.Join(err, f.Close())
+	}
+	return &arrowSink{file: f, counter: counter, writer: w, mem: mem}, nil
+}
+
+func (s *arrowSink) Size() int64 {
+	return s.counter.n
+}
+
+func (s *arrowSink) Write(records []models.PatentRecord) error {
+	if len(records) == 0 {
+		return nil
+	}
+	rec := RecordBatch(s.mem, records)
+	defer rec.Release()
+	return s.writer.Write(rec)
+}
+
+func (s *arrowSink) Close() error {
+	return errors.Join(s.writer.Close(), s.file.Close())
+}
+
+// batchSink hands every write to fn as a record batch.
+type batchSink struct {
+	mem memory.Allocator
+	fn  func(arrow.Record) error
+}
+
+// NewRecordBatchSink returns a sink that converts each write into a RecordBatch and
+// passes it to fn, releasing it once fn returns; fn retains the batch to keep it.
+func NewRecordBatchSink(mem memory.Allocator, fn func(arrow.Record) error) Sink {
+	return &batchSink{mem: mem, fn: fn}
+}
+
+func (s *batchSink) Write(records []models.PatentRecord) error {
+	if len(records) == 0 {
+		return nil
+	}
+	rec := RecordBatch(s.mem, records)
+	defer rec.Release()
+	return s.fn(rec)
+}
+
+func (s *batchSink) Close() error {
+	return nil
+}
+
+func countArrowRows(path string) (int64, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return 0, err
+	}
+	defer f.Close()
+	r, err := ipc.NewFileReader(f)
+	if err != nil {
+		return 0, err
+	}
+	defer r.Close()
+	var rows int64
+	for i := 0; i < r.NumRecords(); i++ {
+		rec, err := r.RecordAt(i)
+		if err != nil {
+			return 0, err
+		}
+		rows += rec.NumRows()
+		rec.Release()
+	}
+	return rows, nil
+}
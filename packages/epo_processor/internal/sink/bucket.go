@@ -0,0 +1,267 @@
+package sink
+
+import (
+	"bufio"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"hash/fnv"
+	"io"
+	"maps"
+	"os"
+	"path/filepath"
+	"slices"
+	"strings"
+
+	"github.com/parquet-go/parquet-go"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// BucketsSuffix is appended to the output path to name the index of a bucketed output.
+const BucketsSuffix = ".buckets.json"
+
+// BucketsIndex lists the bucket files of a bucketed output and the ones the last run
+// rewrote; consumers of an incremental update only need to reload those.
+type BucketsIndex struct {
+	Format  string      `json:"format"`
+	Buckets int         `json:"buckets"`
+	Rows    int64       `json:"rows"`
+	Parts   []Partition `json:"parts"`
+	Updated []string    `json:"updated"`
+}
+
+// bucketSink spreads records over a fixed number of bucket files (data.bucket-0007.parquet,
+// ...) by a hash of their family, so the members of a family share a file. Buckets a run
+// has no records for are left as they are. A bucket file that already exists is merged:
+// its rows are kept unless the run brings a record with the same patent id. Each bucket
+// is written to a temporary file that replaces the bucket when the sink closes.
+type bucketSink struct {
+	path    string
+	format  string
+	count   int
+	open    func(path string) (Sink, error)
+	parts   map[string]Partition
+	buckets map[int]*bucket
+}
+
+type bucket struct {
+	path string
+	sink Sink
+	rows int64
+	// ids holds the patent ids written to a bucket that is merged with an existing file.
+	ids map[string]struct{}
+}
+
+func newBucketSink(path, format string, count int, open func(string) (Sink, error)) (*bucketSink, error) {
+	s := &bucketSink{
+		path:    path,
+		format:  format,
+		count:   count,
+		open:    open,
+		parts:   map[string]Partition{},
+		buckets: map[int]*bucket{},
+	}
+	index, err := ReadBucketsIndex(path)
+	switch {
+	case errors.Is(err, os.ErrNotExist):
+		return s, nil
+	case err != nil:
+		return nil, err
+	case index.Format != format || index.Buckets != count:
+		return nil, fmt.Errorf(
+			"%s holds %d %s buckets; remove the output to write %d %s buckets",
+			path, index.Buckets, index.Format, count, format,
+		)
+	}
+	for _, p := range index.Parts {
+		s.parts[p.Path] = p
+	}
+	return s, nil
+}
+
+// BucketPath names the n-th bucket (0-based) of a bucketed output at path.
+func BucketPath(path string, n int) string {
+	ext := filepath.Ext(path)
+	return fmt.Sprintf("%s.bucket-%04d%s", strings.TrimSuffix(path, ext), n, ext)
+}
+
+func (s *bucketSink) bucketOf(r models.PatentRecord) int {
+	h := fnv.New32a()
+	_, _ = h.Write([]byte(lowestMember(r)))
+	return int(h.Sum32() % uint32(s.count))
+}
+
+func (s *bucketSink) Write(records []models.PatentRecord) error {
+	batches := map[int][]models.PatentRecord{}
+	for _, r := range records {
+		n := s.bucketOf(r)
+		batches[n] = append(batches[n], r)
+	}
+	for _, n := range slices.Sorted(maps.Keys(batches)) {
+		b, err := s.bucket(n)
+		if err != nil {
+			return err
+		}
+		if err := b.sink.Write(batches[n]); err != nil {
+			return err
+		}
+		b.rows += int64(len(batches[n]))
+		if b.ids != nil {
+			for _, r := range batches[n] {
+				b.ids[r.PatentID] = struct{}{}
+			}
+		}
+	}
+	return nil
+}
+
+// bucket returns the open bucket n, opening its temporary file on first use.
+func (s *bucketSink) bucket(n int) (*bucket, error) {
+	if b, ok := s.buckets[n]; ok {
+		return b, nil
+	}
+	b := &bucket{path: BucketPath(s.path, n)}
+	if _, err := os.Stat(b.path); err == nil {
+		b.ids = map[string]struct{}{}
+	}
+	var err error
+	b.sink, err = s.open(b.path + ".tmp")
+	if err != nil {
+		return nil, err
+	}
+	s.buckets[n] = b
+	return b, nil
+}
+
+// Close merges the buckets written by the run into their existing files, moves them in
+// place and writes the buckets index.
+func (s *bucketSink) Close() error {
+	var updated []string
+	for _, n := range slices.Sorted(maps.Keys(s.buckets)) {
+		b := s.buckets[n]
+		if err := s.finish(b); err != nil {
+			delete(s.buckets, n)
+			return errors.Join(fmt.Errorf("bucket %s: %w", filepath.Base(b.path), err), s.abort())
+		}
+		delete(s.buckets, n)
+		updated = append(updated, b.path)
+	}
+	index := BucketsIndex{Format: s.format, Buckets: s.count, Parts: s.Partitions(), Updated: updated}
+	for _, p := range index.Parts {
+		index.Rows += p.Rows
+	}
+	return writeJSON(s.path+BucketsSuffix, index)
+}
+
+func (s *bucketSink) finish(b *bucket) error {
+	if b.ids != nil {
+		err := readRecords(b.path, s.format, func(records []models.PatentRecord) error {
+			kept := slices.DeleteFunc(slices.Clone(records), func(r models.PatentRecord) bool {
+				_, replaced := b.ids[r.PatentID]
+				return replaced
+			})
+			b.rows += int64(len(kept))
+			return b.sink.Write(kept)
+		})
+		if err != nil {
+			return errors.Join(err, b.sink.Close(), os.Remove(b.path+".tmp"))
+		}
+	}
+	if err := b.sink.Close(); err != nil {
+		return errors.Join(err, os.Remove(b.path+".tmp"))
+	}
+	if err := os.Rename(b.path+".tmp", b.path); err != nil {
+		return err
+	}
+	fi, err := os.Stat(b.path)
+	if err != nil {
+		return err
+	}
+	s.parts[b.path] = Partition{Path: b.path, Rows: b.rows, Bytes: fi.Size()}
+	return nil
+}
+
+// Partitions lists the bucket files, including those the run left untouched.
+func (s *bucketSink) Partitions() []Partition {
+	parts := slices.Collect(maps.Values(s.parts))
+	slices.SortFunc(parts, func(a, b Partition) int { return strings.Compare(a.Path, b.Path) })
+	return parts
+}
+
+// abort drops the temporary files of the run, leaving the existing buckets and index.
+func (s *bucketSink) abort() error {
+	var errs []error
+	for n, b := range s.buckets {
+		errs = append(errs, b.sink.Close(), os.Remove(b.path+".tmp"))
+		delete(s.buckets, n)
+	}
+	return errors.Join(errs...)
+}
+
+// ReadBucketsIndex loads the buckets index of a bucketed output at path.
+func ReadBucketsIndex(path string) (BucketsIndex, error) {
+	var index BucketsIndex
+	data, err := os.ReadFile(path + BucketsSuffix)
+	if err != nil {
+		return index, err
+	}
+	if err := json.Unmarshal(data, &index); err != nil {
+		return index, fmt.Errorf("decode buckets index: %w", err)
+	}
+	return index, nil
+}
+
+// readRecords passes the records of a parquet, jsonl or csv output file to fn in batches.
+func readRecords(path, format string, fn func([]models.PatentRecord) error) error {
+	f, err := os.Open(path)
+	if err != nil {
+		return err
+	}
+	defer f.Close()
+	switch format {
+	case "", FormatParquet:
+		r := parquet.NewGenericReader[models.PatentRecord](f)
+		defer r.Close()
+		batch := make([]models.PatentRecord, 1024)
+		for {
+			n, err := r.Read(batch)
+			if n > 0 {
+				if err := fn(batch[:n]); err != nil {
+					return err
+				}
+			}
+			if errors.Is(err, io.EOF) {
+				return nil
+			}
+			if err != nil {
+				return fmt.Errorf("read Parquet file: %w", err)
+			}
+		}
+	case FormatJSONL:
+		dec := json.NewDecoder(bufio.NewReader(f))
+		batch := make([]models.PatentRecord, 0, 1024)
+		for {
+			var r models.PatentRecord
+			if err := dec.Decode(&r); errors.Is(err, io.EOF) {
+				break
+			} else if err != nil {
+				return fmt.Errorf("read JSONL file: %w", err)
+			}
+			if batch = append(batch, r); len(batch) == cap(batch) {
+				if err := fn(batch); err != nil {
+					return err
+				}
+				batch = batch[:0]
+			}
+		}
+		if len(batch) > 0 {
+			return fn(batch)
+		}
+		return nil
+	case FormatCSV:
+		return readCSV(bufio.NewReader(f), fn)
+	default:
+		return fmt.Errorf("cannot read back %s output", format)
+	}
+}
@@ -0,0 +1,83 @@
+package sink
+
+import (
+	"errors"
+	"fmt"
+	"io"
+	"os"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+)
+
+// Checkpoint is a position in an output right after the last record of a parsed file.
+type Checkpoint struct {
+	Offset int64
+	Rows   int64
+}
+
+// flusher is a sink writing a single file that can be brought up to date on disk.
+type flusher interface {
+	Flush() error
+	Size() int64
+}
+
+// Resumable reports whether an output of cfg can be checkpointed and appended to, which
+// needs a single csv or jsonl file and no family table or citation graph next to it.
+func Resumable(cfg config.Parse) bool {
+	return (cfg.OutputFormat == FormatCSV || cfg.OutputFormat == FormatJSONL) &&
+		cfg.Rotation.MaxRowsPerFile == 0 && cfg.Rotation.MaxBytesPerFile == 0 &&
+		cfg.Buckets.Count == 0 && !cfg.FamilyMembers.Enabled && !cfg.Families.Enabled &&
+		!cfg.CitationGraph.Enabled
+}
+
+// Mark writes out the records buffered by s and returns the size of its file, the
+// offset to resume from should the run stop after this point.
+func Mark(s Sink) (int64, error) {
+	if d, ok := s.(*describedSink); ok {
+		s = d.inner
+	}
+	f, ok := s.(flusher)
+	if !ok {
+		return 0, fmt.Errorf("%T cannot be checkpointed", s)
+	}
+	if err := f.Flush(); err != nil {
+		return 0, err
+	}
+	return f.Size(), nil
+}
+
+// Append reopens the output at path to continue after cp: anything written past
+// cp.Offset, such as the records of unfinished files or a partial row, is cut off first.
+// The descriptor, when enabled, counts the cp.Rows rows already in the file.
+func Append(path string, cfg config.Parse, meta Metadata, cp Checkpoint) (Sink, error) {
+	if !Resumable(cfg) {
+		return nil, fmt.Errorf("cannot append to %s output", cfg.OutputFormat)
+	}
+	f, err := os.OpenFile(path, os.O_WRONLY, 0)
+	if err != nil {
+		return nil, fmt.Errorf("reopen output: %w", err)
+	}
+	if fi, err := f.Stat(); err != nil || fi.Size() < cp.Offset {
+		return nil, errors.Join(
+			fmt.Errorf("%s is shorter than its checkpoint; parse it again without resuming", path),
+			err, f.Close())
+	}
+	if err := f.Truncate(cp.Offset); err != nil {
+		return nil, errors.Join(fmt.Errorf("truncate output: %w", err), f.Close())
+	}
+	if _, err := f.Seek(cp.Offset, io.SeekStart); err != nil {
+		return nil, errors.Join(err, f.Close())
+	}
+	var s Sink
+	if cfg.OutputFormat == FormatCSV {
+		s = csvSinkAt(f, cp.Offset, CSVColumns(cfg))
+	} else {
+		s = jsonlSinkAt(f, cp.Offset)
+	}
+	if !cfg.Descriptor.Enabled {
+		return s, nil
+	}
+	d := withDescriptor(s, path, cfg, meta)
+	d.desc.Rows = cp.Rows
+	return d, nil
+}
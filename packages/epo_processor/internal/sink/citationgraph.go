@@ -0,0 +1,142 @@
+package sink
+
+import (
+	"bufio"
+	"encoding/csv"
+	"encoding/xml"
+	"errors"
+	"fmt"
+	"maps"
+	"os"
+	"slices"
+	"strings"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// Formats of the citation graph.
+const (
+	GraphEdgeList = "edgelist"
+	GraphML       = "graphml"
+)
+
+var citationEdgeHeader = []string{"citing_id", "cited_id", "category", "cited_phase", "office"}
+
+// citationEdges returns the edges of the citations of r, one per category; a citation
+// without categories gets one edge with an empty category.
+func citationEdges(r models.PatentRecord) [][]string {
+	var edges [][]string
+	for _, c := range r.Citations {
+		categories := c.Categories
+		if len(categories) == 0 {
+			categories = []string{""}
+		}
+		for _, category := range categories {
+			edges = append(edges, []string{r.PatentID, c.CitedID, category, c.Phase, c.Office})
+		}
+	}
+	return edges
+}
+
+// NewCitationGraph opens the citation graph at path in format, GraphEdgeList or GraphML.
+func NewCitationGraph(path, format string) (Sink, error) {
+	f, err := os.Create(path)
+	if err != nil {
+		return nil, fmt.Errorf("failed to create citation graph file: %w", err)
+	}
+	if format == GraphML {
+		return newGraphMLSink(f)
+	}
+	s := &edgeListSink{file: f, writer: csv.NewWriter(f)}
+	if err := s.writer.Write(citationEdgeHeader); err != nil {
+		return nil, errors.Join(err, f.Close())
+	}
+	return s, nil
+}
+
+// edgeListSink writes the citation edges as CSV, ready for igraph, NetworkX or Gephi.
+type edgeListSink struct {
+	file   *os.File
+	writer *csv.Writer
+}
+
+func (s *edgeListSink) Write(records []models.PatentRecord) error {
+	for _, r := range records {
+		for _, edge := range citationEdges(r) {
+			if err := s.writer.Write(edge); err != nil {
+				return err
+			}
+		}
+	}
+	return nil
+}
+
+func (s *edgeListSink) Close() error {
+	s.writer.Flush()
+	return errors.Join(s.writer.Error(), s.file.Close())
+}
+
+const graphMLHeader = `<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="category" for="edge" attr.name="category" attr.type="string"/>
+  <key id="cited_phase" for="edge" attr.name="cited_phase" attr.type="string"/>
+  <key id="office" for="edge" attr.name="office" attr.type="string"/>
+  <graph id="citations" edgedefault="directed">
+`
+
+// graphMLSink writes the citation edges as a directed GraphML graph. Edges are written
+// as they come; the nodes, every citing and cited id, follow when the sink is closed,
+// which GraphML allows. Only the node ids are held in memory.
+type graphMLSink struct {
+	file  *os.File
+	w     *bufio.Writer
+	nodes map[string]struct{}
+}
+
+func newGraphMLSink(f *os.File) (*graphMLSink, error) {
+	s := &graphMLSink{file: f, w: bufio.NewWriter(f), nodes: map[string]struct{}{}}
+	if _, err := s.w.WriteString(graphMLHeader); err != nil {
+		return nil, errors.Join(err, f.Close())
+	}
+	return s, nil
+}
+
+func (s *graphMLSink) Write(records []models.PatentRecord) error {
+	for _, r := range records {
+		for _, edge := range citationEdges(r) {
+			s.nodes[edge[0]] = struct{}{}
+			s.nodes[edge[1]] = struct{}{}
+			_, err := fmt.Fprintf(s.w, "    <edge source=\"%s\" target=\"%s\">"+
+				"<data key=\"category\">%s</data><data key=\"cited_phase\">%s</data>"+
+				"<data key=\"office\">%s</data></edge>\n",
+				escapeXML(edge[0]), escapeXML(edge[1]), escapeXML(edge[2]), escapeXML(edge[3]),
+				escapeXML(edge[4]))
+			if err != nil {
+				return err
+			}
+		}
+	}
+	return nil
+}
+
+func (s *graphMLSink) Close() error {
+	var err error
+	for _, id := range slices.Sorted(maps.Keys(s.nodes)) {
+		if _, err = fmt.Fprintf(s.w, "    <node id=\"%s\"/>\n", escapeXML(id)); err != nil {
+			break
+		}
+	}
+	if err == nil {
+		_, err = s.w.WriteString("  </graph>\n</graphml>\n")
+	}
+	if err == nil {
+		err = s.w.Flush()
+	}
+	return errors.Join(err, s.file.Close())
+}
+
+func escapeXML(s string) string {
+	var b strings.Builder
+	_ = xml.EscapeText(&b, []byte(s))
+	return b.String()
+}
@@ -0,0 +1,155 @@
+package sink
+
+import (
+	"bytes"
+	"context"
+	"encoding/json"
+	"fmt"
+	"io"
+	"net/http"
+	"net/url"
+	"strings"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// clickHouseColumns mirrors the record struct like duckDBColumns; the JSON objects of the
+// nested lists are read into named tuples.
+var clickHouseColumns = []struct{ name, typ string }{
+	{"patent_id", "String"},
+	{"status", "String"},
+	{"cpc_list", "Array(String)"},
+	{"citations", "Array(Tuple(cited_id String, categories Array(String)))"},
+	{"family_patents", "Array(String)"},
+	{"titles", "Array(Tuple(lang String, text String))"},
+	{"abstracts", "Array(Tuple(lang String, text String))"},
+	{"applicants", "Array(String)"},
+	{"inventors", "Array(String)"},
+	{"ipc_list", "Array(String)"},
+	{"publication_date", "String"},
+	{"npl_citations", "Array(Tuple(text String, doi String, categories Array(String)))"},
+	{"family_id", "String"},
+	{"application_number", "String"},
+	{"filing_date", "String"},
+	{"priority_claims", "Array(Tuple(country String, doc_number String, kind String, date String))"},
+	{"earliest_priority_date", "String"},
+	{"cpc_hierarchy", "Array(String)"},
+	{"citation_provenance", "Array(Tuple(cited_id String, phase String, office String))"},
+	{"extra", "Array(Tuple(name String, `values` Array(String)))"},
+}
+
+// clickHouseSink inserts records into a ClickHouse table over the HTTP interface, as
+// JSONEachRow in one INSERT per cfg.BatchRows records. ClickHouse has no transaction
+// spanning the inserts, so the batches sent before a failed run stay in the table.
+type clickHouseSink struct {
+	cfg    config.ClickHouse
+	client *http.Client
+	table  string
+	buf    bytes.Buffer
+	enc    *json.Encoder
+	rows   int
+}
+
+func newClickHouseSink(cfg config.ClickHouse) (*clickHouseSink, error) {
+	s := &clickHouseSink{
+		cfg:    cfg,
+		client: &http.Client{Timeout: cfg.Timeout},
+		table:  quoteClickHouse(cfg.Database) + "." + quoteClickHouse(cfg.Table),
+	}
+	s.enc = json.NewEncoder(&s.buf)
+	s.enc.SetEscapeHTML(false)
+	if err := s.createTable(); err != nil {
+		return nil, fmt.Errorf("failed to prepare clickhouse table: %w", err)
+	}
+	return s, nil
+}
+
+// createTable creates the table if missing, as a MergeTree ordered by patent_id.
+func (s *clickHouseSink) createTable() error {
+	defs := make([]string, len(clickHouseColumns))
+	for i, c := range clickHouseColumns {
+		defs[i] = quoteClickHouse(c.name) + " " + c.typ
+	}
+	stmt := fmt.Sprintf("CREATE TABLE IF NOT EXISTS %s (%s) ENGINE = MergeTree ORDER BY patent_id",
+		s.table, strings.Join(defs, ", "))
+	return s.exec(stmt, nil)
+}
+
+func (s *clickHouseSink) Write(records []models.PatentRecord) error {
+	for _, r := range records {
+		if err := s.enc.Encode(jsonlRecord(r)); err != nil {
+			return err
+		}
+		s.rows++
+		if s.rows >= s.cfg.BatchRows {
+			if err := s.flush(); err != nil {
+				return err
+			}
+		}
+	}
+	return nil
+}
+
+// flush inserts the buffered rows.
+func (s *clickHouseSink) flush() error {
+	if s.rows == 0 {
+		return nil
+	}
+	err := s.exec(fmt.Sprintf("INSERT INTO %s FORMAT JSONEachRow", s.table), &s.buf)
+	if err != nil {
+		return fmt.Errorf("failed to insert into clickhouse table %s: %w", s.table, err)
+	}
+	s.buf.Reset()
+	s.rows = 0
+	return nil
+}
+
+func (s *clickHouseSink) Close() error {
+	return s.flush()
+}
+
+// abort drops the rows not yet inserted.
+func (s *clickHouseSink) abort() error {
+	s.buf.Reset()
+	s.rows = 0
+	return nil
+}
+
+// exec sends query to the HTTP interface, followed by the data in body if any.
+func (s *clickHouseSink) exec(query string, body io.Reader) error {
+	u, err := url.Parse(s.cfg.URL)
+	if err != nil {
+		return fmt.Errorf("invalid clickhouse url: %w", err)
+	}
+	params := u.Query()
+	params.Set("query", query)
+	u.RawQuery = params.Encode()
+	if body == nil {
+		body = http.NoBody
+	}
+	req, err := http.NewRequestWithContext(context.Background(), http.MethodPost, u.String(), body)
+	if err != nil {
+		return err
+	}
+	if s.cfg.User != "" {
+		req.Header.Set("X-ClickHouse-User", s.cfg.User)
+		req.Header.Set("X-ClickHouse-Key", s.cfg.Password)
+	}
+	resp, err := s.client.Do(req)
+	if err != nil {
+		return err
+	}
+	defer resp.Body.Close()
+	if resp.StatusCode != http.StatusOK {
+		msg, _ := io.ReadAll(io.LimitReader(resp.Body, 4096))
+		return fmt.Errorf("%s: %s", resp.Status, strings.TrimSpace(string(msg)))
+	}
+	_, err = io.Copy(io.Discard, resp.Body)
+	return err
+}
+
+// quoteClickHouse quotes an identifier with backticks.
+func quoteClickHouse(name string) string {
+	return "`" + strings.NewReplacer("\\", "\\\\", "`", "\\`").Replace(name) + "`"
+}
@@ -0,0 +1,73 @@
+package sink
+
+import (
+	"errors"
+	"fmt"
+	"path/filepath"
+	"strings"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// FormatOf returns the file format the extension of path names: .parquet, .csv, or
+// .jsonl and .ndjson.
+func FormatOf(path string) (string, error) {
+	switch strings.ToLower(filepath.Ext(path)) {
+	case ".parquet":
+		return FormatParquet, nil
+	case ".csv":
+		return FormatCSV, nil
+	case ".jsonl", ".ndjson":
+		return FormatJSONL, nil
+	default:
+		return "", fmt.Errorf("%s: unknown format, want a .parquet, .csv or .jsonl file", path)
+	}
+}
+
+// ReadRecords passes the records of the output file at path, in the format its extension
+// names, to fn in batches.
+func ReadRecords(path string, fn func([]models.PatentRecord) error) error {
+	format, err := FormatOf(path)
+	if err != nil {
+		return err
+	}
+	return readRecords(path, format, fn)
+}
+
+// Convert rewrites the records of the output file in to out, in the formats their
+// extensions name, and returns the number of records. A csv output has the columns cfg
+// selects, as after a parse; the other settings that split or extend an output are not
+// applied. Reading a csv back is lossy where the csv format is, see setCSVValue.
+func Convert(in, out string, cfg config.Parse) (int64, error) {
+	inFormat, err := FormatOf(in)
+	if err != nil {
+		return 0, err
+	}
+	s, err := newFileSink(out, cfg, Metadata{Sources: []string{in}, Parameters: map[string]any{"converted_from": inFormat}})
+	if err != nil {
+		return 0, err
+	}
+	var rows int64
+	err = readRecords(in, inFormat, func(records []models.PatentRecord) error {
+		rows += int64(len(records))
+		return s.Write(records)
+	})
+	if err != nil {
+		return rows, errors.Join(fmt.Errorf("convert %s: %w", in, err), Abort(s))
+	}
+	return rows, s.Close()
+}
+
+// newFileSink opens a single output file at path in the format its extension names,
+// without the settings of cfg that split or extend an output.
+func newFileSink(path string, cfg config.Parse, meta Metadata) (Sink, error) {
+	var err error
+	if cfg.OutputFormat, err = FormatOf(path); err != nil {
+		return nil, err
+	}
+	cfg.Rotation, cfg.Buckets = config.Rotation{}, config.Buckets{}
+	cfg.FamilyMembers.Enabled, cfg.Families.Enabled, cfg.CitationGraph.Enabled = false, false, false
+	cfg.Kafka.Enabled = false
+	return New(path, cfg, meta)
+}
@@ -0,0 +1,341 @@
+package sink
+
+import (
+	"encoding/csv"
+	"errors"
+	"fmt"
+	"io"
+	"maps"
+	"os"
+	"slices"
+	"strings"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+var csvHeader = []string{"patent_id", "status", "cpc_list", "citations", "family_patents"}
+
+// CSVColumns are the columns of the csv format: parse.columns when set, else the fixed
+// columns followed by the configured bibliographic and XPath fields.
+func CSVColumns(cfg config.Parse) []string {
+	if len(cfg.Columns) > 0 {
+		return cfg.Columns
+	}
+	return append(slices.Clone(csvHeader), fieldColumns(cfg)...)
+}
+
+// fieldColumns are the optional columns of the delimited formats: parse.fields, then
+// the names of parse.xpath_fields in order, then revision with parse.dedup.policy keep_all.
+func fieldColumns(cfg config.Parse) []string {
+	columns := append(slices.Clone(cfg.Fields), slices.Sorted(maps.Keys(cfg.XPathFields))...)
+	if cfg.Dedup.Policy == "keep_all" {
+		columns = append(columns, models.ColumnRevision)
+	}
+	return columns
+}
+
+// csvSink writes one row per record; list columns are joined with ';' and citation
+// categories are appended to the cited id after ':' (e.g. EP1234567A1:X,Y). Each
+// configured bibliographic field adds a column; titles and abstracts are written as
+// lang:text entries.
+type csvSink struct {
+	file    *os.File
+	counter *countingWriter
+	writer  *csv.Writer
+	columns []string
+}
+
+func newCSVSink(path string, columns []string) (*csvSink, error) {
+	f, err := os.Create(path)
+	if err != nil {
+		return nil, fmt.Errorf("failed to create CSV file: %w", err)
+	}
+	s := csvSinkAt(f, 0, columns)
+	if err := s.writer.Write(columns); err != nil {
+		f.Close()
+		return nil, err
+	}
+	return s, nil
+}
+
+// csvSinkAt writes rows to f, which already holds offset bytes of the output.
+func csvSinkAt(f *os.File, offset int64, columns []string) *csvSink {
+	counter := &countingWriter{w: f, n: offset}
+	return &csvSink{file: f, counter: counter, writer: csv.NewWriter(counter), columns: columns}
+}
+
+func (s *csvSink) Write(records []models.PatentRecord) error {
+	for _, r := range records {
+		if err := s.writer.Write(csvRow(r, s.columns)); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+// Size flushes buffered rows and returns the bytes written to the file.
+func (s *csvSink) Size() int64 {
+	s.writer.Flush()
+	return s.counter.n
+}
+
+func (s *csvSink) Flush() error {
+	s.writer.Flush()
+	return s.writer.Error()
+}
+
+func (s *csvSink) Close() error {
+	s.writer.Flush()
+	return errors.Join(s.writer.Error(), s.file.Close())
+}
+
+// csvRow returns the values of r in columns.
+func csvRow(r models.PatentRecord, columns []string) []string {
+	row := make([]string, 0, len(columns))
+	for _, column := range columns {
+		row = append(row, csvValue(r, column))
+	}
+	return row
+}
+
+// csvValue is the value of r in a csv column: one of csvHeader, a bibliographic field or
+// an XPath field.
+func csvValue(r models.PatentRecord, column string) string {
+	switch column {
+	case "patent_id":
+		return r.PatentID
+	case "status":
+		return r.Status
+	case "cpc_list":
+		return strings.Join(r.CPCList, ";")
+	case "citations":
+		citations := make([]string, 0, len(r.Citations))
+		for _, c := range r.Citations {
+			if len(c.Categories) == 0 {
+				citations = append(citations, c.CitedID)
+				continue
+			}
+			citations = append(citations, c.CitedID+":"+strings.Join(c.Categories, ","))
+		}
+		return strings.Join(citations, ";")
+	case "family_patents":
+		return strings.Join(r.FamilyPatents, ";")
+	case models.FieldTitle:
+		return joinLocalized(r.Titles)
+	case models.FieldAbstract:
+		return joinLocalized(r.Abstracts)
+	case models.FieldApplicants:
+		return strings.Join(r.Applicants, ";")
+	case models.FieldInventors:
+		return strings.Join(r.Inventors, ";")
+	case models.FieldIPC:
+		return strings.Join(r.IPCList, ";")
+	case models.FieldPublicationDate:
+		return r.PublicationDate
+	case models.FieldNPLCitations:
+		return joinNPL(r.NPLCitations)
+	case models.FieldFamilyID:
+		return r.FamilyID
+	case models.FieldApplicationNumber:
+		return r.ApplicationNumber
+	case models.FieldFilingDate:
+		return r.FilingDate
+	case models.FieldPriorityClaims:
+		return joinPriorities(r.PriorityClaims)
+	case models.FieldEarliestPriorityDate:
+		return r.EarliestPriorityDate
+	case models.FieldCPCHierarchy:
+		return strings.Join(r.CPCHierarchy, ";")
+	case models.FieldCitationProvenance:
+		return joinProvenance(r.CitationProvenance)
+	}
+	for _, extra := range r.Extra {
+		if extra.Name == column {
+			return strings.Join(extra.Values, ";")
+		}
+	}
+	return ""
+}
+
+// joinNPL renders each NPL citation like the citations column: its DOI, or its text
+// when it has none, followed by ':' and the categories.
+func joinNPL(citations []models.NPLCitation) string {
+	parts := make([]string, 0, len(citations))
+	for _, c := range citations {
+		ref := c.DOI
+		if ref == "" {
+			ref = c.Text
+		}
+		if len(c.Categories) > 0 {
+			ref += ":" + strings.Join(c.Categories, ",")
+		}
+		parts = append(parts, ref)
+	}
+	return strings.Join(parts, ";")
+}
+
+// joinPriorities renders each priority claim as its number and date, e.g.
+// US60123456P:20000101.
+func joinPriorities(claims []models.PriorityClaim) string {
+	parts := make([]string, 0, len(claims))
+	for _, c := range claims {
+		part := c.Country + c.DocNumber + c.Kind
+		if c.Date != "" {
+			part += ":" + c.Date
+		}
+		parts = append(parts, part)
+	}
+	return strings.Join(parts, ";")
+}
+
+// joinProvenance renders each entry as cited_id:phase:office, e.g. US5000000A:SEA:EP.
+func joinProvenance(provenance []models.CitationProvenance) string {
+	parts := make([]string, 0, len(provenance))
+	for _, p := range provenance {
+		parts = append(parts, p.CitedID+":"+p.Phase+":"+p.Office)
+	}
+	return strings.Join(parts, ";")
+}
+
+func joinLocalized(texts []models.LocalizedText) string {
+	parts := make([]string, 0, len(texts))
+	for _, t := range texts {
+		parts = append(parts, t.Lang+":"+t.Text)
+	}
+	return strings.Join(parts, ";")
+}
+
+// readCSV passes the records of a csv output to fn in batches. The header names the
+// columns; columns that are not known fields are read back as XPath fields.
+func readCSV(r io.Reader, fn func([]models.PatentRecord) error) error {
+	cr := csv.NewReader(r)
+	header, err := cr.Read()
+	if err != nil {
+		return fmt.Errorf("read CSV header: %w", err)
+	}
+	batch := make([]models.PatentRecord, 0, 1024)
+	for {
+		row, err := cr.Read()
+		if errors.Is(err, io.EOF) {
+			break
+		}
+		if err != nil {
+			return fmt.Errorf("read CSV file: %w", err)
+		}
+		var rec models.PatentRecord
+		for i, column := range header {
+			if i < len(row) {
+				setCSVValue(&rec, column, row[i])
+			}
+		}
+		if batch = append(batch, rec); len(batch) == cap(batch) {
+			if err := fn(batch); err != nil {
+				return err
+			}
+			batch = batch[:0]
+		}
+	}
+	if len(batch) > 0 {
+		return fn(batch)
+	}
+	return nil
+}
+
+// setCSVValue is the inverse of csvValue. It cannot tell where a ';' inside a title or
+// abstract was, reads an NPL citation back as a DOI when it starts with "10." and as
+// text otherwise, and keeps the kind code of a priority claim in its number.
+func setCSVValue(r *models.PatentRecord, column, value string) {
+	switch column {
+	case "patent_id":
+		r.PatentID = value
+	case "status":
+		r.Status = value
+	case "cpc_list":
+		r.CPCList = splitList(value)
+	case "citations":
+		for _, part := range splitList(value) {
+			id, categories, _ := strings.Cut(part, ":")
+			r.Citations = append(r.Citations, models.Citation{CitedID: id, Categories: splitCategories(categories)})
+		}
+	case "family_patents":
+		r.FamilyPatents = splitList(value)
+	case models.FieldTitle:
+		r.Titles = splitLocalized(value)
+	case models.FieldAbstract:
+		r.Abstracts = splitLocalized(value)
+	case models.FieldApplicants:
+		r.Applicants = splitList(value)
+	case models.FieldInventors:
+		r.Inventors = splitList(value)
+	case models.FieldIPC:
+		r.IPCList = splitList(value)
+	case models.FieldPublicationDate:
+		r.PublicationDate = value
+	case models.FieldNPLCitations:
+		for _, part := range splitList(value) {
+			ref, categories := part, ""
+			if i := strings.LastIndex(part, ":"); i >= 0 {
+				ref, categories = part[:i], part[i+1:]
+			}
+			c := models.NPLCitation{Text: ref, Categories: splitCategories(categories)}
+			if strings.HasPrefix(ref, "10.") {
+				c.Text, c.DOI = "", ref
+			}
+			r.NPLCitations = append(r.NPLCitations, c)
+		}
+	case models.FieldFamilyID:
+		r.FamilyID = value
+	case models.FieldApplicationNumber:
+		r.ApplicationNumber = value
+	case models.FieldFilingDate:
+		r.FilingDate = value
+	case models.FieldPriorityClaims:
+		for _, part := range splitList(value) {
+			number, date, _ := strings.Cut(part, ":")
+			claim := models.PriorityClaim{DocNumber: number, Date: date}
+			if len(number) > 2 {
+				claim.Country, claim.DocNumber = number[:2], number[2:]
+			}
+			r.PriorityClaims = append(r.PriorityClaims, claim)
+		}
+	case models.FieldEarliestPriorityDate:
+		r.EarliestPriorityDate = value
+	case models.FieldCPCHierarchy:
+		r.CPCHierarchy = splitList(value)
+	case models.FieldCitationProvenance:
+		for _, part := range splitList(value) {
+			id, rest, _ := strings.Cut(part, ":")
+			phase, office, _ := strings.Cut(rest, ":")
+			r.CitationProvenance = append(r.CitationProvenance,
+				models.CitationProvenance{CitedID: id, Phase: phase, Office: office})
+		}
+	default:
+		if values := splitList(value); len(values) > 0 {
+			r.Extra = append(r.Extra, models.ExtraField{Name: column, Values: values})
+		}
+	}
+}
+
+func splitList(value string) []string {
+	if value == "" {
+		return nil
+	}
+	return strings.Split(value, ";")
+}
+
+func splitCategories(value string) []string {
+	if value == "" {
+		return []string{}
+	}
+	return strings.Split(value, ",")
+}
+
+func splitLocalized(value string) []models.LocalizedText {
+	var texts []models.LocalizedText
+	for _, part := range splitList(value) {
+		lang, text, _ := strings.Cut(part, ":")
+		texts = append(texts, models.LocalizedText{Lang: lang, Text: text})
+	}
+	return texts
+}
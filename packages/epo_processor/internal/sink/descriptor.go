@@ -0,0 +1,208 @@
+package sink
+
+import (
+	"reflect"
+	"strings"
+	"time"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// DescriptorSuffix is appended to the output path to name the dataset descriptor.
+const DescriptorSuffix = ".dataset.json"
+
+// Metadata describes where a dataset comes from; it is recorded in the descriptor.
+type Metadata struct {
+	Snapshot   string
+	Sources    []string
+	Parameters map[string]any
+}
+
+// Descriptor is the machine-readable dataset description written next to each output.
+type Descriptor struct {
+	Path        string         `json:"path"`
+	Format      string         `json:"format"`
+	Snapshot    string         `json:"snapshot,omitempty"`
+	License     string         `json:"license,omitempty"`
+	GeneratedAt time.Time      `json:"generated_at"`
+	Fields      []Field        `json:"fields"`
+	Partitions  []Partition    `json:"partitions"`
+	Rows        int64          `json:"rows"`
+	Sources     []string       `json:"sources"`
+	Parameters  map[string]any `json:"parameters"`
+}
+
+type Field struct {
+	Name string `json:"name"`
+	Type string `json:"type"`
+}
+
+type Partition struct {
+	Path  string `json:"path"`
+	Rows  int64  `json:"rows"`
+	Bytes int64  `json:"bytes,omitempty"`
+}
+
+// describedSink counts rows and writes the descriptor once the inner sink closed cleanly.
+type describedSink struct {
+	inner Sink
+	desc  Descriptor
+}
+
+func withDescriptor(inner Sink, path string, cfg config.Parse, meta Metadata) *describedSink {
+	format := cfg.OutputFormat
+	if format == "" {
+		format = FormatParquet
+	}
+	return &describedSink{
+		inner: inner,
+		desc: Descriptor{
+			Path:       path,
+			Format:     format,
+			Snapshot:   meta.Snapshot,
+			License:    cfg.Descriptor.License,
+			Fields:     describeFields(format, cfg),
+			Sources:    meta.Sources,
+			Parameters: meta.Parameters,
+		},
+	}
+}
+
+func (s *describedSink) Write(records []models.PatentRecord) error {
+	if err := s.inner.Write(records); err != nil {
+		return err
+	}
+	s.desc.Rows += int64(len(records))
+	return nil
+}
+
+func (s *describedSink) Close() error {
+	if err := s.inner.Close(); err != nil {
+		return err
+	}
+	s.desc.GeneratedAt = time.Now().UTC()
+	s.desc.Partitions = []Partition{{Path: s.desc.Path, Rows: s.desc.Rows}}
+	if r, ok := s.inner.(*rotatingSink); ok {
+		s.desc.Partitions = r.Partitions()
+	}
+	if b, ok := s.inner.(*bucketSink); ok {
+		// the dataset is every bucket, not just the rows of this run
+		s.desc.Partitions = b.Partitions()
+		s.desc.Rows = 0
+		for _, p := range s.desc.Partitions {
+			s.desc.Rows += p.Rows
+		}
+	}
+	if d, ok := s.inner.(*duckDBSink); ok {
+		// products may share the database, so the dataset is the whole table
+		s.desc.Rows = d.rows
+		s.desc.Partitions = []Partition{{Path: s.desc.Path, Rows: d.rows}}
+	}
+	return writeJSON(s.desc.Path+DescriptorSuffix, s.desc)
+}
+
+// Abort closes s without finalizing the dataset, for outputs of failed runs.
+func Abort(s Sink) error {
+	if f, ok := s.(*fanoutSink); ok {
+		return f.abort()
+	}
+	if d, ok := s.(*describedSink); ok {
+		s = d.inner
+	}
+	if r, ok := s.(*rotatingSink); ok {
+		return r.abort()
+	}
+	if b, ok := s.(*bucketSink); ok {
+		return b.abort()
+	}
+	if p, ok := s.(*postgresSink); ok {
+		return p.abort()
+	}
+	if d, ok := s.(*duckDBSink); ok {
+		return d.abort()
+	}
+	if c, ok := s.(*clickHouseSink); ok {
+		return c.abort()
+	}
+	if e, ok := s.(*elasticsearchSink); ok {
+		return e.abort()
+	}
+	if f, ok := s.(*familiesSink); ok {
+		return f.abort()
+	}
+	if k, ok := s.(*kafkaSink); ok {
+		return k.abort()
+	}
+	return s.Close()
+}
+
+// describeFields lists the columns of the output. Parquet columns and JSONL keys follow
+// the record struct; the CSV columns of CSVColumns are all strings with lists joined by
+// ';'. For tables, the columns of patents.csv are listed, and the relation tables as
+// table.column; neo4j lists its node and relationship files the same way, with their
+// neo4j-admin headers.
+func describeFields(format string, cfg config.Parse) []Field {
+	extra := fieldColumns(cfg)
+	columns := func(prefix string, names ...string) []Field {
+		fields := make([]Field, 0, len(names))
+		for _, name := range names {
+			fields = append(fields, Field{Name: prefix + name, Type: "string"})
+		}
+		return fields
+	}
+	switch format {
+	case FormatCSV:
+		return columns("", CSVColumns(cfg)...)
+	case FormatTables:
+		fields := columns("", append(append([]string{}, patentsHeader...), extra...)...)
+		fields = append(fields, columns("classifications.", classificationsHeader...)...)
+		fields = append(fields, columns("citations.", citationsHeader...)...)
+		return append(fields, columns("family.", familyHeader...)...)
+	case FormatNeo4j:
+		fields := columns("", neo4jPatentsHeader(extra)...)
+		for _, file := range []struct {
+			name   string
+			header []string
+		}{
+			{"cited_patents.", neo4jCitedPatentsHeader},
+			{"families.", neo4jFamiliesHeader},
+			{"classifications.", neo4jClassificationsHeader},
+			{"cites.", neo4jCitesHeader},
+			{"in_family.", neo4jInFamilyHeader},
+			{"classified_as.", neo4jClassifiedAsHeader},
+		} {
+			fields = append(fields, columns(file.name, file.header...)...)
+		}
+		return fields
+	}
+	return structFields(reflect.TypeOf(models.PatentRecord{}))
+}
+
+func structFields(t reflect.Type) []Field {
+	fields := make([]Field, 0, t.NumField())
+	for i := 0; i < t.NumField(); i++ {
+		f := t.Field(i)
+		name, _, _ := strings.Cut(f.Tag.Get("parquet"), ",")
+		if name == "" || name == "-" {
+			continue
+		}
+		fields = append(fields, Field{Name: name, Type: typeName(f.Type)})
+	}
+	return fields
+}
+
+func typeName(t reflect.Type) string {
+	switch t.Kind() {
+	case reflect.Slice:
+		return "list<" + typeName(t.Elem()) + ">"
+	case reflect.Struct:
+		parts := make([]string, 0, t.NumField())
+		for _, f := range structFields(t) {
+			parts = append(parts, f.Name+":"+f.Type)
+		}
+		return "struct<" + strings.Join(parts, ",") + ">"
+	default:
+		return t.Kind().String()
+	}
+}
@@ -0,0 +1,273 @@
+//go:build duckdb
+
+package sink
+
+import (
+	"database/sql"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"path/filepath"
+	"strings"
+	"sync"
+	"time"
+
+	_ "github.com/marcboeker/go-duckdb"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// DuckDBTable is the table the duckdb format appends the records to.
+const DuckDBTable = "patents"
+
+// duckDBColumns mirrors the record struct; list columns are bound as JSON and cast.
+var duckDBColumns = []struct{ name, typ string }{
+	{"patent_id", "VARCHAR"},
+	{"status", "VARCHAR"},
+	{"cpc_list", "VARCHAR[]"},
+	{"citations", "STRUCT(cited_id VARCHAR, categories VARCHAR[])[]"},
+	{"family_patents", "VARCHAR[]"},
+	{"titles", "STRUCT(lang VARCHAR, text VARCHAR)[]"},
+	{"abstracts", "STRUCT(lang VARCHAR, text VARCHAR)[]"},
+	{"applicants", "VARCHAR[]"},
+	{"inventors", "VARCHAR[]"},
+	{"ipc_list", "VARCHAR[]"},
+	{"publication_date", "VARCHAR"},
+	{"npl_citations", "STRUCT(text VARCHAR, doi VARCHAR, categories VARCHAR[])[]"},
+	{"family_id", "VARCHAR"},
+	{"application_number", "VARCHAR"},
+	{"filing_date", "VARCHAR"},
+	{"priority_claims", "STRUCT(country VARCHAR, doc_number VARCHAR, kind VARCHAR, date VARCHAR)[]"},
+	{"earliest_priority_date", "VARCHAR"},
+	{"cpc_hierarchy", "VARCHAR[]"},
+	{"citation_provenance", "STRUCT(cited_id VARCHAR, phase VARCHAR, office VARCHAR)[]"},
+	{"extra", `STRUCT(name VARCHAR, "values" VARCHAR[])[]`},
+}
+
+// partitionExpressions derive the partition columns of the Parquet export.
+var partitionExpressions = map[string]string{
+	"country": "substr(patent_id, 1, 2)",
+	"year":    "substr(publication_date, 1, 4)",
+}
+
+// duckDBSink appends records to the patents table of an embedded DuckDB database, in
+// one transaction per run. With cfg.PartitionBy, Close also exports the table as
+// Parquet partitioned by those columns (hive layout, e.g. country=EP/year=2024).
+type duckDBSink struct {
+	path   string
+	file   *duckDBFile
+	tx     *sql.Tx
+	insert *sql.Stmt
+	cfg    config.DuckDB
+	// rows is the size of the table once the run is committed
+	rows int64
+}
+
+// duckDBFile is a database opened by one or more sinks. DuckDB allows a single
+// read-write instance per file, so products writing to the same database share it;
+// writer serializes their statements.
+type duckDBFile struct {
+	db     *sql.DB
+	users  int
+	writer sync.Mutex
+}
+
+// duckDBFiles holds the databases open in this process by path.
+var duckDBFiles = struct {
+	sync.Mutex
+	open map[string]*duckDBFile
+}{open: map[string]*duckDBFile{}}
+
+// openDuckDB returns the shared database at path, opening it if no sink has yet. While
+// another process holds the database, opening is retried for up to wait.
+func openDuckDB(path string, wait time.Duration) (*duckDBFile, error) {
+	duckDBFiles.Lock()
+	defer duckDBFiles.Unlock()
+	if f, ok := duckDBFiles.open[path]; ok {
+		f.users++
+		return f, nil
+	}
+	deadline := time.Now().Add(wait)
+	for {
+		db, err := sql.Open("duckdb", path)
+		if err == nil {
+			if err = db.Ping(); err != nil {
+				err = errors.Join(err, db.Close())
+			}
+		}
+		if err == nil {
+			f := &duckDBFile{db: db, users: 1}
+			duckDBFiles.open[path] = f
+			return f, nil
+		}
+		if !strings.Contains(err.Error(), "Could not set lock") {
+			return nil, fmt.Errorf("failed to open duckdb database: %w", err)
+		}
+		if time.Now().After(deadline) {
+			return nil, fmt.Errorf("duckdb database %s is in use by another process: %w", path, err)
+		}
+		time.Sleep(time.Second)
+	}
+}
+
+// releaseDuckDB closes the database at path once its last sink is done with it.
+func releaseDuckDB(path string) error {
+	duckDBFiles.Lock()
+	defer duckDBFiles.Unlock()
+	f := duckDBFiles.open[path]
+	if f.users--; f.users > 0 {
+		return nil
+	}
+	delete(duckDBFiles.open, path)
+	return f.db.Close()
+}
+
+func newDuckDBSink(path string, cfg config.DuckDB) (*duckDBSink, error) {
+	if abs, err := filepath.Abs(path); err == nil {
+		path = abs
+	}
+	file, err := openDuckDB(path, cfg.LockWait)
+	if err != nil {
+		return nil, err
+	}
+	defs := make([]string, len(duckDBColumns))
+	params := make([]string, len(duckDBColumns))
+	for i, c := range duckDBColumns {
+		defs[i] = c.name + " " + c.typ
+		params[i] = "?"
+		if strings.HasSuffix(c.typ, "[]") {
+			params[i] = "?::JSON::" + c.typ
+		}
+	}
+	ddl := []string{
+		fmt.Sprintf("CREATE TABLE IF NOT EXISTS %s (%s)", DuckDBTable, strings.Join(defs, ", ")),
+	}
+	// columns added since a database was created are appended to its table, in order
+	for _, def := range defs {
+		ddl = append(ddl, fmt.Sprintf("ALTER TABLE %s ADD COLUMN IF NOT EXISTS %s", DuckDBTable, def))
+	}
+	file.writer.Lock()
+	for _, stmt := range ddl {
+		if _, err = file.db.Exec(stmt); err != nil {
+			break
+		}
+	}
+	file.writer.Unlock()
+	if err != nil {
+		return nil, errors.Join(
+			fmt.Errorf("failed to create table %s: %w", DuckDBTable, err), releaseDuckDB(path))
+	}
+	tx, err := file.db.Begin()
+	if err != nil {
+		return nil, errors.Join(err, releaseDuckDB(path))
+	}
+	insert, err := tx.Prepare(
+		fmt.Sprintf("INSERT INTO %s VALUES (%s)", DuckDBTable, strings.Join(params, ", ")))
+	if err != nil {
+		return nil, errors.Join(err, tx.Rollback(), releaseDuckDB(path))
+	}
+	return &duckDBSink{path: path, file: file, tx: tx, insert: insert, cfg: cfg}, nil
+}
+
+func (s *duckDBSink) Write(records []models.PatentRecord) error {
+	s.file.writer.Lock()
+	defer s.file.writer.Unlock()
+	for _, r := range records {
+		r = jsonlRecord(r)
+		args := []any{r.PatentID, r.Status}
+		lists := []any{r.CPCList, r.Citations, r.FamilyPatents, r.Titles, r.Abstracts,
+			r.Applicants, r.Inventors, r.IPCList}
+		for _, list := range lists {
+			data, err := json.Marshal(list)
+			if err != nil {
+				return err
+			}
+			args = append(args, string(data))
+		}
+		args = append(args, r.PublicationDate)
+		npl, err := json.Marshal(r.NPLCitations)
+		if err != nil {
+			return err
+		}
+		args = append(args, string(npl), r.FamilyID, r.ApplicationNumber, r.FilingDate)
+		priorities, err := json.Marshal(r.PriorityClaims)
+		if err != nil {
+			return err
+		}
+		args = append(args, string(priorities), r.EarliestPriorityDate)
+		hierarchy, err := json.Marshal(r.CPCHierarchy)
+		if err != nil {
+			return err
+		}
+		provenance, err := json.Marshal(r.CitationProvenance)
+		if err != nil {
+			return err
+		}
+		args = append(args, string(hierarchy), string(provenance))
+		extra, err := json.Marshal(r.Extra)
+		if err != nil {
+			return err
+		}
+		args = append(args, string(extra))
+		if _, err := s.insert.Exec(args...); err != nil {
+			return fmt.Errorf("failed to insert %s: %w", r.PatentID, err)
+		}
+	}
+	return nil
+}
+
+func (s *duckDBSink) Close() error {
+	s.file.writer.Lock()
+	err := s.commit()
+	s.file.writer.Unlock()
+	return errors.Join(err, releaseDuckDB(s.path))
+}
+
+// commit commits the run, counts the table and exports it; the caller holds the writer.
+func (s *duckDBSink) commit() error {
+	if err := errors.Join(s.insert.Close(), s.tx.Commit()); err != nil {
+		return err
+	}
+	if err := s.file.db.QueryRow("SELECT count(*) FROM " + DuckDBTable).Scan(&s.rows); err != nil {
+		return err
+	}
+	if len(s.cfg.PartitionBy) > 0 {
+		return s.export()
+	}
+	return nil
+}
+
+func (s *duckDBSink) abort() error {
+	return errors.Join(s.insert.Close(), s.tx.Rollback(), releaseDuckDB(s.path))
+}
+
+// export writes the whole table, not only this run's rows, to cfg.ExportDir.
+func (s *duckDBSink) export() error {
+	derived := make([]string, len(s.cfg.PartitionBy))
+	for i, column := range s.cfg.PartitionBy {
+		derived[i] = partitionExpressions[column] + " AS " + column
+	}
+	query := fmt.Sprintf(
+		"COPY (SELECT *, %s FROM %s) TO '%s' (FORMAT parquet, PARTITION_BY (%s), OVERWRITE_OR_IGNORE)",
+		strings.Join(derived, ", "),
+		DuckDBTable,
+		strings.ReplaceAll(s.cfg.ExportDir, "'", "''"),
+		strings.Join(s.cfg.PartitionBy, ", "),
+	)
+	if _, err := s.file.db.Exec(query); err != nil {
+		return fmt.Errorf("failed to export partitioned parquet: %w", err)
+	}
+	return nil
+}
+
+func countDuckDBRows(path string) (int64, error) {
+	db, err := sql.Open("duckdb", path+"?access_mode=read_only")
+	if err != nil {
+		return 0, err
+	}
+	defer db.Close()
+	var n int64
+	err = db.QueryRow("SELECT count(*) FROM " + DuckDBTable).Scan(&n)
+	return n, err
+}
@@ -0,0 +1,34 @@
+//go:build !duckdb
+
+package sink
+
+import (
+	"errors"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// DuckDBTable is the table the duckdb format appends the records to.
+const DuckDBTable = "patents"
+
+// errNoDuckDB fails the duckdb format in binaries built without it: DuckDB is a C++
+// library, linked only with cgo and the duckdb build tag.
+var errNoDuckDB = errors.New("the duckdb format needs a binary built with -tags duckdb and cgo (make build-duckdb)")
+
+// duckDBSink stands in for the DuckDB sink of duckdb.go; it is never created.
+type duckDBSink struct {
+	rows int64
+}
+
+func newDuckDBSink(string, config.DuckDB) (*duckDBSink, error) {
+	return nil, errNoDuckDB
+}
+
+func (s *duckDBSink) Write([]models.PatentRecord) error { return errNoDuckDB }
+func (s *duckDBSink) Close() error                      { return errNoDuckDB }
+func (s *duckDBSink) abort() error                      { return nil }
+
+func countDuckDBRows(string) (int64, error) {
+	return 0, errNoDuckDB
+}
@@ -0,0 +1,247 @@
+package sink
+
+import (
+	"bytes"
+	"context"
+	"encoding/json"
+	"fmt"
+	"io"
+	"net/http"
+	"net/url"
+	"strings"
+	"time"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// elasticsearchMapping is the mapping the index is created with: identifiers and codes
+// are keywords, titles and abstracts full text, and dates accept both the DOCDB and the
+// ISO form. Nested lists are plain objects, so their fields are searched as flat arrays.
+var elasticsearchMapping = map[string]any{
+	"properties": map[string]any{
+		"patent_id":        esKeyword,
+		"status":           esKeyword,
+		"cpc_list":         esKeyword,
+		"citations":        esKeywords("cited_id", "categories"),
+		"family_patents":   esKeyword,
+		"titles":           esLocalized,
+		"abstracts":        esLocalized,
+		"applicants":       esName,
+		"inventors":        esName,
+		"ipc_list":         esKeyword,
+		"publication_date": esDate,
+		"npl_citations": map[string]any{"properties": map[string]any{
+			"text":       map[string]any{"type": "text"},
+			"doi":        esKeyword,
+			"categories": esKeyword,
+		}},
+		"family_id":              esKeyword,
+		"application_number":     esKeyword,
+		"filing_date":            esDate,
+		"priority_claims":        esKeywords("country", "doc_number", "kind", "date"),
+		"earliest_priority_date": esDate,
+		"cpc_hierarchy":          esKeyword,
+		"citation_provenance":    esKeywords("cited_id", "phase", "office"),
+		"extra":                  esKeywords("name", "values"),
+	},
+}
+
+var (
+	esKeyword   = map[string]any{"type": "keyword"}
+	esDate      = map[string]any{"type": "date", "format": "yyyy-MM-dd||yyyyMMdd", "ignore_malformed": true}
+	esName      = map[string]any{"type": "text", "fields": map[string]any{"keyword": esKeyword}}
+	esLocalized = map[string]any{"properties": map[string]any{
+		"lang": esKeyword,
+		"text": map[string]any{"type": "text"},
+	}}
+)
+
+// esKeywords maps an object of keyword fields.
+func esKeywords(fields ...string) map[string]any {
+	properties := make(map[string]any, len(fields))
+	for _, f := range fields {
+		properties[f] = esKeyword
+	}
+	return map[string]any{"properties": properties}
+}
+
+// elasticsearchSink indexes records into an Elasticsearch or OpenSearch index with the
+// _bulk API, cfg.BatchRows documents per request, using the patent_id as document id so
+// that a repeated run overwrites instead of duplicating. Documents rejected with 429 or
+// a 5xx status are sent again up to cfg.Retries times; any other rejection fails the run.
+type elasticsearchSink struct {
+	cfg    config.Elasticsearch
+	client *http.Client
+	batch  []models.PatentRecord
+}
+
+func newElasticsearchSink(cfg config.Elasticsearch) (*elasticsearchSink, error) {
+	s := &elasticsearchSink{cfg: cfg, client: &http.Client{Timeout: cfg.Timeout}}
+	if cfg.CreateIndex {
+		if err := s.createIndex(); err != nil {
+			return nil, fmt.Errorf("failed to prepare elasticsearch index %s: %w", cfg.Index, err)
+		}
+	}
+	return s, nil
+}
+
+// createIndex creates the index with elasticsearchMapping unless it exists.
+func (s *elasticsearchSink) createIndex() error {
+	resp, err := s.do(http.MethodHead, url.PathEscape(s.cfg.Index), nil)
+	if err != nil {
+		return err
+	}
+	resp.Body.Close()
+	if resp.StatusCode == http.StatusOK {
+		return nil
+	}
+	body, err := json.Marshal(map[string]any{"mappings": elasticsearchMapping})
+	if err != nil {
+		return err
+	}
+	resp, err = s.do(http.MethodPut, url.PathEscape(s.cfg.Index), body)
+	if err != nil {
+		return err
+	}
+	return readElasticsearchResponse(resp, nil)
+}
+
+func (s *elasticsearchSink) Write(records []models.PatentRecord) error {
+	for _, r := range records {
+		s.batch = append(s.batch, r)
+		if len(s.batch) >= s.cfg.BatchRows {
+			if err := s.flush(); err != nil {
+				return err
+			}
+		}
+	}
+	return nil
+}
+
+// flush indexes the buffered records, retrying the documents rejected as temporary
+// failures with a doubling delay.
+func (s *elasticsearchSink) flush() error {
+	pending := s.batch
+	s.batch = nil
+	delay := time.Second
+	for attempt := 0; len(pending) > 0; attempt++ {
+		retry, err := s.bulk(pending)
+		if err != nil {
+			return fmt.Errorf("failed to index into %s: %w", s.cfg.Index, err)
+		}
+		if len(retry) > 0 && attempt >= s.cfg.Retries {
+			return fmt.Errorf("failed to index %d documents into %s after %d retries",
+				len(retry), s.cfg.Index, s.cfg.Retries)
+		}
+		if len(retry) > 0 {
+			time.Sleep(delay)
+			delay *= 2
+		}
+		pending = retry
+	}
+	return nil
+}
+
+// bulkResponse is the part of a _bulk response that tells which documents failed.
+type bulkResponse struct {
+	Errors bool `json:"errors"`
+	Items  []map[string]struct {
+		Status int             `json:"status"`
+		Error  json.RawMessage `json:"error"`
+	} `json:"items"`
+}
+
+// bulk sends one _bulk request for records and returns those rejected with a status
+// worth retrying, all of them when the whole request was.
+func (s *elasticsearchSink) bulk(records []models.PatentRecord) ([]models.PatentRecord, error) {
+	var body bytes.Buffer
+	enc := json.NewEncoder(&body)
+	enc.SetEscapeHTML(false)
+	for _, r := range records {
+		action := map[string]any{"index": map[string]string{"_index": s.cfg.Index, "_id": r.PatentID}}
+		if err := enc.Encode(action); err != nil {
+			return nil, err
+		}
+		if err := enc.Encode(jsonlRecord(r)); err != nil {
+			return nil, err
+		}
+	}
+	resp, err := s.do(http.MethodPost, "_bulk", body.Bytes())
+	if err != nil {
+		return nil, err
+	}
+	if resp.StatusCode == http.StatusTooManyRequests || resp.StatusCode >= 500 {
+		resp.Body.Close()
+		return records, nil
+	}
+	var res bulkResponse
+	if err := readElasticsearchResponse(resp, &res); err != nil {
+		return nil, err
+	}
+	if !res.Errors {
+		return nil, nil
+	}
+	var retry []models.PatentRecord
+	for i, item := range res.Items {
+		for _, result := range item {
+			switch {
+			case result.Status < 300:
+			case result.Status == http.StatusTooManyRequests || result.Status >= 500:
+				retry = append(retry, records[i])
+			default:
+				return nil, fmt.Errorf("document %s rejected (%d): %s",
+					records[i].PatentID, result.Status, result.Error)
+			}
+		}
+	}
+	return retry, nil
+}
+
+func (s *elasticsearchSink) Close() error {
+	return s.flush()
+}
+
+// abort drops the records not yet indexed.
+func (s *elasticsearchSink) abort() error {
+	s.batch = nil
+	return nil
+}
+
+// do sends a request to path below cfg.URL with the configured credentials.
+func (s *elasticsearchSink) do(method, path string, body []byte) (*http.Response, error) {
+	endpoint, err := url.JoinPath(s.cfg.URL, path)
+	if err != nil {
+		return nil, fmt.Errorf("invalid elasticsearch url: %w", err)
+	}
+	req, err := http.NewRequestWithContext(context.Background(), method, endpoint, bytes.NewReader(body))
+	if err != nil {
+		return nil, err
+	}
+	req.Header.Set("Content-Type", "application/json")
+	if path == "_bulk" {
+		req.Header.Set("Content-Type", "application/x-ndjson")
+	}
+	switch {
+	case s.cfg.APIKey != "":
+		req.Header.Set("Authorization", "ApiKey "+s.cfg.APIKey)
+	case s.cfg.User != "":
+		req.SetBasicAuth(s.cfg.User, s.cfg.Password)
+	}
+	return s.client.Do(req)
+}
+
+// readElasticsearchResponse decodes a successful response into v, if given, and turns
+// any other status into an error with the start of the body.
+func readElasticsearchResponse(resp *http.Response, v any) error {
+	defer resp.Body.Close()
+	if resp.StatusCode < 200 || resp.StatusCode >= 300 {
+		msg, _ := io.ReadAll(io.LimitReader(resp.Body, 4096))
+		return fmt.Errorf("%s: %s", resp.Status, strings.TrimSpace(string(msg)))
+	}
+	if v == nil {
+		_, err := io.Copy(io.Discard, resp.Body)
+		return err
+	}
+	return json.NewDecoder(resp.Body).Decode(v)
+}
@@ -0,0 +1,136 @@
+package sink
+
+import (
+	"encoding/csv"
+	"errors"
+	"fmt"
+	"maps"
+	"os"
+	"slices"
+	"strconv"
+	"strings"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+var (
+	familyMembersHeader = []string{"family_id", "patent_id", "member_id"}
+	familiesHeader      = []string{"family_id", "size", "members"}
+)
+
+// FamilyID identifies the family of r by its DOCDB family id when the family_id field
+// was extracted, else by its lowest publication id.
+func FamilyID(r models.PatentRecord) string {
+	if r.FamilyID != "" {
+		return r.FamilyID
+	}
+	return lowestMember(r)
+}
+
+// lowestMember is the lowest publication id of the family of r, which is the same for
+// every member as long as their family lists are complete. Buckets are keyed on it, so
+// that enabling family_id does not move the families of an existing output.
+func lowestMember(r models.PatentRecord) string {
+	key := r.PatentID
+	for _, id := range r.FamilyPatents {
+		key = min(key, id)
+	}
+	return key
+}
+
+// familyMembersSink writes the family of each record in long format: one row per member,
+// the record itself included, along with the family id. It is written next to the main
+// output from the same records, so the family needs no second parse.
+type familyMembersSink struct {
+	file   *os.File
+	writer *csv.Writer
+}
+
+// NewFamilyMembers opens the family members table at path.
+func NewFamilyMembers(path string) (Sink, error) {
+	f, err := os.Create(path)
+	if err != nil {
+		return nil, fmt.Errorf("failed to create family members file: %w", err)
+	}
+	s := &familyMembersSink{file: f, writer: csv.NewWriter(f)}
+	if err := s.writer.Write(familyMembersHeader); err != nil {
+		return nil, errors.Join(err, f.Close())
+	}
+	return s, nil
+}
+
+func (s *familyMembersSink) Write(records []models.PatentRecord) error {
+	for _, r := range records {
+		family := FamilyID(r)
+		members := append([]string{r.PatentID}, r.FamilyPatents...)
+		slices.Sort(members)
+		for _, member := range slices.Compact(members) {
+			if err := s.writer.Write([]string{family, r.PatentID, member}); err != nil {
+				return err
+			}
+		}
+	}
+	return nil
+}
+
+func (s *familyMembersSink) Close() error {
+	s.writer.Flush()
+	return errors.Join(s.writer.Error(), s.file.Close())
+}
+
+// familiesSink aggregates the families of the whole corpus into one row per family: the
+// family id, the number of members and the sorted members joined with ';'. Every
+// publication seen, as a record or as a family member, is kept in memory with its family
+// until the table is written on Close. A publication belongs to the family of its own
+// record when it has one, else to that of the first record listing it.
+type familiesSink struct {
+	file    *os.File
+	members map[string]string
+}
+
+// NewFamilies opens the families table at path.
+func NewFamilies(path string) (Sink, error) {
+	f, err := os.Create(path)
+	if err != nil {
+		return nil, fmt.Errorf("failed to create families file: %w", err)
+	}
+	return &familiesSink{file: f, members: make(map[string]string)}, nil
+}
+
+func (s *familiesSink) Write(records []models.PatentRecord) error {
+	for _, r := range records {
+		family := FamilyID(r)
+		s.members[r.PatentID] = family
+		for _, member := range r.FamilyPatents {
+			if _, ok := s.members[member]; !ok {
+				s.members[member] = family
+			}
+		}
+	}
+	return nil
+}
+
+func (s *familiesSink) Close() error {
+	families := make(map[string][]string)
+	for member, family := range s.members {
+		families[family] = append(families[family], member)
+	}
+	w := csv.NewWriter(s.file)
+	if err := w.Write(familiesHeader); err != nil {
+		return errors.Join(err, s.file.Close())
+	}
+	for _, family := range slices.Sorted(maps.Keys(families)) {
+		members := families[family]
+		slices.Sort(members)
+		if err := w.Write([]string{family, strconv.Itoa(len(members)), strings.Join(members, ";")}); err != nil {
+			return errors.Join(err, s.file.Close())
+		}
+	}
+	w.Flush()
+	return errors.Join(w.Error(), s.file.Close())
+}
+
+// abort leaves the families table empty instead of writing an incomplete one.
+func (s *familiesSink) abort() error {
+	return s.file.Close()
+}
@@ -0,0 +1,46 @@
+package sink
+
+import (
+	"errors"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// fanoutSink writes every batch to several sinks, so one parse feeds all of them.
+type fanoutSink struct {
+	sinks []Sink
+}
+
+// Fanout returns a sink that writes each batch to all of sinks in order. Closing it
+// closes every sink, even after one of them fails.
+func Fanout(sinks ...Sink) Sink {
+	if len(sinks) == 1 {
+		return sinks[0]
+	}
+	return &fanoutSink{sinks: sinks}
+}
+
+func (s *fanoutSink) Write(records []models.PatentRecord) error {
+	for _, out := range s.sinks {
+		if err := out.Write(records); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+func (s *fanoutSink) Close() error {
+	errs := make([]error, 0, len(s.sinks))
+	for _, out := range s.sinks {
+		errs = append(errs, out.Close())
+	}
+	return errors.Join(errs...)
+}
+
+func (s *fanoutSink) abort() error {
+	errs := make([]error, 0, len(s.sinks))
+	for _, out := range s.sinks {
+		errs = append(errs, Abort(out))
+	}
+	return errors.Join(errs...)
+}
@@ -0,0 +1,86 @@
+package sink
+
+import (
+	"encoding/csv"
+	"errors"
+	"fmt"
+	"maps"
+	"os"
+	"slices"
+	"strconv"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+var forwardCitationsHeader = []string{"patent_id", "forward_citations"}
+
+// ForwardCitationResult counts the patents ForwardCitations read and the rows it wrote.
+type ForwardCitationResult struct {
+	Patents int64 `json:"patents"`
+	Rows    int64 `json:"rows"`
+}
+
+// ForwardCitations counts for each patent_id of the output files ins how many other
+// parsed patents cite it, and writes the counts to out as CSV (patent_id,
+// forward_citations), sorted by patent_id. A citing patent counts once per cited patent,
+// however many citations or categories it lists for it; self-citations and repeated
+// records of the same patent_id are ignored. With external, the publications that are
+// cited but not parsed themselves get a row as well. All counts are held in memory.
+func ForwardCitations(out string, ins []string, external bool) (ForwardCitationResult, error) {
+	var res ForwardCitationResult
+	counts := map[string]int64{}
+	parsed := map[string]bool{}
+	for _, in := range ins {
+		format, err := FormatOf(in)
+		if err != nil {
+			return res, err
+		}
+		err = readRecords(in, format, func(records []models.PatentRecord) error {
+			for _, r := range records {
+				if parsed[r.PatentID] {
+					continue
+				}
+				parsed[r.PatentID] = true
+				cited := make(map[string]struct{}, len(r.Citations))
+				for _, c := range r.Citations {
+					if c.CitedID != "" && c.CitedID != r.PatentID {
+						cited[c.CitedID] = struct{}{}
+					}
+				}
+				for id := range cited {
+					counts[id]++
+				}
+			}
+			return nil
+		})
+		if err != nil {
+			return res, fmt.Errorf("forward citations %s: %w", in, err)
+		}
+	}
+	res.Patents = int64(len(parsed))
+	ids := slices.Collect(maps.Keys(parsed))
+	if external {
+		for id := range counts {
+			if !parsed[id] {
+				ids = append(ids, id)
+			}
+		}
+	}
+	slices.Sort(ids)
+	f, err := os.Create(out)
+	if err != nil {
+		return res, fmt.Errorf("failed to create forward citations file: %w", err)
+	}
+	w := csv.NewWriter(f)
+	if err := w.Write(forwardCitationsHeader); err != nil {
+		return res, errors.Join(err, f.Close())
+	}
+	for _, id := range ids {
+		if err := w.Write([]string{id, strconv.FormatInt(counts[id], 10)}); err != nil {
+			return res, errors.Join(err, f.Close())
+		}
+		res.Rows++
+	}
+	w.Flush()
+	return res, errors.Join(w.Error(), f.Close())
+}
@@ -0,0 +1,86 @@
+package sink
+
+import (
+	"bufio"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"os"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// jsonlSink writes one JSON object per line with the record's nested arrays intact.
+// Empty lists are written as [] rather than null so jq and DuckDB see a stable schema.
+type jsonlSink struct {
+	file    *os.File
+	buf     *bufio.Writer
+	counter *countingWriter
+	enc     *json.Encoder
+}
+
+func newJSONLSink(path string) (*jsonlSink, error) {
+	f, err := os.Create(path)
+	if err != nil {
+		return nil, fmt.Errorf("failed to create JSONL file: %w", err)
+	}
+	return jsonlSinkAt(f, 0), nil
+}
+
+// jsonlSinkAt writes lines to f, which already holds offset bytes of the output.
+func jsonlSinkAt(f *os.File, offset int64) *jsonlSink {
+	buf := bufio.NewWriter(f)
+	counter := &countingWriter{w: buf, n: offset}
+	enc := json.NewEncoder(counter)
+	enc.SetEscapeHTML(false)
+	return &jsonlSink{file: f, buf: buf, counter: counter, enc: enc}
+}
+
+func (s *jsonlSink) Write(records []models.PatentRecord) error {
+	for _, r := range records {
+		if err := s.enc.Encode(jsonlRecord(r)); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+// Size returns the bytes encoded so far, including those still buffered.
+func (s *jsonlSink) Size() int64 {
+	return s.counter.n
+}
+
+func (s *jsonlSink) Flush() error {
+	return s.buf.Flush()
+}
+
+func (s *jsonlSink) Close() error {
+	return errors.Join(s.buf.Flush(), s.file.Close())
+}
+
+func jsonlRecord(r models.PatentRecord) models.PatentRecord {
+	r.CPCList = nonNil(r.CPCList)
+	r.FamilyPatents = nonNil(r.FamilyPatents)
+	citations := make([]models.Citation, len(r.Citations))
+	for i, c := range r.Citations {
+		c.Categories = nonNil(c.Categories)
+		citations[i] = c
+	}
+	r.Citations = citations
+	if r.NPLCitations != nil {
+		npl := make([]models.NPLCitation, len(r.NPLCitations))
+		for i, c := range r.NPLCitations {
+			c.Categories = nonNil(c.Categories)
+			npl[i] = c
+		}
+		r.NPLCitations = npl
+	}
+	return r
+}
+
+func nonNil[T any](s []T) []T {
+	if s == nil {
+		return []T{}
+	}
+	return s
+}
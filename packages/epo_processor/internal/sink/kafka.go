@@ -0,0 +1,199 @@
+package sink
+
+import (
+	"bytes"
+	"context"
+	"crypto/tls"
+	"encoding/json"
+	"fmt"
+	"sync"
+	"time"
+
+	"github.com/twmb/franz-go/pkg/kgo"
+	"github.com/twmb/franz-go/pkg/sasl"
+	"github.com/twmb/franz-go/pkg/sasl/plain"
+	"github.com/twmb/franz-go/pkg/sasl/scram"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+const kafkaClientID = "epo-processor"
+
+// kafkaSink publishes every record as a JSON message to cfg.Topic, alongside the output,
+// with the franz-go client. The client batches the messages per partition up to
+// cfg.MaxMessageBytes, compresses them and retries retriable errors, including leader
+// elections. Keys are hashed onto the partitions with murmur2 like the Java client, so
+// the messages of a patent keep their order; messages without a key stick to a partition
+// per batch. Every cfg.BatchRows messages Write waits until they are acknowledged, so
+// errors surface during the parse and the buffered messages stay bounded. Delivery is at
+// least once: a retried or re-parsed record is published again.
+type kafkaSink struct {
+	cfg    config.Kafka
+	client *kgo.Client
+	rows   int
+
+	mu  sync.Mutex
+	err error
+}
+
+// NewKafka connects to cfg.Brokers and checks that the cluster answers.
+func NewKafka(cfg config.Kafka) (Sink, error) {
+	opts, err := kafkaOptions(cfg)
+	if err != nil {
+		return nil, err
+	}
+	client, err := kgo.NewClient(opts...)
+	if err != nil {
+		return nil, fmt.Errorf("failed to connect to kafka: %w", err)
+	}
+	ctx, cancel := kafkaContext(cfg.Timeout)
+	defer cancel()
+	if err := client.Ping(ctx); err != nil {
+		client.Close()
+		return nil, fmt.Errorf("failed to connect to kafka: %w", err)
+	}
+	return &kafkaSink{cfg: cfg, client: client}, nil
+}
+
+// kafkaOptions translates the sink config into client options.
+func kafkaOptions(cfg config.Kafka) ([]kgo.Opt, error) {
+	opts := []kgo.Opt{
+		kgo.SeedBrokers(cfg.Brokers...),
+		kgo.ClientID(kafkaClientID),
+		kgo.DefaultProduceTopic(cfg.Topic),
+		kgo.RecordPartitioner(kgo.StickyKeyPartitioner(nil)),
+		kgo.ProducerBatchMaxBytes(int32(cfg.MaxMessageBytes)),
+		kgo.RecordRetries(cfg.Retries),
+	}
+	if cfg.Timeout > 0 {
+		opts = append(opts, kgo.DialTimeout(cfg.Timeout), kgo.ProduceRequestTimeout(cfg.Timeout))
+	}
+	if cfg.Acks == "leader" {
+		// idempotent writes need acknowledgements from all in-sync replicas
+		opts = append(opts, kgo.RequiredAcks(kgo.LeaderAck()), kgo.DisableIdempotentWrite())
+	} else {
+		opts = append(opts, kgo.RequiredAcks(kgo.AllISRAcks()))
+	}
+	switch cfg.Compression {
+	case "gzip":
+		opts = append(opts, kgo.ProducerBatchCompression(kgo.GzipCompression()))
+	case "snappy":
+		opts = append(opts, kgo.ProducerBatchCompression(kgo.SnappyCompression()))
+	case "lz4":
+		opts = append(opts, kgo.ProducerBatchCompression(kgo.Lz4Compression()))
+	case "zstd":
+		opts = append(opts, kgo.ProducerBatchCompression(kgo.ZstdCompression()))
+	default:
+		opts = append(opts, kgo.ProducerBatchCompression(kgo.NoCompression()))
+	}
+	if cfg.TLS {
+		opts = append(opts, kgo.DialTLSConfig(&tls.Config{MinVersion: tls.VersionTLS12}))
+	}
+	var mechanism sasl.Mechanism
+	switch cfg.SASL.Mechanism {
+	case "plain":
+		mechanism = plain.Auth{User: cfg.SASL.User, Pass: cfg.SASL.Password}.AsMechanism()
+	case "scram-sha-256":
+		mechanism = scram.Auth{User: cfg.SASL.User, Pass: cfg.SASL.Password}.AsSha256Mechanism()
+	case "scram-sha-512":
+		mechanism = scram.Auth{User: cfg.SASL.User, Pass: cfg.SASL.Password}.AsSha512Mechanism()
+	case "none":
+	default:
+		return nil, fmt.Errorf("unsupported kafka sasl mechanism %q", cfg.SASL.Mechanism)
+	}
+	if mechanism != nil {
+		opts = append(opts, kgo.SASL(mechanism))
+	}
+	return opts, nil
+}
+
+func (s *kafkaSink) Write(records []models.PatentRecord) error {
+	if err := s.failed(); err != nil {
+		return err
+	}
+	for _, r := range records {
+		m, err := kafkaRecord(r, s.cfg.Key)
+		if err != nil {
+			return err
+		}
+		s.client.Produce(context.Background(), m, s.delivered)
+		s.rows++
+	}
+	if s.rows >= s.cfg.BatchRows {
+		return s.flush()
+	}
+	return nil
+}
+
+// kafkaRecord encodes r as the JSON object of the jsonl format, keyed by key.
+func kafkaRecord(r models.PatentRecord, key string) (*kgo.Record, error) {
+	var value bytes.Buffer
+	enc := json.NewEncoder(&value)
+	enc.SetEscapeHTML(false)
+	if err := enc.Encode(jsonlRecord(r)); err != nil {
+		return nil, err
+	}
+	m := &kgo.Record{Value: bytes.TrimSuffix(value.Bytes(), []byte("\n"))}
+	switch key {
+	case "patent_id":
+		m.Key = []byte(r.PatentID)
+	case "family_id":
+		m.Key = []byte(r.FamilyID)
+	}
+	return m, nil
+}
+
+// delivered records the first message the client gave up on.
+func (s *kafkaSink) delivered(_ *kgo.Record, err error) {
+	if err == nil {
+		return
+	}
+	s.mu.Lock()
+	defer s.mu.Unlock()
+	if s.err == nil {
+		s.err = fmt.Errorf("failed to publish to kafka topic %s: %w", s.cfg.Topic, err)
+	}
+}
+
+func (s *kafkaSink) failed() error {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+	return s.err
+}
+
+// flush waits until the produced messages are acknowledged or given up on.
+func (s *kafkaSink) flush() error {
+	// retries may take a few request timeouts, e.g. across a leader election
+	ctx, cancel := kafkaContext(time.Duration(s.cfg.Retries+1) * s.cfg.Timeout)
+	defer cancel()
+	if err := s.client.Flush(ctx); err != nil {
+		return fmt.Errorf("failed to publish to kafka topic %s: %w", s.cfg.Topic, err)
+	}
+	s.rows = 0
+	return s.failed()
+}
+
+func (s *kafkaSink) Close() error {
+	err := s.flush()
+	s.client.Close()
+	return err
+}
+
+// abort drops the unpublished messages and closes the client.
+func (s *kafkaSink) abort() error {
+	ctx, cancel := kafkaContext(s.cfg.Timeout)
+	defer cancel()
+	err := s.client.AbortBufferedRecords(ctx)
+	s.client.Close()
+	s.rows = 0
+	return err
+}
+
+// kafkaContext bounds a wait by timeout, or not at all when it is 0.
+func kafkaContext(timeout time.Duration) (context.Context, context.CancelFunc) {
+	if timeout <= 0 {
+		return context.WithCancel(context.Background())
+	}
+	return context.WithTimeout(context.Background(), timeout)
+}
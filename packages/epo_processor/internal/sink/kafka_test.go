@@ -0,0 +1,69 @@
+package sink
+
+import (
+	"encoding/json"
+	"strings"
+	"testing"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+func TestKafkaRecord(t *testing.T) {
+	r := models.PatentRecord{PatentID: "EP1234567A1", FamilyID: "42", Status: "A1",
+		Titles: []models.LocalizedText{{Lang: "en", Text: "Anode <lithium> & cathode"}}}
+	tests := []struct {
+		key  string
+		want string
+	}{
+		{"patent_id", "EP1234567A1"},
+		{"family_id", "42"},
+		{"none", ""},
+	}
+	for _, tt := range tests {
+		t.Run(tt.key, func(t *testing.T) {
+			m, err := kafkaRecord(r, tt.key)
+			if err != nil {
+				t.Fatalf("kafkaRecord: %v", err)
+			}
+			if tt.want == "" && m.Key != nil {
+				t.Errorf("key = %q, want none", m.Key)
+			}
+			if string(m.Key) != tt.want {
+				t.Errorf("key = %q, want %q", m.Key, tt.want)
+			}
+			var got map[string]any
+			if err := json.Unmarshal(m.Value, &got); err != nil {
+				t.Fatalf("value is not a JSON object: %v\n%s", err, m.Value)
+			}
+			if got["patent_id"] != "EP1234567A1" {
+				t.Errorf("patent_id = %v", got["patent_id"])
+			}
+			// list columns are [] rather than null, as in the jsonl output
+			if cpc, ok := got["cpc_list"].([]any); !ok || len(cpc) != 0 {
+				t.Errorf("cpc_list = %#v, want []", got["cpc_list"])
+			}
+			if !strings.Contains(string(m.Value), "Anode <lithium> & cathode") {
+				t.Errorf("title HTML-escaped in %s", m.Value)
+			}
+			if last := m.Value[len(m.Value)-1]; last != '}' {
+				t.Errorf("value ends with %q, want the closing brace", last)
+			}
+		})
+	}
+}
+
+func TestKafkaOptions(t *testing.T) {
+	cfg := config.Kafka{Brokers: []string{"localhost:9092"}, Topic: "t", Acks: "all",
+		Compression: "zstd", MaxMessageBytes: 1 << 20, SASL: config.KafkaSASL{Mechanism: "none"}}
+	for _, mechanism := range []string{"none", "plain", "scram-sha-256", "scram-sha-512"} {
+		cfg.SASL = config.KafkaSASL{Mechanism: mechanism, User: "u", Password: "p"}
+		if _, err := kafkaOptions(cfg); err != nil {
+			t.Errorf("kafkaOptions with sasl %s: %v", mechanism, err)
+		}
+	}
+	cfg.SASL.Mechanism = "gssapi"
+	if _, err := kafkaOptions(cfg); err == nil {
+		t.Error("kafkaOptions accepted the unsupported sasl mechanism gssapi")
+	}
+}
@@ -0,0 +1,80 @@
+package sink
+
+import (
+	"errors"
+	"fmt"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// MergeResult counts the records Merge wrote and the older duplicates it dropped.
+type MergeResult struct {
+	Records    int64 `json:"records"`
+	Duplicates int64 `json:"duplicates"`
+}
+
+// rowRef locates a record: the input it is in and its position there.
+type rowRef struct {
+	input int
+	row   int64
+}
+
+// Merge writes the union of the output files ins to out, keeping one record per
+// patent_id. ins are ordered oldest first, e.g. the back-file baseline followed by the
+// weekly front-file outputs, and the record of the latest input wins; within one input
+// the last row wins. Each input is read twice: once to find the rows to keep, then to
+// write them. The formats are chosen by the file extensions, as for Convert.
+func Merge(out string, ins []string, cfg config.Parse) (MergeResult, error) {
+	var res MergeResult
+	formats := make([]string, len(ins))
+	for i, in := range ins {
+		format, err := FormatOf(in)
+		if err != nil {
+			return res, err
+		}
+		formats[i] = format
+	}
+	keep := map[string]rowRef{}
+	for i, in := range ins {
+		var row int64
+		err := readRecords(in, formats[i], func(records []models.PatentRecord) error {
+			for _, r := range records {
+				if _, dup := keep[r.PatentID]; dup {
+					res.Duplicates++
+				}
+				keep[r.PatentID] = rowRef{input: i, row: row}
+				row++
+			}
+			return nil
+		})
+		if err != nil {
+			return res, fmt.Errorf("merge %s: %w", in, err)
+		}
+	}
+	s, err := newFileSink(out, cfg, Metadata{Sources: ins, Parameters: map[string]any{"merged": len(ins)}})
+	if err != nil {
+		return res, err
+	}
+	for i, in := range ins {
+		var row int64
+		err := readRecords(in, formats[i], func(records []models.PatentRecord) error {
+			kept := make([]models.PatentRecord, 0, len(records))
+			for _, r := range records {
+				if keep[r.PatentID] == (rowRef{input: i, row: row}) {
+					kept = append(kept, r)
+				}
+				row++
+			}
+			res.Records += int64(len(kept))
+			if len(kept) == 0 {
+				return nil
+			}
+			return s.Write(kept)
+		})
+		if err != nil {
+			return res, errors.Join(fmt.Errorf("merge %s: %w", in, err), Abort(s))
+		}
+	}
+	return res, s.Close()
+}
@@ -0,0 +1,197 @@
+package sink
+
+import (
+	"encoding/csv"
+	"errors"
+	"fmt"
+	"maps"
+	"os"
+	"path/filepath"
+	"slices"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// Files written by the neo4j format, relative to the output directory.
+const (
+	Neo4jPatents         = "patents.csv"
+	Neo4jCitedPatents    = "cited_patents.csv"
+	Neo4jFamilies        = "families.csv"
+	Neo4jClassifications = "classifications.csv"
+	Neo4jCites           = "cites.csv"
+	Neo4jInFamily        = "in_family.csv"
+	Neo4jClassifiedAs    = "classified_as.csv"
+)
+
+var (
+	neo4jCitedPatentsHeader    = []string{"patent_id:ID(Patent)", ":LABEL"}
+	neo4jFamiliesHeader        = []string{"family_id:ID(Family)", ":LABEL"}
+	neo4jClassificationsHeader = []string{"classification_id:ID(Classification)", "scheme", "symbol", ":LABEL"}
+	neo4jCitesHeader           = []string{":START_ID(Patent)", ":END_ID(Patent)", "category", "cited_phase", "office", ":TYPE"}
+	neo4jInFamilyHeader        = []string{":START_ID(Patent)", ":END_ID(Family)", ":TYPE"}
+	neo4jClassifiedAsHeader    = []string{":START_ID(Patent)", ":END_ID(Classification)", ":TYPE"}
+)
+
+// neo4jPatentsHeader is the header of patents.csv. The list fields are typed string[],
+// which neo4j-admin splits on its default array delimiter ';'.
+func neo4jPatentsHeader(fields []string) []string {
+	header := []string{"patent_id:ID(Patent)", "status"}
+	for _, field := range fields {
+		switch field {
+		case models.FieldApplicants, models.FieldInventors, models.FieldIPC, models.FieldCPCHierarchy:
+			header = append(header, field+":string[]")
+		default:
+			header = append(header, field)
+		}
+	}
+	return append(header, ":LABEL")
+}
+
+// neo4jSink writes node and relationship CSVs for `neo4j-admin database import full`:
+// a Patent node per record (with the configured fields), Family and Classification
+// nodes, and CITES, IN_FAMILY and CLASSIFIED_AS relationships. Nodes must be unique and
+// every relationship needs both ends, so the ids written so far are kept in memory;
+// patents that are only cited or family members are written to cited_patents.csv when
+// the sink is closed. A patent parsed twice keeps the node of its first record.
+type neo4jSink struct {
+	files           []*os.File
+	tables          map[string]*csv.Writer
+	fields          []string
+	patents         map[string]struct{}
+	referenced      map[string]struct{}
+	families        map[string]struct{}
+	classifications map[string]struct{}
+	inFamily        map[string]struct{}
+}
+
+func newNeo4jSink(dir string, fields []string) (*neo4jSink, error) {
+	if err := os.MkdirAll(dir, 0o755); err != nil {
+		return nil, fmt.Errorf("failed to create output directory: %w", err)
+	}
+	s := &neo4jSink{
+		tables:          make(map[string]*csv.Writer),
+		fields:          fields,
+		patents:         make(map[string]struct{}),
+		referenced:      make(map[string]struct{}),
+		families:        make(map[string]struct{}),
+		classifications: make(map[string]struct{}),
+		inFamily:        make(map[string]struct{}),
+	}
+	headers := map[string][]string{
+		Neo4jPatents:         neo4jPatentsHeader(fields),
+		Neo4jCitedPatents:    neo4jCitedPatentsHeader,
+		Neo4jFamilies:        neo4jFamiliesHeader,
+		Neo4jClassifications: neo4jClassificationsHeader,
+		Neo4jCites:           neo4jCitesHeader,
+		Neo4jInFamily:        neo4jInFamilyHeader,
+		Neo4jClassifiedAs:    neo4jClassifiedAsHeader,
+	}
+	for name, header := range headers {
+		f, err := os.Create(filepath.Join(dir, name))
+		if err != nil {
+			return nil, errors.Join(fmt.Errorf("failed to create %s: %w", name, err), s.close())
+		}
+		s.files = append(s.files, f)
+		w := csv.NewWriter(f)
+		s.tables[name] = w
+		if err := w.Write(header); err != nil {
+			return nil, errors.Join(err, s.close())
+		}
+	}
+	return s, nil
+}
+
+func (s *neo4jSink) Write(records []models.PatentRecord) error {
+	for _, r := range records {
+		if _, ok := s.patents[r.PatentID]; ok {
+			continue
+		}
+		s.patents[r.PatentID] = struct{}{}
+		if err := s.write(r); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+// write adds the nodes and relationships of r.
+func (s *neo4jSink) write(r models.PatentRecord) error {
+	row := append(patentRow(r, s.fields), "Patent")
+	if err := s.tables[Neo4jPatents].Write(row); err != nil {
+		return err
+	}
+	for _, row := range citationEdges(r) {
+		s.referenced[row[1]] = struct{}{}
+		if err := s.tables[Neo4jCites].Write(append(row, "CITES")); err != nil {
+			return err
+		}
+	}
+	family := FamilyID(r)
+	if _, ok := s.families[family]; !ok {
+		s.families[family] = struct{}{}
+		if err := s.tables[Neo4jFamilies].Write([]string{family, "Family"}); err != nil {
+			return err
+		}
+	}
+	for _, member := range append([]string{r.PatentID}, r.FamilyPatents...) {
+		if _, ok := s.inFamily[member]; ok {
+			continue
+		}
+		s.inFamily[member] = struct{}{}
+		s.referenced[member] = struct{}{}
+		if err := s.tables[Neo4jInFamily].Write([]string{member, family, "IN_FAMILY"}); err != nil {
+			return err
+		}
+	}
+	for _, symbol := range r.CPCList {
+		if err := s.classify(r.PatentID, "CPC", symbol); err != nil {
+			return err
+		}
+	}
+	for _, symbol := range r.IPCList {
+		if err := s.classify(r.PatentID, "IPC", symbol); err != nil {
+			return err
+		}
+	}
+	return nil
+}
+
+// classify links patent to the Classification node of symbol, identified as
+// <scheme>:<symbol> so that the CPC and IPC nodes of a symbol stay apart.
+func (s *neo4jSink) classify(patent, scheme, symbol string) error {
+	id := scheme + ":" + symbol
+	if _, ok := s.classifications[id]; !ok {
+		s.classifications[id] = struct{}{}
+		row := []string{id, scheme, symbol, "Classification"}
+		if err := s.tables[Neo4jClassifications].Write(row); err != nil {
+			return err
+		}
+	}
+	return s.tables[Neo4jClassifiedAs].Write([]string{patent, id, "CLASSIFIED_AS"})
+}
+
+// Close writes the patents that were referenced but not parsed, then closes the files.
+func (s *neo4jSink) Close() error {
+	var err error
+	for _, id := range slices.Sorted(maps.Keys(s.referenced)) {
+		if _, ok := s.patents[id]; ok {
+			continue
+		}
+		if err = s.tables[Neo4jCitedPatents].Write([]string{id, "Patent"}); err != nil {
+			break
+		}
+	}
+	return errors.Join(err, s.close())
+}
+
+func (s *neo4jSink) close() error {
+	var errs []error
+	for _, w := range s.tables {
+		w.Flush()
+		errs = append(errs, w.Error())
+	}
+	for _, f := range s.files {
+		errs = append(errs, f.Close())
+	}
+	return errors.Join(errs...)
+}
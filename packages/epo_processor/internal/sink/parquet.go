@@ -0,0 +1,35 @@
+package sink
+
+import (
+	"errors"
+	"fmt"
+	"os"
+
+	"github.com/parquet-go/parquet-go"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// parquetSink writes records with list-typed columns for classifications,
+// citations and family members.
+type parquetSink struct {
+	file   *os.File
+	writer *parquet.GenericWriter[models.PatentRecord]
+}
+
+func newParquetSink(path string) (*parquetSink, error) {
+	f, err := os.Create(path)
+	if err != nil {
+		return nil, fmt.Errorf("failed to create Parquet file: %w", err)
+	}
+	return &parquetSink{file: f, writer: parquet.NewGenericWriter[models.PatentRecord](f)}, nil
+}
+
+func (s *parquetSink) Write(records []models.PatentRecord) error {
+	_, err := s.writer.Write(records)
+	return err
+}
+
+func (s *parquetSink) Close() error {
+	return errors.Join(s.writer.Close(), s.file.Close())
+}
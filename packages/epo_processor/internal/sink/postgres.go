@@ -0,0 +1,139 @@
+package sink
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"slices"
+	"strings"
+
+	"github.com/jackc/pgx/v5"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// postgresSink loads records into the tables of the tables format (patents,
+// classifications, citations and family) with the COPY protocol. All batches of a
+// run share one transaction: Close commits it and Abort rolls it back, so a failed
+// run leaves the database unchanged. With Replace, the tables are truncated first.
+type postgresSink struct {
+	ctx     context.Context
+	conn    *pgx.Conn
+	tx      pgx.Tx
+	schema  string
+	fields  []string
+	columns map[string][]string
+}
+
+func newPostgresSink(cfg config.Postgres, fields []string) (*postgresSink, error) {
+	ctx := context.Background()
+	conn, err := pgx.Connect(ctx, cfg.DSN)
+	if err != nil {
+		return nil, fmt.Errorf("failed to connect to postgres: %w", err)
+	}
+	s := &postgresSink{
+		ctx:    ctx,
+		conn:   conn,
+		schema: cfg.Schema,
+		fields: fields,
+		columns: map[string][]string{
+			TablePatents:         append(slices.Clone(patentsHeader), fields...),
+			TableClassifications: classificationsHeader,
+			TableCitations:       citationsHeader,
+			TableFamily:          familyHeader,
+		},
+	}
+	if err := s.createTables(); err != nil {
+		return nil, errors.Join(err, conn.Close(ctx))
+	}
+	s.tx, err = conn.Begin(ctx)
+	if err != nil {
+		return nil, errors.Join(fmt.Errorf("failed to begin transaction: %w", err), conn.Close(ctx))
+	}
+	if cfg.Replace {
+		for table := range s.columns {
+			if _, err := s.tx.Exec(ctx, "TRUNCATE "+s.tableName(table).Sanitize()); err != nil {
+				return nil, errors.Join(
+					fmt.Errorf("failed to truncate postgres tables: %w", err), s.abort())
+			}
+		}
+	}
+	return s, nil
+}
+
+// tableName maps a table file of the tables format (patents.csv) to its table.
+func (s *postgresSink) tableName(table string) pgx.Identifier {
+	return pgx.Identifier{s.schema, strings.TrimSuffix(table, ".csv")}
+}
+
+// createTables creates the schema and the tables outside the load transaction, each
+// under an advisory lock on its name, so products loading into the same database at
+// once do not race on the catalog.
+func (s *postgresSink) createTables() error {
+	schema := pgx.Identifier{s.schema}.Sanitize()
+	if err := s.locked(schema, "CREATE SCHEMA IF NOT EXISTS "+schema); err != nil {
+		return fmt.Errorf("failed to prepare postgres tables: %w", err)
+	}
+	for table, columns := range s.columns {
+		defs := make([]string, len(columns))
+		for i, column := range columns {
+			defs[i] = pgx.Identifier{column}.Sanitize() + " text"
+		}
+		name := s.tableName(table).Sanitize()
+		stmt := fmt.Sprintf("CREATE TABLE IF NOT EXISTS %s (%s)", name, strings.Join(defs, ", "))
+		if err := s.locked(name, stmt); err != nil {
+			return fmt.Errorf("failed to prepare postgres tables: %w", err)
+		}
+	}
+	return nil
+}
+
+// locked runs stmt while holding the session advisory lock keyed by name.
+func (s *postgresSink) locked(name, stmt string) error {
+	key := "epo_processor:" + name
+	if _, err := s.conn.Exec(s.ctx, "SELECT pg_advisory_lock(hashtext($1))", key); err != nil {
+		return err
+	}
+	_, err := s.conn.Exec(s.ctx, stmt)
+	_, unlockErr := s.conn.Exec(s.ctx, "SELECT pg_advisory_unlock(hashtext($1))", key)
+	return errors.Join(err, unlockErr)
+}
+
+func (s *postgresSink) Write(records []models.PatentRecord) error {
+	rows := make(map[string][][]any, len(s.columns))
+	for _, r := range records {
+		rows[TablePatents] = append(rows[TablePatents], values(patentRow(r, s.fields)))
+		for table, relations := range relationRows(r) {
+			for _, row := range relations {
+				rows[table] = append(rows[table], values(row))
+			}
+		}
+	}
+	for table, batch := range rows {
+		_, err := s.tx.CopyFrom(s.ctx, s.tableName(table), s.columns[table], pgx.CopyFromRows(batch))
+		if err != nil {
+			return fmt.Errorf("failed to copy into %s: %w", s.tableName(table).Sanitize(), err)
+		}
+	}
+	return nil
+}
+
+func (s *postgresSink) Close() error {
+	if err := s.tx.Commit(s.ctx); err != nil {
+		return errors.Join(fmt.Errorf("failed to commit: %w", err), s.conn.Close(s.ctx))
+	}
+	return s.conn.Close(s.ctx)
+}
+
+func (s *postgresSink) abort() error {
+	return errors.Join(s.tx.Rollback(s.ctx), s.conn.Close(s.ctx))
+}
+
+func values(row []string) []any {
+	out := make([]any, len(row))
+	for i, v := range row {
+		out[i] = v
+	}
+	return out
+}
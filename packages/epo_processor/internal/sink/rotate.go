@@ -0,0 +1,201 @@
+package sink
+
+import (
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+	"strings"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// PartsSuffix is appended to the output path to name the index of a rotated output.
+const PartsSuffix = ".parts.json"
+
+// PartsIndex lists the numbered files a rotated output was split into, in order.
+type PartsIndex struct {
+	Format string      `json:"format"`
+	Rows   int64       `json:"rows"`
+	Parts  []Partition `json:"parts"`
+}
+
+// sizer reports the bytes a sink has written so far, including buffered output.
+type sizer interface {
+	Size() int64
+}
+
+// rotatingSink splits the output into numbered parts (data.part-00001.csv, ...) that
+// each start with their own header. Row limits are exact; the byte limit is checked
+// after every batch, so a part may exceed it by at most one batch.
+type rotatingSink struct {
+	path     string
+	format   string
+	maxRows  int64
+	maxBytes int64
+	open     func(path string) (Sink, error)
+	current  Sink
+	parts    []Partition
+}
+
+func newRotatingSink(path, format string, cfg config.Rotation, open func(string) (Sink, error)) *rotatingSink {
+	return &rotatingSink{
+		path:     path,
+		format:   format,
+		maxRows:  int64(cfg.MaxRowsPerFile),
+		maxBytes: int64(cfg.MaxBytesPerFile),
+		open:     open,
+	}
+}
+
+// PartPath names the n-th part (1-based) of a rotated output at path.
+func PartPath(path string, n int) string {
+	ext := filepath.Ext(path)
+	return fmt.Sprintf("%s.part-%05d%s", strings.TrimSuffix(path, ext), n, ext)
+}
+
+func (s *rotatingSink) Write(records []models.PatentRecord) error {
+	for len(records) > 0 {
+		if s.current == nil {
+			if err := s.openPart(); err != nil {
+				return err
+			}
+		}
+		part := &s.parts[len(s.parts)-1]
+		n := int64(len(records))
+		if s.maxRows > 0 {
+			n = min(n, s.maxRows-part.Rows)
+		}
+		if err := s.current.Write(records[:n]); err != nil {
+			return err
+		}
+		part.Rows += n
+		records = records[n:]
+		if s.full(*part) {
+			if err := s.closePart(); err != nil {
+				return err
+			}
+		}
+	}
+	return nil
+}
+
+func (s *rotatingSink) full(part Partition) bool {
+	if s.maxRows > 0 && part.Rows >= s.maxRows {
+		return true
+	}
+	if sz, ok := s.current.(sizer); ok && s.maxBytes > 0 {
+		return sz.Size() >= s.maxBytes
+	}
+	return false
+}
+
+func (s *rotatingSink) openPart() error {
+	path := PartPath(s.path, len(s.parts)+1)
+	current, err := s.open(path)
+	if err != nil {
+		return err
+	}
+	s.current = current
+	s.parts = append(s.parts, Partition{Path: path})
+	return nil
+}
+
+func (s *rotatingSink) closePart() error {
+	err := s.current.Close()
+	s.current = nil
+	if err != nil {
+		return err
+	}
+	part := &s.parts[len(s.parts)-1]
+	fi, err := os.Stat(part.Path)
+	if err != nil {
+		return err
+	}
+	part.Bytes = fi.Size()
+	return nil
+}
+
+// Close finishes the last part and writes the parts index. An empty output still
+// gets one part, so readers always find the header.
+func (s *rotatingSink) Close() error {
+	if s.current == nil && len(s.parts) == 0 {
+		if err := s.openPart(); err != nil {
+			return err
+		}
+	}
+	if s.current != nil {
+		if err := s.closePart(); err != nil {
+			return err
+		}
+	}
+	index := PartsIndex{Format: s.format, Parts: s.parts}
+	for _, p := range s.parts {
+		index.Rows += p.Rows
+	}
+	return writeJSON(s.path+PartsSuffix, index)
+}
+
+// Partitions lists the parts written so far.
+func (s *rotatingSink) Partitions() []Partition {
+	return s.parts
+}
+
+// abort closes the open part without writing the parts index.
+func (s *rotatingSink) abort() error {
+	if s.current == nil {
+		return nil
+	}
+	err := s.current.Close()
+	s.current = nil
+	return err
+}
+
+// ReadPartsIndex loads the parts index of a rotated output at path.
+func ReadPartsIndex(path string) (PartsIndex, error) {
+	var index PartsIndex
+	data, err := os.ReadFile(path + PartsSuffix)
+	if err != nil {
+		return index, err
+	}
+	if err := json.Unmarshal(data, &index); err != nil {
+		return index, fmt.Errorf("decode parts index: %w", err)
+	}
+	return index, nil
+}
+
+// countingWriter counts the bytes passed through to w.
+type countingWriter struct {
+	w io.Writer
+	n int64
+}
+
+func (c *countingWriter) Write(p []byte) (int, error) {
+	n, err := c.w.Write(p)
+	c.n += int64(n)
+	return n, err
+}
+
+func writeJSON(path string, v any) error {
+	data, err := json.MarshalIndent(v, "", "  ")
+	if err != nil {
+		return err
+	}
+	// a temp file of its own, as products sharing a database write the same descriptor
+	f, err := os.CreateTemp(filepath.Dir(path), filepath.Base(path)+".*.tmp")
+	if err != nil {
+		return fmt.Errorf("write %s: %w", filepath.Base(path), err)
+	}
+	tmp := f.Name()
+	_, err = f.Write(append(data, '\n'))
+	if err = errors.Join(err, f.Chmod(0o644), f.Close()); err == nil {
+		err = os.Rename(tmp, path)
+	}
+	if err != nil {
+		return errors.Join(fmt.Errorf("write %s: %w", filepath.Base(path), err), os.Remove(tmp))
+	}
+	return nil
+}
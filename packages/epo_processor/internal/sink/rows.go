@@ -0,0 +1,136 @@
+package sink
+
+import (
+	"bufio"
+	"encoding/csv"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io"
+	"os"
+	"path/filepath"
+
+	"github.com/parquet-go/parquet-go"
+)
+
+// ReadDescriptor loads the dataset descriptor written next to the output at path.
+func ReadDescriptor(path string) (Descriptor, error) {
+	var desc Descriptor
+	data, err := os.ReadFile(path + DescriptorSuffix)
+	if err != nil {
+		return desc, err
+	}
+	if err := json.Unmarshal(data, &desc); err != nil {
+		return desc, fmt.Errorf("decode dataset descriptor: %w", err)
+	}
+	return desc, nil
+}
+
+// CountRows counts the records stored in an output file of the given format. For a
+// rotated output, the rows of all parts listed in its parts index are summed.
+func CountRows(path, format string) (int64, error) {
+	if index, err := ReadPartsIndex(path); err == nil {
+		var rows int64
+		for _, p := range index.Parts {
+			n, err := CountRows(p.Path, format)
+			if err != nil {
+				return 0, fmt.Errorf("%s: %w", p.Path, err)
+			}
+			rows += n
+		}
+		return rows, nil
+	} else if !errors.Is(err, os.ErrNotExist) {
+		return 0, err
+	}
+	if index, err := ReadBucketsIndex(path); err == nil {
+		var rows int64
+		for _, p := range index.Parts {
+			n, err := CountRows(p.Path, format)
+			if err != nil {
+				return 0, fmt.Errorf("%s: %w", p.Path, err)
+			}
+			rows += n
+		}
+		return rows, nil
+	} else if !errors.Is(err, os.ErrNotExist) {
+		return 0, err
+	}
+	switch format {
+	case "", FormatParquet:
+		return countParquetRows(path)
+	case FormatCSV:
+		return countCSVRows(path)
+	case FormatJSONL:
+		return countLines(path)
+	case FormatTables:
+		return countCSVRows(filepath.Join(path, TablePatents))
+	case FormatDuckDB:
+		return countDuckDBRows(path)
+	case FormatNeo4j:
+		return countCSVRows(filepath.Join(path, Neo4jPatents))
+	case FormatArrow:
+		return countArrowRows(path)
+	default:
+		return 0, fmt.Errorf("unsupported output format %q", format)
+	}
+}
+
+func countParquetRows(path string) (int64, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return 0, err
+	}
+	defer f.Close()
+	fi, err := f.Stat()
+	if err != nil {
+		return 0, err
+	}
+	pf, err := parquet.OpenFile(f, fi.Size())
+	if err != nil {
+		return 0, fmt.Errorf("open Parquet file: %w", err)
+	}
+	return pf.NumRows(), nil
+}
+
+func countLines(path string) (int64, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return 0, err
+	}
+	defer f.Close()
+	sc := bufio.NewScanner(f)
+	sc.Buffer(make([]byte, 0, 64*1024), 64*1024*1024)
+	var lines int64
+	for sc.Scan() {
+		lines++
+	}
+	return lines, sc.Err()
+}
+
+// countCSVRows counts data rows, excluding the header. Quoted fields may span lines,
+// so rows are counted with a CSV reader rather than by newlines.
+func countCSVRows(path string) (int64, error) {
+	f, err := os.Open(path)
+	if err != nil {
+		return 0, err
+	}
+	defer f.Close()
+	r := csv.NewReader(f)
+	r.FieldsPerRecord = -1
+	r.ReuseRecord = true
+	var rows int64
+	for {
+		_, err := r.Read()
+		if errors.Is(err, io.EOF) {
+			break
+		}
+		if err != nil {
+			return 0, fmt.Errorf("read CSV file: %w", err)
+		}
+		rows++
+	}
+	if rows == 0 {
+		return 0, nil
+	}
+	return rows - 1, nil
+}
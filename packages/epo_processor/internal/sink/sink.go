@@ -0,0 +1,124 @@
+package sink
+
+import (
+	"errors"
+	"fmt"
+	"slices"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+const (
+	FormatParquet = "parquet"
+	FormatCSV     = "csv"
+	FormatJSONL   = "jsonl"
+	FormatTables  = "tables"
+	// FormatPostgres loads the tables of FormatTables into cfg.Postgres instead of files.
+	FormatPostgres = "postgres"
+	// FormatDuckDB appends to the patents table of a DuckDB database file at the path.
+	FormatDuckDB = "duckdb"
+	// FormatNeo4j writes node and relationship CSVs for neo4j-admin into the directory at
+	// the path.
+	FormatNeo4j = "neo4j"
+	// FormatClickHouse inserts into the cfg.ClickHouse table over HTTP instead of a file.
+	FormatClickHouse = "clickhouse"
+	// FormatElasticsearch indexes into the cfg.Elasticsearch index with the _bulk API.
+	FormatElasticsearch = "elasticsearch"
+	// FormatArrow writes an Arrow IPC file (Feather v2) with one record batch per write.
+	FormatArrow = "arrow"
+)
+
+// Sink receives batches of parsed records. The parser serializes calls to Write,
+// so implementations need not be safe for concurrent use.
+type Sink interface {
+	Write(records []models.PatentRecord) error
+	Close() error
+}
+
+// New opens the sink selected by cfg.OutputFormat at path. When cfg.Rotation sets a
+// limit, the output is split into numbered parts listed in <path>.parts.json; with
+// cfg.Buckets it is spread over bucket files listed in <path>.buckets.json. When
+// cfg.Descriptor is enabled, closing the sink also writes a dataset descriptor next
+// to the output. The postgres, clickhouse and elasticsearch formats ignore path and
+// write no descriptor. With cfg.FamilyMembers, cfg.Families and cfg.CitationGraph, the same
+// records also fill the family members table, the families table and the citation graph;
+// with cfg.Kafka they are published to a Kafka topic as well.
+func New(path string, cfg config.Parse, meta Metadata) (Sink, error) {
+	open := func(path string) (Sink, error) {
+		switch cfg.OutputFormat {
+		case "", FormatParquet:
+			return newParquetSink(path)
+		case FormatCSV:
+			return newCSVSink(path, CSVColumns(cfg))
+		case FormatJSONL:
+			return newJSONLSink(path)
+		case FormatTables:
+			return newTablesSink(path, fieldColumns(cfg))
+		case FormatPostgres:
+			return newPostgresSink(cfg.Postgres, fieldColumns(cfg))
+		case FormatDuckDB:
+			return newDuckDBSink(path, cfg.DuckDB)
+		case FormatNeo4j:
+			return newNeo4jSink(path, fieldColumns(cfg))
+		case FormatClickHouse:
+			return newClickHouseSink(cfg.ClickHouse)
+		case FormatElasticsearch:
+			return newElasticsearchSink(cfg.Elasticsearch)
+		case FormatArrow:
+			return newArrowSink(path)
+		default:
+			return nil, fmt.Errorf("unsupported output format %q", cfg.OutputFormat)
+		}
+	}
+	var s Sink
+	var err error
+	format := cfg.OutputFormat
+	if format == "" {
+		format = FormatParquet
+	}
+	switch {
+	case cfg.Buckets.Count > 0:
+		s, err = newBucketSink(path, format, cfg.Buckets.Count, open)
+	case cfg.Rotation.MaxRowsPerFile > 0 || cfg.Rotation.MaxBytesPerFile > 0:
+		s = newRotatingSink(path, format, cfg.Rotation, open)
+	default:
+		s, err = open(path)
+	}
+	if err != nil {
+		return nil, err
+	}
+	if cfg.Descriptor.Enabled && !slices.Contains([]string{FormatPostgres, FormatClickHouse, FormatElasticsearch}, cfg.OutputFormat) {
+		s = withDescriptor(s, path, cfg, meta)
+	}
+	sinks := []Sink{s}
+	if cfg.FamilyMembers.Enabled {
+		members, err := NewFamilyMembers(cfg.FamilyMembers.Path)
+		if err != nil {
+			return nil, errors.Join(err, Abort(Fanout(sinks...)))
+		}
+		sinks = append(sinks, members)
+	}
+	if cfg.Families.Enabled {
+		families, err := NewFamilies(cfg.Families.Path)
+		if err != nil {
+			return nil, errors.Join(err, Abort(Fanout(sinks...)))
+		}
+		sinks = append(sinks, families)
+	}
+	if cfg.CitationGraph.Enabled {
+		graph, err := NewCitationGraph(cfg.CitationGraph.Path, cfg.CitationGraph.Format)
+		if err != nil {
+			return nil, errors.Join(err, Abort(Fanout(sinks...)))
+		}
+		sinks = append(sinks, graph)
+	}
+	if cfg.Kafka.Enabled {
+		kafka, err := NewKafka(cfg.Kafka)
+		if err != nil {
+			return nil, errors.Join(err, Abort(Fanout(sinks...)))
+		}
+		sinks = append(sinks, kafka)
+	}
+	return Fanout(sinks...), nil
+}
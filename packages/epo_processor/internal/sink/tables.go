@@ -0,0 +1,120 @@
+package sink
+
+import (
+	"encoding/csv"
+	"errors"
+	"fmt"
+	"os"
+	"path/filepath"
+	"slices"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// Table files written by the tables format, relative to the output directory.
+const (
+	TablePatents         = "patents.csv"
+	TableClassifications = "classifications.csv"
+	TableCitations       = "citations.csv"
+	TableFamily          = "family.csv"
+)
+
+var (
+	patentsHeader         = []string{"patent_id", "status"}
+	classificationsHeader = []string{"patent_id", "scheme", "symbol"}
+	citationsHeader       = []string{"citing_id", "cited_id", "category"}
+	familyHeader          = []string{"patent_id", "family_member_id"}
+)
+
+// tablesSink writes a load-ready star schema into the output directory: one row per
+// record in patents.csv (with the configured bibliographic fields) and one row per
+// classification, citation category and family member in the relation tables. A
+// citation without categories gets one row with an empty category.
+type tablesSink struct {
+	files  []*os.File
+	tables map[string]*csv.Writer
+	fields []string
+}
+
+func newTablesSink(dir string, fields []string) (*tablesSink, error) {
+	if err := os.MkdirAll(dir, 0o755); err != nil {
+		return nil, fmt.Errorf("failed to create output directory: %w", err)
+	}
+	s := &tablesSink{tables: make(map[string]*csv.Writer), fields: fields}
+	headers := map[string][]string{
+		TablePatents:         append(slices.Clone(patentsHeader), fields...),
+		TableClassifications: classificationsHeader,
+		TableCitations:       citationsHeader,
+		TableFamily:          familyHeader,
+	}
+	for name, header := range headers {
+		f, err := os.Create(filepath.Join(dir, name))
+		if err != nil {
+			return nil, errors.Join(fmt.Errorf("failed to create %s: %w", name, err), s.Close())
+		}
+		s.files = append(s.files, f)
+		w := csv.NewWriter(f)
+		s.tables[name] = w
+		if err := w.Write(header); err != nil {
+			return nil, errors.Join(err, s.Close())
+		}
+	}
+	return s, nil
+}
+
+func (s *tablesSink) Write(records []models.PatentRecord) error {
+	for _, r := range records {
+		if err := s.tables[TablePatents].Write(patentRow(r, s.fields)); err != nil {
+			return err
+		}
+		for table, rows := range relationRows(r) {
+			for _, row := range rows {
+				if err := s.tables[table].Write(row); err != nil {
+					return err
+				}
+			}
+		}
+	}
+	return nil
+}
+
+// patentRow is the patents.csv row of r: patent_id, status and the configured fields.
+func patentRow(r models.PatentRecord, fields []string) []string {
+	return csvRow(r, append(slices.Clone(patentsHeader), fields...))
+}
+
+// relationRows returns the rows of r in each relation table.
+func relationRows(r models.PatentRecord) map[string][][]string {
+	rows := make(map[string][][]string, 3)
+	for _, symbol := range r.CPCList {
+		rows[TableClassifications] = append(rows[TableClassifications], []string{r.PatentID, "CPC", symbol})
+	}
+	for _, symbol := range r.IPCList {
+		rows[TableClassifications] = append(rows[TableClassifications], []string{r.PatentID, "IPC", symbol})
+	}
+	for _, c := range r.Citations {
+		categories := c.Categories
+		if len(categories) == 0 {
+			categories = []string{""}
+		}
+		for _, category := range categories {
+			rows[TableCitations] = append(rows[TableCitations], []string{r.PatentID, c.CitedID, category})
+		}
+	}
+	for _, member := range r.FamilyPatents {
+		rows[TableFamily] = append(rows[TableFamily], []string{r.PatentID, member})
+	}
+	return rows
+}
+
+func (s *tablesSink) Close() error {
+	var errs []error
+	for _, w := range s.tables {
+		w.Flush()
+		errs = append(errs, w.Error())
+	}
+	for _, f := range s.files {
+		errs = append(errs, f.Close())
+	}
+	return errors.Join(errs...)
+}
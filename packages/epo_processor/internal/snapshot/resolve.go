@@ -0,0 +1,17 @@
+package snapshot
+
+import (
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/state"
+)
+
+// Resolve returns the pinned label or the one recorded in the manifest, which may be nil.
+func Resolve(cfg config.Snapshot, manifest *state.Manifest) string {
+	if cfg.Label != "" {
+		return cfg.Label
+	}
+	if manifest != nil {
+		return manifest.SnapshotLabel()
+	}
+	return ""
+}
@@ -0,0 +1,56 @@
+package snapshot
+
+import (
+	"fmt"
+	"strings"
+	"time"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+)
+
+// Placeholder is replaced by the snapshot label in configured output paths.
+const Placeholder = "{snapshot}"
+
+// Unlabeled is used when no delivery date is known and no label is pinned.
+const Unlabeled = "unlabeled"
+
+// Latest returns the newest delivery publication datetime of p.
+func Latest(p models.Product) (time.Time, bool) {
+	var latest time.Time
+	for _, d := range p.Deliveries {
+		t, err := d.PublishedAt()
+		if err == nil && t.After(latest) {
+			latest = t
+		}
+	}
+	return latest, !latest.IsZero()
+}
+
+// Label formats t as e.g. docdb_2024w37 (format "week") or docdb_20240912 (format "date").
+// A pinned cfg.Label always wins.
+func Label(cfg config.Snapshot, t time.Time) string {
+	if cfg.Label != "" {
+		return cfg.Label
+	}
+	var stamp string
+	switch cfg.Format {
+	case "date":
+		stamp = t.Format("20060102")
+	default:
+		year, week := t.ISOWeek()
+		stamp = fmt.Sprintf("%dw%02d", year, week)
+	}
+	if cfg.Prefix == "" {
+		return stamp
+	}
+	return cfg.Prefix + "_" + stamp
+}
+
+// Expand substitutes label for the {snapshot} placeholder in path.
+func Expand(path, label string) string {
+	if label == "" {
+		label = Unlabeled
+	}
+	return strings.ReplaceAll(path, Placeholder, label)
+}
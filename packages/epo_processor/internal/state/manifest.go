@@ -0,0 +1,683 @@
+package state
+
+import (
+	"bytes"
+	"database/sql"
+	"encoding/json"
+	"errors"
+	"fmt"
+	"io/fs"
+	"maps"
+	"net/url"
+	"os"
+	"path/filepath"
+	"sort"
+	"sync"
+	"time"
+
+	_ "modernc.org/sqlite" // pure-Go driver "sqlite", so CGO_ENABLED=0 builds keep the manifest
+)
+
+type Status string
+
+const (
+	StatusPending  Status = ""
+	StatusDone     Status = "done"
+	StatusFailed   Status = "failed"
+	StatusOK       Status = "ok"
+	StatusMismatch Status = "mismatch"
+	StatusInFlight Status = "in_flight"
+)
+
+// Attempt records one try of a download; Error is empty when it succeeded and
+// Finished is nil while it is still running (or the process died during it).
+type Attempt struct {
+	Started  time.Time  `json:"started"`
+	Finished *time.Time `json:"finished,omitempty"`
+	Error    string     `json:"error,omitempty"`
+}
+
+// ItemState is the per-item record of which pipeline steps already ran.
+type ItemState struct {
+	Name     string `json:"name"`
+	Download Status `json:"download,omitempty"`
+	Checksum Status `json:"checksum,omitempty"`
+	Extract  Status `json:"extract,omitempty"`
+	Parse    Status `json:"parse,omitempty"`
+	// Published is the publication date of the delivery of the item (YYYY-MM-DD).
+	Published string    `json:"published,omitempty"`
+	Error     string    `json:"error,omitempty"`
+	Attempts  []Attempt `json:"attempts,omitempty"`
+	UpdatedAt time.Time `json:"updated_at"`
+}
+
+// ParseCheckpoint records how far a parse into Output got: the XML files whose records
+// are in it, and its size and row count after the last of them.
+type ParseCheckpoint struct {
+	Output string          `json:"output"`
+	Offset int64           `json:"offset"`
+	Rows   int64           `json:"rows"`
+	Files  map[string]bool `json:"files"`
+}
+
+// Meta keys of the manifest.
+const (
+	metaSnapshot     = "snapshot"
+	metaLastDelivery = "last_delivery"
+)
+
+// schema has one row per item and per XML file, so an update writes the rows it changes
+// whatever the size of the manifest.
+const schema = `
+CREATE TABLE IF NOT EXISTS meta (
+	key   TEXT PRIMARY KEY,
+	value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS items (
+	name       TEXT PRIMARY KEY,
+	download   TEXT NOT NULL DEFAULT '',
+	checksum   TEXT NOT NULL DEFAULT '',
+	extract    TEXT NOT NULL DEFAULT '',
+	parse      TEXT NOT NULL DEFAULT '',
+	published  TEXT NOT NULL DEFAULT '',
+	error      TEXT NOT NULL DEFAULT '',
+	attempts   TEXT NOT NULL DEFAULT '[]',
+	updated_at TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS files (
+	path   TEXT PRIMARY KEY,
+	status TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS checkpoint (
+	id         INTEGER PRIMARY KEY CHECK (id = 1),
+	output     TEXT NOT NULL,
+	end_offset INTEGER NOT NULL,
+	row_count  INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS checkpoint_files (
+	path TEXT PRIMARY KEY
+);`
+
+// Manifest persists pipeline progress so re-runs only perform missing steps. It is an
+// SQLite database in WAL mode: every update is a transaction that is durable once it
+// returns, and SQLite's file lock serializes the updates of processes sharing the
+// manifest, which wait up to busyTimeout for each other. The manifest is read into
+// memory by Open and answers reads from there; UpdateItem re-reads the item inside its
+// transaction so that concurrent processes do not undo each other's steps.
+type Manifest struct {
+	mu sync.Mutex
+	db *sql.DB
+
+	snapshot     string
+	lastDelivery *time.Time
+	items        map[string]*ItemState
+	files        map[string]Status
+	checkpoint   *ParseCheckpoint
+}
+
+// busyTimeout is how long an update waits for another process holding the manifest lock.
+const busyTimeout = 30 * time.Second
+
+// Open opens the manifest database at path, creating it when it does not exist yet.
+func Open(path string) (*Manifest, error) {
+	if err := checkNotJSON(path); err != nil {
+		return nil, err
+	}
+	if err := os.MkdirAll(filepath.Dir(path), 0o755); err != nil {
+		return nil, fmt.Errorf("create manifest directory: %w", err)
+	}
+	db, err := sql.Open("sqlite", dsn(path))
+	if err != nil {
+		return nil, fmt.Errorf("open manifest %s: %w", path, err)
+	}
+	// one connection: the mutex serializes the updates of this process anyway
+	db.SetMaxOpenConns(1)
+	m := &Manifest{
+		db:    db,
+		items: make(map[string]*ItemState),
+		files: make(map[string]Status),
+	}
+	if err := m.load(); err != nil {
+		return nil, errors.Join(fmt.Errorf("open manifest %s: %w", path, err), db.Close())
+	}
+	return m, nil
+}
+
+// dsn sets the pragmas of every connection: WAL with full sync, so a committed update
+// survives a crash, and a busy timeout instead of failing while another process writes.
+// Transactions take the write lock when they begin, so two processes never both read and
+// then fail to upgrade.
+func dsn(path string) string {
+	q := url.Values{}
+	q.Add("_pragma", fmt.Sprintf("busy_timeout(%d)", busyTimeout.Milliseconds()))
+	q.Add("_pragma", "journal_mode(WAL)")
+	q.Add("_pragma", "synchronous(FULL)")
+	q.Set("_txlock", "immediate")
+	return "file:" + filepath.ToSlash(path) + "?" + q.Encode()
+}
+
+// checkNotJSON rejects a manifest in the JSON format of earlier versions, which SQLite
+// would report as a corrupt database.
+func checkNotJSON(path string) error {
+	f, err := os.Open(path)
+	if errors.Is(err, fs.ErrNotExist) {
+		return nil
+	}
+	if err != nil {
+		return fmt.Errorf("read manifest: %w", err)
+	}
+	defer f.Close()
+	head := make([]byte, 64)
+	n, _ := f.Read(head)
+	if bytes.HasPrefix(bytes.TrimSpace(head[:n]), []byte("{")) {
+		return fmt.Errorf("manifest %s is a JSON manifest of an earlier version; point state.path at a new file", path)
+	}
+	return nil
+}
+
+// load creates the tables if needed and reads the manifest into memory.
+func (m *Manifest) load() error {
+	if _, err := m.db.Exec(schema); err != nil {
+		return err
+	}
+	if err := m.loadMeta(); err != nil {
+		return err
+	}
+	items, err := queryItems(m.db, "")
+	if err != nil {
+		return err
+	}
+	for _, it := range items {
+		m.items[it.Name] = it
+	}
+	if err := m.loadFiles(); err != nil {
+		return err
+	}
+	return m.loadCheckpoint()
+}
+
+func (m *Manifest) loadMeta() error {
+	rows, err := m.db.Query(`SELECT key, value FROM meta`)
+	if err != nil {
+		return err
+	}
+	defer rows.Close()
+	for rows.Next() {
+		var key, value string
+		if err := rows.Scan(&key, &value); err != nil {
+			return err
+		}
+		switch key {
+		case metaSnapshot:
+			m.snapshot = value
+		case metaLastDelivery:
+			t, err := time.Parse(time.RFC3339Nano, value)
+			if err != nil {
+				return fmt.Errorf("decode last delivery: %w", err)
+			}
+			m.lastDelivery = &t
+		}
+	}
+	return rows.Err()
+}
+
+func (m *Manifest) loadFiles() error {
+	rows, err := m.db.Query(`SELECT path, status FROM files`)
+	if err != nil {
+		return err
+	}
+	defer rows.Close()
+	for rows.Next() {
+		var path string
+		var st Status
+		if err := rows.Scan(&path, &st); err != nil {
+			return err
+		}
+		m.files[path] = st
+	}
+	return rows.Err()
+}
+
+func (m *Manifest) loadCheckpoint() error {
+	cp := ParseCheckpoint{Files: make(map[string]bool)}
+	err := m.db.QueryRow(`SELECT output, end_offset, row_count FROM checkpoint WHERE id = 1`).
+		Scan(&cp.Output, &cp.Offset, &cp.Rows)
+	if errors.Is(err, sql.ErrNoRows) {
+		return nil
+	}
+	if err != nil {
+		return err
+	}
+	rows, err := m.db.Query(`SELECT path FROM checkpoint_files`)
+	if err != nil {
+		return err
+	}
+	defer rows.Close()
+	for rows.Next() {
+		var path string
+		if err := rows.Scan(&path); err != nil {
+			return err
+		}
+		cp.Files[path] = true
+	}
+	if err := rows.Err(); err != nil {
+		return err
+	}
+	m.checkpoint = &cp
+	return nil
+}
+
+// queryer is a *sql.DB or *sql.Tx.
+type queryer interface {
+	Query(query string, args ...any) (*sql.Rows, error)
+	Exec(query string, args ...any) (sql.Result, error)
+}
+
+// queryItems reads the item name, or every item when name is empty.
+func queryItems(q queryer, name string) ([]*ItemState, error) {
+	query := `SELECT name, download, checksum, extract, parse, published, error, attempts, updated_at FROM items`
+	var args []any
+	if name != "" {
+		query += ` WHERE name = ?`
+		args = append(args, name)
+	}
+	rows, err := q.Query(query, args...)
+	if err != nil {
+		return nil, err
+	}
+	defer rows.Close()
+	var items []*ItemState
+	for rows.Next() {
+		var it ItemState
+		var attempts, updated string
+		if err := rows.Scan(&it.Name, &it.Download, &it.Checksum, &it.Extract, &it.Parse,
+			&it.Published, &it.Error, &attempts, &updated); err != nil {
+			return nil, err
+		}
+		if err := json.Unmarshal([]byte(attempts), &it.Attempts); err != nil {
+			return nil, fmt.Errorf("decode attempts of %s: %w", it.Name, err)
+		}
+		if len(it.Attempts) == 0 {
+			it.Attempts = nil
+		}
+		if it.UpdatedAt, err = time.Parse(time.RFC3339Nano, updated); err != nil {
+			return nil, fmt.Errorf("decode updated_at of %s: %w", it.Name, err)
+		}
+		items = append(items, &it)
+	}
+	return items, rows.Err()
+}
+
+// putItem inserts or replaces the row of it.
+func putItem(q queryer, it *ItemState) error {
+	attempts, err := json.Marshal(it.Attempts)
+	if err != nil {
+		return err
+	}
+	if it.Attempts == nil {
+		attempts = []byte("[]")
+	}
+	_, err = q.Exec(`INSERT OR REPLACE INTO items
+		(name, download, checksum, extract, parse, published, error, attempts, updated_at)
+		VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)`,
+		it.Name, it.Download, it.Checksum, it.Extract, it.Parse, it.Published, it.Error,
+		string(attempts), it.UpdatedAt.Format(time.RFC3339Nano))
+	return err
+}
+
+// update runs fn in a transaction and commits it.
+func (m *Manifest) update(fn func(tx *sql.Tx) error) error {
+	tx, err := m.db.Begin()
+	if err != nil {
+		return fmt.Errorf("update manifest: %w", err)
+	}
+	if err := fn(tx); err != nil {
+		return errors.Join(fmt.Errorf("update manifest: %w", err), tx.Rollback())
+	}
+	if err := tx.Commit(); err != nil {
+		return fmt.Errorf("update manifest: %w", err)
+	}
+	return nil
+}
+
+// Item returns a copy of the state recorded for name.
+func (m *Manifest) Item(name string) (ItemState, bool) {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	it, ok := m.items[name]
+	if !ok {
+		return ItemState{Name: name}, false
+	}
+	return copyItem(it), true
+}
+
+func copyItem(it *ItemState) ItemState {
+	c := *it
+	c.Attempts = append([]Attempt(nil), it.Attempts...)
+	return c
+}
+
+// UpdateItem applies fn to the state of name, as recorded in the database, and persists it.
+func (m *Manifest) UpdateItem(name string, fn func(*ItemState)) error {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	var it *ItemState
+	err := m.update(func(tx *sql.Tx) error {
+		items, err := queryItems(tx, name)
+		if err != nil {
+			return err
+		}
+		it = &ItemState{Name: name}
+		if len(items) > 0 {
+			it = items[0]
+		}
+		fn(it)
+		it.UpdatedAt = time.Now().UTC()
+		return putItem(tx, it)
+	})
+	if err != nil {
+		return err
+	}
+	m.items[name] = it
+	return nil
+}
+
+// Enqueue records every item not yet in the manifest as pending in one transaction, so
+// the full download queue is on disk before the first task starts. The publication
+// date of an item already in the manifest is filled in when it was not recorded.
+func (m *Manifest) Enqueue(items []ItemState) error {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	now := time.Now().UTC()
+	var changed []*ItemState
+	for _, item := range items {
+		it, ok := m.items[item.Name]
+		switch {
+		case !ok:
+			it = &ItemState{Name: item.Name, Published: item.Published, UpdatedAt: now}
+		case it.Published == "" && item.Published != "":
+			c := copyItem(it)
+			it = &c
+			it.Published = item.Published
+		default:
+			continue
+		}
+		changed = append(changed, it)
+	}
+	if len(changed) == 0 {
+		return nil
+	}
+	err := m.update(func(tx *sql.Tx) error {
+		for _, it := range changed {
+			// the row may have been written by another process since Open
+			if _, err := tx.Exec(`INSERT INTO items (name, published, updated_at) VALUES (?, ?, ?)
+				ON CONFLICT (name) DO UPDATE SET published = excluded.published
+				WHERE items.published = ''`,
+				it.Name, it.Published, it.UpdatedAt.Format(time.RFC3339Nano)); err != nil {
+				return err
+			}
+		}
+		return nil
+	})
+	if err != nil {
+		return err
+	}
+	for _, it := range changed {
+		m.items[it.Name] = it
+	}
+	return nil
+}
+
+// SnapshotLabel returns the recorded snapshot label, if any.
+func (m *Manifest) SnapshotLabel() string {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	return m.snapshot
+}
+
+// SetSnapshotLabel records the snapshot label and persists it.
+func (m *Manifest) SetSnapshotLabel(label string) error {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	if err := m.setMeta(metaSnapshot, label); err != nil {
+		return err
+	}
+	m.snapshot = label
+	return nil
+}
+
+func (m *Manifest) setMeta(key, value string) error {
+	return m.update(func(tx *sql.Tx) error {
+		_, err := tx.Exec(`INSERT OR REPLACE INTO meta (key, value) VALUES (?, ?)`, key, value)
+		return err
+	})
+}
+
+// Watermark returns the recorded last delivery datetime, or the zero time.
+func (m *Manifest) Watermark() time.Time {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	if m.lastDelivery == nil {
+		return time.Time{}
+	}
+	return *m.lastDelivery
+}
+
+// AdvanceWatermark records t as the last delivery datetime unless a newer one is recorded.
+func (m *Manifest) AdvanceWatermark(t time.Time) error {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	if m.lastDelivery != nil && !t.After(*m.lastDelivery) {
+		return nil
+	}
+	if err := m.setMeta(metaLastDelivery, t.Format(time.RFC3339Nano)); err != nil {
+		return err
+	}
+	m.lastDelivery = &t
+	return nil
+}
+
+// FileStatus returns the parse status recorded for an XML file.
+func (m *Manifest) FileStatus(path string) Status {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	return m.files[path]
+}
+
+// FileStatuses returns a copy of the recorded XML file statuses.
+func (m *Manifest) FileStatuses() map[string]Status {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	return maps.Clone(m.files)
+}
+
+// SetFileStatus records the parse status of an XML file and persists it.
+func (m *Manifest) SetFileStatus(path string, st Status) error {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	err := m.update(func(tx *sql.Tx) error {
+		return putFile(tx, path, st)
+	})
+	if err != nil {
+		return err
+	}
+	m.files[path] = st
+	return nil
+}
+
+func putFile(q queryer, path string, st Status) error {
+	_, err := q.Exec(`INSERT OR REPLACE INTO files (path, status) VALUES (?, ?)`, path, st)
+	return err
+}
+
+// StartCheckpoint replaces the parse checkpoint with an empty one for output, whose
+// header ends at offset, and persists it.
+func (m *Manifest) StartCheckpoint(output string, offset int64) error {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	err := m.update(func(tx *sql.Tx) error {
+		if _, err := tx.Exec(`DELETE FROM checkpoint_files`); err != nil {
+			return err
+		}
+		_, err := tx.Exec(`INSERT OR REPLACE INTO checkpoint (id, output, end_offset, row_count)
+			VALUES (1, ?, ?, 0)`, output, offset)
+		return err
+	})
+	if err != nil {
+		return err
+	}
+	m.checkpoint = &ParseCheckpoint{Output: output, Offset: offset, Files: map[string]bool{}}
+	return nil
+}
+
+// ParseCheckpoint returns a copy of the parse checkpoint, if any.
+func (m *Manifest) ParseCheckpoint() (ParseCheckpoint, bool) {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	if m.checkpoint == nil {
+		return ParseCheckpoint{}, false
+	}
+	cp := *m.checkpoint
+	cp.Files = maps.Clone(cp.Files)
+	return cp, true
+}
+
+// AdvanceCheckpoint records path as parsed, both in the files and in the checkpoint,
+// whose output now ends at offset after rows rows, in one transaction.
+func (m *Manifest) AdvanceCheckpoint(path string, offset, rows int64) error {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	err := m.update(func(tx *sql.Tx) error {
+		if err := putFile(tx, path, StatusDone); err != nil {
+			return err
+		}
+		if m.checkpoint == nil {
+			return nil
+		}
+		if _, err := tx.Exec(`INSERT OR IGNORE INTO checkpoint_files (path) VALUES (?)`, path); err != nil {
+			return err
+		}
+		_, err := tx.Exec(`UPDATE checkpoint SET end_offset = ?, row_count = ? WHERE id = 1`, offset, rows)
+		return err
+	})
+	if err != nil {
+		return err
+	}
+	m.files[path] = StatusDone
+	if m.checkpoint != nil {
+		m.checkpoint.Files[path] = true
+		m.checkpoint.Offset, m.checkpoint.Rows = offset, rows
+	}
+	return nil
+}
+
+// ClearCheckpoint drops the parse checkpoint once its output is finalized.
+func (m *Manifest) ClearCheckpoint() error {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	err := m.update(func(tx *sql.Tx) error {
+		if _, err := tx.Exec(`DELETE FROM checkpoint_files`); err != nil {
+			return err
+		}
+		_, err := tx.Exec(`DELETE FROM checkpoint`)
+		return err
+	})
+	if err != nil {
+		return err
+	}
+	m.checkpoint = nil
+	return nil
+}
+
+// Close checkpoints the write-ahead log into the database and closes it.
+func (m *Manifest) Close() error {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	_, err := m.db.Exec(`PRAGMA wal_checkpoint(TRUNCATE)`)
+	return errors.Join(err, m.db.Close())
+}
+
+// StepCounts tallies the statuses of one pipeline step.
+type StepCounts map[Status]int
+
+// Summary aggregates the manifest per pipeline step.
+type Summary struct {
+	Snapshot     string     `json:"snapshot,omitempty"`
+	LastDelivery *time.Time `json:"last_delivery,omitempty"`
+	Items        int        `json:"items"`
+	Download     StepCounts `json:"download"`
+	Checksum     StepCounts `json:"checksum"`
+	Extract      StepCounts `json:"extract"`
+	Parse        StepCounts `json:"parse"`
+	Files        StepCounts `json:"files"`
+}
+
+func (m *Manifest) Summary() Summary {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	s := Summary{
+		Snapshot:     m.snapshot,
+		LastDelivery: m.lastDelivery,
+		Items:        len(m.items),
+		Download:     StepCounts{},
+		Checksum:     StepCounts{},
+		Extract:      StepCounts{},
+		Parse:        StepCounts{},
+		Files:        StepCounts{},
+	}
+	for _, it := range m.items {
+		s.Download[it.Download]++
+		s.Checksum[it.Checksum]++
+		s.Extract[it.Extract]++
+		s.Parse[it.Parse]++
+	}
+	for _, st := range m.files {
+		s.Files[st]++
+	}
+	return s
+}
+
+// Names returns the recorded item names in sorted order.
+func (m *Manifest) Names() []string {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	names := make([]string, 0, len(m.items))
+	for name := range m.items {
+		names = append(names, name)
+	}
+	sort.Strings(names)
+	return names
+}
+
+// UpdateItems applies fn to every item matching pred and persists them in one transaction.
+func (m *Manifest) UpdateItems(pred func(ItemState) bool, fn func(*ItemState)) error {
+	m.mu.Lock()
+	defer m.mu.Unlock()
+	now := time.Now().UTC()
+	var changed []*ItemState
+	err := m.update(func(tx *sql.Tx) error {
+		items, err := queryItems(tx, "")
+		if err != nil {
+			return err
+		}
+		for _, it := range items {
+			if !pred(copyItem(it)) {
+				continue
+			}
+			fn(it)
+			it.UpdatedAt = now
+			if err := putItem(tx, it); err != nil {
+				return err
+			}
+			changed = append(changed, it)
+		}
+		return nil
+	})
+	if err != nil {
+		return err
+	}
+	for _, it := range changed {
+		m.items[it.Name] = it
+	}
+	return nil
+}
@@ -0,0 +1,139 @@
+package state
+
+import (
+	"os"
+	"path/filepath"
+	"slices"
+	"testing"
+	"time"
+)
+
+func openManifest(t *testing.T, path string) *Manifest {
+	t.Helper()
+	m, err := Open(path)
+	if err != nil {
+		t.Fatalf("Open: %v", err)
+	}
+	t.Cleanup(func() { m.Close() })
+	return m
+}
+
+func TestManifestPersists(t *testing.T) {
+	path := filepath.Join(t.TempDir(), "state", "manifest.db")
+	m := openManifest(t, path)
+	delivery := time.Date(2024, 9, 10, 12, 0, 0, 0, time.UTC)
+	started := delivery.Add(time.Hour)
+	steps := []error{
+		m.Enqueue([]ItemState{{Name: "a.zip", Published: "2024-09-10"}, {Name: "b.zip"}}),
+		m.UpdateItem("a.zip", func(it *ItemState) {
+			it.Download = StatusDone
+			it.Attempts = append(it.Attempts, Attempt{Started: started, Finished: &started})
+		}),
+		m.UpdateItems(func(it ItemState) bool { return it.Download == StatusPending },
+			func(it *ItemState) { it.Download = StatusFailed; it.Error = "timeout" }),
+		m.SetSnapshotLabel("docdb_2024w37"),
+		m.AdvanceWatermark(delivery),
+		m.AdvanceWatermark(delivery.Add(-time.Hour)),
+		m.SetFileStatus("a/1.xml", StatusFailed),
+		m.StartCheckpoint("out.jsonl", 10),
+		m.AdvanceCheckpoint("a/2.xml", 100, 3),
+	}
+	for i, err := range steps {
+		if err != nil {
+			t.Fatalf("update %d: %v", i, err)
+		}
+	}
+	if err := m.Close(); err != nil {
+		t.Fatalf("Close: %v", err)
+	}
+
+	m = openManifest(t, path)
+	a, ok := m.Item("a.zip")
+	if !ok || a.Download != StatusDone || a.Published != "2024-09-10" || len(a.Attempts) != 1 ||
+		!a.Attempts[0].Started.Equal(started) {
+		t.Errorf("a.zip = %+v, want downloaded with its attempt", a)
+	}
+	if b, _ := m.Item("b.zip"); b.Download != StatusFailed || b.Error != "timeout" {
+		t.Errorf("b.zip = %+v, want failed", b)
+	}
+	if got := m.Names(); !slices.Equal(got, []string{"a.zip", "b.zip"}) {
+		t.Errorf("Names = %v", got)
+	}
+	if got := m.SnapshotLabel(); got != "docdb_2024w37" {
+		t.Errorf("SnapshotLabel = %q", got)
+	}
+	if got := m.Watermark(); !got.Equal(delivery) {
+		t.Errorf("Watermark = %v, want %v", got, delivery)
+	}
+	if got := m.FileStatus("a/1.xml"); got != StatusFailed {
+		t.Errorf("FileStatus(a/1.xml) = %q", got)
+	}
+	if got := m.FileStatus("a/2.xml"); got != StatusDone {
+		t.Errorf("FileStatus(a/2.xml) = %q, want done by AdvanceCheckpoint", got)
+	}
+	cp, ok := m.ParseCheckpoint()
+	if !ok || cp.Output != "out.jsonl" || cp.Offset != 100 || cp.Rows != 3 || !cp.Files["a/2.xml"] || len(cp.Files) != 1 {
+		t.Errorf("ParseCheckpoint = %+v, %v", cp, ok)
+	}
+	s := m.Summary()
+	if s.Items != 2 || s.Download[StatusDone] != 1 || s.Download[StatusFailed] != 1 || s.Files[StatusDone] != 1 {
+		t.Errorf("Summary = %+v", s)
+	}
+
+	if err := m.ClearCheckpoint(); err != nil {
+		t.Fatal(err)
+	}
+	if err := m.Close(); err != nil {
+		t.Fatal(err)
+	}
+	if _, ok := openManifest(t, path).ParseCheckpoint(); ok {
+		t.Error("checkpoint still present after ClearCheckpoint")
+	}
+}
+
+func TestEnqueueKeepsRecordedItems(t *testing.T) {
+	m := openManifest(t, filepath.Join(t.TempDir(), "manifest.db"))
+	if err := m.Enqueue([]ItemState{{Name: "a.zip"}}); err != nil {
+		t.Fatal(err)
+	}
+	if err := m.UpdateItem("a.zip", func(it *ItemState) { it.Download = StatusDone }); err != nil {
+		t.Fatal(err)
+	}
+	if err := m.Enqueue([]ItemState{{Name: "a.zip", Published: "2024-09-10"}}); err != nil {
+		t.Fatal(err)
+	}
+	if a, _ := m.Item("a.zip"); a.Download != StatusDone || a.Published != "2024-09-10" {
+		t.Errorf("a.zip = %+v, want still downloaded with the publication date filled in", a)
+	}
+}
+
+// TestSharedManifest checks that two processes updating the same manifest keep each
+// other's updates, which each reads inside its own transaction.
+func TestSharedManifest(t *testing.T) {
+	path := filepath.Join(t.TempDir(), "manifest.db")
+	run, audit := openManifest(t, path), openManifest(t, path)
+	if err := run.UpdateItem("a.zip", func(it *ItemState) { it.Download = StatusDone }); err != nil {
+		t.Fatal(err)
+	}
+	if err := audit.UpdateItem("a.zip", func(it *ItemState) { it.Extract = StatusDone }); err != nil {
+		t.Fatal(err)
+	}
+	if err := run.UpdateItem("a.zip", func(it *ItemState) { it.Parse = StatusDone }); err != nil {
+		t.Fatal(err)
+	}
+	a, _ := run.Item("a.zip")
+	if a.Download != StatusDone || a.Extract != StatusDone || a.Parse != StatusDone {
+		t.Errorf("a.zip = %+v, want every step of both processes", a)
+	}
+}
+
+func TestOpenRejectsJSONManifest(t *testing.T) {
+	path := filepath.Join(t.TempDir(), "manifest.json")
+	if err := os.WriteFile(path, []byte(`{"items": {}}`), 0o644); err != nil {
+		t.Fatal(err)
+	}
+	if m, err := Open(path); err == nil {
+		m.Close()
+		t.Fatal("Open accepted a JSON manifest")
+	}
+}
@@ -0,0 +1,202 @@
+// Package storage reads and writes the objects named by s3://bucket/key URIs, which may
+// stand in for the download directory, the parse input and the parse output.
+package storage
+
+import (
+	"context"
+	"errors"
+	"fmt"
+	"io"
+	"path"
+	"path/filepath"
+	"strings"
+
+	"github.com/aws/aws-sdk-go-v2/aws"
+	awsconfig "github.com/aws/aws-sdk-go-v2/config"
+	"github.com/aws/aws-sdk-go-v2/feature/s3/manager"
+	"github.com/aws/aws-sdk-go-v2/service/s3"
+	"github.com/aws/aws-sdk-go-v2/service/s3/types"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+)
+
+// Scheme prefixes the paths that name S3 objects.
+const Scheme = "s3://"
+
+// IsS3 reports whether p is an S3 URI rather than a local path.
+func IsS3(p string) bool {
+	return strings.HasPrefix(p, Scheme)
+}
+
+// Join joins elem to base like filepath.Join, keeping the scheme of an S3 URI intact.
+func Join(base string, elem ...string) string {
+	if !IsS3(base) {
+		return filepath.Join(append([]string{base}, elem...)...)
+	}
+	return Scheme + path.Join(append([]string{strings.TrimPrefix(base, Scheme)}, elem...)...)
+}
+
+// split returns the bucket and key of uri.
+func split(uri string) (bucket, key string, err error) {
+	bucket, key, _ = strings.Cut(strings.TrimPrefix(uri, Scheme), "/")
+	if !IsS3(uri) || bucket == "" {
+		return "", "", fmt.Errorf("invalid S3 URI %q, expected s3://bucket/key", uri)
+	}
+	return bucket, key, nil
+}
+
+// Object is an S3 object found by List.
+type Object struct {
+	URI  string
+	Size int64
+}
+
+// S3 is a client for AWS S3 or an S3-compatible store. Credentials come from the
+// standard AWS chain: environment, shared config files, or the role of the instance or
+// task.
+type S3 struct {
+	client   *s3.Client
+	uploader *manager.Uploader
+}
+
+func NewS3(ctx context.Context, cfg config.S3) (*S3, error) {
+	var opts []func(*awsconfig.LoadOptions) error
+	if cfg.Region != "" {
+		opts = append(opts, awsconfig.WithRegion(cfg.Region))
+	}
+	awsCfg, err := awsconfig.LoadDefaultConfig(ctx, opts...)
+	if err != nil {
+		return nil, fmt.Errorf("load AWS config: %w", err)
+	}
+	client := s3.NewFromConfig(awsCfg, func(o *s3.Options) {
+		if cfg.Endpoint != "" {
+			o.BaseEndpoint = aws.String(cfg.Endpoint)
+		}
+		o.UsePathStyle = cfg.PathStyle
+	})
+	uploader := manager.NewUploader(client, func(u *manager.Uploader) {
+		u.PartSize = int64(cfg.PartSize)
+		u.Concurrency = cfg.UploadConcurrency
+	})
+	return &S3{client: client, uploader: uploader}, nil
+}
+
+// Upload streams body to the object at uri as a multipart upload and returns the bytes
+// written. Only part_size bytes per concurrent part are buffered, and the object appears
+// once the upload completes; a failed upload is aborted and leaves no object.
+func (s *S3) Upload(ctx context.Context, uri string, body io.Reader) (int64, error) {
+	bucket, key, err := split(uri)
+	if err != nil {
+		return 0, err
+	}
+	counted := &countingReader{r: body}
+	_, err = s.uploader.Upload(ctx, &s3.PutObjectInput{
+		Bucket: aws.String(bucket),
+		Key:    aws.String(key),
+		Body:   counted,
+	})
+	if err != nil {
+		return counted.n, fmt.Errorf("upload %s: %w", uri, err)
+	}
+	return counted.n, nil
+}
+
+// Open streams the object at uri and returns its size.
+func (s *S3) Open(ctx context.Context, uri string) (io.ReadCloser, int64, error) {
+	bucket, key, err := split(uri)
+	if err != nil {
+		return nil, 0, err
+	}
+	out, err := s.client.GetObject(ctx, &s3.GetObjectInput{
+		Bucket: aws.String(bucket),
+		Key:    aws.String(key),
+	})
+	if err != nil {
+		return nil, 0, fmt.Errorf("open %s: %w", uri, err)
+	}
+	return out.Body, aws.ToInt64(out.ContentLength), nil
+}
+
+// Stat returns the size of the object at uri; ok is false when there is none.
+func (s *S3) Stat(ctx context.Context, uri string) (size int64, ok bool, err error) {
+	bucket, key, err := split(uri)
+	if err != nil {
+		return 0, false, err
+	}
+	out, err := s.client.HeadObject(ctx, &s3.HeadObjectInput{
+		Bucket: aws.String(bucket),
+		Key:    aws.String(key),
+	})
+	var notFound *types.NotFound
+	if errors.As(err, &notFound) {
+		return 0, false, nil
+	}
+	if err != nil {
+		return 0, false, fmt.Errorf("stat %s: %w", uri, err)
+	}
+	return aws.ToInt64(out.ContentLength), true, nil
+}
+
+// Remove deletes the object at uri; removing a missing object is not an error.
+func (s *S3) Remove(ctx context.Context, uri string) error {
+	bucket, key, err := split(uri)
+	if err != nil {
+		return err
+	}
+	_, err = s.client.DeleteObject(ctx, &s3.DeleteObjectInput{
+		Bucket: aws.String(bucket),
+		Key:    aws.String(key),
+	})
+	if err != nil {
+		return fmt.Errorf("remove %s: %w", uri, err)
+	}
+	return nil
+}
+
+// List returns the object at uri if there is one, else the objects below uri taken as a
+// prefix, in key order.
+func (s *S3) List(ctx context.Context, uri string) ([]Object, error) {
+	bucket, prefix, err := split(uri)
+	if err != nil {
+		return nil, err
+	}
+	if prefix != "" && !strings.HasSuffix(prefix, "/") {
+		size, ok, err := s.Stat(ctx, uri)
+		if err != nil {
+			return nil, err
+		}
+		if ok {
+			return []Object{{URI: uri, Size: size}}, nil
+		}
+		prefix += "/"
+	}
+	pages := s3.NewListObjectsV2Paginator(s.client, &s3.ListObjectsV2Input{
+		Bucket: aws.String(bucket),
+		Prefix: aws.String(prefix),
+	})
+	var objects []Object
+	for pages.HasMorePages() {
+		page, err := pages.NextPage(ctx)
+		if err != nil {
+			return nil, fmt.Errorf("list %s: %w", uri, err)
+		}
+		for _, o := range page.Contents {
+			objects = append(objects, Object{
+				URI:  Scheme + bucket + "/" + aws.ToString(o.Key),
+				Size: aws.ToInt64(o.Size),
+			})
+		}
+	}
+	return objects, nil
+}
+
+type countingReader struct {
+	r io.Reader
+	n int64
+}
+
+func (c *countingReader) Read(p []byte) (int, error) {
+	n, err := c.r.Read(p)
+	c.n += int64(n)
+	return n, err
+}
@@ -19,12 +19,14 @@ import (
 	"go.opentelemetry.io/otel/exporters/stdout/stdouttrace"
 	"go.opentelemetry.io/otel/log/global"
 	"go.opentelemetry.io/otel/metric"
+	metricnoop "go.opentelemetry.io/otel/metric/noop"
 	"go.opentelemetry.io/otel/sdk/log"
 	sdkmetric "go.opentelemetry.io/otel/sdk/metric"
 	"go.opentelemetry.io/otel/sdk/resource"
 	sdktrace "go.opentelemetry.io/otel/sdk/trace"
 	semconv "go.opentelemetry.io/otel/semconv/v1.26.0"
 	"go.opentelemetry.io/otel/trace"
+	tracenoop "go.opentelemetry.io/otel/trace/noop"
 	"go.uber.org/zap"
 	"go.uber.org/zap/zapcore"
 	"gopkg.in/natefinch/lumberjack.v2"
@@ -32,6 +34,7 @@ import (
 
 // Config for OTEL setup (unchanged)
 type Config struct {
+	Enabled     bool              // Export traces, metrics and logs; false keeps only the log file
 	ServiceName string            // e.g., "epo-processor"
 	Exporter    string            // "stdout" or "otlp"
 	Endpoint    string            // OTLP endpoint, e.g., "localhost:4317" (required for "otlp")
@@ -42,11 +45,30 @@ type Config struct {
 	LogLevel    string            // "debug", "info", "warn", "error" (default "info")
 }
 
+// logLevel filters the log file of the logger returned by InitOTEL.
+var logLevel = zap.NewAtomicLevelAt(zap.InfoLevel)
+
+// SetLogLevel changes the level of the logger returned by InitOTEL, also after it was
+// built; an empty name means info.
+func SetLogLevel(name string) error {
+	return logLevel.UnmarshalText([]byte(strings.ToLower(name)))
+}
+
 // InitOTEL sets up providers, tracer, meter, and returns them + bridged logger.
 func InitOTEL(
 	cfg Config,
 ) (trace.Tracer, metric.Meter, *zap.SugaredLogger, func(context.Context) error, error) {
 	ctx := context.Background()
+	if !cfg.Enabled {
+		zapLogger := newLogger(cfg)
+		shutdown := func(context.Context) error {
+			_ = zapLogger.Sync()
+			return nil
+		}
+		return tracenoop.NewTracerProvider().Tracer(cfg.ServiceName),
+			metricnoop.NewMeterProvider().Meter(cfg.ServiceName),
+			zapLogger.Sugar(), shutdown, nil
+	}
 
 	// Resource with service name (unchanged)
 	res, err := resource.Merge(
@@ -196,38 +218,12 @@ func InitOTEL(
 	tracer := otel.Tracer(cfg.ServiceName)
 	meter := otel.Meter(cfg.ServiceName) // Added: Get scoped meter
 
-	level := zap.NewAtomicLevelAt(zap.InfoLevel)
-	if cfg.LogLevel != "" {
-		l := strings.ToLower(cfg.LogLevel)
-		if err := level.UnmarshalText([]byte(l)); err != nil {
-			// Fallback to info on invalid
-			level = zap.NewAtomicLevelAt(zap.InfoLevel)
-		}
-	}
-	var cores []zapcore.Core
-	if cfg.LogFile != "" {
-		jsonConfig := zap.NewProductionEncoderConfig()
-		jsonConfig.TimeKey = "timestamp"
-		jsonEncoder := zapcore.NewJSONEncoder(jsonConfig)
-		jsonWriter := zapcore.AddSync(
-			zapcore.NewMultiWriteSyncer(zapcore.AddSync(&lumberjack.Logger{
-				Filename:   cfg.LogFile,
-				MaxSize:    100, // MB
-				MaxBackups: 5,
-			})),
-		)
-		jsonCore := zapcore.NewCore(jsonEncoder, jsonWriter, level)
-		cores = append(cores, jsonCore)
-	}
-
 	otelCore := otelzap.NewCore(
 		cfg.ServiceName,
 		otelzap.WithLoggerProvider(global.GetLoggerProvider()),
 		otelzap.WithVersion("1.0.0"),
 	)
-	cores = append(cores, otelCore)
-
-	zapLogger := zap.New(zapcore.NewTee(cores...))
+	zapLogger := newLogger(cfg, otelCore)
 	logger := zapLogger.Sugar()
 
 	// Shutdown now includes metric provider
@@ -248,3 +244,28 @@ func InitOTEL(
 
 	return tracer, meter, logger, shutdown, nil
 }
+
+// newLogger writes JSON logs to cfg.LogFile, at the level of SetLogLevel, and to extra.
+func newLogger(cfg Config, extra ...zapcore.Core) *zap.Logger {
+	level := logLevel
+	if err := SetLogLevel(cfg.LogLevel); err != nil {
+		// Fallback to info on invalid
+		level.SetLevel(zap.InfoLevel)
+	}
+	var cores []zapcore.Core
+	if cfg.LogFile != "" {
+		jsonConfig := zap.NewProductionEncoderConfig()
+		jsonConfig.TimeKey = "timestamp"
+		jsonEncoder := zapcore.NewJSONEncoder(jsonConfig)
+		jsonWriter := zapcore.AddSync(
+			zapcore.NewMultiWriteSyncer(zapcore.AddSync(&lumberjack.Logger{
+				Filename:   cfg.LogFile,
+				MaxSize:    100, // MB
+				MaxBackups: 5,
+			})),
+		)
+		jsonCore := zapcore.NewCore(jsonEncoder, jsonWriter, level)
+		cores = append(cores, jsonCore)
+	}
+	return zap.New(zapcore.NewTee(append(cores, extra...)...))
+}
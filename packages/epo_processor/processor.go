@@ -0,0 +1,186 @@
+package epoprocessor
+
+import (
+	"context"
+
+	ET "github.com/IBM/fp-go/v2/either"
+	"github.com/apache/arrow/go/v18/arrow"
+	"github.com/apache/arrow/go/v18/arrow/memory"
+
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/config"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/download"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/failures"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/models"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/parse"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/pipeline"
+	"github.com/Qubut/IP-Claim/packages/epo_processor/internal/sink"
+)
+
+type (
+	// Summary is the outcome of Run: the stage results, the snapshot label and, with
+	// state.enabled, the item counts.
+	Summary       = pipeline.Summary
+	StageResult   = pipeline.StageResult
+	Counts        = pipeline.Counts
+	CitationStats = parse.CitationStats
+	Inspection    = parse.Inspection
+	ProductItem   = download.ProductItem
+	PlanItem      = download.PlanItem
+	ProductInfo   = download.ProductInfo
+	DeliveryInfo  = download.DeliveryInfo
+	VerifyResult  = download.VerifyResult
+	// Failure is an item that failed in a stage; PartialError is the error of a stage in
+	// which some items failed.
+	Failure      = failures.Failure
+	PartialError = failures.Partial
+	// PatentRecord is a parsed exchange-document, one row of the output.
+	PatentRecord = models.PatentRecord
+)
+
+// ArrowSchema is the schema of the Arrow record batches of NewRecordBatch,
+// Parser.RecordBatches and the arrow output format.
+var ArrowSchema = sink.ArrowSchema
+
+// NewRecordBatch converts records into an Arrow record batch with ArrowSchema, allocated
+// from mem; the caller releases it.
+func NewRecordBatch(mem memory.Allocator, records []PatentRecord) arrow.Record {
+	return sink.RecordBatch(mem, records)
+}
+
+// Processor runs the stages of one configuration. Its methods may be called in any order,
+// but not concurrently with each other.
+type Processor struct {
+	cfg      config.Config
+	services *internal.Services
+}
+
+// Run executes the enabled stages in order, as the epo-processor command does without a
+// subcommand. Cancelling ctx stops the stages; with state.enabled, a later Run continues
+// where this one stopped.
+func (p *Processor) Run(ctx context.Context) (Summary, error) {
+	return pipeline.Run(ctx, p.cfg, p.services, nil)
+}
+
+// Downloader returns the download stage.
+func (p *Processor) Downloader() *Downloader {
+	return &Downloader{d: p.services.Downloader}
+}
+
+// Extractor returns the extract stage.
+func (p *Processor) Extractor() *Extractor {
+	return &Extractor{e: p.services.Extractor, dir: p.cfg.Download.Directory}
+}
+
+// Parser returns the parse stage.
+func (p *Processor) Parser() *Parser {
+	return &Parser{p: p.services.Parser, cfg: p.cfg}
+}
+
+// Close removes the scratch files of the processor and closes its skip report.
+func (p *Processor) Close() error {
+	return p.services.Close()
+}
+
+// Downloader fetches the product items from the EPO API.
+type Downloader struct {
+	d internal.DownloaderInterface
+}
+
+// Download fetches the product items selected by download.filter and returns the size
+// of each downloaded file.
+func (d *Downloader) Download(ctx context.Context) ([]int64, error) {
+	return ET.UnwrapError(d.d.FetchEPOFiles(ctx)())
+}
+
+// Items lists the product items selected by download.filter without downloading them.
+func (d *Downloader) Items(ctx context.Context) ([]ProductItem, error) {
+	return ET.UnwrapError(d.d.ProductItems(ctx)())
+}
+
+// Plan lists the items a download would fetch or skip now, without downloading them.
+func (d *Downloader) Plan(ctx context.Context) ([]PlanItem, error) {
+	return ET.UnwrapError(d.d.Plan(ctx)())
+}
+
+// Products lists the products the EPO API offers.
+func (d *Downloader) Products(ctx context.Context) ([]ProductInfo, error) {
+	return ET.UnwrapError(d.d.Products(ctx)())
+}
+
+// Deliveries lists all deliveries of the configured product, ignoring download.filter.
+func (d *Downloader) Deliveries(ctx context.Context) ([]DeliveryInfo, error) {
+	return ET.UnwrapError(d.d.Deliveries(ctx)())
+}
+
+// NewDeliveries lists the deliveries published after the last one downloaded; it needs
+// state.enabled.
+func (d *Downloader) NewDeliveries(ctx context.Context) ([]string, error) {
+	return ET.UnwrapError(d.d.NewDeliveries(ctx)())
+}
+
+// Verify checks the downloaded items against the checksums of the product.
+func (d *Downloader) Verify(ctx context.Context) ([]VerifyResult, error) {
+	return ET.UnwrapError(d.d.VerifyEPOFiles(ctx)())
+}
+
+// Snapshot returns the label of the newest delivery seen by the last download.
+func (d *Downloader) Snapshot() string {
+	return d.d.Snapshot()
+}
+
+// Extractor unpacks the downloaded archives into XML files.
+type Extractor struct {
+	e   internal.ExtractorInterface
+	dir string
+}
+
+// ExtractAll extracts every archive in the download directory.
+func (e *Extractor) ExtractAll(ctx context.Context) error {
+	_, err := ET.UnwrapError(e.e.ExtractAll(ctx, e.dir)())
+	return err
+}
+
+// ExtractArchive extracts one archive and returns the directory it was extracted to.
+func (e *Extractor) ExtractArchive(ctx context.Context, path string) (string, error) {
+	return ET.UnwrapError(e.e.ExtractArchive(ctx, path)())
+}
+
+// Parser turns extracted XML files into the configured output.
+type Parser struct {
+	p   internal.ParserInterface
+	cfg config.Config
+}
+
+// Parse parses the configured input into the configured output.
+func (p *Parser) Parse(ctx context.Context) error {
+	return p.ParseDir(ctx, p.cfg.ParseInputDir(), p.cfg.Parse.OutputCSV)
+}
+
+// ParseDir parses the XML files under inputDir into output, in the configured format.
+func (p *Parser) ParseDir(ctx context.Context, inputDir, output string) error {
+	return p.p.ParseAll(ctx, inputDir, output, int64(p.cfg.Parse.Workers))
+}
+
+// RecordBatches parses the XML files under inputDir like ParseDir, but hands the records
+// to fn as Arrow record batches with ArrowSchema, one per parsed file, instead of writing
+// an output. fn is not called concurrently; a batch is released once fn returns, so fn
+// retains it to keep it, e.g. to feed a query engine.
+func (p *Parser) RecordBatches(
+	ctx context.Context,
+	inputDir string,
+	mem memory.Allocator,
+	fn func(arrow.Record) error,
+) error {
+	return p.p.ParseInto(ctx, inputDir, sink.NewRecordBatchSink(mem, fn), int64(p.cfg.Parse.Workers))
+}
+
+// Inspect parses the single XML file at path without writing any output.
+func (p *Parser) Inspect(ctx context.Context, path string) (Inspection, error) {
+	return p.p.Inspect(ctx, path)
+}
+
+// CitationStats returns the citation counts of the last parse.
+func (p *Parser) CitationStats() CitationStats {
+	return p.p.CitationStats()
+}